@@ -89,8 +89,8 @@ var API = (function() {
 
         // ===== Todo APIs =====
         getTodos: async function(tab) {
-            var path = '/todos';
-            if (tab) path += '?tab=' + encodeURIComponent(tab);
+            var path = '/todos?all=1';
+            if (tab) path += '&tab=' + encodeURIComponent(tab);
             return await request('GET', path);
         },
 
@@ -279,8 +279,8 @@ var API = (function() {
         },
 
         getSharedInbox: async function(type) {
-            var path = '/share/inbox';
-            if (type) path += '?type=' + encodeURIComponent(type);
+            var path = '/share/inbox?all=1';
+            if (type) path += '&type=' + encodeURIComponent(type);
             return await request('GET', path);
         },
 
@@ -407,11 +407,11 @@ var API = (function() {
 
         // ===== Expense APIs =====
         getExpenses: async function(from, to, tags) {
-            var params = [];
+            var params = ['all=1'];
             if (from) params.push('from=' + encodeURIComponent(from));
             if (to) params.push('to=' + encodeURIComponent(to));
             if (tags) params.push('tags=' + encodeURIComponent(tags));
-            var path = '/expenses' + (params.length ? '?' + params.join('&') : '');
+            var path = '/expenses?' + params.join('&');
             return await request('GET', path);
         },
 
@@ -458,7 +458,7 @@ var API = (function() {
 
         // ===== Trip APIs (差旅) =====
         getTrips: async function() {
-            return await request('GET', '/trips');
+            return await request('GET', '/trips?all=1');
         },
 
         createTrip: async function(data) {
@@ -1,4 +1,4 @@
-const CACHE_NAME = 'next-v16';
+const CACHE_NAME = 'next-v17';
 const STATIC_ASSETS = [
     '/',
     '/index.html',
@@ -0,0 +1,245 @@
+//! One-off maintenance commands: `next-server <command> [options]`.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::db;
+use crate::models::import::{DesktopFile, DesktopImportRequest};
+use crate::services::desktop_import;
+use crate::services::todo_text::{self, TextFormat};
+
+const USAGE: &str = "用法:
+  next-server import-desktop --user <用户名> [--dir <目录>] [--todos <文件>] [--routines <文件>] [--reviews <文件>] [--dry-run]
+      导入桌面版的 todos.json / routines.json / reviews.json；--dir 下存在的文件会自动使用
+  next-server export-todos --user <用户名> [--format todotxt|markdown] [--tab <tab>] [--open-only] [--out <文件>]
+      导出任务为 todo.txt 或 Markdown 清单（默认输出到终端）
+  next-server import-todos --user <用户名> --file <文件> [--format todotxt|markdown] [--dry-run]
+      从 todo.txt 或 Markdown 清单导入任务；未指定格式时 .md 文件按 Markdown 处理";
+
+/// Run a command and return the process exit code
+pub fn run(db_path: &str, args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("import-desktop") => import_desktop(db_path, &args[1..]),
+        Some("export-todos") => export_todos(db_path, &args[1..]),
+        Some("import-todos") => import_todos(db_path, &args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        Some(other) => Err(format!("未知命令: {}\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            1
+        }
+    }
+}
+
+/// `--name value` pairs and bare `--flag`s
+struct Options {
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String], flags: &[&str]) -> Result<Self, String> {
+        let mut options = Options {
+            values: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                return Err(format!("无法识别的参数: {}", arg));
+            };
+            if flags.contains(&name) {
+                options.flags.push(name.to_string());
+                continue;
+            }
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("--{} 需要一个值", name))?;
+            options.values.push((name.to_string(), value.clone()));
+        }
+        Ok(options)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+}
+
+fn user_id(db: &Connection, username: &str) -> Result<String, String> {
+    db.query_row(
+        "SELECT id FROM users WHERE username = ?1",
+        [username],
+        |r| r.get(0),
+    )
+    .map_err(|_| format!("用户不存在: {}", username))
+}
+
+fn read_json_file(path: &Path) -> Result<DesktopFile, String> {
+    let raw =
+        std::fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+    // The desktop app treats an empty file as an empty list
+    if raw.trim().is_empty() {
+        return Ok(DesktopFile::default());
+    }
+    serde_json::from_str(&raw).map_err(|e| format!("{} 不是有效的 JSON: {}", path.display(), e))
+}
+
+fn import_desktop(db_path: &str, args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &["dry-run"])?;
+    let username = options
+        .get("user")
+        .ok_or_else(|| format!("缺少 --user\n{}", USAGE))?;
+
+    let dir = options.get("dir").map(PathBuf::from);
+    let file = |name: &str| -> Result<Option<DesktopFile>, String> {
+        let path = match (options.get(name), &dir) {
+            (Some(p), _) => PathBuf::from(p),
+            (None, Some(d)) if d.join(format!("{}.json", name)).exists() => {
+                d.join(format!("{}.json", name))
+            }
+            _ => return Ok(None),
+        };
+        read_json_file(&path).map(Some)
+    };
+    let req = DesktopImportRequest {
+        todos: file("todos")?,
+        routines: file("routines")?,
+        reviews: file("reviews")?,
+        dry_run: options.flag("dry-run"),
+    };
+    if req.todos.is_none() && req.routines.is_none() && req.reviews.is_none() {
+        return Err(format!("没有找到可导入的文件\n{}", USAGE));
+    }
+
+    let conn = db::init_db(db_path);
+    let user_id = user_id(&conn, username)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let report = desktop_import::run(&conn, &user_id, &req, &now)?;
+
+    for item in &report.items {
+        let mut line = format!(
+            "[{}] {} {} {}",
+            item.action, item.item_type, item.id, item.text
+        );
+        if let Some(id) = &item.new_id {
+            line.push_str(&format!(" → {}", id));
+        }
+        if let Some(id) = &item.existing_id {
+            line.push_str(&format!(" (已有 {})", id));
+        }
+        if !item.differences.is_empty() {
+            line.push_str(&format!(" 不同字段: {}", item.differences.join(", ")));
+        }
+        if let Some(msg) = &item.message {
+            line.push_str(&format!(" — {}", msg));
+        }
+        println!("{}", line);
+    }
+    for (name, counts) in [
+        ("todos", &report.todos),
+        ("routines", &report.routines),
+        ("reviews", &report.reviews),
+    ] {
+        println!(
+            "{}: 新建 {}，重复 {}，无效 {}",
+            name, counts.created, counts.duplicates, counts.invalid
+        );
+    }
+    if report.dry_run {
+        println!("（预览模式，未写入数据库）");
+    }
+    Ok(())
+}
+
+fn text_format(name: Option<&str>, default: TextFormat) -> Result<TextFormat, String> {
+    match name {
+        Some(f) => TextFormat::parse(f)
+            .ok_or_else(|| format!("不支持的格式: {}（可选: todotxt, markdown）", f)),
+        None => Ok(default),
+    }
+}
+
+fn export_todos(db_path: &str, args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &["open-only"])?;
+    let username = options
+        .get("user")
+        .ok_or_else(|| format!("缺少 --user\n{}", USAGE))?;
+    let format = text_format(options.get("format"), TextFormat::TodoTxt)?;
+
+    let conn = db::init_db(db_path);
+    let user_id = user_id(&conn, username)?;
+    let text = todo_text::export(
+        &conn,
+        &user_id,
+        format,
+        options.get("tab"),
+        !options.flag("open-only"),
+    );
+    match options.get("out") {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|e| format!("无法写入 {}: {}", path, e))?;
+            println!("已导出到 {}", path);
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn import_todos(db_path: &str, args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &["dry-run"])?;
+    let username = options
+        .get("user")
+        .ok_or_else(|| format!("缺少 --user\n{}", USAGE))?;
+    let path = options
+        .get("file")
+        .ok_or_else(|| format!("缺少 --file\n{}", USAGE))?;
+    let by_extension = if path.ends_with(".md") || path.ends_with(".markdown") {
+        TextFormat::Markdown
+    } else {
+        TextFormat::TodoTxt
+    };
+    let format = text_format(options.get("format"), by_extension)?;
+    let content = std::fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+
+    let mut items = todo_text::parse(format, &content);
+    if items.is_empty() {
+        return Err(format!("{} 中没有找到可导入的任务", path));
+    }
+    let conn = db::init_db(db_path);
+    let user_id = user_id(&conn, username)?;
+    todo_text::resolve_projects(&conn, &user_id, &mut items);
+    for todo in &items {
+        println!(
+            "{:>4}: [{}] {} ({} / {})",
+            todo.line,
+            if todo.completed { "x" } else { " " },
+            todo.text,
+            todo.tab,
+            todo.quadrant
+        );
+    }
+    if options.flag("dry-run") {
+        println!("（预览模式，将导入 {} 个任务，未写入数据库）", items.len());
+        return Ok(());
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let created = todo_text::import(&conn, &user_id, &items, &now)?;
+    println!("已导入 {} 个任务", created.len());
+    Ok(())
+}
@@ -23,6 +23,9 @@ pub(crate) fn init_connection(conn: &Connection) {
     conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
     create_tables(conn);
     run_migrations(conn);
+    create_search_index(conn);
+    create_sync_log(conn);
+    create_completion_log(conn);
 }
 
 fn run_migrations(conn: &Connection) {
@@ -42,6 +45,20 @@ fn run_migrations(conn: &Connection) {
             .ok();
     }
 
+    // Raw values and change-set id on todo_changelog, for replay/revert
+    let has_changelog_raw: bool = conn
+        .prepare("SELECT from_raw FROM todo_changelog LIMIT 1")
+        .is_ok();
+    if !has_changelog_raw {
+        conn.execute_batch(
+            "ALTER TABLE todo_changelog ADD COLUMN from_raw TEXT;
+             ALTER TABLE todo_changelog ADD COLUMN to_raw TEXT;
+             ALTER TABLE todo_changelog ADD COLUMN batch_id TEXT;
+             CREATE INDEX IF NOT EXISTS idx_changelog_batch ON todo_changelog(batch_id);",
+        )
+        .ok();
+    }
+
     // Add is_collaborative to todos
     let has_todo_collab: bool = conn
         .prepare("SELECT is_collaborative FROM todos LIMIT 1")
@@ -121,6 +138,350 @@ fn run_migrations(conn: &Connection) {
         conn.execute_batch("ALTER TABLE users ADD COLUMN ai_calls_remaining INTEGER DEFAULT NULL;")
             .ok();
     }
+
+    // Add recurrence rule and occurrence link to todos
+    let has_recurrence: bool = conn.prepare("SELECT recurrence FROM todos LIMIT 1").is_ok();
+    if !has_recurrence {
+        conn.execute_batch(
+            "ALTER TABLE todos ADD COLUMN recurrence TEXT;
+             ALTER TABLE todos ADD COLUMN recurrence_prev_id TEXT;",
+        )
+        .ok();
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_todos_recurrence_prev ON todos(recurrence_prev_id);",
+    )
+    .ok();
+
+    // Day of month a monthly series falls on, kept across short months
+    let has_recurrence_day: bool = conn
+        .prepare("SELECT recurrence_day FROM todos LIMIT 1")
+        .is_ok();
+    if !has_recurrence_day {
+        conn.execute_batch("ALTER TABLE todos ADD COLUMN recurrence_day INTEGER;")
+            .ok();
+    }
+
+    // Per-user switches for the horizon rollover job
+    let has_rollover: bool = conn
+        .prepare("SELECT auto_rollover FROM user_settings LIMIT 1")
+        .is_ok();
+    if !has_rollover {
+        conn.execute_batch(
+            "ALTER TABLE user_settings ADD COLUMN auto_rollover INTEGER DEFAULT 1;
+             ALTER TABLE user_settings ADD COLUMN escalate_overdue INTEGER DEFAULT 0;",
+        )
+        .ok();
+    }
+    // Moves the rollover job made, kept apart from the pruned changelog
+    let has_rollover_log: bool = conn
+        .prepare("SELECT todo_id FROM todo_rollovers LIMIT 1")
+        .is_ok();
+    if !has_rollover_log {
+        conn.execute_batch(
+            "CREATE TABLE todo_rollovers (
+                todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+                field TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (todo_id, field, value)
+             );
+             INSERT OR IGNORE INTO todo_rollovers (todo_id, field, value)
+             SELECT todo_id, field, to_raw FROM todo_changelog
+             WHERE changed_by = 'system' AND field IN ('tab', 'quadrant') AND to_raw IS NOT NULL
+             AND todo_id IN (SELECT id FROM todos);",
+        )
+        .ok();
+    }
+
+    // Link todos to a project
+    let has_project: bool = conn.prepare("SELECT project_id FROM todos LIMIT 1").is_ok();
+    if !has_project {
+        conn.execute_batch("ALTER TABLE todos ADD COLUMN project_id TEXT;")
+            .ok();
+    }
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_todos_project ON todos(project_id);")
+        .ok();
+
+    // Soft delete (trash) for expenses, trips and reviews
+    for table in ["expense_entries", "trips", "reviews"] {
+        let has_deleted: bool = conn
+            .prepare(&format!("SELECT deleted FROM {} LIMIT 1", table))
+            .is_ok();
+        if !has_deleted {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {0} ADD COLUMN deleted INTEGER DEFAULT 0;
+                 ALTER TABLE {0} ADD COLUMN deleted_at TEXT;",
+                table
+            ))
+            .ok();
+        }
+    }
+
+    // Per-user trash retention, in days (0 keeps trashed items forever)
+    let has_retention: bool = conn
+        .prepare("SELECT trash_retention_days FROM user_settings LIMIT 1")
+        .is_ok();
+    if !has_retention {
+        conn.execute_batch(
+            "ALTER TABLE user_settings ADD COLUMN trash_retention_days INTEGER DEFAULT 30;",
+        )
+        .ok();
+    }
+    // Routine schedule, see RoutineSchedule
+    let has_schedule: bool = conn
+        .prepare("SELECT schedule FROM routines LIMIT 1")
+        .is_ok();
+    if !has_schedule {
+        conn.execute_batch("ALTER TABLE routines ADD COLUMN schedule TEXT DEFAULT 'daily';")
+            .ok();
+    }
+}
+
+/// A table feeding the search index. `{r}` in the SQL fragments stands for
+/// the source row.
+struct SearchSource {
+    /// Trigger and `search_rows` key
+    name: &'static str,
+    table: &'static str,
+    module: &'static str,
+    title: &'static str,
+    body: &'static str,
+    parent: &'static str,
+    user: &'static str,
+    join: &'static str,
+    /// Rows indexed
+    cond: &'static str,
+    /// Columns whose update reindexes the row
+    columns: &'static str,
+}
+
+const SEARCH_SOURCES: [SearchSource; 5] = [
+    // Soft-deleted todos drop out of the index
+    SearchSource {
+        name: "todos",
+        table: "todos",
+        module: "todo",
+        title: "{r}.text",
+        body: "COALESCE({r}.content, '')",
+        parent: "{r}.id",
+        user: "{r}.user_id",
+        join: "",
+        cond: "{r}.deleted = 0",
+        columns: "text, content, deleted",
+    },
+    SearchSource {
+        name: "english",
+        table: "english_scenarios",
+        module: "english",
+        title: "{r}.title",
+        body: "COALESCE({r}.content, '')",
+        parent: "{r}.id",
+        user: "{r}.user_id",
+        join: "",
+        cond: "1",
+        columns: "title, content",
+    },
+    // Expense entry notes
+    SearchSource {
+        name: "expense",
+        table: "expense_entries",
+        module: "expense",
+        title: "COALESCE({r}.notes, '')",
+        body: "''",
+        parent: "{r}.id",
+        user: "{r}.user_id",
+        join: "",
+        cond: "1",
+        columns: "notes",
+    },
+    // Expense line items (parent = entry)
+    SearchSource {
+        name: "expense_item",
+        table: "expense_items",
+        module: "expense",
+        title: "{r}.name",
+        body: "''",
+        parent: "{r}.entry_id",
+        user: "e.user_id",
+        join: "JOIN expense_entries e ON e.id = {r}.entry_id",
+        cond: "1",
+        columns: "name",
+    },
+    // Trip items (parent = trip)
+    SearchSource {
+        name: "trip_item",
+        table: "trip_items",
+        module: "trip",
+        title: "{r}.description",
+        body: "''",
+        parent: "{r}.trip_id",
+        user: "t.user_id",
+        join: "JOIN trips t ON t.id = {r}.trip_id",
+        cond: "1",
+        columns: "description",
+    },
+];
+
+/// FTS5 search index over todos, English scenarios, expenses and trip items.
+/// Kept in sync by triggers; backfilled once when the index is first created.
+/// `search_rows` fixes each item's rowid, so triggers replace an item's entry
+/// by rowid instead of scanning the UNINDEXED columns.
+fn create_search_index(conn: &Connection) {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'search_rows'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+
+    if !exists {
+        // An index from before search_rows is rebuilt with the new triggers
+        let mut sql = "DROP TABLE IF EXISTS search_index;".to_string();
+        for SearchSource { name, .. } in SEARCH_SOURCES {
+            for suffix in ["ai", "au", "ad"] {
+                sql.push_str(&format!("DROP TRIGGER IF EXISTS search_{name}_{suffix};"));
+            }
+        }
+        if let Err(e) = conn.execute_batch(&sql) {
+            eprintln!("[db] search index reset failed: {}", e);
+            return;
+        }
+    }
+
+    let mut sql = "
+        CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            title,
+            body,
+            module UNINDEXED,
+            item_id UNINDEXED,
+            parent_id UNINDEXED,
+            user_id UNINDEXED,
+            tokenize = 'trigram'
+        );
+        CREATE TABLE IF NOT EXISTS search_rows (
+            id INTEGER PRIMARY KEY,
+            source TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            UNIQUE(source, item_id)
+        );
+        "
+    .to_string();
+    let mut backfill = String::new();
+    for SearchSource {
+        name,
+        table,
+        module,
+        title,
+        body,
+        parent,
+        user,
+        join,
+        cond,
+        columns,
+    } in SEARCH_SOURCES
+    {
+        let insert = |r: &str| {
+            format!(
+                "INSERT INTO search_index (rowid, title, body, module, item_id, parent_id, user_id)
+                 SELECT s.id, {title}, {body}, '{module}', {r}.id, {parent}, {user}
+                 FROM {from} {join}
+                 WHERE s.source = '{name}' AND s.item_id = {r}.id AND {cond};",
+                from = if r == "new" {
+                    "search_rows s".to_string()
+                } else {
+                    format!("{table} {r} JOIN search_rows s ON s.source = '{name}' AND s.item_id = {r}.id")
+                },
+            )
+            .replace("{r}", r)
+        };
+        let row_id =
+            format!("(SELECT id FROM search_rows WHERE source = '{name}' AND item_id = old.id)");
+        sql.push_str(&format!(
+            "CREATE TRIGGER IF NOT EXISTS search_{name}_ai AFTER INSERT ON {table} BEGIN
+                INSERT OR IGNORE INTO search_rows (source, item_id) VALUES ('{name}', new.id);
+                {insert}
+            END;
+            CREATE TRIGGER IF NOT EXISTS search_{name}_au AFTER UPDATE OF {columns} ON {table} BEGIN
+                DELETE FROM search_index WHERE rowid = {row_id};
+                INSERT OR IGNORE INTO search_rows (source, item_id) VALUES ('{name}', new.id);
+                {insert}
+            END;
+            CREATE TRIGGER IF NOT EXISTS search_{name}_ad AFTER DELETE ON {table} BEGIN
+                DELETE FROM search_index WHERE rowid = {row_id};
+                DELETE FROM search_rows WHERE source = '{name}' AND item_id = old.id;
+            END;
+            ",
+            insert = insert("new"),
+        ));
+        backfill.push_str(&format!(
+            "INSERT OR IGNORE INTO search_rows (source, item_id) SELECT '{name}', id FROM {table};
+            {}
+            ",
+            insert("src")
+        ));
+    }
+    if let Err(e) = conn.execute_batch(&sql) {
+        eprintln!("[db] search index setup failed: {}", e);
+        return;
+    }
+
+    if !exists {
+        conn.execute_batch(&backfill).ok();
+    }
+}
+
+/// Change log behind `GET /api/sync`. Every write to a synced table appends
+/// a row through triggers, so `seq` orders changes exactly as they were
+/// committed. Collaborator rows log under the collaborator's user id.
+fn create_sync_log(conn: &Connection) {
+    if let Err(e) = conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS sync_changes (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id TEXT NOT NULL,
+            record_type TEXT NOT NULL,
+            record_id TEXT NOT NULL,
+            op TEXT NOT NULL,
+            -- Seq of the insert folded into this row by compaction
+            created_seq INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_sync_changes_user ON sync_changes(user_id, seq);
+        CREATE INDEX IF NOT EXISTS idx_sync_changes_record ON sync_changes(record_type, record_id);
+        ",
+    ) {
+        eprintln!("[db] sync log setup failed: {}", e);
+        return;
+    }
+
+    // (table, record type, record id column)
+    for (table, record_type, id_col) in [
+        ("todos", "todo", "id"),
+        ("routines", "routine", "id"),
+        ("reviews", "review", "id"),
+        ("reminders", "reminder", "id"),
+        ("expense_entries", "expense", "id"),
+        ("todo_collaborators", "todo", "todo_id"),
+        ("routine_collaborators", "routine", "routine_id"),
+    ] {
+        let mut sql = String::new();
+        for (suffix, event, row, op) in [
+            ("ai", "INSERT", "new", "insert"),
+            ("au", "UPDATE", "new", "update"),
+            ("ad", "DELETE", "old", "delete"),
+        ] {
+            sql.push_str(&format!(
+                "CREATE TRIGGER IF NOT EXISTS sync_{table}_{suffix} AFTER {event} ON {table} BEGIN
+                    INSERT INTO sync_changes (user_id, record_type, record_id, op)
+                    VALUES ({row}.user_id, '{record_type}', {row}.{id_col}, '{op}');
+                END;
+                "
+            ));
+        }
+        if let Err(e) = conn.execute_batch(&sql) {
+            eprintln!("[db] sync triggers on {} failed: {}", table, e);
+        }
+    }
 }
 
 fn create_tables(conn: &Connection) {
@@ -173,6 +534,122 @@ fn create_tables(conn: &Connection) {
         );
         CREATE INDEX IF NOT EXISTS idx_changelog_todo ON todo_changelog(todo_id);
 
+        -- Todo checklist items (subtasks)
+        CREATE TABLE IF NOT EXISTS todo_checklist_items (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            text TEXT NOT NULL,
+            checked INTEGER DEFAULT 0,
+            checked_at TEXT,
+            sort_order REAL DEFAULT 0.0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_checklist_todo ON todo_checklist_items(todo_id, sort_order);
+
+        -- Todo dependencies (todo_id cannot start until blocker_id is done)
+        CREATE TABLE IF NOT EXISTS todo_dependencies (
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            blocker_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (todo_id, blocker_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_todo_deps_blocker ON todo_dependencies(blocker_id);
+
+        -- Files attached to todos (stored under the owner's upload directory)
+        CREATE TABLE IF NOT EXISTS todo_attachments (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            user_id TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            storage_path TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            mime_type TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_todo_attachments_todo ON todo_attachments(todo_id);
+
+        -- Discussion threads on todos; mentions is a JSON array of user ids
+        CREATE TABLE IF NOT EXISTS todo_comments (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            user_id TEXT NOT NULL,
+            body TEXT NOT NULL,
+            mentions TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_todo_comments_todo ON todo_comments(todo_id, created_at);
+
+        -- Time tracking; ended_at/duration_secs stay NULL while a timer runs
+        CREATE TABLE IF NOT EXISTS time_entries (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            duration_secs INTEGER,
+            note TEXT DEFAULT '',
+            source TEXT NOT NULL DEFAULT 'timer',
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_time_entries_user ON time_entries(user_id, started_at);
+        CREATE INDEX IF NOT EXISTS idx_time_entries_todo ON time_entries(todo_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_running ON time_entries(user_id) WHERE ended_at IS NULL;
+
+        -- Focus (Pomodoro) sessions; ended_at/actual_secs stay NULL while running
+        CREATE TABLE IF NOT EXISTS focus_sessions (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            todo_id TEXT REFERENCES todos(id) ON DELETE SET NULL,
+            planned_minutes INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            actual_secs INTEGER,
+            interrupted INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_focus_sessions_user ON focus_sessions(user_id, started_at);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_focus_sessions_running ON focus_sessions(user_id) WHERE ended_at IS NULL;
+
+        -- Projects: a grouping level above tags; todos link via todos.project_id
+        CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL REFERENCES users(id),
+            name TEXT NOT NULL,
+            color TEXT NOT NULL DEFAULT '#4A90D9',
+            status TEXT NOT NULL DEFAULT 'active',
+            deadline TEXT,
+            description TEXT DEFAULT '',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(user_id, name)
+        );
+
+        -- Todo templates: items is a JSON array of TemplateItem
+        CREATE TABLE IF NOT EXISTS todo_templates (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL REFERENCES users(id),
+            name TEXT NOT NULL,
+            description TEXT DEFAULT '',
+            items TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(user_id, name)
+        );
+
+        -- Saved todo views (named filter expressions)
+        CREATE TABLE IF NOT EXISTS saved_views (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL REFERENCES users(id),
+            name TEXT NOT NULL,
+            filter TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(user_id, name)
+        );
+
         -- Routines
         CREATE TABLE IF NOT EXISTS routines (
             id TEXT PRIMARY KEY,
@@ -531,6 +1008,66 @@ fn create_tables(conn: &Connection) {
     .expect("Failed to create tables");
 }
 
+/// History of routine and review completions for streaks and reports.
+/// `routines` and `reviews` only keep the latest completion, so triggers
+/// copy every one of them here, whichever path wrote it (API, sync push,
+/// imports). Unticking removes the completion it undid.
+fn create_completion_log(conn: &Connection) {
+    if let Err(e) = conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS review_completions (
+            id TEXT PRIMARY KEY,
+            review_id TEXT NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+            user_id TEXT NOT NULL,
+            completed_at TEXT NOT NULL,
+            UNIQUE(review_id, completed_at)
+        );
+        CREATE INDEX IF NOT EXISTS idx_review_comp_user ON review_completions(user_id, completed_at);
+
+        CREATE TRIGGER IF NOT EXISTS routine_done_ai AFTER INSERT ON routines
+        WHEN new.completed_today = 1 AND new.last_completed_date IS NOT NULL BEGIN
+            INSERT OR IGNORE INTO routine_completions (id, routine_id, user_id, completed_date, created_at)
+            VALUES (lower(hex(randomblob(4))), new.id, new.user_id, new.last_completed_date, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+        END;
+        CREATE TRIGGER IF NOT EXISTS routine_done_au AFTER UPDATE OF completed_today, last_completed_date ON routines
+        WHEN new.completed_today = 1 AND new.last_completed_date IS NOT NULL BEGIN
+            INSERT OR IGNORE INTO routine_completions (id, routine_id, user_id, completed_date, created_at)
+            VALUES (lower(hex(randomblob(4))), new.id, new.user_id, new.last_completed_date, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+        END;
+        CREATE TRIGGER IF NOT EXISTS routine_undone_au AFTER UPDATE OF completed_today, last_completed_date ON routines
+        WHEN new.completed_today = 0 AND new.last_completed_date IS NULL AND old.last_completed_date IS NOT NULL BEGIN
+            DELETE FROM routine_completions
+            WHERE routine_id = old.id AND user_id = old.user_id AND completed_date = old.last_completed_date;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS review_done_ai AFTER INSERT ON reviews
+        WHEN new.last_completed IS NOT NULL BEGIN
+            INSERT OR IGNORE INTO review_completions (id, review_id, user_id, completed_at)
+            VALUES (lower(hex(randomblob(4))), new.id, new.user_id, new.last_completed);
+        END;
+        CREATE TRIGGER IF NOT EXISTS review_done_au AFTER UPDATE OF last_completed ON reviews
+        WHEN new.last_completed IS NOT NULL AND new.last_completed IS NOT old.last_completed BEGIN
+            INSERT OR IGNORE INTO review_completions (id, review_id, user_id, completed_at)
+            VALUES (lower(hex(randomblob(4))), new.id, new.user_id, new.last_completed);
+        END;
+        CREATE TRIGGER IF NOT EXISTS review_undone_au AFTER UPDATE OF last_completed ON reviews
+        WHEN new.last_completed IS NULL AND old.last_completed IS NOT NULL BEGIN
+            DELETE FROM review_completions WHERE review_id = old.id AND completed_at = old.last_completed;
+        END;
+
+        -- Completions from before the log existed
+        INSERT OR IGNORE INTO routine_completions (id, routine_id, user_id, completed_date, created_at)
+        SELECT lower(hex(randomblob(4))), id, user_id, last_completed_date, created_at FROM routines
+        WHERE completed_today = 1 AND last_completed_date IS NOT NULL;
+        INSERT OR IGNORE INTO review_completions (id, review_id, user_id, completed_at)
+        SELECT lower(hex(randomblob(4))), id, user_id, last_completed FROM reviews
+        WHERE last_completed IS NOT NULL;
+        ",
+    ) {
+        eprintln!("[db] completion log setup failed: {}", e);
+    }
+}
+
 /// Daily backup: VACUUM INTO backup file
 pub fn daily_backup(conn: &Connection, backup_dir: &str) {
     fs::create_dir_all(backup_dir).ok();
@@ -35,6 +35,10 @@ pub fn build_app(state: state::AppState) -> Router {
         )
         .route("/counts", get(routes::todos::get_todo_counts))
         .route("/batch", put(routes::todos::batch_update_todos))
+        .route(
+            "/batch/{batch_id}/revert",
+            post(routes::todos::revert_batch),
+        )
         .route(
             "/{id}",
             get(routes::todos::get_todo)
@@ -42,9 +46,44 @@ pub fn build_app(state: state::AppState) -> Router {
                 .delete(routes::todos::delete_todo),
         )
         .route("/{id}/restore", post(routes::todos::restore_todo))
+        .route("/{id}/move", post(routes::todos::move_todo))
+        .route("/{id}/history", get(routes::todos::todo_history))
+        .route("/{id}/revert", post(routes::todos::revert_todo))
         .route(
             "/{id}/permanent",
             delete(routes::todos::permanent_delete_todo),
+        )
+        .route("/{id}/checklist", post(routes::todos::add_checklist_item))
+        .route(
+            "/{id}/checklist/reorder",
+            put(routes::todos::reorder_checklist),
+        )
+        .route(
+            "/{id}/checklist/{item_id}",
+            put(routes::todos::update_checklist_item).delete(routes::todos::delete_checklist_item),
+        )
+        .route(
+            "/{id}/attachments",
+            get(routes::todos::list_attachments)
+                .post(routes::todos::upload_attachments)
+                .layer(DefaultBodyLimit::max(50_000_000)),
+        )
+        .route(
+            "/{id}/attachments/{attachment_id}",
+            get(routes::todos::download_attachment).delete(routes::todos::delete_attachment),
+        )
+        .route(
+            "/{id}/comments",
+            get(routes::todos::list_comments).post(routes::todos::create_comment),
+        )
+        .route(
+            "/{id}/comments/{comment_id}",
+            put(routes::todos::update_comment).delete(routes::todos::delete_comment),
+        )
+        .route("/{id}/dependencies", post(routes::todos::add_dependency))
+        .route(
+            "/{id}/dependencies/{blocker_id}",
+            delete(routes::todos::remove_dependency),
         );
 
     let routine_routes = Router::new()
@@ -52,8 +91,21 @@ pub fn build_app(state: state::AppState) -> Router {
             "/",
             get(routes::routines::list_routines).post(routes::routines::create_routine),
         )
-        .route("/{id}", delete(routes::routines::delete_routine))
-        .route("/{id}/toggle", post(routes::routines::toggle_routine));
+        .route(
+            "/{id}",
+            put(routes::routines::update_routine).delete(routes::routines::delete_routine),
+        )
+        .route("/{id}/toggle", post(routes::routines::toggle_routine))
+        .route("/{id}/stats", get(routes::routines::get_routine_stats))
+        .route("/{id}/heatmap", get(routes::routines::get_routine_heatmap))
+        .route(
+            "/{id}/completions",
+            post(routes::routines::backfill_routine),
+        )
+        .route(
+            "/{id}/completions/{date}",
+            delete(routes::routines::remove_routine_completion),
+        );
 
     let review_routes = Router::new()
         .route(
@@ -62,7 +114,9 @@ pub fn build_app(state: state::AppState) -> Router {
         )
         .route(
             "/{id}",
-            put(routes::reviews::update_review).delete(routes::reviews::delete_review),
+            get(routes::reviews::get_review)
+                .put(routes::reviews::update_review)
+                .delete(routes::reviews::delete_review),
         )
         .route("/{id}/complete", post(routes::reviews::complete_review))
         .route("/{id}/uncomplete", post(routes::reviews::uncomplete_review));
@@ -205,6 +259,104 @@ pub fn build_app(state: state::AppState) -> Router {
             put(routes::contacts::update_contact).delete(routes::contacts::delete_contact),
         );
 
+    // Focus sessions
+    let focus_routes = Router::new()
+        .route("/current", get(routes::focus::current_session))
+        .route("/start", post(routes::focus::start_session))
+        .route("/stop", post(routes::focus::stop_session))
+        .route("/sessions", get(routes::focus::list_sessions))
+        .route("/sessions/{id}", delete(routes::focus::delete_session))
+        .route("/stats", get(routes::focus::get_stats));
+
+    // Weekly / monthly productivity reports
+    let report_routes = Router::new()
+        .route("/", get(routes::reports::get_report))
+        .route("/download", get(routes::reports::download_report));
+
+    // Time tracking
+    let time_routes = Router::new()
+        .route("/current", get(routes::time_entries::current_timer))
+        .route("/start", post(routes::time_entries::start_timer))
+        .route("/stop", post(routes::time_entries::stop_timer))
+        .route(
+            "/entries",
+            get(routes::time_entries::list_entries).post(routes::time_entries::create_entry),
+        )
+        .route("/entries/{id}", delete(routes::time_entries::delete_entry))
+        .route("/report", get(routes::time_entries::report));
+
+    // Projects
+    let project_routes = Router::new()
+        .route(
+            "/",
+            get(routes::projects::list_projects).post(routes::projects::create_project),
+        )
+        .route(
+            "/{id}",
+            get(routes::projects::get_project)
+                .put(routes::projects::update_project)
+                .delete(routes::projects::delete_project),
+        );
+
+    // Imports from other apps and formats
+    let import_routes = Router::new()
+        .route(
+            "/desktop",
+            post(routes::import::import_desktop).layer(DefaultBodyLimit::max(10_000_000)),
+        )
+        .route("/todotxt", post(routes::import::import_todotxt))
+        .route("/markdown", post(routes::import::import_markdown))
+        .route("/csv/preview", post(routes::import::preview_csv))
+        .route("/csv", post(routes::import::import_csv));
+    let export_routes = Router::new().route("/todos", get(routes::export::export_todos));
+
+    // Delta sync for offline clients
+    let sync_routes = Router::new()
+        .route("/", get(routes::sync::get_changes))
+        .route("/push", post(routes::sync::push_changes));
+
+    // Trash across todos, expenses, trips and reviews
+    let trash_routes = Router::new()
+        .route(
+            "/",
+            get(routes::trash::list_trash).delete(routes::trash::empty_trash),
+        )
+        .route("/{type}/{id}/restore", post(routes::trash::restore_item));
+
+    // Tags across todos and expenses
+    let tag_routes = Router::new()
+        .route("/", get(routes::tags::list_tags))
+        .route("/merge", post(routes::tags::merge_tags))
+        .route(
+            "/{name}",
+            put(routes::tags::rename_tag).delete(routes::tags::delete_tag),
+        );
+
+    // Todo templates
+    let template_routes = Router::new()
+        .route(
+            "/",
+            get(routes::templates::list_templates).post(routes::templates::create_template),
+        )
+        .route(
+            "/{id}",
+            get(routes::templates::get_template)
+                .put(routes::templates::update_template)
+                .delete(routes::templates::delete_template),
+        )
+        .route("/{id}/apply", post(routes::templates::apply_template));
+
+    // Saved todo views
+    let view_routes = Router::new()
+        .route(
+            "/",
+            get(routes::views::list_views).post(routes::views::create_view),
+        )
+        .route(
+            "/{id}",
+            put(routes::views::update_view).delete(routes::views::delete_view),
+        );
+
     let collaborate_routes = Router::new()
         .route(
             "/todos/{id}",
@@ -252,6 +404,17 @@ pub fn build_app(state: state::AppState) -> Router {
         .nest("/push", push_routes)
         .nest("/share", share_routes)
         .nest("/contacts", contacts_routes)
+        .nest("/views", view_routes)
+        .nest("/projects", project_routes)
+        .nest("/templates", template_routes)
+        .nest("/tags", tag_routes)
+        .nest("/trash", trash_routes)
+        .nest("/sync", sync_routes)
+        .nest("/import", import_routes)
+        .nest("/export", export_routes)
+        .nest("/time", time_routes)
+        .nest("/focus", focus_routes)
+        .nest("/reports", report_routes)
         .nest("/collaborate", collaborate_routes)
         .nest(
             "/admin",
@@ -262,6 +425,11 @@ pub fn build_app(state: state::AppState) -> Router {
                 .route("/users/{id}/reject", post(routes::admin::reject_user)),
         )
         .route("/moment", get(routes::moment::get_moment))
+        .route("/search", get(routes::search::search))
+        .route(
+            "/settings",
+            get(routes::settings::get_settings).put(routes::settings::update_settings),
+        )
         .route(
             "/uploads/{user_id}/{filename}",
             get(routes::expenses::serve_photo),
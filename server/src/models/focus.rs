@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// A Pomodoro-style focus session, optionally linked to a todo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub todo_id: Option<String>,
+    #[serde(default)]
+    pub todo_text: String,
+    pub planned_minutes: i64,
+    pub started_at: String,
+    /// None while the session is running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    /// Focused seconds; for a running session, up to now
+    pub actual_secs: i64,
+    /// Stopped before the planned length ran out
+    #[serde(default)]
+    pub interrupted: bool,
+    #[serde(default)]
+    pub running: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartFocusRequest {
+    #[serde(default)]
+    pub todo_id: Option<String>,
+    /// Defaults to 25
+    #[serde(default)]
+    pub planned_minutes: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StopFocusRequest {
+    /// Defaults to whether the planned length was reached
+    #[serde(default)]
+    pub interrupted: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FocusQuery {
+    /// YYYY-MM-DD, inclusive
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// Focus totals for one local day
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FocusDay {
+    pub date: String,
+    pub minutes: i64,
+    pub sessions: i64,
+    pub completed: i64,
+    pub interrupted: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusStats {
+    pub minutes: i64,
+    pub sessions: i64,
+    pub completed: i64,
+    pub interrupted: i64,
+    pub days: Vec<FocusDay>,
+}
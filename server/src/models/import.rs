@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `todos.json`, `routines.json` or `reviews.json` as written by the desktop
+/// app. Items stay raw so one malformed entry doesn't sink the whole file.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DesktopFile {
+    #[serde(default)]
+    pub items: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DesktopImportRequest {
+    #[serde(default)]
+    pub todos: Option<DesktopFile>,
+    #[serde(default)]
+    pub routines: Option<DesktopFile>,
+    #[serde(default)]
+    pub reviews: Option<DesktopFile>,
+    /// Only report what would happen
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Desktop `Todo`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesktopTodo {
+    #[serde(default)]
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tab: Option<String>,
+    #[serde(default)]
+    pub quadrant: Option<String>,
+    #[serde(default)]
+    pub progress: i64,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub changelog: Vec<DesktopChange>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+}
+
+/// Desktop `ChangeEntry`; older files use `from`/`to`/`time`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesktopChange {
+    #[serde(default)]
+    pub field: String,
+    #[serde(default, alias = "from")]
+    pub old_value: Value,
+    #[serde(default, alias = "to")]
+    pub new_value: Value,
+    #[serde(default, alias = "time")]
+    pub timestamp: String,
+}
+
+/// Desktop `Routine`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesktopRoutine {
+    #[serde(default)]
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub completed_today: bool,
+    #[serde(default)]
+    pub last_completed_date: Option<String>,
+    #[serde(default)]
+    pub created_at: String,
+    /// Same format as the server's; files from older versions have none
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+/// Desktop `ReviewItem`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesktopReview {
+    #[serde(default)]
+    pub id: String,
+    pub text: String,
+    pub frequency: String,
+    #[serde(default)]
+    pub frequency_config: Value,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub last_completed: Option<String>,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// What happened (or would happen) to one item of the files
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntry {
+    /// "todo", "routine" or "review"
+    #[serde(rename = "type")]
+    pub item_type: String,
+    /// Id in the desktop file
+    pub id: String,
+    pub text: String,
+    /// "create", "duplicate" or "invalid"
+    pub action: String,
+    /// Server id when it differs from the desktop one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_id: Option<String>,
+    /// Record a duplicate matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<String>,
+    /// Fields where the file disagrees with the matched record
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportCounts {
+    pub created: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DesktopImportReport {
+    pub dry_run: bool,
+    pub todos: ImportCounts,
+    pub routines: ImportCounts,
+    pub reviews: ImportCounts,
+    pub items: Vec<ImportEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextImportRequest {
+    /// Contents of a todo.txt or Markdown file
+    pub content: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A todo read from a todo.txt line or Markdown checklist item
+#[derive(Debug, Clone, Serialize)]
+pub struct TextTodo {
+    /// 1-based line in the imported file
+    pub line: usize,
+    pub text: String,
+    pub tab: String,
+    pub quadrant: String,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    pub completed: bool,
+    /// YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_on: Option<String>,
+    /// YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TextExportQuery {
+    /// "todotxt" (default) or "markdown"
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub tab: Option<String>,
+    /// Defaults to true
+    #[serde(default)]
+    pub include_completed: Option<bool>,
+}
+
+/// CSV header for each todo field; unmapped fields are left empty
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvMapping {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CsvImportRequest {
+    pub content: String,
+    /// Defaults to the mapping guessed from the headers
+    #[serde(default)]
+    pub mapping: Option<CsvMapping>,
+    /// Tool the file came from, noted in the changelog
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Import the valid rows even if some rows have errors
+    #[serde(default)]
+    pub skip_invalid: bool,
+}
+
+/// One data row as it would be imported
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvRow {
+    /// 1-based data row, not counting the header
+    pub row: usize,
+    pub text: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    pub tab: String,
+    pub quadrant: String,
+    pub tags: Vec<String>,
+    pub completed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvPreview {
+    pub headers: Vec<String>,
+    /// Mapping used for this preview
+    pub mapping: CsvMapping,
+    pub rows: Vec<CsvRow>,
+    pub valid: usize,
+    pub invalid: usize,
+}
@@ -3,9 +3,20 @@ pub mod contact;
 pub mod conversation;
 pub mod english;
 pub mod expense;
+pub mod focus;
 pub mod friend;
+pub mod import;
+pub mod project;
 pub mod reminder;
+pub mod report;
 pub mod review;
 pub mod routine;
+pub mod settings;
+pub mod sync;
+pub mod tag;
+pub mod template;
+pub mod time_entry;
 pub mod todo;
+pub mod trash;
 pub mod trip;
+pub mod view;
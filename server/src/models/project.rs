@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// A project groups todos above the level of tags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    /// "active", "paused", "done" or "archived"
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub progress: ProjectProgress,
+}
+
+/// Rollup over a project's (non-deleted) todos
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectProgress {
+    pub total: i64,
+    pub completed: i64,
+    pub overdue: i64,
+    /// Mean progress of the todos, completed ones counting as 100
+    pub percent: i64,
+    pub time_spent_secs: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProjectRequest {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub deadline: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdateProjectRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    /// An empty string clears the deadline
+    #[serde(default)]
+    pub deadline: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProjectListQuery {
+    /// Only projects with this status; archived ones are hidden by default
+    #[serde(default)]
+    pub status: Option<String>,
+}
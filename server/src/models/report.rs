@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::focus::FocusStats;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery {
+    /// "week" (default, Monday to Sunday) or "month"
+    #[serde(default)]
+    pub period: Option<String>,
+    /// Any day in the period, YYYY-MM-DD; defaults to today
+    #[serde(default)]
+    pub date: Option<String>,
+    /// Download format: "markdown" (default) or "html"
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TodoReport {
+    /// Created during the period
+    pub created: i64,
+    /// Completed during the period, whenever they were created
+    pub completed: i64,
+    /// Average hours from creation to completion of the completed ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_lead_time_hours: Option<f64>,
+    /// Open and past their due date at the end of the period
+    pub overdue: i64,
+    /// Completed during the period after their due date
+    pub completed_late: i64,
+}
+
+/// Open todos per quadrant at the end of one local day
+#[derive(Debug, Clone, Serialize)]
+pub struct QuadrantDay {
+    pub date: String,
+    pub counts: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuadrantReport {
+    /// Todos completed during the period, by quadrant
+    pub completed: BTreeMap<String, i64>,
+    /// Todos moved into each quadrant during the period
+    pub moved_in: BTreeMap<String, i64>,
+    pub days: Vec<QuadrantDay>,
+}
+
+/// How often one routine or review was done when it was due
+#[derive(Debug, Clone, Serialize)]
+pub struct HabitItem {
+    pub id: String,
+    pub text: String,
+    pub done: i64,
+    pub due: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HabitReport {
+    pub done: i64,
+    pub due: i64,
+    /// Percentage of due occurrences that were done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<f64>,
+    pub items: Vec<HabitItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// "week" or "month"
+    pub period: String,
+    /// First and last local day, inclusive
+    pub from: String,
+    pub to: String,
+    pub todos: TodoReport,
+    pub quadrants: QuadrantReport,
+    pub routines: HabitReport,
+    pub reviews: HabitReport,
+    pub focus: FocusStats,
+}
@@ -1,5 +1,108 @@
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate};
 use serde::{Deserialize, Serialize};
 
+/// Which days a routine is due. Stored as "daily", "weekdays:1,3,5"
+/// (ISO weekdays, 1 = Monday), "weekly:N" (any N days a week) or
+/// "every_other_day" (counted from the day it was created).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutineSchedule {
+    Daily,
+    Weekdays(Vec<u32>),
+    TimesPerWeek(u32),
+    EveryOtherDay,
+}
+
+impl RoutineSchedule {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "" | "daily" => Some(RoutineSchedule::Daily),
+            "every_other_day" => Some(RoutineSchedule::EveryOtherDay),
+            s => {
+                if let Some(days) = s.strip_prefix("weekdays:") {
+                    let days: BTreeSet<u32> = days
+                        .split(',')
+                        .map(|d| d.trim().parse().ok().filter(|d| (1..=7).contains(d)))
+                        .collect::<Option<_>>()?;
+                    return match days.len() {
+                        0 => None,
+                        7 => Some(RoutineSchedule::Daily),
+                        _ => Some(RoutineSchedule::Weekdays(days.into_iter().collect())),
+                    };
+                }
+                let n: u32 = s.strip_prefix("weekly:")?.trim().parse().ok()?;
+                match n {
+                    1..=6 => Some(RoutineSchedule::TimesPerWeek(n)),
+                    7 => Some(RoutineSchedule::Daily),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            RoutineSchedule::Daily => "daily".into(),
+            RoutineSchedule::Weekdays(days) => format!(
+                "weekdays:{}",
+                days.iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            RoutineSchedule::TimesPerWeek(n) => format!("weekly:{}", n),
+            RoutineSchedule::EveryOtherDay => "every_other_day".into(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        const NAMES: [&str; 7] = ["一", "二", "三", "四", "五", "六", "日"];
+        match self {
+            RoutineSchedule::Daily => "每天".into(),
+            RoutineSchedule::Weekdays(days) => format!(
+                "每周{}",
+                days.iter()
+                    .map(|d| NAMES[(*d as usize - 1) % 7])
+                    .collect::<Vec<_>>()
+                    .join("、")
+            ),
+            RoutineSchedule::TimesPerWeek(n) => format!("每周 {} 次", n),
+            RoutineSchedule::EveryOtherDay => "隔天".into(),
+        }
+    }
+
+    /// Whether `date` is a scheduled day. Any day may count towards a
+    /// times-per-week quota. `anchor` is the day the routine started.
+    pub fn is_scheduled(&self, date: NaiveDate, anchor: NaiveDate) -> bool {
+        match self {
+            RoutineSchedule::Daily | RoutineSchedule::TimesPerWeek(_) => true,
+            RoutineSchedule::Weekdays(days) => days.contains(&date.weekday().number_from_monday()),
+            RoutineSchedule::EveryOtherDay => (date - anchor).num_days().rem_euclid(2) == 0,
+        }
+    }
+
+    /// Whether the routine belongs on `date`'s list: a scheduled day, a
+    /// week whose quota isn't met yet, or a day it was done anyway
+    pub fn is_due(&self, date: NaiveDate, anchor: NaiveDate, done: &BTreeSet<NaiveDate>) -> bool {
+        if done.contains(&date) {
+            return true;
+        }
+        match self {
+            RoutineSchedule::TimesPerWeek(n) => {
+                let monday = week_start(date);
+                (done.range(monday..date).count() as u32) < *n
+            }
+            _ => self.is_scheduled(date, anchor),
+        }
+    }
+}
+
+/// Monday of the ISO week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Routine {
     pub id: String,
@@ -16,9 +119,98 @@ pub struct Routine {
     pub owner_name: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub owner_id: Option<String>,
+    /// Scheduled days done in a row (rest days neither count nor break
+    /// it), up to today or up to the last scheduled day while today is
+    /// still open. For times-per-week routines, the days done in
+    /// consecutive weeks that met the quota.
+    #[serde(default)]
+    pub current_streak: i64,
+    #[serde(default)]
+    pub longest_streak: i64,
+    /// See `RoutineSchedule`
+    #[serde(default = "default_schedule")]
+    pub schedule: String,
+    #[serde(default)]
+    pub schedule_label: String,
+    /// Scheduled for today, still short of this week's quota, or done today
+    #[serde(default)]
+    pub due_today: bool,
+}
+
+pub fn default_schedule() -> String {
+    "daily".into()
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateRoutineRequest {
     pub text: String,
+    /// Defaults to "daily"
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoutineRequest {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListRoutinesQuery {
+    /// "1"/"true" includes routines that have a rest day today
+    #[serde(default)]
+    pub all: Option<String>,
+}
+
+impl ListRoutinesQuery {
+    pub fn include_rest_days(&self) -> bool {
+        matches!(self.all.as_deref(), Some("1" | "true"))
+    }
+}
+
+/// Completion history of one routine for the requesting user
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutineStats {
+    pub routine_id: String,
+    pub schedule: String,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    /// Days done in total
+    pub total: i64,
+    /// Percentage of scheduled days done over the last 7/30/365 days,
+    /// counted from creation for younger routines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_7: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_30: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_365: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_completed_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeatmapDay {
+    pub date: String,
+    pub done: bool,
+    /// False on rest days; always true for times-per-week routines
+    pub scheduled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeatmapQuery {
+    /// YYYY-MM-DD, inclusive; defaults to the last 365 days
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// Mark a missed day as done
+#[derive(Debug, Deserialize)]
+pub struct BackfillRequest {
+    /// YYYY-MM-DD
+    pub date: String,
 }
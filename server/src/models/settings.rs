@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-user switches for background jobs
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSettings {
+    /// Move todos into a nearer tab as their due date approaches
+    pub auto_rollover: bool,
+    /// Move overdue todos into the urgent quadrant
+    pub escalate_overdue: bool,
+    /// Days a trashed item is kept before it is purged; 0 keeps it forever
+    pub trash_retention_days: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsRequest {
+    #[serde(default)]
+    pub auto_rollover: Option<bool>,
+    #[serde(default)]
+    pub escalate_overdue: Option<bool>,
+    #[serde(default)]
+    pub trash_retention_days: Option<i64>,
+}
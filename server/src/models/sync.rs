@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SyncQuery {
+    /// Cursor from the previous response; omit for a full snapshot
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One record that changed after the cursor
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncChange {
+    /// "todo", "routine", "review", "reminder" or "expense"
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub id: String,
+    /// "created", "updated" or "deleted"
+    pub op: String,
+    /// Version of the record; send it back as `base_seq` when pushing edits
+    pub seq: i64,
+    /// Current server copy; absent for tombstones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<Value>,
+}
+
+/// A queued offline edit
+#[derive(Debug, Deserialize)]
+pub struct SyncMutation {
+    #[serde(rename = "type")]
+    pub item_type: String,
+    /// "create", "update" or "delete"
+    pub op: String,
+    /// Required for update/delete; optional client-generated id on create
+    #[serde(default)]
+    pub id: Option<String>,
+    /// `seq` the client last saw; a newer server version is a conflict
+    #[serde(default)]
+    pub base_seq: Option<i64>,
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncPushRequest {
+    pub mutations: Vec<SyncMutation>,
+}
+
+/// Outcome of one mutation, in request order
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResult {
+    #[serde(rename = "type")]
+    pub item_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// "applied", "conflict", "not_found" or "invalid"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<i64>,
+    /// Server copy after the mutation, or the conflicting version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
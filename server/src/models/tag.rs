@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// A tag and how many live todos and expense entries carry it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub name: String,
+    pub todos: i64,
+    pub expenses: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameTagRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeTagsRequest {
+    /// Tags to fold into `into`
+    pub sources: Vec<String>,
+    pub into: String,
+}
+
+/// Rows touched by a rename, merge or delete
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TagChange {
+    pub todos: i64,
+    pub expenses: i64,
+    /// Changelog batch of the todo edits; revert with the todo undo API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
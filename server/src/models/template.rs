@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// A reusable set of todos, instantiated against an anchor date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub items: Vec<TemplateItem>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One todo of a template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateItem {
+    pub text: String,
+    #[serde(default)]
+    pub content: String,
+    /// Days from the anchor date to the due date; no due date when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_offset_days: Option<i64>,
+    #[serde(default = "default_quadrant")]
+    pub quadrant: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub checklist: Vec<String>,
+}
+
+fn default_quadrant() -> String {
+    "not-important-not-urgent".into()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub items: Vec<TemplateItem>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdateTemplateRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub items: Option<Vec<TemplateItem>>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ApplyTemplateRequest {
+    /// YYYY-MM-DD the offsets count from; defaults to today
+    #[serde(default)]
+    pub anchor_date: Option<String>,
+    /// Existing project (id or name) to file the new todos under
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Name of a new project to create for the todos
+    #[serde(default)]
+    pub new_project: Option<String>,
+}
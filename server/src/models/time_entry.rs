@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// A span of time spent on a todo, from a timer or entered by hand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: String,
+    pub todo_id: String,
+    #[serde(default)]
+    pub todo_text: String,
+    pub started_at: String,
+    /// None while the timer is running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    /// Elapsed seconds; for a running timer, up to now
+    pub duration_secs: i64,
+    #[serde(default)]
+    pub running: bool,
+    #[serde(default)]
+    pub note: String,
+    /// "timer" or "manual"
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartTimerRequest {
+    pub todo_id: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManualEntryRequest {
+    pub todo_id: String,
+    pub started_at: String,
+    /// Either an end time or a duration in minutes
+    #[serde(default)]
+    pub ended_at: Option<String>,
+    #[serde(default)]
+    pub minutes: Option<i64>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeEntryQuery {
+    #[serde(default)]
+    pub todo_id: Option<String>,
+    /// YYYY-MM-DD, inclusive
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeReportQuery {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    /// "day" (default) or "tag"
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// Total time for one day or tag in a report
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeReportRow {
+    pub key: String,
+    pub seconds: i64,
+    pub entries: i64,
+}
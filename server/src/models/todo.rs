@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -71,6 +71,15 @@ impl Quadrant {
             Quadrant::NotImportantNotUrgent => "短平快",
         }
     }
+
+    /// The urgent quadrant of the same importance, used when a todo is overdue
+    pub fn escalated(&self) -> Option<Quadrant> {
+        match self {
+            Quadrant::ImportantNotUrgent => Some(Quadrant::ImportantUrgent),
+            Quadrant::NotImportantNotUrgent => Some(Quadrant::NotImportantUrgent),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +92,16 @@ pub struct ChangeEntry {
     pub new_value: String,
     #[serde(alias = "time")]
     pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+    /// Groups entries written by one edit, so they can be undone together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Whether the entry carries raw values that can be replayed
+    #[serde(default)]
+    pub revertible: bool,
 }
 
 impl ChangeEntry {
@@ -94,6 +113,10 @@ impl ChangeEntry {
             old_value: old_value.to_string(),
             new_value: new_value.to_string(),
             timestamp: Utc::now().to_rfc3339(),
+            id: None,
+            changed_by: None,
+            batch_id: None,
+            revertible: false,
         }
     }
 }
@@ -130,6 +153,81 @@ pub struct Todo {
     pub deleted_at: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub next_reminder: Option<TodoReminder>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    /// Previous occurrence this todo was spawned from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_prev_id: Option<String>,
+    /// Project the owner filed this todo under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    /// Todos that must be completed before this one
+    #[serde(default)]
+    pub blocked_by: Vec<TodoLink>,
+    /// Todos waiting on this one
+    #[serde(default)]
+    pub blocking: Vec<TodoLink>,
+    /// True while any blocker is still open
+    #[serde(default)]
+    pub blocked: bool,
+    #[serde(default)]
+    pub attachment_count: i64,
+    /// Tracked time from all participants, running timers included
+    #[serde(default)]
+    pub time_spent_secs: i64,
+    /// Manual position within tab + quadrant (the viewer's own for shared todos)
+    #[serde(default)]
+    pub sort_order: f64,
+}
+
+/// Brief reference to another todo (used for dependencies)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoLink {
+    pub id: String,
+    pub text: String,
+    pub completed: bool,
+}
+
+/// A file attached to a todo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoAttachment {
+    pub id: String,
+    pub todo_id: String,
+    pub filename: String,
+    pub file_size: i64,
+    pub mime_type: String,
+    /// Uploader's user id
+    pub user_id: String,
+    pub created_at: String,
+    #[serde(skip)]
+    pub storage_path: String,
+}
+
+/// A comment in a todo's discussion thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoComment {
+    pub id: String,
+    pub todo_id: String,
+    pub user_id: String,
+    pub author_name: String,
+    #[serde(default)]
+    pub author_avatar: String,
+    pub body: String,
+    /// Participants mentioned with `@name`
+    #[serde(default)]
+    pub mentions: Vec<CommentMention>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub edited: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentMention {
+    pub user_id: String,
+    pub name: String,
 }
 
 /// Minimal reminder info attached to a todo card
@@ -140,6 +238,115 @@ pub struct TodoReminder {
     pub status: String,
 }
 
+/// Recurrence rule of a todo. Stored as "daily", "weekly", "monthly",
+/// "weekdays" or "every:N" (every N days).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+    Weekdays,
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => Some(Recurrence::Weekly),
+            "monthly" => Some(Recurrence::Monthly),
+            "weekdays" => Some(Recurrence::Weekdays),
+            _ => {
+                let n: u32 = s.strip_prefix("every:")?.parse().ok()?;
+                if (1..=365).contains(&n) {
+                    Some(Recurrence::EveryNDays(n))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            Recurrence::Daily => "daily".into(),
+            Recurrence::Weekly => "weekly".into(),
+            Recurrence::Monthly => "monthly".into(),
+            Recurrence::Weekdays => "weekdays".into(),
+            Recurrence::EveryNDays(n) => format!("every:{}", n),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Recurrence::Daily => "每天".into(),
+            Recurrence::Weekly => "每周".into(),
+            Recurrence::Monthly => "每月".into(),
+            Recurrence::Weekdays => "工作日".into(),
+            Recurrence::EveryNDays(n) => format!("每 {} 天", n),
+        }
+    }
+
+    /// The first occurrence date strictly after `date`. Monthly series fall
+    /// on `month_day`, or on the last day of shorter months.
+    pub fn next_after(&self, date: NaiveDate, month_day: u32) -> NaiveDate {
+        match self {
+            Recurrence::Daily => date + Duration::days(1),
+            Recurrence::Weekly => date + Duration::weeks(1),
+            Recurrence::Monthly => {
+                let this_month = clamp_month_day(date, month_day);
+                if this_month > date {
+                    return this_month;
+                }
+                date.with_day(1)
+                    .and_then(|first| first.checked_add_months(Months::new(1)))
+                    .map(|next_month| clamp_month_day(next_month, month_day))
+                    .unwrap_or(date + Duration::days(30))
+            }
+            Recurrence::Weekdays => {
+                let mut next = date + Duration::days(1);
+                while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+                    next += Duration::days(1);
+                }
+                next
+            }
+            Recurrence::EveryNDays(n) => date + Duration::days(*n as i64),
+        }
+    }
+}
+
+/// `day` of the month `date` falls in, or the month's last day when it is shorter
+pub fn clamp_month_day(date: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day.max(1))
+        .rev()
+        .find_map(|d| date.with_day(d))
+        .unwrap_or(date)
+}
+
+/// A checklist item (subtask) inside a todo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub checked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<String>,
+    #[serde(default)]
+    pub sort_order: f64,
+}
+
+impl ChecklistItem {
+    /// Progress derived from checked items, None when the checklist is empty
+    pub fn derive_progress(items: &[ChecklistItem]) -> Option<u8> {
+        if items.is_empty() {
+            return None;
+        }
+        let checked = items.iter().filter(|i| i.checked).count();
+        Some((checked * 100 / items.len()) as u8)
+    }
+}
+
 impl Todo {
     pub fn generate_id() -> String {
         uuid::Uuid::new_v4().to_string()[..8].to_string()
@@ -156,6 +363,12 @@ impl Todo {
             "tags" => "标签",
             "text" => "标题",
             "content" => "内容",
+            "checklist" => "清单",
+            "recurrence" => "重复",
+            "recurrence_spawn" => "生成下一次",
+            "dependency" => "前置任务",
+            "attachment" => "附件",
+            "imported" => "导入",
             _ => field,
         }
     }
@@ -172,6 +385,10 @@ pub struct TodoUpdate {
     pub due_date: Option<String>,
     pub assignee: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// Recurrence rule; an empty string clears it
+    pub recurrence: Option<String>,
+    /// Project id or name; an empty string removes the todo from its project
+    pub project_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -191,6 +408,51 @@ pub struct CreateTodoRequest {
     pub assignee: Option<String>,
     #[serde(default)]
     pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub checklist: Option<Vec<String>>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// Project id or name
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateChecklistItemRequest {
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdateChecklistItemRequest {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderChecklistRequest {
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevertRequest {
+    /// Changelog entry to rewind to (state right after it)
+    #[serde(default)]
+    pub entry_id: Option<i64>,
+    /// RFC 3339 timestamp to rewind to
+    #[serde(default)]
+    pub at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentRequest {
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddDependencyRequest {
+    pub blocker_id: String,
 }
 
 fn default_tab() -> String {
@@ -212,6 +474,20 @@ pub struct BatchUpdateItem {
     pub progress: Option<u8>,
     #[serde(default)]
     pub completed: Option<bool>,
+    /// Drop the todo right before this sibling (after tab/quadrant changes)
+    #[serde(default)]
+    pub before: Option<String>,
+    /// Drop the todo right after this sibling
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct MoveTodoRequest {
+    #[serde(default)]
+    pub before: Option<String>,
+    #[serde(default)]
+    pub after: Option<String>,
 }
 
 #[cfg(test)]
@@ -256,4 +532,77 @@ mod tests {
         assert_eq!(Quadrant::parse("invalid"), Quadrant::NotImportantNotUrgent);
         assert_eq!(Quadrant::parse(""), Quadrant::NotImportantNotUrgent);
     }
+
+    #[test]
+    fn test_recurrence_parse() {
+        assert_eq!(Recurrence::parse("weekly"), Some(Recurrence::Weekly));
+        assert_eq!(
+            Recurrence::parse("every:3"),
+            Some(Recurrence::EveryNDays(3))
+        );
+        assert_eq!(Recurrence::parse("every:0"), None);
+        assert_eq!(Recurrence::parse("yearly"), None);
+        assert_eq!(Recurrence::EveryNDays(3).as_string(), "every:3");
+    }
+
+    #[test]
+    fn test_recurrence_next_after() {
+        let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(
+            Recurrence::Daily.next_after(d("2025-12-31"), 31),
+            d("2026-01-01")
+        );
+        // A series started on the 31st keeps returning to the month's end
+        let mut date = d("2026-01-31");
+        let mut dates = Vec::new();
+        for _ in 0..4 {
+            date = Recurrence::Monthly.next_after(date, 31);
+            dates.push(date);
+        }
+        assert_eq!(
+            dates,
+            [
+                d("2026-02-28"),
+                d("2026-03-31"),
+                d("2026-04-30"),
+                d("2026-05-31")
+            ]
+        );
+        assert_eq!(
+            Recurrence::Monthly.next_after(d("2026-03-10"), 15),
+            d("2026-03-15")
+        );
+        // Friday → Monday
+        assert_eq!(
+            Recurrence::Weekdays.next_after(d("2026-03-06"), 6),
+            d("2026-03-09")
+        );
+        assert_eq!(
+            Recurrence::EveryNDays(10).next_after(d("2026-03-06"), 6),
+            d("2026-03-16")
+        );
+    }
+
+    fn item(checked: bool) -> ChecklistItem {
+        ChecklistItem {
+            id: Todo::generate_id(),
+            text: "step".into(),
+            checked,
+            checked_at: None,
+            sort_order: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_checklist_progress() {
+        assert_eq!(ChecklistItem::derive_progress(&[]), None);
+        assert_eq!(
+            ChecklistItem::derive_progress(&[item(true), item(false), item(false)]),
+            Some(33)
+        );
+        assert_eq!(
+            ChecklistItem::derive_progress(&[item(true), item(true)]),
+            Some(100)
+        );
+    }
 }
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// A soft-deleted todo, expense entry, trip or review
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashItem {
+    /// "todo", "expense", "trip" or "review"
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub id: String,
+    pub title: String,
+    pub deleted_at: String,
+    /// When the purge job will remove it; absent if kept forever
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purge_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TrashQuery {
+    #[serde(default, rename = "type")]
+    pub item_type: Option<String>,
+}
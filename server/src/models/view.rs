@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// A named todo filter saved by a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedView {
+    pub id: String,
+    pub name: String,
+    pub filter: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateViewRequest {
+    pub name: String,
+    pub filter: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateViewRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub filter: Option<String>,
+}
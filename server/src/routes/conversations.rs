@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::auth::{ActiveUserId, UserId};
+use crate::services::pagination::{self, PagePlan, PageQuery, SortField, SortKey};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -47,36 +48,73 @@ pub struct RenameRequest {
     pub title: String,
 }
 
+/// Sort options of `GET /api/conversations`; most recently active first by default
+const CONVERSATION_SORTS: [SortField<ConversationItem>; 3] = [
+    SortField {
+        name: "updated",
+        descending: true,
+        key: |c| vec![SortKey::text(&c.updated_at)],
+        columns: &["updated_at"],
+    },
+    SortField {
+        name: "created",
+        descending: true,
+        key: |c| vec![SortKey::text(&c.created_at)],
+        columns: &["created_at"],
+    },
+    SortField {
+        name: "title",
+        descending: false,
+        key: |c| vec![SortKey::text(c.title.as_deref().unwrap_or_default())],
+        columns: &["COALESCE(title, '')"],
+    },
+];
+
 /// GET /api/conversations — list user's conversations
 pub async fn list_conversations(
     State(state): State<AppState>,
     user_id: UserId,
+    Query(page_query): Query<PageQuery>,
 ) -> impl axum::response::IntoResponse {
+    let plan = match PagePlan::new(&page_query, &CONVERSATION_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"success": false, "message": msg})),
+            )
+        }
+    };
     let db = state.db.lock();
-
-    let mut stmt = db
-        .prepare(
-            "SELECT id, title, created_at, updated_at FROM conversations WHERE user_id=?1 AND is_archived=0 ORDER BY updated_at DESC LIMIT 50",
-        )
-        .unwrap();
-
-    let items: Vec<ConversationItem> = stmt
-        .query_map([&user_id.0], |row| {
+    let result = pagination::query_page(
+        &db,
+        "SELECT id, title, created_at, updated_at FROM conversations WHERE user_id=?1 AND is_archived=0",
+        &[&user_id.0],
+        &plan,
+        |row| {
             Ok(ConversationItem {
                 id: row.get(0)?,
                 title: row.get(1)?,
                 created_at: row.get(2)?,
                 updated_at: row.get(3)?,
             })
-        })
-        .unwrap()
-        .flatten()
-        .collect();
+        },
+        |c| &c.id,
+    );
 
-    (
-        StatusCode::OK,
-        Json(json!({"success": true, "items": items})),
-    )
+    match result {
+        Ok((items, page)) => (
+            StatusCode::OK,
+            Json(json!({"success": true, "items": items, "page": page})),
+        ),
+        Err(e) => {
+            eprintln!("[conversations] list DB error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"success": false, "message": "读取对话失败"})),
+            )
+        }
+    }
 }
 
 /// GET /api/conversations/:id/messages — get messages for a conversation
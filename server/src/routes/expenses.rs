@@ -1,7 +1,7 @@
 use axum::{
     body::Body,
     extract::{Multipart, Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -11,12 +11,18 @@ use serde_json::json;
 
 use crate::auth::{check_guest_ai_quota, ActiveUserId, UserId};
 use crate::models::expense::*;
+use crate::services::pagination::{self, PageInfo, PagePlan, PageQuery, SortField, SortKey};
+use crate::services::{etag, tags};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
 pub struct ExpenseListResponse {
     pub success: bool,
     pub entries: Vec<ExpenseEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -113,67 +119,97 @@ fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<ExpenseEntry> {
     })
 }
 
+/// Sort options of `GET /api/expenses`; newest spending first by default
+const ENTRY_SORTS: [SortField<ExpenseEntry>; 3] = [
+    SortField {
+        name: "date",
+        descending: true,
+        key: |e| vec![SortKey::text(&e.date), SortKey::text(&e.created_at)],
+        columns: &["date", "created_at"],
+    },
+    SortField {
+        name: "amount",
+        descending: true,
+        key: |e| vec![SortKey::Float(e.amount)],
+        columns: &["amount"],
+    },
+    SortField {
+        name: "created",
+        descending: true,
+        key: |e| vec![SortKey::text(&e.created_at)],
+        columns: &["created_at"],
+    },
+];
+
 // ===== List entries =====
 pub async fn list_entries(
     State(state): State<AppState>,
     user_id: UserId,
     Query(query): Query<ExpenseListQuery>,
+    Query(page_query): Query<PageQuery>,
 ) -> (StatusCode, Json<ExpenseListResponse>) {
+    let fail = |status: StatusCode, msg: String| {
+        (
+            status,
+            Json(ExpenseListResponse {
+                success: false,
+                entries: vec![],
+                message: Some(msg),
+                page: None,
+            }),
+        )
+    };
+    let plan = match PagePlan::new(&page_query, &ENTRY_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
     let db = state.db.lock();
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
     let from = query.from.unwrap_or_else(|| "2020-01-01".to_string());
     let to = query.to.unwrap_or(today);
+    let filter_tags: Vec<String> = query
+        .tags
+        .as_deref()
+        .map(|t| t.split(',').map(String::from).collect())
+        .unwrap_or_default();
 
-    let sql = "
+    let mut sql = "
         SELECT e.id, e.amount, e.date, e.notes, e.tags, e.ai_processed, e.currency, e.created_at, e.updated_at,
                (SELECT COUNT(*) FROM expense_photos WHERE entry_id = e.id) as photo_count,
                (SELECT COUNT(*) FROM expense_items WHERE entry_id = e.id) as item_count
         FROM expense_entries e
-        WHERE e.user_id = ?1 AND e.date >= ?2 AND e.date <= ?3
-        ORDER BY e.date DESC, e.created_at DESC
-    ";
-
-    let mut stmt = match db.prepare(sql) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("[Expense] list error: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ExpenseListResponse {
-                    success: false,
-                    entries: vec![],
-                }),
-            );
-        }
-    };
-
-    let entries: Vec<ExpenseEntry> = match stmt
-        .query_map(rusqlite::params![user_id.0, from, to], |row| {
-            row_to_entry(row)
-        }) {
-        Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
-        Err(e) => {
-            eprintln!("[Expense] query error: {}", e);
-            vec![]
-        }
-    };
-
+        WHERE e.user_id = ?1 AND e.deleted = 0 AND e.date >= ?2 AND e.date <= ?3
+    "
+    .to_string();
     // Filter by tags if specified
-    let entries = if let Some(tag_filter) = &query.tags {
-        let filter_tags: Vec<&str> = tag_filter.split(',').collect();
-        entries
-            .into_iter()
-            .filter(|e| filter_tags.iter().any(|ft| e.tags.iter().any(|t| t == ft)))
-            .collect()
-    } else {
-        entries
-    };
+    if !filter_tags.is_empty() {
+        let marks: Vec<String> = (0..filter_tags.len())
+            .map(|i| format!("?{}", i + 4))
+            .collect();
+        sql.push_str(&format!(
+            " AND EXISTS (SELECT 1 FROM json_each(e.tags) WHERE json_each.value IN ({}))",
+            marks.join(", ")
+        ));
+    }
+    let mut params: Vec<&dyn rusqlite::ToSql> = vec![&user_id.0, &from, &to];
+    params.extend(filter_tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+
+    let (entries, page) =
+        match pagination::query_page(&db, &sql, &params, &plan, row_to_entry, |e| &e.id) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[Expense] list error: {}", e);
+                return fail(StatusCode::INTERNAL_SERVER_ERROR, "读取记账失败".into());
+            }
+        };
 
     (
         StatusCode::OK,
         Json(ExpenseListResponse {
             success: true,
             entries,
+            message: None,
+            page: Some(page),
         }),
     )
 }
@@ -289,36 +325,22 @@ pub async fn create_entry(
     }
 }
 
-// ===== Get entry detail =====
-pub async fn get_entry(
-    State(state): State<AppState>,
-    user_id: UserId,
-    Path(id): Path<String>,
-) -> (StatusCode, Json<ExpenseDetailResponse>) {
-    let db = state.db.lock();
-
+/// Entry with its line items and photos
+fn load_entry_detail(
+    db: &rusqlite::Connection,
+    id: &str,
+    user_id: &str,
+) -> Option<ExpenseEntryDetail> {
     let entry = db.query_row(
         "SELECT e.id, e.amount, e.date, e.notes, e.tags, e.ai_processed, e.currency, e.created_at, e.updated_at,
                 (SELECT COUNT(*) FROM expense_photos WHERE entry_id = e.id),
                 (SELECT COUNT(*) FROM expense_items WHERE entry_id = e.id)
-         FROM expense_entries e WHERE e.id = ?1 AND e.user_id = ?2",
-        rusqlite::params![id, user_id.0],
+         FROM expense_entries e WHERE e.id = ?1 AND e.user_id = ?2 AND e.deleted = 0",
+        rusqlite::params![id, user_id],
         row_to_entry,
     );
 
-    let entry = match entry {
-        Ok(e) => e,
-        Err(_) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ExpenseDetailResponse {
-                    success: false,
-                    entry: None,
-                    message: Some("条目不存在".into()),
-                }),
-            );
-        }
-    };
+    let entry = entry.ok()?;
 
     // Get items
     let items: Vec<ExpenseItem> = db
@@ -365,15 +387,42 @@ pub async fn get_entry(
         })
         .unwrap_or_default();
 
+    Some(ExpenseEntryDetail {
+        entry,
+        items,
+        photos,
+    })
+}
+
+fn entry_not_found() -> (StatusCode, HeaderMap, Json<ExpenseDetailResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        HeaderMap::new(),
+        Json(ExpenseDetailResponse {
+            success: false,
+            entry: None,
+            message: Some("条目不存在".into()),
+        }),
+    )
+}
+
+// ===== Get entry detail =====
+pub async fn get_entry(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, HeaderMap, Json<ExpenseDetailResponse>) {
+    let db = state.db.lock();
+
+    let Some(detail) = load_entry_detail(&db, &id, &user_id.0) else {
+        return entry_not_found();
+    };
     (
         StatusCode::OK,
+        etag::headers(&detail.entry.updated_at),
         Json(ExpenseDetailResponse {
             success: true,
-            entry: Some(ExpenseEntryDetail {
-                entry,
-                items,
-                photos,
-            }),
+            entry: Some(detail),
             message: None,
         }),
     )
@@ -384,27 +433,24 @@ pub async fn update_entry(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<UpdateExpenseRequest>,
-) -> (StatusCode, Json<SimpleResponse>) {
+) -> (StatusCode, HeaderMap, Json<ExpenseDetailResponse>) {
     let db = state.db.lock();
     let now = chrono::Utc::now().to_rfc3339();
 
     // Check ownership
-    let exists: bool = db
-        .query_row(
-            "SELECT COUNT(*) FROM expense_entries WHERE id = ?1 AND user_id = ?2",
-            rusqlite::params![id, user_id.0],
-            |row| row.get::<_, i64>(0),
-        )
-        .unwrap_or(0)
-        > 0;
-
-    if !exists {
+    let Some(current) = load_entry_detail(&db, &id, &user_id.0) else {
+        return entry_not_found();
+    };
+    if !etag::matches(&headers, &current.entry.updated_at) {
         return (
-            StatusCode::NOT_FOUND,
-            Json(SimpleResponse {
+            StatusCode::PRECONDITION_FAILED,
+            etag::headers(&current.entry.updated_at),
+            Json(ExpenseDetailResponse {
                 success: false,
-                message: Some("条目不存在".into()),
+                entry: Some(current),
+                message: Some("条目已在其他地方被修改，请合并后重试".into()),
             }),
         );
     }
@@ -445,7 +491,7 @@ pub async fn update_entry(
     );
 
     // Build params dynamically using a trait-object vec
-    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now)];
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now.clone())];
     if let Some(amount) = req.amount {
         params.push(Box::new(amount));
     }
@@ -468,8 +514,10 @@ pub async fn update_entry(
     match db.execute(&sql, param_refs.as_slice()) {
         Ok(_) => (
             StatusCode::OK,
-            Json(SimpleResponse {
+            etag::headers(&now),
+            Json(ExpenseDetailResponse {
                 success: true,
+                entry: None,
                 message: None,
             }),
         ),
@@ -477,8 +525,10 @@ pub async fn update_entry(
             eprintln!("[Expense] update error: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(SimpleResponse {
+                HeaderMap::new(),
+                Json(ExpenseDetailResponse {
                     success: false,
+                    entry: None,
                     message: Some("更新失败".into()),
                 }),
             )
@@ -487,25 +537,18 @@ pub async fn update_entry(
 }
 
 // ===== Delete entry =====
+/// Moves the entry to the trash; photos stay until it is purged
 pub async fn delete_entry(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Path(id): Path<String>,
 ) -> (StatusCode, Json<SimpleResponse>) {
     let db = state.db.lock();
-
-    // Get photos to delete files
-    let photos: Vec<String> = db
-        .prepare("SELECT storage_path FROM expense_photos WHERE entry_id = ?1")
-        .and_then(|mut stmt| {
-            stmt.query_map(rusqlite::params![id], |row| row.get::<_, String>(0))
-                .map(|rows| rows.filter_map(|r| r.ok()).collect())
-        })
-        .unwrap_or_default();
+    let now = chrono::Utc::now().to_rfc3339();
 
     let result = db.execute(
-        "DELETE FROM expense_entries WHERE id = ?1 AND user_id = ?2",
-        rusqlite::params![id, user_id.0],
+        "UPDATE expense_entries SET deleted = 1, deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted = 0",
+        rusqlite::params![now, id, user_id.0],
     );
 
     match result {
@@ -516,19 +559,13 @@ pub async fn delete_entry(
                 message: Some("条目不存在".into()),
             }),
         ),
-        Ok(_) => {
-            // Delete photo files
-            for path in photos {
-                std::fs::remove_file(&path).ok();
-            }
-            (
-                StatusCode::OK,
-                Json(SimpleResponse {
-                    success: true,
-                    message: None,
-                }),
-            )
-        }
+        Ok(_) => (
+            StatusCode::OK,
+            Json(SimpleResponse {
+                success: true,
+                message: None,
+            }),
+        ),
         Err(e) => {
             eprintln!("[Expense] delete error: {}", e);
             (
@@ -583,7 +620,7 @@ pub async fn get_summary(
 
     let total_amount: f64 = db
         .query_row(
-            "SELECT COALESCE(SUM(amount), 0) FROM expense_entries WHERE user_id = ?1 AND date >= ?2 AND date <= ?3",
+            "SELECT COALESCE(SUM(amount), 0) FROM expense_entries WHERE user_id = ?1 AND deleted = 0 AND date >= ?2 AND date <= ?3",
             rusqlite::params![user_id.0, from, to],
             |row| row.get(0),
         )
@@ -591,7 +628,7 @@ pub async fn get_summary(
 
     let entry_count: i64 = db
         .query_row(
-            "SELECT COUNT(*) FROM expense_entries WHERE user_id = ?1 AND date >= ?2 AND date <= ?3",
+            "SELECT COUNT(*) FROM expense_entries WHERE user_id = ?1 AND deleted = 0 AND date >= ?2 AND date <= ?3",
             rusqlite::params![user_id.0, from, to],
             |row| row.get(0),
         )
@@ -601,7 +638,7 @@ pub async fn get_summary(
     let mut tag_map: std::collections::HashMap<String, (f64, i64)> =
         std::collections::HashMap::new();
     if let Ok(mut stmt) = db.prepare(
-        "SELECT tags, amount FROM expense_entries WHERE user_id = ?1 AND date >= ?2 AND date <= ?3",
+        "SELECT tags, amount FROM expense_entries WHERE user_id = ?1 AND deleted = 0 AND date >= ?2 AND date <= ?3",
     ) {
         if let Ok(rows) = stmt.query_map(rusqlite::params![user_id.0, from, to], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
@@ -649,22 +686,11 @@ pub async fn list_tags(
     user_id: UserId,
 ) -> (StatusCode, Json<TagsResponse>) {
     let db = state.db.lock();
-    let mut all_tags: Vec<String> = Vec::new();
-
-    if let Ok(mut stmt) = db.prepare("SELECT tags FROM expense_entries WHERE user_id = ?1") {
-        if let Ok(rows) =
-            stmt.query_map(rusqlite::params![user_id.0], |row| row.get::<_, String>(0))
-        {
-            for row in rows.flatten() {
-                let tags: Vec<String> = serde_json::from_str(&row).unwrap_or_default();
-                for tag in tags {
-                    if !all_tags.contains(&tag) {
-                        all_tags.push(tag);
-                    }
-                }
-            }
-        }
-    }
+    let all_tags: Vec<String> = tags::usage(&db, &user_id.0)
+        .into_iter()
+        .filter(|t| t.expenses > 0)
+        .map(|t| t.name)
+        .collect();
 
     (
         StatusCode::OK,
@@ -675,6 +701,18 @@ pub async fn list_tags(
     )
 }
 
+/// Directory holding a user's uploaded files, next to the database
+pub fn upload_dir(user_id: &str) -> String {
+    format!(
+        "{}/uploads/{}",
+        std::env::var("DATABASE_PATH")
+            .unwrap_or_else(|_| "data/next.db".to_string())
+            .replace("/next.db", "")
+            .replace("\\next.db", ""),
+        user_id
+    )
+}
+
 // ===== Upload photos =====
 pub async fn upload_photos(
     State(state): State<AppState>,
@@ -687,7 +725,7 @@ pub async fn upload_photos(
         let db = state.db.lock();
         let exists: bool = db
             .query_row(
-                "SELECT COUNT(*) FROM expense_entries WHERE id = ?1 AND user_id = ?2",
+                "SELECT COUNT(*) FROM expense_entries WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
                 rusqlite::params![entry_id, user_id.0],
                 |row| row.get::<_, i64>(0),
             )
@@ -702,14 +740,7 @@ pub async fn upload_photos(
     }
 
     // Ensure upload directory
-    let upload_dir = format!(
-        "{}/uploads/{}",
-        std::env::var("DATABASE_PATH")
-            .unwrap_or_else(|_| "data/next.db".to_string())
-            .replace("/next.db", "")
-            .replace("\\next.db", ""),
-        user_id.0
-    );
+    let upload_dir = upload_dir(&user_id.0);
     std::fs::create_dir_all(&upload_dir).ok();
 
     let mut uploaded: Vec<ExpensePhoto> = Vec::new();
@@ -846,7 +877,7 @@ pub async fn serve_photo(
             .query_row(
                 "SELECT COUNT(*) FROM trip_collaborators tc
                  JOIN trips t ON t.id = tc.trip_id
-                 WHERE tc.user_id = ?1 AND t.user_id = ?2",
+                 WHERE tc.user_id = ?1 AND t.user_id = ?2 AND t.deleted = 0",
                 rusqlite::params![user_id.0, path_user_id],
                 |row| row.get::<_, i64>(0),
             )
@@ -857,14 +888,7 @@ pub async fn serve_photo(
         }
     }
 
-    let upload_dir = format!(
-        "{}/uploads/{}",
-        std::env::var("DATABASE_PATH")
-            .unwrap_or_else(|_| "data/next.db".to_string())
-            .replace("/next.db", "")
-            .replace("\\next.db", ""),
-        path_user_id
-    );
+    let upload_dir = upload_dir(&path_user_id);
     let file_path = format!("{}/{}", upload_dir, filename);
 
     match tokio::fs::read(&file_path).await {
@@ -913,7 +937,7 @@ pub async fn parse_receipts(
         // Verify ownership
         let exists: bool = db
             .query_row(
-                "SELECT COUNT(*) FROM expense_entries WHERE id = ?1 AND user_id = ?2",
+                "SELECT COUNT(*) FROM expense_entries WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
                 rusqlite::params![entry_id, user_id.0],
                 |row| row.get::<_, i64>(0),
             )
@@ -1393,7 +1417,7 @@ pub async fn get_analytics(
     let mut entry_count: i64 = 0;
 
     if let Ok(mut stmt) = db.prepare(
-        "SELECT amount, date, tags FROM expense_entries WHERE user_id = ?1 AND date >= ?2 AND date <= ?3",
+        "SELECT amount, date, tags FROM expense_entries WHERE user_id = ?1 AND deleted = 0 AND date >= ?2 AND date <= ?3",
     ) {
         if let Ok(rows) = stmt.query_map(rusqlite::params![user_id.0, from, to], |row| {
             Ok((
@@ -0,0 +1,57 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::auth::UserId;
+use crate::models::import::TextExportQuery;
+use crate::services::todo_text::{self, TextFormat};
+use crate::state::AppState;
+
+// ─── Todos as todo.txt / Markdown ───
+
+pub async fn export_todos(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<TextExportQuery>,
+) -> Response {
+    let format = match query.format.as_deref().filter(|f| !f.is_empty()) {
+        None => TextFormat::TodoTxt,
+        Some(f) => match TextFormat::parse(f) {
+            Some(format) => format,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "success": false, "message": format!("不支持的格式: {}（可选: todotxt, markdown）", f) })),
+                )
+                    .into_response()
+            }
+        },
+    };
+    let tab = query.tab.as_deref().filter(|t| !t.is_empty());
+
+    let db = state.db.lock();
+    let text = todo_text::export(
+        &db,
+        &user_id.0,
+        format,
+        tab,
+        query.include_completed.unwrap_or(true),
+    );
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", format.file_name()),
+            ),
+        ],
+        Body::from(text),
+    )
+        .into_response()
+}
@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::focus::*;
+use crate::services::{focus, time_tracking};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct FocusResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<FocusSession>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FocusListResponse {
+    pub success: bool,
+    pub items: Vec<FocusSession>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FocusStatsResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FocusStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn session_result(
+    result: Result<FocusSession, String>,
+    error_status: StatusCode,
+) -> (StatusCode, Json<FocusResponse>) {
+    match result {
+        Ok(session) => (
+            StatusCode::OK,
+            Json(FocusResponse {
+                success: true,
+                item: Some(session),
+                message: None,
+            }),
+        ),
+        Err(msg) => (
+            error_status,
+            Json(FocusResponse {
+                success: false,
+                item: None,
+                message: Some(msg),
+            }),
+        ),
+    }
+}
+
+fn list_error(msg: String) -> (StatusCode, Json<FocusListResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(FocusListResponse {
+            success: false,
+            items: Vec::new(),
+            message: Some(msg),
+        }),
+    )
+}
+
+pub async fn current_session(
+    State(state): State<AppState>,
+    user_id: UserId,
+) -> (StatusCode, Json<FocusResponse>) {
+    let db = state.db.lock();
+    (
+        StatusCode::OK,
+        Json(FocusResponse {
+            success: true,
+            item: focus::current(&db, &user_id.0, chrono::Utc::now()),
+            message: None,
+        }),
+    )
+}
+
+pub async fn start_session(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    req: Option<Json<StartFocusRequest>>,
+) -> (StatusCode, Json<FocusResponse>) {
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+    let db = state.db.lock();
+    let now = chrono::Utc::now();
+    let error_status = if focus::current(&db, &user_id.0, now).is_some() {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    let result = focus::start(
+        &db,
+        &user_id.0,
+        req.todo_id.as_deref(),
+        req.planned_minutes,
+        now,
+    );
+    session_result(result, error_status)
+}
+
+pub async fn stop_session(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    req: Option<Json<StopFocusRequest>>,
+) -> (StatusCode, Json<FocusResponse>) {
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+    let db = state.db.lock();
+    let result = focus::stop(&db, &user_id.0, req.interrupted, chrono::Utc::now());
+    session_result(result, StatusCode::NOT_FOUND)
+}
+
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<FocusQuery>,
+) -> (StatusCode, Json<FocusListResponse>) {
+    let range = match time_tracking::day_range(query.from.as_deref(), query.to.as_deref()) {
+        Ok(r) => r,
+        Err(msg) => return list_error(msg),
+    };
+    let db = state.db.lock();
+    let items = focus::list(&db, &user_id.0, &range, chrono::Utc::now());
+    (
+        StatusCode::OK,
+        Json(FocusListResponse {
+            success: true,
+            items,
+            message: None,
+        }),
+    )
+}
+
+pub async fn delete_session(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<FocusResponse>) {
+    let db = state.db.lock();
+    if focus::delete(&db, &user_id.0, &id) {
+        (
+            StatusCode::OK,
+            Json(FocusResponse {
+                success: true,
+                item: None,
+                message: Some("记录已删除".into()),
+            }),
+        )
+    } else {
+        session_result(Err("记录不存在".into()), StatusCode::NOT_FOUND)
+    }
+}
+
+pub async fn get_stats(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<FocusQuery>,
+) -> (StatusCode, Json<FocusStatsResponse>) {
+    let range = match time_tracking::day_range(query.from.as_deref(), query.to.as_deref()) {
+        Ok(r) => r,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(FocusStatsResponse {
+                    success: false,
+                    stats: None,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
+    let db = state.db.lock();
+    let stats = focus::stats(&db, &user_id.0, &range, chrono::Utc::now());
+    (
+        StatusCode::OK,
+        Json(FocusStatsResponse {
+            success: true,
+            stats: Some(stats),
+            message: None,
+        }),
+    )
+}
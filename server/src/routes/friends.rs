@@ -9,6 +9,9 @@ use std::collections::HashMap;
 
 use crate::auth::{reject_if_guest, ActiveUserId, UserId};
 use crate::models::friend::*;
+use crate::models::template::TemplateItem;
+use crate::services::pagination::{self, PageInfo, PagePlan, PageQuery, SortField, SortKey};
+use crate::services::{ordering, templates};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -33,6 +36,8 @@ pub struct SharedItemsResponse {
     pub items: Vec<SharedItem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -508,7 +513,7 @@ pub async fn share_item(
         }
         "review" => {
             db.query_row(
-                "SELECT id, text, frequency, frequency_config, notes, category FROM reviews WHERE id = ?1 AND user_id = ?2",
+                "SELECT id, text, frequency, frequency_config, notes, category FROM reviews WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
                 rusqlite::params![req.item_id, user_id.0],
                 |row| {
                     Ok(json!({
@@ -557,7 +562,7 @@ pub async fn share_item(
         }
         "expense" => {
             db.query_row(
-                "SELECT id, amount, date, notes, tags, currency FROM expense_entries WHERE id = ?1 AND user_id = ?2",
+                "SELECT id, amount, date, notes, tags, currency FROM expense_entries WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
                 rusqlite::params![req.item_id, user_id.0],
                 |row| {
                     Ok(json!({
@@ -572,6 +577,14 @@ pub async fn share_item(
             )
             .ok()
         }
+        "template" => templates::find(&db, &user_id.0, &req.item_id).map(|t| {
+            json!({
+                "id": t.id,
+                "name": t.name,
+                "description": t.description,
+                "items": t.items
+            })
+        }),
         _ => {
             return (
                 StatusCode::BAD_REQUEST,
@@ -616,35 +629,48 @@ pub async fn share_item(
     )
 }
 
+/// Sort options of `GET /api/share/inbox`; newest first by default
+const INBOX_SORTS: [SortField<SharedItem>; 1] = [SortField {
+    name: "created",
+    descending: true,
+    key: |s| vec![SortKey::text(&s.created_at)],
+    columns: &["created_at"],
+}];
+
 pub async fn shared_inbox(
     State(state): State<AppState>,
     user_id: UserId,
     Query(params): Query<HashMap<String, String>>,
+    Query(page_query): Query<PageQuery>,
 ) -> (StatusCode, Json<SharedItemsResponse>) {
+    let fail = |status, message: String| {
+        (
+            status,
+            Json(SharedItemsResponse {
+                success: false,
+                items: Vec::new(),
+                message: Some(message),
+                page: None,
+            }),
+        )
+    };
+    let plan = match PagePlan::new(&page_query, &INBOX_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
     let db = state.db.lock();
     let item_type_filter = params.get("type").cloned().unwrap_or_default();
 
-    let (sql, use_filter) = if item_type_filter.is_empty() {
-        (
-            "SELECT s.id, s.sender_id, u.display_name, u.username, s.recipient_id, s.item_type, s.item_id, s.item_snapshot, s.message, s.status, s.created_at
-             FROM shared_items s
-             JOIN users u ON s.sender_id = u.id
-             WHERE s.recipient_id = ?1 AND s.status IN ('unread', 'read')
-             ORDER BY s.created_at DESC".to_string(),
-            false,
-        )
-    } else {
-        (
-            "SELECT s.id, s.sender_id, u.display_name, u.username, s.recipient_id, s.item_type, s.item_id, s.item_snapshot, s.message, s.status, s.created_at
+    let mut sql = "SELECT s.id, s.sender_id, u.display_name, u.username, s.recipient_id, s.item_type, s.item_id, s.item_snapshot, s.message, s.status, s.created_at
              FROM shared_items s
              JOIN users u ON s.sender_id = u.id
-             WHERE s.recipient_id = ?1 AND s.status IN ('unread', 'read') AND s.item_type = ?2
-             ORDER BY s.created_at DESC".to_string(),
-            true,
-        )
-    };
-
-    let mut stmt = db.prepare(&sql).unwrap();
+             WHERE s.recipient_id = ?1 AND s.status IN ('unread', 'read')"
+        .to_string();
+    let mut sql_params: Vec<&dyn rusqlite::types::ToSql> = vec![&user_id.0];
+    if !item_type_filter.is_empty() {
+        sql.push_str(" AND s.item_type = ?2");
+        sql_params.push(&item_type_filter);
+    }
 
     let row_mapper = |row: &rusqlite::Row| -> rusqlite::Result<SharedItem> {
         let display_name: Option<String> = row.get(2)?;
@@ -666,26 +692,21 @@ pub async fn shared_inbox(
         })
     };
 
-    let items: Vec<SharedItem> = if use_filter {
-        stmt.query_map(rusqlite::params![user_id.0, item_type_filter], &row_mapper)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
-    } else {
-        stmt.query_map([&user_id.0], &row_mapper)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
-    };
-
-    (
-        StatusCode::OK,
-        Json(SharedItemsResponse {
-            success: true,
-            items,
-            message: None,
-        }),
-    )
+    match pagination::query_page(&db, &sql, &sql_params, &plan, row_mapper, |s| &s.id) {
+        Ok((items, page)) => (
+            StatusCode::OK,
+            Json(SharedItemsResponse {
+                success: true,
+                items,
+                message: None,
+                page: Some(page),
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[friends] shared_inbox DB error: {}", e);
+            fail(StatusCode::INTERNAL_SERVER_ERROR, "读取分享失败".into())
+        }
+    }
 }
 
 pub async fn shared_inbox_count(
@@ -751,10 +772,11 @@ pub async fn accept_shared(
                 .as_str()
                 .unwrap_or("not-important-not-urgent");
             let tags = snapshot["tags"].as_str().unwrap_or("[]");
+            let sort_order = ordering::append_key(&db, &user_id.0, tab, quadrant);
 
             db.execute(
-                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, tags, sort_order, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 0, ?7, 0.0, ?8, ?9)",
-                rusqlite::params![new_id, user_id.0, text, content, tab, quadrant, tags, now, now],
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, tags, sort_order, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 0, ?7, ?8, ?9, ?10)",
+                rusqlite::params![new_id, user_id.0, text, content, tab, quadrant, tags, sort_order, now, now],
             )
             .ok();
         }
@@ -806,6 +828,20 @@ pub async fn accept_shared(
             )
             .ok();
         }
+        "template" => {
+            let name = snapshot["name"].as_str().unwrap_or("(分享的模板)");
+            let description = snapshot["description"].as_str().unwrap_or("");
+            let items: Vec<TemplateItem> =
+                serde_json::from_value(snapshot["items"].clone()).unwrap_or_default();
+            if let Err(msg) = templates::validate(name, description, &items)
+                .and_then(|fields| templates::insert(&db, &new_id, &user_id.0, &fields, true, &now))
+            {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"success": false, "message": msg})),
+                );
+            }
+        }
         _ => {}
     }
 
@@ -0,0 +1,243 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::auth::ActiveUserId;
+use crate::models::import::*;
+use crate::services::todo_text::{self, TextFormat};
+use crate::services::{csv_import, desktop_import};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct DesktopImportResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<DesktopImportReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// ─── Desktop app JSON files ───
+
+pub async fn import_desktop(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<DesktopImportRequest>,
+) -> (StatusCode, Json<DesktopImportResponse>) {
+    if req.todos.is_none() && req.routines.is_none() && req.reviews.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(DesktopImportResponse {
+                success: false,
+                report: None,
+                message: Some("请提供 todos、routines 或 reviews 文件内容".into()),
+            }),
+        );
+    }
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    match desktop_import::run(&db, &user_id.0, &req, &now) {
+        Ok(report) => {
+            let total = report.todos.created + report.routines.created + report.reviews.created;
+            let skipped = report.items.len() - total;
+            let message = if report.dry_run {
+                format!("预览：将导入 {} 项，跳过 {} 项", total, skipped)
+            } else {
+                format!("已导入 {} 项，跳过 {} 项", total, skipped)
+            };
+            (
+                StatusCode::OK,
+                Json(DesktopImportResponse {
+                    success: true,
+                    report: Some(report),
+                    message: Some(message),
+                }),
+            )
+        }
+        Err(msg) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DesktopImportResponse {
+                success: false,
+                report: None,
+                message: Some(msg),
+            }),
+        ),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TextImportResponse {
+    pub success: bool,
+    pub dry_run: bool,
+    pub items: Vec<TextTodo>,
+    /// Ids of the created todos
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub created: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn import_text(
+    state: &AppState,
+    user_id: &str,
+    format: TextFormat,
+    req: &TextImportRequest,
+) -> (StatusCode, Json<TextImportResponse>) {
+    let mut items = todo_text::parse(format, &req.content);
+    let fail = |status, message: String| {
+        (
+            status,
+            Json(TextImportResponse {
+                success: false,
+                dry_run: req.dry_run,
+                items: Vec::new(),
+                created: Vec::new(),
+                message: Some(message),
+            }),
+        )
+    };
+    if items.is_empty() {
+        return fail(StatusCode::BAD_REQUEST, "没有找到可导入的任务".into());
+    }
+
+    let db = state.db.lock();
+    todo_text::resolve_projects(&db, user_id, &mut items);
+    if req.dry_run {
+        let count = items.len();
+        return (
+            StatusCode::OK,
+            Json(TextImportResponse {
+                success: true,
+                dry_run: true,
+                items,
+                created: Vec::new(),
+                message: Some(format!("预览：将导入 {} 个任务", count)),
+            }),
+        );
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    match todo_text::import(&db, user_id, &items, &now) {
+        Ok(created) => (
+            StatusCode::OK,
+            Json(TextImportResponse {
+                success: true,
+                dry_run: false,
+                message: Some(format!("已导入 {} 个任务", created.len())),
+                items,
+                created,
+            }),
+        ),
+        Err(msg) => fail(StatusCode::INTERNAL_SERVER_ERROR, msg),
+    }
+}
+
+// ─── todo.txt ───
+
+pub async fn import_todotxt(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<TextImportRequest>,
+) -> (StatusCode, Json<TextImportResponse>) {
+    import_text(&state, &user_id.0, TextFormat::TodoTxt, &req)
+}
+
+// ─── Markdown checklist ───
+
+pub async fn import_markdown(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<TextImportRequest>,
+) -> (StatusCode, Json<TextImportResponse>) {
+    import_text(&state, &user_id.0, TextFormat::Markdown, &req)
+}
+
+// ─── CSV from other task managers ───
+
+#[derive(Debug, Serialize)]
+pub struct CsvImportResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<CsvPreview>,
+    /// Ids of the created todos
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub created: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn csv_error(
+    status: StatusCode,
+    preview: Option<CsvPreview>,
+    message: String,
+) -> (StatusCode, Json<CsvImportResponse>) {
+    (
+        status,
+        Json(CsvImportResponse {
+            success: false,
+            preview,
+            created: Vec::new(),
+            message: Some(message),
+        }),
+    )
+}
+
+pub async fn preview_csv(
+    _user_id: ActiveUserId,
+    Json(req): Json<CsvImportRequest>,
+) -> (StatusCode, Json<CsvImportResponse>) {
+    match csv_import::preview(&req.content, req.mapping.as_ref()) {
+        Ok(preview) => {
+            let message = format!("可导入 {} 行，{} 行有错误", preview.valid, preview.invalid);
+            (
+                StatusCode::OK,
+                Json(CsvImportResponse {
+                    success: true,
+                    preview: Some(preview),
+                    created: Vec::new(),
+                    message: Some(message),
+                }),
+            )
+        }
+        Err(msg) => csv_error(StatusCode::BAD_REQUEST, None, msg),
+    }
+}
+
+pub async fn import_csv(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<CsvImportRequest>,
+) -> (StatusCode, Json<CsvImportResponse>) {
+    let preview = match csv_import::preview(&req.content, req.mapping.as_ref()) {
+        Ok(p) => p,
+        Err(msg) => return csv_error(StatusCode::BAD_REQUEST, None, msg),
+    };
+    if preview.invalid > 0 && !req.skip_invalid {
+        let message = format!("{} 行有错误，请修正或选择跳过这些行", preview.invalid);
+        return csv_error(StatusCode::BAD_REQUEST, Some(preview), message);
+    }
+    if preview.valid == 0 {
+        return csv_error(
+            StatusCode::BAD_REQUEST,
+            Some(preview),
+            "没有找到可导入的任务".into(),
+        );
+    }
+
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    match csv_import::import(&db, &user_id.0, &preview.rows, req.source.as_deref(), &now) {
+        Ok(created) => (
+            StatusCode::OK,
+            Json(CsvImportResponse {
+                success: true,
+                message: Some(format!(
+                    "已导入 {} 个任务，跳过 {} 行",
+                    created.len(),
+                    preview.invalid
+                )),
+                preview: Some(preview),
+                created,
+            }),
+        ),
+        Err(msg) => csv_error(StatusCode::INTERNAL_SERVER_ERROR, Some(preview), msg),
+    }
+}
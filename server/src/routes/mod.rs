@@ -5,14 +5,27 @@ pub mod contacts;
 pub mod conversations;
 pub mod english;
 pub mod expenses;
+pub mod export;
+pub mod focus;
 pub mod friends;
+pub mod import;
 pub mod moment;
 pub mod notifications;
+pub mod projects;
 pub mod push;
 pub mod quotes;
 pub mod reminders;
+pub mod reports;
 pub mod reviews;
 pub mod routine_collab;
 pub mod routines;
+pub mod search;
+pub mod settings;
+pub mod sync;
+pub mod tags;
+pub mod templates;
+pub mod time_entries;
 pub mod todos;
+pub mod trash;
 pub mod trips;
+pub mod views;
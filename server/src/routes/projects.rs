@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::project::*;
+use crate::services::projects;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct ProjectsResponse {
+    pub success: bool,
+    pub items: Vec<Project>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<Project>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn project_error(status: StatusCode, message: String) -> (StatusCode, Json<ProjectResponse>) {
+    (
+        status,
+        Json(ProjectResponse {
+            success: false,
+            item: None,
+            message: Some(message),
+        }),
+    )
+}
+
+// ─── List projects ───
+
+pub async fn list_projects(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<ProjectListQuery>,
+) -> (StatusCode, Json<ProjectsResponse>) {
+    let status = query.status.as_deref().filter(|s| !s.is_empty());
+    if let Some(s) = status {
+        if !projects::STATUSES.contains(&s) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ProjectsResponse {
+                    success: false,
+                    items: Vec::new(),
+                    message: Some(format!("无效的项目状态: {}", s)),
+                }),
+            );
+        }
+    }
+    let db = state.db.lock();
+    (
+        StatusCode::OK,
+        Json(ProjectsResponse {
+            success: true,
+            items: projects::list(&db, &user_id.0, status),
+            message: None,
+        }),
+    )
+}
+
+// ─── Get project ───
+
+pub async fn get_project(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ProjectResponse>) {
+    let db = state.db.lock();
+    match projects::get(&db, &user_id.0, &id) {
+        Some(project) => (
+            StatusCode::OK,
+            Json(ProjectResponse {
+                success: true,
+                item: Some(project),
+                message: None,
+            }),
+        ),
+        None => project_error(StatusCode::NOT_FOUND, "项目不存在".into()),
+    }
+}
+
+// ─── Create project ───
+
+pub async fn create_project(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<CreateProjectRequest>,
+) -> (StatusCode, Json<ProjectResponse>) {
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    match projects::create(&db, &user_id.0, &req, &now) {
+        Ok(project) => (
+            StatusCode::OK,
+            Json(ProjectResponse {
+                success: true,
+                item: Some(project),
+                message: Some("项目已创建".into()),
+            }),
+        ),
+        Err(msg) => project_error(StatusCode::BAD_REQUEST, msg),
+    }
+}
+
+// ─── Update project ───
+
+pub async fn update_project(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateProjectRequest>,
+) -> (StatusCode, Json<ProjectResponse>) {
+    let db = state.db.lock();
+    let Some(project) = projects::get(&db, &user_id.0, &id) else {
+        return project_error(StatusCode::NOT_FOUND, "项目不存在".into());
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    match projects::update(&db, &user_id.0, &project, &req, &now) {
+        Ok(project) => (
+            StatusCode::OK,
+            Json(ProjectResponse {
+                success: true,
+                item: Some(project),
+                message: Some("项目已更新".into()),
+            }),
+        ),
+        Err(msg) => project_error(StatusCode::BAD_REQUEST, msg),
+    }
+}
+
+// ─── Delete project ───
+
+pub async fn delete_project(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ProjectResponse>) {
+    let db = state.db.lock();
+    if !projects::delete(&db, &user_id.0, &id) {
+        return project_error(StatusCode::NOT_FOUND, "项目不存在".into());
+    }
+    (
+        StatusCode::OK,
+        Json(ProjectResponse {
+            success: true,
+            item: None,
+            message: Some("项目已删除，其中的任务已移出项目".into()),
+        }),
+    )
+}
@@ -7,12 +7,17 @@ use serde::{Deserialize, Serialize};
 
 use crate::auth::{ActiveUserId, UserId};
 use crate::models::reminder::*;
+use crate::services::pagination::{self, PageInfo, PagePlan, PageQuery, SortField, SortKey};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
 pub struct RemindersResponse {
     pub success: bool,
     pub items: Vec<ReminderItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -61,40 +66,77 @@ fn row_to_reminder(row: &rusqlite::Row) -> rusqlite::Result<ReminderItem> {
     })
 }
 
+/// Sort options of `GET /api/reminders`; soonest first by default
+const REMINDER_SORTS: [SortField<ReminderItem>; 2] = [
+    SortField {
+        name: "remind_at",
+        descending: false,
+        key: |r| vec![SortKey::text(&r.remind_at)],
+        columns: &["remind_at"],
+    },
+    SortField {
+        name: "created",
+        descending: true,
+        key: |r| vec![SortKey::text(&r.created_at)],
+        columns: &["created_at"],
+    },
+];
+
 // GET /api/reminders?status=pending
 pub async fn list_reminders(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Query(query): Query<ListQuery>,
-) -> Result<Json<RemindersResponse>, StatusCode> {
+    Query(page_query): Query<PageQuery>,
+) -> (StatusCode, Json<RemindersResponse>) {
+    let fail = |status: StatusCode, msg: String| {
+        (
+            status,
+            Json(RemindersResponse {
+                success: false,
+                items: vec![],
+                message: Some(msg),
+                page: None,
+            }),
+        )
+    };
+    let plan = match PagePlan::new(&page_query, &REMINDER_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
     let db = state.db.lock();
 
     let status_filter = query.status.as_deref().unwrap_or("all");
     let (sql, params): (String, Vec<Box<dyn rusqlite::types::ToSql>>) = if status_filter == "all" {
         (
-            "SELECT id, text, remind_at, status, related_todo_id, repeat, created_at, triggered_at, acknowledged_at FROM reminders WHERE user_id=?1 AND status != 'cancelled' ORDER BY remind_at ASC LIMIT 50".into(),
+            "SELECT id, text, remind_at, status, related_todo_id, repeat, created_at, triggered_at, acknowledged_at FROM reminders WHERE user_id=?1 AND status != 'cancelled'".into(),
             vec![Box::new(user_id)],
         )
     } else {
         (
-            "SELECT id, text, remind_at, status, related_todo_id, repeat, created_at, triggered_at, acknowledged_at FROM reminders WHERE user_id=?1 AND status=?2 ORDER BY remind_at ASC LIMIT 50".into(),
+            "SELECT id, text, remind_at, status, related_todo_id, repeat, created_at, triggered_at, acknowledged_at FROM reminders WHERE user_id=?1 AND status=?2".into(),
             vec![Box::new(user_id), Box::new(status_filter.to_string())],
         )
     };
 
     let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-    let mut stmt = db
-        .prepare(&sql)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let rows = stmt
-        .query_map(param_refs.as_slice(), row_to_reminder)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let items: Vec<ReminderItem> = rows.flatten().collect();
-    Ok(Json(RemindersResponse {
-        success: true,
-        items,
-    }))
+    let (items, page) =
+        match pagination::query_page(&db, &sql, &param_refs, &plan, row_to_reminder, |r| &r.id) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[Reminder] list error: {}", e);
+                return fail(StatusCode::INTERNAL_SERVER_ERROR, "读取提醒失败".into());
+            }
+        };
+    (
+        StatusCode::OK,
+        Json(RemindersResponse {
+            success: true,
+            items,
+            message: None,
+            page: Some(page),
+        }),
+    )
 }
 
 // POST /api/reminders
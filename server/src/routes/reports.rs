@@ -0,0 +1,100 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::auth::UserId;
+use crate::models::report::{Report, ReportQuery};
+use crate::services::reports::{self, ReportFormat};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct ReportResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+pub async fn get_report(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<ReportQuery>,
+) -> (StatusCode, Json<ReportResponse>) {
+    let report = reports::resolve(query.period.as_deref(), query.date.as_deref()).and_then(
+        |(period, date)| {
+            let db = state.db.lock();
+            reports::build(&db, &user_id.0, period, date, chrono::Utc::now())
+        },
+    );
+    let report = match report {
+        Ok(r) => r,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ReportResponse {
+                    success: false,
+                    report: None,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ReportResponse {
+            success: true,
+            report: Some(report),
+            message: None,
+        }),
+    )
+}
+
+pub async fn download_report(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+    let format = match query.format.as_deref().filter(|f| !f.is_empty()) {
+        None => ReportFormat::Markdown,
+        Some(f) => match ReportFormat::parse(f) {
+            Some(format) => format,
+            None => return bad_request(format!("不支持的格式: {}（可选: markdown, html）", f)),
+        },
+    };
+    let (period, date) = match reports::resolve(query.period.as_deref(), query.date.as_deref()) {
+        Ok(p) => p,
+        Err(msg) => return bad_request(msg),
+    };
+
+    let db = state.db.lock();
+    let report = match reports::build(&db, &user_id.0, period, date, chrono::Utc::now()) {
+        Ok(r) => r,
+        Err(msg) => return bad_request(msg),
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", format.file_name(&report)),
+            ),
+        ],
+        Body::from(reports::render(&report, format)),
+    )
+        .into_response()
+}
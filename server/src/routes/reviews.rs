@@ -1,12 +1,13 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use serde::Serialize;
 
 use crate::auth::{ActiveUserId, UserId};
 use crate::models::review::*;
+use crate::services::etag;
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -82,7 +83,7 @@ pub async fn list_reviews(
 
     let mut stmt = db
         .prepare(
-            "SELECT id, text, frequency, frequency_config, notes, category, last_completed, paused, created_at, updated_at FROM reviews WHERE user_id = ?1",
+            "SELECT id, text, frequency, frequency_config, notes, category, last_completed, paused, created_at, updated_at FROM reviews WHERE user_id = ?1 AND deleted = 0",
         )
         .unwrap();
 
@@ -183,34 +184,74 @@ pub async fn create_review(
     )
 }
 
+fn load_review(db: &rusqlite::Connection, id: &str, user_id: &str) -> Option<ReviewItem> {
+    db.query_row(
+        "SELECT id, text, frequency, frequency_config, notes, category, last_completed, paused, created_at, updated_at FROM reviews WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
+        rusqlite::params![id, user_id],
+        row_to_review,
+    )
+    .ok()
+}
+
+fn review_not_found(id: &str) -> (StatusCode, HeaderMap, Json<ReviewResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        HeaderMap::new(),
+        Json(ReviewResponse {
+            success: false,
+            item: None,
+            message: Some(format!("例行事项不存在: {}", id)),
+        }),
+    )
+}
+
+pub async fn get_review(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, HeaderMap, Json<ReviewResponse>) {
+    let db = state.db.lock();
+    let Some(mut item) = load_review(&db, &id, &user_id.0) else {
+        return review_not_found(&id);
+    };
+    item.compute_due_status();
+    (
+        StatusCode::OK,
+        etag::headers(&item.updated_at),
+        Json(ReviewResponse {
+            success: true,
+            item: Some(item),
+            message: None,
+        }),
+    )
+}
+
 pub async fn update_review(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<UpdateReviewRequest>,
-) -> (StatusCode, Json<ReviewResponse>) {
+) -> (StatusCode, HeaderMap, Json<ReviewResponse>) {
     let db = state.db.lock();
 
-    let result = db.query_row(
-        "SELECT id, text, frequency, frequency_config, notes, category, last_completed, paused, created_at, updated_at FROM reviews WHERE id = ?1 AND user_id = ?2",
-        rusqlite::params![id, user_id.0],
-        row_to_review,
-    );
-
-    let mut item = match result {
-        Ok(r) => r,
-        Err(_) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ReviewResponse {
-                    success: false,
-                    item: None,
-                    message: Some(format!("例行事项不存在: {}", id)),
-                }),
-            )
-        }
+    let Some(mut item) = load_review(&db, &id, &user_id.0) else {
+        return review_not_found(&id);
     };
 
+    if !etag::matches(&headers, &item.updated_at) {
+        item.compute_due_status();
+        return (
+            StatusCode::PRECONDITION_FAILED,
+            etag::headers(&item.updated_at),
+            Json(ReviewResponse {
+                success: false,
+                item: Some(item),
+                message: Some("例行事项已在其他地方被修改，请合并后重试".into()),
+            }),
+        );
+    }
+
     if let Some(text) = req.text {
         item.text = text;
     }
@@ -258,6 +299,7 @@ pub async fn update_review(
 
     (
         StatusCode::OK,
+        etag::headers(&item.updated_at),
         Json(ReviewResponse {
             success: true,
             item: Some(item),
@@ -276,7 +318,7 @@ pub async fn complete_review(
 
     let rows = db
         .execute(
-            "UPDATE reviews SET last_completed = ?1, updated_at = ?1 WHERE id = ?2 AND user_id = ?3",
+            "UPDATE reviews SET last_completed = ?1, updated_at = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted = 0",
             rusqlite::params![now, id, user_id.0],
         )
         .unwrap_or(0);
@@ -321,7 +363,7 @@ pub async fn uncomplete_review(
 
     let rows = db
         .execute(
-            "UPDATE reviews SET last_completed = NULL, updated_at = ?1 WHERE id = ?2 AND user_id = ?3",
+            "UPDATE reviews SET last_completed = NULL, updated_at = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted = 0",
             rusqlite::params![now, id, user_id.0],
         )
         .unwrap_or(0);
@@ -365,8 +407,8 @@ pub async fn delete_review(
 
     let rows = db
         .execute(
-            "DELETE FROM reviews WHERE id = ?1 AND user_id = ?2",
-            rusqlite::params![id, user_id.0],
+            "UPDATE reviews SET deleted = 1, deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted = 0",
+            rusqlite::params![chrono::Utc::now().to_rfc3339(), id, user_id.0],
         )
         .unwrap_or(0);
 
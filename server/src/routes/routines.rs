@@ -1,13 +1,15 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use chrono::NaiveDate;
 use rusqlite::Connection;
 use serde::Serialize;
 
 use crate::auth::{ActiveUserId, UserId};
 use crate::models::routine::*;
+use crate::services::{routine_stats, time};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -79,9 +81,32 @@ fn get_user_display_name(db: &Connection, uid: &str) -> Option<String> {
     .ok()
 }
 
+/// Schedule, whether it's due today and streaks, from the user's history
+fn fill_progress(db: &Connection, routine: &mut Routine, user_id: &str) {
+    let Some(history) = routine_stats::History::load(db, &routine.id, user_id) else {
+        return;
+    };
+    let today = time::local_today();
+    let (current, longest) = history.streaks(today);
+    routine.current_streak = current;
+    routine.longest_streak = longest;
+    routine.schedule = history.schedule.as_string();
+    routine.schedule_label = history.schedule.label();
+    routine.due_today = history.is_due(today);
+}
+
+/// Canonical form of a requested schedule
+fn parse_schedule(schedule: Option<&str>) -> Result<String, String> {
+    let raw = schedule.unwrap_or("daily");
+    RoutineSchedule::parse(raw)
+        .map(|s| s.as_string())
+        .ok_or_else(|| format!("无效的日程: {}", raw))
+}
+
 pub async fn list_routines(
     State(state): State<AppState>,
     user_id: UserId,
+    Query(query): Query<ListRoutinesQuery>,
 ) -> (StatusCode, Json<RoutinesResponse>) {
     let db = state.db.lock();
     ensure_collab_tables(&db);
@@ -121,6 +146,11 @@ pub async fn list_routines(
                 is_collaborative,
                 owner_name: None,
                 owner_id: None,
+                current_streak: 0,
+                longest_streak: 0,
+                schedule: default_schedule(),
+                schedule_label: String::new(),
+                due_today: true,
             })
         }) {
             for r in rows.flatten() {
@@ -170,11 +200,23 @@ pub async fn list_routines(
                     is_collaborative: Some(true),
                     owner_name,
                     owner_id: Some(owner_id),
+                    current_streak: 0,
+                    longest_streak: 0,
+                    schedule: default_schedule(),
+                    schedule_label: String::new(),
+                    due_today: true,
                 });
             }
         }
     }
 
+    for item in &mut items {
+        fill_progress(&db, item, &user_id.0);
+    }
+    if !query.include_rest_days() {
+        items.retain(|r| r.due_today);
+    }
+
     (
         StatusCode::OK,
         Json(RoutinesResponse {
@@ -190,17 +232,30 @@ pub async fn create_routine(
     user_id: ActiveUserId,
     Json(req): Json<CreateRoutineRequest>,
 ) -> (StatusCode, Json<RoutineResponse>) {
+    let schedule = match parse_schedule(req.schedule.as_deref()) {
+        Ok(s) => s,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(RoutineResponse {
+                    success: false,
+                    item: None,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
     let db = state.db.lock();
     let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
     db.execute(
-        "INSERT INTO routines (id, user_id, text, completed_today, last_completed_date, created_at) VALUES (?1,?2,?3,0,NULL,?4)",
-        rusqlite::params![id, user_id.0, req.text, now],
+        "INSERT INTO routines (id, user_id, text, completed_today, last_completed_date, created_at, schedule) VALUES (?1,?2,?3,0,NULL,?4,?5)",
+        rusqlite::params![id, user_id.0, req.text, now, schedule],
     )
     .unwrap();
 
-    let routine = Routine {
+    let mut routine = Routine {
         id,
         text: req.text,
         completed_today: false,
@@ -209,7 +264,13 @@ pub async fn create_routine(
         is_collaborative: None,
         owner_name: None,
         owner_id: None,
+        current_streak: 0,
+        longest_streak: 0,
+        schedule,
+        schedule_label: String::new(),
+        due_today: true,
     };
+    fill_progress(&db, &mut routine, &user_id.0);
 
     (
         StatusCode::OK,
@@ -221,6 +282,92 @@ pub async fn create_routine(
     )
 }
 
+/// Rename a routine or change its schedule (owner only)
+pub async fn update_routine(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateRoutineRequest>,
+) -> (StatusCode, Json<RoutineResponse>) {
+    let fail = |status, message: String| {
+        (
+            status,
+            Json(RoutineResponse {
+                success: false,
+                item: None,
+                message: Some(message),
+            }),
+        )
+    };
+    let text = req.text.as_deref().map(str::trim);
+    if text.is_some_and(str::is_empty) {
+        return fail(StatusCode::BAD_REQUEST, "日常任务内容不能为空".into());
+    }
+    let schedule = match req
+        .schedule
+        .as_deref()
+        .map(|s| parse_schedule(Some(s)))
+        .transpose()
+    {
+        Ok(s) => s,
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
+
+    let db = state.db.lock();
+    let rows = db
+        .execute(
+            "UPDATE routines SET text = COALESCE(?1, text), schedule = COALESCE(?2, schedule) WHERE id = ?3 AND user_id = ?4",
+            rusqlite::params![text, schedule, id, user_id.0],
+        )
+        .unwrap_or(0);
+    if rows == 0 {
+        return fail(StatusCode::NOT_FOUND, format!("日常任务不存在: {}", id));
+    }
+
+    let today = time::local_today().to_string();
+    let result = db.query_row(
+        "SELECT text, completed_today, last_completed_date, created_at, COALESCE(is_collaborative, 0) FROM routines WHERE id = ?1",
+        [&id],
+        |row| {
+            let completed_int: i32 = row.get(1)?;
+            let last_date: Option<String> = row.get(2)?;
+            let is_collab_int: i32 = row.get(4)?;
+            Ok(Routine {
+                id: id.clone(),
+                text: row.get(0)?,
+                completed_today: completed_int != 0 && last_date.as_deref() == Some(today.as_str()),
+                last_completed_date: last_date,
+                created_at: row.get(3)?,
+                is_collaborative: if is_collab_int != 0 { Some(true) } else { None },
+                owner_name: None,
+                owner_id: None,
+                current_streak: 0,
+                longest_streak: 0,
+                schedule: default_schedule(),
+                schedule_label: String::new(),
+                due_today: true,
+            })
+        },
+    );
+    let mut routine = match result {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("[routines] update_routine DB error: {}", e);
+            return fail(StatusCode::INTERNAL_SERVER_ERROR, "更新失败".into());
+        }
+    };
+    fill_progress(&db, &mut routine, &user_id.0);
+
+    (
+        StatusCode::OK,
+        Json(RoutineResponse {
+            success: true,
+            item: Some(routine),
+            message: Some("日常任务已更新".into()),
+        }),
+    )
+}
+
 pub async fn toggle_routine(
     State(state): State<AppState>,
     user_id: ActiveUserId,
@@ -300,7 +447,7 @@ pub async fn toggle_routine(
             .unwrap_or_default();
         let oname = get_user_display_name(&db, &oid);
 
-        let routine = Routine {
+        let mut routine = Routine {
             id,
             text,
             completed_today,
@@ -309,8 +456,14 @@ pub async fn toggle_routine(
             is_collaborative: Some(true),
             owner_name: oname,
             owner_id: Some(oid),
+            current_streak: 0,
+            longest_streak: 0,
+            schedule: default_schedule(),
+            schedule_label: String::new(),
+            due_today: true,
         };
 
+        fill_progress(&db, &mut routine, &user_id.0);
         let message = if completed_today {
             "已完成"
         } else {
@@ -351,6 +504,11 @@ pub async fn toggle_routine(
                 is_collaborative: if is_collab_int != 0 { Some(true) } else { None },
                 owner_name: None,
                 owner_id: None,
+                current_streak: 0,
+                longest_streak: 0,
+                schedule: default_schedule(),
+                schedule_label: String::new(),
+                due_today: true,
             })
         },
     );
@@ -385,6 +543,7 @@ pub async fn toggle_routine(
         ],
     )
     .unwrap();
+    fill_progress(&db, &mut routine, &user_id.0);
 
     let message = if routine.completed_today {
         "已完成"
@@ -446,3 +605,237 @@ pub async fn delete_routine(
         }),
     )
 }
+
+#[derive(Debug, Serialize)]
+pub struct RoutineStatsResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<RoutineStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    pub days: Vec<HeatmapDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Owner id and creation time of a routine the user owns or shares
+fn routine_access(db: &Connection, id: &str, user_id: &str) -> Option<(String, String)> {
+    ensure_collab_tables(db);
+    db.query_row(
+        "SELECT user_id, created_at FROM routines WHERE id = ?1 AND (user_id = ?2 OR EXISTS (
+             SELECT 1 FROM routine_collaborators WHERE routine_id = ?1 AND user_id = ?2 AND status = 'active'))",
+        [id, user_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .ok()
+}
+
+fn load_stats(db: &Connection, id: &str, user_id: &str, today: NaiveDate) -> Option<RoutineStats> {
+    routine_stats::History::load(db, id, user_id).map(|h| h.stats(id, today))
+}
+
+fn stats_error(status: StatusCode, message: String) -> (StatusCode, Json<RoutineStatsResponse>) {
+    (
+        status,
+        Json(RoutineStatsResponse {
+            success: false,
+            stats: None,
+            message: Some(message),
+        }),
+    )
+}
+
+pub async fn get_routine_stats(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<RoutineStatsResponse>) {
+    let db = state.db.lock();
+    if routine_access(&db, &id, &user_id.0).is_none() {
+        return stats_error(StatusCode::NOT_FOUND, format!("日常任务不存在: {}", id));
+    }
+    let stats = load_stats(&db, &id, &user_id.0, time::local_today());
+    (
+        StatusCode::OK,
+        Json(RoutineStatsResponse {
+            success: true,
+            stats,
+            message: None,
+        }),
+    )
+}
+
+pub async fn get_routine_heatmap(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+    Query(query): Query<HeatmapQuery>,
+) -> (StatusCode, Json<HeatmapResponse>) {
+    let fail = |status, message: String| {
+        (
+            status,
+            Json(HeatmapResponse {
+                success: false,
+                from: None,
+                to: None,
+                days: Vec::new(),
+                message: Some(message),
+            }),
+        )
+    };
+    let parse = |s: &str| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", s))
+    };
+    let to = match query.to.as_deref().map(parse).transpose() {
+        Ok(d) => d.unwrap_or_else(time::local_today),
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
+    let from = match query.from.as_deref().map(parse).transpose() {
+        Ok(Some(d)) => d,
+        Ok(None) => match to.checked_sub_days(chrono::Days::new(364)) {
+            Some(d) => d,
+            None => return fail(StatusCode::BAD_REQUEST, "日期超出范围".into()),
+        },
+        Err(msg) => return fail(StatusCode::BAD_REQUEST, msg),
+    };
+    if from > to {
+        return fail(StatusCode::BAD_REQUEST, "开始日期不能晚于结束日期".into());
+    }
+    if (to - from).num_days() > 730 {
+        return fail(StatusCode::BAD_REQUEST, "时间范围不能超过两年".into());
+    }
+
+    let db = state.db.lock();
+    if routine_access(&db, &id, &user_id.0).is_none() {
+        return fail(StatusCode::NOT_FOUND, format!("日常任务不存在: {}", id));
+    }
+    let days = routine_stats::History::load(&db, &id, &user_id.0)
+        .map(|h| h.heatmap(from, to))
+        .unwrap_or_default();
+    (
+        StatusCode::OK,
+        Json(HeatmapResponse {
+            success: true,
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            days,
+            message: None,
+        }),
+    )
+}
+
+pub async fn backfill_routine(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<BackfillRequest>,
+) -> (StatusCode, Json<RoutineStatsResponse>) {
+    let Ok(date) = NaiveDate::parse_from_str(&req.date, "%Y-%m-%d") else {
+        return stats_error(
+            StatusCode::BAD_REQUEST,
+            format!("日期格式无效: {}", req.date),
+        );
+    };
+    let today = time::local_today();
+    if date > today {
+        return stats_error(StatusCode::BAD_REQUEST, "不能补记未来的日期".into());
+    }
+
+    let db = state.db.lock();
+    let Some((owner_id, created_at)) = routine_access(&db, &id, &user_id.0) else {
+        return stats_error(StatusCode::NOT_FOUND, format!("日常任务不存在: {}", id));
+    };
+    if routine_stats::created_on(&created_at).is_some_and(|c| date < c) {
+        return stats_error(StatusCode::BAD_REQUEST, "不能早于日常任务的创建日期".into());
+    }
+
+    let date_str = date.to_string();
+    if owner_id == user_id.0 && date == today {
+        // Same as ticking it off today
+        db.execute(
+            "UPDATE routines SET completed_today = 1, last_completed_date = ?1 WHERE id = ?2",
+            rusqlite::params![date_str, id],
+        )
+        .ok();
+    } else {
+        let comp_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+        db.execute(
+            "INSERT OR IGNORE INTO routine_completions (id, routine_id, user_id, completed_date, created_at) VALUES (?1,?2,?3,?4,?5)",
+            rusqlite::params![comp_id, id, user_id.0, date_str, now],
+        )
+        .ok();
+        if owner_id == user_id.0 {
+            db.execute(
+                "UPDATE routines SET last_completed_date = ?1 WHERE id = ?2 AND (last_completed_date IS NULL OR last_completed_date < ?1)",
+                rusqlite::params![date_str, id],
+            )
+            .ok();
+        }
+    }
+
+    let stats = load_stats(&db, &id, &user_id.0, today);
+    (
+        StatusCode::OK,
+        Json(RoutineStatsResponse {
+            success: true,
+            stats,
+            message: Some(format!("已补记 {}", date_str)),
+        }),
+    )
+}
+
+pub async fn remove_routine_completion(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, date)): Path<(String, String)>,
+) -> (StatusCode, Json<RoutineStatsResponse>) {
+    let db = state.db.lock();
+    let Some((owner_id, _)) = routine_access(&db, &id, &user_id.0) else {
+        return stats_error(StatusCode::NOT_FOUND, format!("日常任务不存在: {}", id));
+    };
+    let today = time::local_today();
+
+    if owner_id == user_id.0 && date == today.to_string() {
+        // Same as unticking today
+        db.execute(
+            "UPDATE routines SET completed_today = 0, last_completed_date = NULL WHERE id = ?1",
+            [&id],
+        )
+        .ok();
+    } else {
+        db.execute(
+            "DELETE FROM routine_completions WHERE routine_id = ?1 AND user_id = ?2 AND completed_date = ?3",
+            rusqlite::params![id, user_id.0, date],
+        )
+        .ok();
+        if owner_id == user_id.0 {
+            db.execute(
+                "UPDATE routines SET completed_today = 0, last_completed_date = (
+                     SELECT MAX(completed_date) FROM routine_completions WHERE routine_id = ?1 AND user_id = ?2)
+                 WHERE id = ?1 AND last_completed_date = ?3",
+                rusqlite::params![id, user_id.0, date],
+            )
+            .ok();
+        }
+    }
+
+    let stats = load_stats(&db, &id, &user_id.0, today);
+    (
+        StatusCode::OK,
+        Json(RoutineStatsResponse {
+            success: true,
+            stats,
+            message: Some(format!("已取消 {} 的完成记录", date)),
+        }),
+    )
+}
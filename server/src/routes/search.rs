@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::UserId;
+use crate::services::search;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+    /// Restrict to one module: todo / english / expense / trip
+    pub module: Option<String>,
+    /// Max results per module (default 10, max 50)
+    pub limit: Option<usize>,
+}
+
+/// GET /api/search?q= — ranked, highlighted results grouped by module
+pub async fn search(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<SearchQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"success": false, "message": "请输入搜索关键词"})),
+        );
+    }
+    if q.chars().count() > search::MAX_QUERY_CHARS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"success": false, "message": "搜索关键词过长"})),
+        );
+    }
+
+    let modules: Vec<&str> = match query.module.as_deref() {
+        Some(m) if search::MODULES.contains(&m) => vec![m],
+        Some(m) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"success": false, "message": format!("未知模块: {}", m)})),
+            )
+        }
+        None => search::MODULES.to_vec(),
+    };
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    let db = state.db.lock();
+    let mut results = serde_json::Map::new();
+    let mut total = 0;
+    for module in modules {
+        let hits = search::search_module(&db, &user_id.0, q, module, limit);
+        total += hits.len();
+        results.insert(
+            module.to_string(),
+            json!(hits.iter().map(|h| h.to_json(true)).collect::<Vec<_>>()),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "query": q,
+            "total": total,
+            "results": results
+        })),
+    )
+}
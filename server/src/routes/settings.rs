@@ -0,0 +1,107 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::settings::*;
+use crate::services::trash;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct SettingsResponse {
+    pub success: bool,
+    pub settings: UserSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn load_settings(db: &rusqlite::Connection, user_id: &str) -> UserSettings {
+    db.query_row(
+        "SELECT COALESCE(auto_rollover, 1), COALESCE(escalate_overdue, 0), COALESCE(trash_retention_days, ?2) \
+         FROM user_settings WHERE user_id = ?1",
+        rusqlite::params![user_id, trash::DEFAULT_RETENTION_DAYS],
+        |row| {
+            Ok(UserSettings {
+                auto_rollover: row.get(0)?,
+                escalate_overdue: row.get(1)?,
+                trash_retention_days: row.get(2)?,
+            })
+        },
+    )
+    .unwrap_or(UserSettings {
+        auto_rollover: true,
+        escalate_overdue: false,
+        trash_retention_days: trash::DEFAULT_RETENTION_DAYS,
+    })
+}
+
+// ─── Get settings ───
+
+pub async fn get_settings(
+    State(state): State<AppState>,
+    user_id: UserId,
+) -> (StatusCode, Json<SettingsResponse>) {
+    let db = state.db.lock();
+    (
+        StatusCode::OK,
+        Json(SettingsResponse {
+            success: true,
+            settings: load_settings(&db, &user_id.0),
+            message: None,
+        }),
+    )
+}
+
+// ─── Update settings ───
+
+pub async fn update_settings(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<UpdateSettingsRequest>,
+) -> (StatusCode, Json<SettingsResponse>) {
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let current = load_settings(&db, &user_id.0);
+    let auto_rollover = req.auto_rollover.unwrap_or(current.auto_rollover);
+    let escalate_overdue = req.escalate_overdue.unwrap_or(current.escalate_overdue);
+    let retention = req
+        .trash_retention_days
+        .unwrap_or(current.trash_retention_days);
+    if !(0..=trash::MAX_RETENTION_DAYS).contains(&retention) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(SettingsResponse {
+                success: false,
+                settings: current,
+                message: Some(format!(
+                    "回收站保留天数需在 0-{} 之间（0 为永久保留）",
+                    trash::MAX_RETENTION_DAYS
+                )),
+            }),
+        );
+    }
+    if let Err(e) = db.execute(
+        "INSERT INTO user_settings (user_id, auto_rollover, escalate_overdue, trash_retention_days, updated_at) VALUES (?1, ?2, ?3, ?4, ?5) \
+         ON CONFLICT(user_id) DO UPDATE SET auto_rollover = ?2, escalate_overdue = ?3, trash_retention_days = ?4, updated_at = ?5",
+        rusqlite::params![user_id.0, auto_rollover, escalate_overdue, retention, now],
+    ) {
+        eprintln!("[settings] update DB error: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(SettingsResponse {
+                success: false,
+                settings: current,
+                message: None,
+            }),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        Json(SettingsResponse {
+            success: true,
+            settings: load_settings(&db, &user_id.0),
+            message: None,
+        }),
+    )
+}
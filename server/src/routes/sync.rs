@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::sync::*;
+use crate::services::sync;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub success: bool,
+    pub items: Vec<SyncChange>,
+    /// Pass back as `since` on the next call
+    pub cursor: String,
+    pub has_more: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPushResponse {
+    pub success: bool,
+    pub results: Vec<SyncResult>,
+    /// Cursor after the push; the client's own edits come back once more
+    /// through `GET /api/sync`
+    pub cursor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn sync_error(message: String) -> (StatusCode, Json<SyncResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(SyncResponse {
+            success: false,
+            items: Vec::new(),
+            cursor: String::new(),
+            has_more: false,
+            message: Some(message),
+        }),
+    )
+}
+
+// ─── Pull changes ───
+
+pub async fn get_changes(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<SyncQuery>,
+) -> (StatusCode, Json<SyncResponse>) {
+    let since = match query.since.as_deref().filter(|s| !s.is_empty()) {
+        Some(s) => match s.parse::<i64>() {
+            Ok(n) if n >= 0 => Some(n),
+            _ => return sync_error(format!("无效的 since: {}", s)),
+        },
+        None => None,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(sync::DEFAULT_LIMIT)
+        .clamp(1, sync::MAX_LIMIT);
+
+    let db = state.db.lock();
+    let (items, cursor, has_more) = sync::changes(&db, &user_id.0, since, limit);
+    (
+        StatusCode::OK,
+        Json(SyncResponse {
+            success: true,
+            items,
+            cursor: cursor.to_string(),
+            has_more,
+            message: None,
+        }),
+    )
+}
+
+// ─── Push offline mutations ───
+
+pub async fn push_changes(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<SyncPushRequest>,
+) -> (StatusCode, Json<SyncPushResponse>) {
+    if req.mutations.len() > sync::MAX_MUTATIONS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(SyncPushResponse {
+                success: false,
+                results: Vec::new(),
+                cursor: String::new(),
+                message: Some(format!("每次最多提交 {} 条修改", sync::MAX_MUTATIONS)),
+            }),
+        );
+    }
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    let results: Vec<SyncResult> = req
+        .mutations
+        .iter()
+        .map(|m| sync::apply(&db, &user_id.0, m, &now))
+        .collect();
+    let applied = results.iter().filter(|r| r.status == "applied").count();
+    (
+        StatusCode::OK,
+        Json(SyncPushResponse {
+            success: true,
+            message: Some(format!("已应用 {}/{} 条修改", applied, results.len())),
+            results,
+            cursor: sync::current_seq(&db).to_string(),
+        }),
+    )
+}
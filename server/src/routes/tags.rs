@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::tag::*;
+use crate::services::tags;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct TagUsageResponse {
+    pub success: bool,
+    pub items: Vec<TagUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagChangeResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<TagChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn change_result(
+    result: Result<TagChange, String>,
+    describe: impl FnOnce(&TagChange) -> String,
+) -> (StatusCode, Json<TagChangeResponse>) {
+    match result {
+        Ok(change) if change.todos + change.expenses == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(TagChangeResponse {
+                success: false,
+                result: None,
+                message: Some("标签不存在".into()),
+            }),
+        ),
+        Ok(change) => (
+            StatusCode::OK,
+            Json(TagChangeResponse {
+                success: true,
+                message: Some(describe(&change)),
+                result: Some(change),
+            }),
+        ),
+        Err(msg) => (
+            StatusCode::BAD_REQUEST,
+            Json(TagChangeResponse {
+                success: false,
+                result: None,
+                message: Some(msg),
+            }),
+        ),
+    }
+}
+
+fn bad_request(msg: String) -> (StatusCode, Json<TagChangeResponse>) {
+    change_result(Err(msg), |_| String::new())
+}
+
+// ─── List tags ───
+
+pub async fn list_tags(State(state): State<AppState>, user_id: UserId) -> Json<TagUsageResponse> {
+    let db = state.db.lock();
+    Json(TagUsageResponse {
+        success: true,
+        items: tags::usage(&db, &user_id.0),
+    })
+}
+
+// ─── Rename tag ───
+
+pub async fn rename_tag(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(name): Path<String>,
+    Json(req): Json<RenameTagRequest>,
+) -> (StatusCode, Json<TagChangeResponse>) {
+    let target = match tags::validate_name(&req.name) {
+        Ok(t) => t,
+        Err(msg) => return bad_request(msg),
+    };
+    if target == name {
+        return bad_request("新旧标签相同".into());
+    }
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = tags::replace(
+        &db,
+        &user_id.0,
+        std::slice::from_ref(&name),
+        Some(&target),
+        &now,
+    );
+    change_result(result, |c| {
+        format!(
+            "已将「{}」改为「{}」（{} 个任务，{} 笔记账）",
+            name, target, c.todos, c.expenses
+        )
+    })
+}
+
+// ─── Merge tags ───
+
+pub async fn merge_tags(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<MergeTagsRequest>,
+) -> (StatusCode, Json<TagChangeResponse>) {
+    let target = match tags::validate_name(&req.into) {
+        Ok(t) => t,
+        Err(msg) => return bad_request(msg),
+    };
+    let sources: Vec<String> = req
+        .sources
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty() && *s != target)
+        .collect();
+    if sources.is_empty() {
+        return bad_request("请指定要合并的标签".into());
+    }
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = tags::replace(&db, &user_id.0, &sources, Some(&target), &now);
+    change_result(result, |c| {
+        format!(
+            "已将 {} 个标签合并为「{}」（{} 个任务，{} 笔记账）",
+            sources.len(),
+            target,
+            c.todos,
+            c.expenses
+        )
+    })
+}
+
+// ─── Delete tag ───
+
+pub async fn delete_tag(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<TagChangeResponse>) {
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = tags::replace(&db, &user_id.0, std::slice::from_ref(&name), None, &now);
+    change_result(result, |c| {
+        format!(
+            "已删除标签「{}」（{} 个任务，{} 笔记账）",
+            name, c.todos, c.expenses
+        )
+    })
+}
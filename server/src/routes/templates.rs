@@ -0,0 +1,252 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::template::*;
+use crate::services::{templates, time};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct TemplatesResponse {
+    pub success: bool,
+    pub items: Vec<TodoTemplate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<TodoTemplate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplyTemplateResponse {
+    pub success: bool,
+    pub todo_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn template_error(status: StatusCode, message: String) -> (StatusCode, Json<TemplateResponse>) {
+    (
+        status,
+        Json(TemplateResponse {
+            success: false,
+            item: None,
+            message: Some(message),
+        }),
+    )
+}
+
+fn template_result(
+    result: Result<TodoTemplate, String>,
+    message: &str,
+) -> (StatusCode, Json<TemplateResponse>) {
+    match result {
+        Ok(template) => (
+            StatusCode::OK,
+            Json(TemplateResponse {
+                success: true,
+                item: Some(template),
+                message: Some(message.into()),
+            }),
+        ),
+        Err(msg) => template_error(StatusCode::BAD_REQUEST, msg),
+    }
+}
+
+// ─── List templates ───
+
+pub async fn list_templates(
+    State(state): State<AppState>,
+    user_id: UserId,
+) -> Json<TemplatesResponse> {
+    let db = state.db.lock();
+    Json(TemplatesResponse {
+        success: true,
+        items: templates::list(&db, &user_id.0),
+    })
+}
+
+// ─── Get template ───
+
+pub async fn get_template(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<TemplateResponse>) {
+    let db = state.db.lock();
+    match templates::find(&db, &user_id.0, &id) {
+        Some(template) => (
+            StatusCode::OK,
+            Json(TemplateResponse {
+                success: true,
+                item: Some(template),
+                message: None,
+            }),
+        ),
+        None => template_error(StatusCode::NOT_FOUND, "模板不存在".into()),
+    }
+}
+
+// ─── Create template ───
+
+pub async fn create_template(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<CreateTemplateRequest>,
+) -> (StatusCode, Json<TemplateResponse>) {
+    let fields = match templates::validate(
+        &req.name,
+        req.description.as_deref().unwrap_or(""),
+        &req.items,
+    ) {
+        Ok(v) => v,
+        Err(msg) => return template_error(StatusCode::BAD_REQUEST, msg),
+    };
+    let db = state.db.lock();
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = templates::insert(&db, &id, &user_id.0, &fields, false, &now);
+    template_result(result, "模板已创建")
+}
+
+// ─── Update template ───
+
+pub async fn update_template(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateTemplateRequest>,
+) -> (StatusCode, Json<TemplateResponse>) {
+    let db = state.db.lock();
+    let Some(template) = templates::find(&db, &user_id.0, &id) else {
+        return template_error(StatusCode::NOT_FOUND, "模板不存在".into());
+    };
+    let fields = match templates::validate(
+        req.name.as_deref().unwrap_or(&template.name),
+        req.description.as_deref().unwrap_or(&template.description),
+        req.items.as_deref().unwrap_or(&template.items),
+    ) {
+        Ok(v) => v,
+        Err(msg) => return template_error(StatusCode::BAD_REQUEST, msg),
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    let items_json = serde_json::to_string(&fields.items).unwrap_or_else(|_| "[]".into());
+    if db
+        .execute(
+            "UPDATE todo_templates SET name = ?1, description = ?2, items = ?3, updated_at = ?4 \
+             WHERE id = ?5 AND user_id = ?6",
+            rusqlite::params![
+                fields.name,
+                fields.description,
+                items_json,
+                now,
+                template.id,
+                user_id.0
+            ],
+        )
+        .is_err()
+    {
+        return template_error(
+            StatusCode::BAD_REQUEST,
+            format!("模板已存在: {}", fields.name),
+        );
+    }
+    let result = templates::find(&db, &user_id.0, &template.id).ok_or_else(|| "模板不存在".into());
+    template_result(result, "模板已更新")
+}
+
+// ─── Delete template ───
+
+pub async fn delete_template(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<TemplateResponse>) {
+    let db = state.db.lock();
+    let deleted = db
+        .execute(
+            "DELETE FROM todo_templates WHERE id = ?1 AND user_id = ?2",
+            [&id, &user_id.0],
+        )
+        .unwrap_or(0);
+    if deleted == 0 {
+        return template_error(StatusCode::NOT_FOUND, "模板不存在".into());
+    }
+    (
+        StatusCode::OK,
+        Json(TemplateResponse {
+            success: true,
+            item: None,
+            message: Some("模板已删除".into()),
+        }),
+    )
+}
+
+// ─── Apply template ───
+
+pub async fn apply_template(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    req: Option<Json<ApplyTemplateRequest>>,
+) -> (StatusCode, Json<ApplyTemplateResponse>) {
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+    let fail = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(ApplyTemplateResponse {
+                success: false,
+                todo_ids: Vec::new(),
+                project_id: None,
+                message: Some(message),
+            }),
+        )
+    };
+    let anchor = match req.anchor_date.as_deref().filter(|d| !d.is_empty()) {
+        Some(d) => match chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return fail(StatusCode::BAD_REQUEST, format!("日期格式无效: {}", d)),
+        },
+        None => time::today(),
+    };
+
+    let db = state.db.lock();
+    let Some(template) = templates::find(&db, &user_id.0, &id) else {
+        return fail(StatusCode::NOT_FOUND, "模板不存在".into());
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    match templates::apply(
+        &db,
+        &user_id.0,
+        &template,
+        anchor,
+        req.project_id.as_deref(),
+        req.new_project.as_deref(),
+        &now,
+    ) {
+        Ok(applied) => (
+            StatusCode::OK,
+            Json(ApplyTemplateResponse {
+                success: true,
+                message: Some(format!(
+                    "已按模板「{}」创建 {} 个任务",
+                    template.name,
+                    applied.todo_ids.len()
+                )),
+                todo_ids: applied.todo_ids,
+                project_id: applied.project_id,
+            }),
+        ),
+        Err(msg) => fail(StatusCode::BAD_REQUEST, msg),
+    }
+}
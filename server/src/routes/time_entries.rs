@@ -0,0 +1,234 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::time_entry::*;
+use crate::services::time_tracking;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct TimeEntryResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<TimeEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeEntriesResponse {
+    pub success: bool,
+    pub items: Vec<TimeEntry>,
+    pub total_secs: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeReportResponse {
+    pub success: bool,
+    pub group: &'static str,
+    pub items: Vec<TimeReportRow>,
+    pub total_secs: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn entry_result(
+    result: Result<TimeEntry, String>,
+    error_status: StatusCode,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    match result {
+        Ok(entry) => (
+            StatusCode::OK,
+            Json(TimeEntryResponse {
+                success: true,
+                item: Some(entry),
+                message: None,
+            }),
+        ),
+        Err(msg) => (
+            error_status,
+            Json(TimeEntryResponse {
+                success: false,
+                item: None,
+                message: Some(msg),
+            }),
+        ),
+    }
+}
+
+// ─── Running timer ───
+
+pub async fn current_timer(
+    State(state): State<AppState>,
+    user_id: UserId,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    let db = state.db.lock();
+    (
+        StatusCode::OK,
+        Json(TimeEntryResponse {
+            success: true,
+            item: time_tracking::running(&db, &user_id.0, chrono::Utc::now()),
+            message: None,
+        }),
+    )
+}
+
+pub async fn start_timer(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<StartTimerRequest>,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    let db = state.db.lock();
+    let result = time_tracking::start(
+        &db,
+        &user_id.0,
+        &req.todo_id,
+        req.note.as_deref(),
+        chrono::Utc::now(),
+    );
+    entry_result(result, StatusCode::CONFLICT)
+}
+
+pub async fn stop_timer(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    let db = state.db.lock();
+    let result = time_tracking::stop(&db, &user_id.0, chrono::Utc::now());
+    entry_result(result, StatusCode::NOT_FOUND)
+}
+
+// ─── Entries ───
+
+pub async fn list_entries(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<TimeEntryQuery>,
+) -> (StatusCode, Json<TimeEntriesResponse>) {
+    let range = match time_tracking::day_range(query.from.as_deref(), query.to.as_deref()) {
+        Ok(r) => r,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(TimeEntriesResponse {
+                    success: false,
+                    items: Vec::new(),
+                    total_secs: 0,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
+    let db = state.db.lock();
+    let items = time_tracking::list(
+        &db,
+        &user_id.0,
+        query.todo_id.as_deref(),
+        &range,
+        chrono::Utc::now(),
+    );
+    let total_secs = items.iter().map(|e| e.duration_secs).sum();
+    (
+        StatusCode::OK,
+        Json(TimeEntriesResponse {
+            success: true,
+            items,
+            total_secs,
+            message: None,
+        }),
+    )
+}
+
+pub async fn create_entry(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<ManualEntryRequest>,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    let db = state.db.lock();
+    let result = time_tracking::add_manual(&db, &user_id.0, &req, chrono::Utc::now());
+    entry_result(result, StatusCode::BAD_REQUEST)
+}
+
+pub async fn delete_entry(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<TimeEntryResponse>) {
+    let db = state.db.lock();
+    if time_tracking::delete(&db, &user_id.0, &id) {
+        (
+            StatusCode::OK,
+            Json(TimeEntryResponse {
+                success: true,
+                item: None,
+                message: Some("记录已删除".into()),
+            }),
+        )
+    } else {
+        entry_result(Err("记录不存在".into()), StatusCode::NOT_FOUND)
+    }
+}
+
+// ─── Report ───
+
+pub async fn report(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<TimeReportQuery>,
+) -> (StatusCode, Json<TimeReportResponse>) {
+    let group = match query.group.as_deref() {
+        None | Some("") | Some("day") => "day",
+        Some("tag") => "tag",
+        Some(other) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(TimeReportResponse {
+                    success: false,
+                    group: "day",
+                    items: Vec::new(),
+                    total_secs: 0,
+                    message: Some(format!("不支持的分组方式: {}", other)),
+                }),
+            )
+        }
+    };
+    let range = match time_tracking::day_range(query.from.as_deref(), query.to.as_deref()) {
+        Ok(r) => r,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(TimeReportResponse {
+                    success: false,
+                    group,
+                    items: Vec::new(),
+                    total_secs: 0,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
+
+    let db = state.db.lock();
+    let now = chrono::Utc::now();
+    let total_secs = time_tracking::list(&db, &user_id.0, None, &range, now)
+        .iter()
+        .map(|e| e.duration_secs)
+        .sum();
+    let items = time_tracking::report(&db, &user_id.0, &range, group == "tag", now);
+    (
+        StatusCode::OK,
+        Json(TimeReportResponse {
+            success: true,
+            group,
+            items,
+            total_secs,
+            message: None,
+        }),
+    )
+}
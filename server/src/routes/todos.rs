@@ -1,6 +1,8 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -9,7 +11,13 @@ use serde_json::json;
 
 use crate::auth::{ActiveUserId, UserId};
 use crate::models::todo::*;
-use crate::services::collaboration;
+use crate::services::changelog::{self, ChangeSet};
+use crate::services::pagination::{self, PageInfo, PagePlan, PageQuery, SortField, SortKey};
+use crate::services::todo_filter::{self, TodoFilter};
+use crate::services::{
+    attachments, checklist, collaboration, comments, completion, dependencies, etag, history,
+    ordering, projects, push, recurrence, time, time_tracking,
+};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -18,6 +26,8 @@ pub struct TodosResponse {
     pub items: Vec<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -36,9 +46,23 @@ pub struct SimpleResponse {
     pub message: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BatchUpdateResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Change set id, for reverting the whole batch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListQuery {
     pub tab: Option<String>,
+    /// Filter expression, e.g. `tag:work due<7d !completed`
+    pub filter: Option<String>,
+    /// Saved view id or name
+    pub view: Option<String>,
 }
 
 /// Load the next pending/triggered reminder for a todo
@@ -67,7 +91,7 @@ fn load_next_reminder(
 fn load_changelog(db: &rusqlite::Connection, todo_id: &str) -> Vec<ChangeEntry> {
     let mut stmt = db
         .prepare(
-            "SELECT field, label, from_val, to_val, time FROM todo_changelog WHERE todo_id = ?1 ORDER BY id DESC LIMIT 50",
+            "SELECT field, label, from_val, to_val, time, id, changed_by, batch_id, to_raw IS NOT NULL FROM todo_changelog WHERE todo_id = ?1 ORDER BY id DESC LIMIT 50",
         )
         .unwrap();
     stmt.query_map([todo_id], |row| {
@@ -77,6 +101,10 @@ fn load_changelog(db: &rusqlite::Connection, todo_id: &str) -> Vec<ChangeEntry>
             old_value: row.get(2).unwrap_or_default(),
             new_value: row.get(3).unwrap_or_default(),
             timestamp: row.get(4)?,
+            id: row.get(5)?,
+            changed_by: row.get(6)?,
+            batch_id: row.get(7)?,
+            revertible: row.get(8)?,
         })
     })
     .unwrap()
@@ -109,69 +137,206 @@ fn row_to_todo(row: &rusqlite::Row) -> rusqlite::Result<Todo> {
         deleted_at: row.get(14)?,
         changelog: Vec::new(), // loaded separately
         next_reminder: None,   // loaded separately
+        checklist: Vec::new(), // loaded separately
+        recurrence: row.get(15).unwrap_or_default(),
+        recurrence_prev_id: row.get(16).unwrap_or_default(),
+        project_id: row.get(17).unwrap_or_default(),
+        blocked_by: Vec::new(), // loaded separately
+        blocking: Vec::new(),   // loaded separately
+        blocked: false,
+        attachment_count: 0,
+        time_spent_secs: 0,
+        sort_order: row.get(18).unwrap_or_default(),
     })
 }
 
+/// Run a todo SELECT with extra conditions plus a compiled filter, one page
+/// at a time; also returns how many todos match in total
+fn select_todos(
+    db: &rusqlite::Connection,
+    select: &str,
+    mut conditions: Vec<String>,
+    mut params: Vec<Box<dyn rusqlite::types::ToSql>>,
+    filter: &TodoFilter,
+    cols: &todo_filter::Columns,
+    plan: &PagePlan<Todo>,
+) -> rusqlite::Result<(Vec<Todo>, usize)> {
+    let (filter_conditions, filter_params) = filter.to_sql(cols, params.len() + 1);
+    conditions.extend(filter_conditions);
+    params.extend(filter_params);
+
+    let select = format!("{} WHERE {}", select, conditions.join(" AND "));
+    let total: i64 = db.query_row(
+        &pagination::count_sql(&select),
+        rusqlite::params_from_iter(params.iter()),
+        |r| r.get(0),
+    )?;
+    let (sql, cursor_params) = plan.sql(&select, params.len() + 1);
+    let mut param_refs: Vec<&dyn rusqlite::types::ToSql> =
+        params.iter().map(|p| p.as_ref()).collect();
+    param_refs.extend(
+        cursor_params
+            .iter()
+            .map(|p| p as &dyn rusqlite::types::ToSql),
+    );
+    let items = db
+        .prepare(&sql)?
+        .query_map(param_refs.as_slice(), row_to_todo)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok((items, total as usize))
+}
+
+/// Own and collaborative todos visible to the user, optionally limited to a
+/// tab and a filter, cut to one page
+fn query_visible_todos(
+    db: &rusqlite::Connection,
+    user_id: &str,
+    tab: Option<&str>,
+    filter: &TodoFilter,
+    plan: &PagePlan<Todo>,
+) -> rusqlite::Result<(Vec<Todo>, PageInfo)> {
+    let mut own_conditions = vec!["user_id = ?1".to_string(), "deleted = 0".to_string()];
+    // Own todos take priority when the user is also listed as a collaborator
+    let mut collab_conditions = vec![
+        "tc.user_id = ?1".to_string(),
+        "tc.status = 'active'".to_string(),
+        "t.deleted = 0".to_string(),
+        "t.user_id != ?1".to_string(),
+    ];
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(user_id.to_string())];
+    let mut collab_params: Vec<Box<dyn rusqlite::types::ToSql>> =
+        vec![Box::new(user_id.to_string())];
+    if let Some(tab) = tab {
+        own_conditions.push("tab = ?2".into());
+        collab_conditions.push("tc.tab = ?2".into());
+        params.push(Box::new(tab.to_string()));
+        collab_params.push(Box::new(tab.to_string()));
+    }
+
+    let (mut items, own_total) = select_todos(
+        db,
+        "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at, recurrence, recurrence_prev_id, project_id, sort_order FROM todos",
+        own_conditions,
+        params,
+        filter,
+        &todo_filter::OWN_COLUMNS,
+        plan,
+    )?;
+
+    // Collaborative todos (from todo_collaborators) - use collaborator view settings
+    let (collab_items, collab_total) = select_todos(
+        db,
+        "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at, t.recurrence, t.recurrence_prev_id, t.project_id, tc.sort_order FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id",
+        collab_conditions,
+        collab_params,
+        filter,
+        &todo_filter::COLLAB_COLUMNS,
+        plan,
+    )?;
+
+    // Each side holds at most one page past the cursor; merge them
+    items.extend(collab_items);
+    Ok(plan.page(items, own_total + collab_total, |t| &t.id))
+}
+
+/// Sort options of `GET /api/todos`; the default keeps open todos first,
+/// then follows the manual order
+const TODO_SORTS: [SortField<Todo>; 5] = [
+    SortField {
+        name: "default",
+        descending: false,
+        key: |t| {
+            vec![
+                SortKey::Int(t.completed as i64),
+                SortKey::Float(t.sort_order),
+                SortKey::text(&t.created_at),
+            ]
+        },
+        columns: &["completed", "sort_order", "created_at"],
+    },
+    SortField {
+        name: "created",
+        descending: true,
+        key: |t| vec![SortKey::text(&t.created_at)],
+        columns: &["created_at"],
+    },
+    SortField {
+        name: "updated",
+        descending: true,
+        key: |t| vec![SortKey::text(&t.updated_at)],
+        columns: &["updated_at"],
+    },
+    SortField {
+        name: "due",
+        descending: false,
+        key: |t| SortKey::nulls_last(t.due_date.as_deref()).to_vec(),
+        columns: &["(due_date IS NULL)", "COALESCE(due_date, '')"],
+    },
+    SortField {
+        name: "text",
+        descending: false,
+        key: |t| vec![SortKey::text(&t.text)],
+        columns: &["text"],
+    },
+];
+
+fn list_error(status: StatusCode, message: String) -> (StatusCode, Json<TodosResponse>) {
+    (
+        status,
+        Json(TodosResponse {
+            success: false,
+            items: Vec::new(),
+            message: Some(message),
+            page: None,
+        }),
+    )
+}
+
 pub async fn list_todos(
     State(state): State<AppState>,
     user_id: UserId,
     Query(query): Query<ListQuery>,
+    Query(page_query): Query<PageQuery>,
 ) -> (StatusCode, Json<TodosResponse>) {
     let db = state.db.lock();
 
-    // Own todos
-    let mut items: Vec<Todo> = if let Some(tab) = &query.tab {
-        let mut stmt = db
-            .prepare(
-                "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at FROM todos WHERE user_id = ?1 AND tab = ?2 AND deleted = 0 ORDER BY completed ASC, created_at ASC",
-            )
-            .unwrap();
-        stmt.query_map(rusqlite::params![user_id.0, tab], row_to_todo)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
-    } else {
-        let mut stmt = db
-            .prepare(
-                "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at FROM todos WHERE user_id = ?1 AND deleted = 0 ORDER BY completed ASC, created_at ASC",
-            )
-            .unwrap();
-        stmt.query_map([&user_id.0], row_to_todo)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
+    // A saved view and an ad-hoc filter combine with AND
+    let mut expression = String::new();
+    if let Some(view) = query.view.as_deref().filter(|v| !v.is_empty()) {
+        match todo_filter::saved_view_filter(&db, &user_id.0, view) {
+            Some(f) => expression.push_str(&f),
+            None => return list_error(StatusCode::NOT_FOUND, format!("视图不存在: {}", view)),
+        }
+    }
+    if let Some(filter) = &query.filter {
+        expression.push(' ');
+        expression.push_str(filter);
+    }
+    let filter = match TodoFilter::parse(&expression, time::today()) {
+        Ok(f) => f,
+        Err(msg) => return list_error(StatusCode::BAD_REQUEST, msg),
     };
 
-    // Collaborative todos (from todo_collaborators) - use collaborator view settings
-    let collab_sql_tab = "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE tc.user_id = ?1 AND tc.status = 'active' AND t.deleted = 0 AND tc.tab = ?2 ORDER BY t.completed ASC, t.created_at ASC";
-    let collab_sql_all = "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE tc.user_id = ?1 AND tc.status = 'active' AND t.deleted = 0 ORDER BY t.completed ASC, t.created_at ASC";
-
-    let collab_items: Vec<Todo> = if let Some(tab) = &query.tab {
-        let mut stmt = db.prepare(collab_sql_tab).unwrap();
-        stmt.query_map(rusqlite::params![user_id.0, tab], row_to_todo)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
-    } else {
-        let mut stmt = db.prepare(collab_sql_all).unwrap();
-        stmt.query_map([&user_id.0], row_to_todo)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
+    let plan = match PagePlan::new(&page_query, &TODO_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => return list_error(StatusCode::BAD_REQUEST, msg),
     };
-
-    // Merge and deduplicate (own todos take priority)
-    let own_ids: std::collections::HashSet<String> = items.iter().map(|t| t.id.clone()).collect();
-    for ct in collab_items {
-        if !own_ids.contains(&ct.id) {
-            items.push(ct);
-        }
-    }
+    let (mut items, page) =
+        match query_visible_todos(&db, &user_id.0, query.tab.as_deref(), &filter, &plan) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[todos] list query error: {}", e);
+                return list_error(StatusCode::INTERNAL_SERVER_ERROR, "读取待办失败".into());
+            }
+        };
 
     // Load changelogs and next reminders
     for todo in &mut items {
         todo.changelog = load_changelog(&db, &todo.id);
         todo.next_reminder = load_next_reminder(&db, &todo.id, &user_id.0);
+        todo.checklist = checklist::load_items(&db, &todo.id);
+        attach_related(&db, todo);
     }
 
     // Enrich with collaboration info
@@ -192,49 +357,62 @@ pub async fn list_todos(
             success: true,
             items: enriched_items,
             message: None,
+            page: Some(page),
         }),
     )
 }
 
-pub async fn get_todo(
-    State(state): State<AppState>,
-    user_id: UserId,
-    Path(id): Path<String>,
-) -> (StatusCode, Json<TodoResponse>) {
-    let db = state.db.lock();
+/// Fill in dependency links, attachment count and tracked time
+fn attach_related(db: &rusqlite::Connection, todo: &mut Todo) {
+    dependencies::attach(db, todo);
+    todo.attachment_count = attachments::count(db, &todo.id);
+    todo.time_spent_secs = time_tracking::total_secs(db, &todo.id, chrono::Utc::now());
+}
 
-    // Try owner first, then collaborator
+/// Load a todo visible to the user: owner view first, then collaborator view
+fn fetch_todo(db: &rusqlite::Connection, id: &str, user_id: &str) -> rusqlite::Result<Todo> {
     let result = db.query_row(
-        "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at FROM todos WHERE id = ?1 AND user_id = ?2",
-        rusqlite::params![id, user_id.0],
+        "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at, recurrence, recurrence_prev_id, project_id, sort_order FROM todos WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![id, user_id],
         row_to_todo,
     );
 
-    let result = match result {
-        Ok(todo) => Ok(todo),
+    let mut todo = match result {
+        Ok(todo) => todo,
         Err(_) => {
             db.query_row(
-                "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE t.id = ?1 AND tc.user_id = ?2 AND tc.status = 'active' AND t.deleted = 0",
-                rusqlite::params![id, user_id.0],
+                "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at, t.recurrence, t.recurrence_prev_id, t.project_id, tc.sort_order FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE t.id = ?1 AND tc.user_id = ?2 AND tc.status = 'active' AND t.deleted = 0",
+                rusqlite::params![id, user_id],
                 row_to_todo,
-            )
+            )?
         }
     };
+    todo.changelog = load_changelog(db, &todo.id);
+    todo.checklist = checklist::load_items(db, &todo.id);
+    attach_related(db, &mut todo);
+    Ok(todo)
+}
 
-    match result {
-        Ok(mut todo) => {
-            todo.changelog = load_changelog(&db, &todo.id);
-            (
-                StatusCode::OK,
-                Json(TodoResponse {
-                    success: true,
-                    item: Some(todo),
-                    message: None,
-                }),
-            )
-        }
+pub async fn get_todo(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, HeaderMap, Json<TodoResponse>) {
+    let db = state.db.lock();
+
+    match fetch_todo(&db, &id, &user_id.0) {
+        Ok(todo) => (
+            StatusCode::OK,
+            etag::headers(&todo.updated_at),
+            Json(TodoResponse {
+                success: true,
+                item: Some(todo),
+                message: None,
+            }),
+        ),
         Err(_) => (
             StatusCode::NOT_FOUND,
+            HeaderMap::new(),
             Json(TodoResponse {
                 success: false,
                 item: None,
@@ -271,17 +449,71 @@ pub async fn create_todo(
         );
     }
 
+    let recurrence_rule = match recurrence::normalize_rule(req.recurrence.as_deref().unwrap_or(""))
+    {
+        Ok(r) => r,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(TodoResponse {
+                    success: false,
+                    item: None,
+                    message: Some(msg),
+                }),
+            )
+        }
+    };
+
+    let checklist_texts: Vec<String> = req
+        .checklist
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| !t.trim().is_empty())
+        .collect();
+    if checklist_texts.len() > checklist::MAX_ITEMS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(TodoResponse {
+                success: false,
+                item: None,
+                message: Some(format!("每个任务最多 {} 个清单项", checklist::MAX_ITEMS)),
+            }),
+        );
+    }
+
     let db = state.db.lock();
+    let project_id = match req.project_id.as_deref().map(str::trim) {
+        Some(key) if !key.is_empty() => match projects::resolve(&db, &user_id.0, key) {
+            Some((project_id, _)) => Some(project_id),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(TodoResponse {
+                        success: false,
+                        item: None,
+                        message: Some(format!("项目不存在: {}", key)),
+                    }),
+                )
+            }
+        },
+        _ => None,
+    };
     let now = chrono::Utc::now().to_rfc3339();
     let id = Todo::generate_id();
-    let progress = req.progress.unwrap_or(0).min(100);
+    // A checklist drives progress, so a fresh one starts at 0
+    let progress = if checklist_texts.is_empty() {
+        req.progress.unwrap_or(0).min(100)
+    } else {
+        0
+    };
     let completed = progress == 100;
     let completed_at = if completed { Some(now.clone()) } else { None };
     let tags = req.tags.unwrap_or_default();
     let tags_json = serde_json::to_string(&tags).unwrap();
+    let sort_order = ordering::append_key(&db, &user_id.0, &req.tab, &req.quadrant);
 
     if let Err(e) = db.execute(
-        "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, assignee, tags, created_at, updated_at, deleted, deleted_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,0,NULL)",
+        "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, assignee, tags, created_at, updated_at, deleted, deleted_at, recurrence, project_id, sort_order) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,0,NULL,?15,?16,?17)",
         rusqlite::params![
             id,
             user_id.0,
@@ -297,6 +529,9 @@ pub async fn create_todo(
             tags_json,
             now,
             now,
+            recurrence_rule,
+            project_id,
+            sort_order,
         ],
     ) {
         eprintln!("[todos] create_todo DB error: {}", e);
@@ -306,6 +541,14 @@ pub async fn create_todo(
         }));
     }
 
+    let mut checklist_items = Vec::new();
+    for text in &checklist_texts {
+        match checklist::add_item(&db, &id, text, &now) {
+            Ok(item) => checklist_items.push(item),
+            Err(e) => eprintln!("[todos] create_todo checklist error: {}", e),
+        }
+    }
+
     let todo = Todo {
         id,
         text: req.text,
@@ -324,6 +567,16 @@ pub async fn create_todo(
         deleted: false,
         deleted_at: None,
         next_reminder: None,
+        checklist: checklist_items,
+        recurrence: recurrence_rule,
+        recurrence_prev_id: None,
+        project_id,
+        blocked_by: Vec::new(),
+        blocking: Vec::new(),
+        blocked: false,
+        attachment_count: 0,
+        time_spent_secs: 0,
+        sort_order,
     };
 
     (
@@ -340,24 +593,54 @@ pub async fn update_todo(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(update): Json<TodoUpdate>,
-) -> (StatusCode, Json<TodoResponse>) {
+) -> (StatusCode, HeaderMap, Json<TodoResponse>) {
     let db = state.db.lock();
 
+    if let Ok(current) = fetch_todo(&db, &id, &user_id.0) {
+        if !etag::matches(&headers, &current.updated_at) {
+            return (
+                StatusCode::PRECONDITION_FAILED,
+                etag::headers(&current.updated_at),
+                Json(TodoResponse {
+                    success: false,
+                    item: Some(current),
+                    message: Some("任务已在其他地方被修改，请合并后重试".into()),
+                }),
+            );
+        }
+    }
+
+    let (status, Json(resp)) = apply_update(&db, &user_id.0, &id, update);
+    let headers = resp
+        .item
+        .as_ref()
+        .map(|t| etag::headers(&t.updated_at))
+        .unwrap_or_default();
+    (status, headers, Json(resp))
+}
+
+fn apply_update(
+    db: &rusqlite::Connection,
+    user_id: &str,
+    id: &str,
+    update: TodoUpdate,
+) -> (StatusCode, Json<TodoResponse>) {
     // Check role: owner or collaborator
-    let is_collaborator = !collaboration::check_todo_owner(&db, &id, &user_id.0)
-        && collaboration::check_todo_collaborator(&db, &id, &user_id.0);
+    let is_collaborator = !collaboration::check_todo_owner(db, id, user_id)
+        && collaboration::check_todo_collaborator(db, id, user_id);
 
     let current = if is_collaborator {
         db.query_row(
-            "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE t.id = ?1 AND tc.user_id = ?2 AND tc.status = 'active'",
-            rusqlite::params![id, user_id.0],
+            "SELECT t.id, t.text, t.content, tc.tab, tc.quadrant, t.progress, t.completed_at, t.completed, t.due_date, t.deleted, t.assignee, t.tags, t.created_at, t.updated_at, t.deleted_at, t.recurrence, t.recurrence_prev_id, t.project_id, tc.sort_order FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id WHERE t.id = ?1 AND tc.user_id = ?2 AND tc.status = 'active'",
+            rusqlite::params![id, user_id],
             row_to_todo,
         )
     } else {
         db.query_row(
-            "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at FROM todos WHERE id = ?1 AND user_id = ?2",
-            rusqlite::params![id, user_id.0],
+            "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at, recurrence, recurrence_prev_id, project_id, sort_order FROM todos WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id],
             row_to_todo,
         )
     };
@@ -376,93 +659,97 @@ pub async fn update_todo(
         }
     };
 
+    let new_recurrence = match update.recurrence.as_deref().map(recurrence::normalize_rule) {
+        Some(Ok(r)) => Some(r),
+        Some(Err(msg)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(TodoResponse {
+                    success: false,
+                    item: None,
+                    message: Some(msg),
+                }),
+            )
+        }
+        None => None,
+    };
+
     let now = chrono::Utc::now().to_rfc3339();
 
+    // Projects belong to the owner, so only the owner files a todo under one
+    if let Some(key) = &update.project_id {
+        if is_collaborator {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(TodoResponse {
+                    success: false,
+                    item: None,
+                    message: Some("只有任务创建者可以设置项目".into()),
+                }),
+            );
+        }
+        match projects::assign_todo(db, id, user_id, Some(key.as_str()), &now) {
+            Ok(project_id) => todo.project_id = project_id,
+            Err(msg) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(TodoResponse {
+                        success: false,
+                        item: None,
+                        message: Some(msg),
+                    }),
+                )
+            }
+        }
+    }
+
+    let was_completed = todo.completed;
+    let old_position = (todo.tab.as_str(), todo.quadrant.as_str());
+    todo.checklist = checklist::load_items(db, id);
+
     // Track changes and apply updates
+    let changes = ChangeSet::new(user_id, &now);
     if let Some(text) = &update.text {
         if *text != todo.text {
-            insert_changelog(
-                &db,
-                &id,
-                "text",
-                Todo::field_label("text"),
-                &todo.text,
-                text,
-                &now,
-            );
+            changes.record(db, id, "text", &todo.text, text);
             todo.text = text.clone();
         }
     }
     if let Some(content) = &update.content {
         if *content != todo.content {
-            insert_changelog(
-                &db,
-                &id,
-                "content",
-                Todo::field_label("content"),
-                "(已更新)",
-                "(已更新)",
-                &now,
-            );
+            changes.record(db, id, "content", &todo.content, content);
             todo.content = content.clone();
         }
     }
     if let Some(tab_str) = &update.tab {
         let old_tab = todo.tab.as_str().to_string();
         if *tab_str != old_tab {
-            insert_changelog(
-                &db,
-                &id,
-                "tab",
-                Todo::field_label("tab"),
-                &old_tab,
-                tab_str,
-                &now,
-            );
+            changes.record(db, id, "tab", &old_tab, tab_str);
             todo.tab = Tab::parse(tab_str);
         }
     }
     if let Some(q_str) = &update.quadrant {
         let old_q = todo.quadrant.as_str().to_string();
         if *q_str != old_q {
-            let old_label = todo.quadrant.label();
             let new_q = Quadrant::parse(q_str);
-            let new_label = new_q.label();
-            insert_changelog(
-                &db,
-                &id,
-                "quadrant",
-                Todo::field_label("quadrant"),
-                old_label,
-                new_label,
-                &now,
-            );
+            changes.record(db, id, "quadrant", &old_q, new_q.as_str());
             todo.quadrant = new_q;
         }
     }
-    if let Some(progress) = update.progress {
+    // Progress of a todo with a checklist is derived from its items
+    if let Some(progress) = update.progress.filter(|_| todo.checklist.is_empty()) {
         let new_progress = progress.min(100);
         if new_progress != todo.progress {
-            insert_changelog(
-                &db,
-                &id,
+            changes.record(
+                db,
+                id,
                 "progress",
-                Todo::field_label("progress"),
                 &todo.progress.to_string(),
                 &new_progress.to_string(),
-                &now,
             );
             todo.progress = new_progress;
             if new_progress == 100 && !todo.completed {
-                insert_changelog(
-                    &db,
-                    &id,
-                    "completed",
-                    Todo::field_label("completed"),
-                    "未完成",
-                    "已完成",
-                    &now,
-                );
+                changes.record(db, id, "completed", "0", "1");
                 todo.completed = true;
                 todo.completed_at = Some(now.clone());
             }
@@ -470,18 +757,12 @@ pub async fn update_todo(
     }
     if let Some(completed) = update.completed {
         if completed != todo.completed {
-            insert_changelog(
-                &db,
-                &id,
+            changes.record(
+                db,
+                id,
                 "completed",
-                Todo::field_label("completed"),
-                if todo.completed {
-                    "已完成"
-                } else {
-                    "未完成"
-                },
-                if completed { "已完成" } else { "未完成" },
-                &now,
+                if todo.completed { "1" } else { "0" },
+                if completed { "1" } else { "0" },
             );
             todo.completed = completed;
             todo.completed_at = if completed { Some(now.clone()) } else { None };
@@ -490,61 +771,54 @@ pub async fn update_todo(
     if let Some(due_date) = &update.due_date {
         let old = todo.due_date.clone().unwrap_or_default();
         if *due_date != old {
-            insert_changelog(
-                &db,
-                &id,
-                "due_date",
-                Todo::field_label("due_date"),
-                &old,
-                due_date,
-                &now,
-            );
+            changes.record(db, id, "due_date", &old, due_date);
             todo.due_date = Some(due_date.clone());
         }
     }
     if let Some(assignee) = &update.assignee {
         if *assignee != todo.assignee {
-            insert_changelog(
-                &db,
-                &id,
-                "assignee",
-                Todo::field_label("assignee"),
-                &todo.assignee,
-                assignee,
-                &now,
-            );
+            changes.record(db, id, "assignee", &todo.assignee, assignee);
             todo.assignee = assignee.clone();
         }
     }
     if let Some(tags) = &update.tags {
-        let old_tags = todo.tags.join(", ");
-        let new_tags = tags.join(", ");
-        if old_tags != new_tags {
-            insert_changelog(
-                &db,
-                &id,
+        if *tags != todo.tags {
+            changes.record(
+                db,
+                id,
                 "tags",
-                Todo::field_label("tags"),
-                &old_tags,
-                &new_tags,
-                &now,
+                &serde_json::to_string(&todo.tags).unwrap(),
+                &serde_json::to_string(tags).unwrap(),
             );
             todo.tags = tags.clone();
         }
     }
 
-    todo.updated_at = now;
+    if let Some(rule) = new_recurrence {
+        if rule != todo.recurrence {
+            changes.record(
+                db,
+                id,
+                "recurrence",
+                todo.recurrence.as_deref().unwrap_or(""),
+                rule.as_deref().unwrap_or(""),
+            );
+            todo.recurrence = rule;
+        }
+    }
+
+    todo.updated_at = now.clone();
     let tags_json = serde_json::to_string(&todo.tags).unwrap();
 
     if is_collaborator {
         // Collaborator: tab/quadrant updates go to todo_collaborators
         db.execute(
             "UPDATE todo_collaborators SET tab=?1, quadrant=?2 WHERE todo_id=?3 AND user_id=?4 AND status='active'",
-            rusqlite::params![todo.tab.as_str(), todo.quadrant.as_str(), id, user_id.0],
+            rusqlite::params![todo.tab.as_str(), todo.quadrant.as_str(), id, user_id],
         ).ok();
         // Shared fields update the main todos table (verify collaborator access via subquery)
         db.execute(
-            "UPDATE todos SET text=?1, content=?2, progress=?3, completed=?4, completed_at=?5, due_date=?6, assignee=?7, tags=?8, updated_at=?9 WHERE id=?10 AND id IN (SELECT todo_id FROM todo_collaborators WHERE user_id=?11 AND status='active')",
+            "UPDATE todos SET text=?1, content=?2, progress=?3, completed=?4, completed_at=?5, due_date=?6, assignee=?7, tags=?8, updated_at=?9, recurrence=?10 WHERE id=?11 AND id IN (SELECT todo_id FROM todo_collaborators WHERE user_id=?12 AND status='active')",
             rusqlite::params![
                 todo.text,
                 todo.content,
@@ -555,12 +829,13 @@ pub async fn update_todo(
                 todo.assignee,
                 tags_json,
                 todo.updated_at,
+                todo.recurrence,
                 id,
-                user_id.0,
+                user_id,
             ],
         ).ok();
     } else if let Err(e) = db.execute(
-        "UPDATE todos SET text=?1, content=?2, tab=?3, quadrant=?4, progress=?5, completed=?6, completed_at=?7, due_date=?8, assignee=?9, tags=?10, updated_at=?11 WHERE id=?12 AND user_id=?13",
+        "UPDATE todos SET text=?1, content=?2, tab=?3, quadrant=?4, progress=?5, completed=?6, completed_at=?7, due_date=?8, assignee=?9, tags=?10, updated_at=?11, recurrence=?12 WHERE id=?13 AND user_id=?14",
         rusqlite::params![
             todo.text,
             todo.content,
@@ -573,8 +848,9 @@ pub async fn update_todo(
             todo.assignee,
             tags_json,
             todo.updated_at,
+            todo.recurrence,
             id,
-            user_id.0,
+            user_id,
         ],
     ) {
         eprintln!("[todos] update_todo DB error: {}", e);
@@ -584,41 +860,36 @@ pub async fn update_todo(
         }));
     }
 
-    todo.changelog = load_changelog(&db, &id);
+    // A todo moved to another list goes to its end
+    if old_position != (todo.tab.as_str(), todo.quadrant.as_str()) {
+        if let Ok(key) = ordering::place(db, user_id, id, None, None, &now) {
+            todo.sort_order = key;
+        }
+    }
+
+    let spawned = if todo.completed && !was_completed {
+        completion::on_completed(db, id, &now)
+    } else {
+        None
+    };
+
+    todo.changelog = load_changelog(db, id);
+    attach_related(db, &mut todo);
 
     (
         StatusCode::OK,
         Json(TodoResponse {
             success: true,
             item: Some(todo),
-            message: Some("任务更新成功".into()),
+            message: Some(if spawned.is_some() {
+                "任务已完成，已生成下一次重复任务".into()
+            } else {
+                "任务更新成功".into()
+            }),
         }),
     )
 }
 
-fn insert_changelog(
-    db: &rusqlite::Connection,
-    todo_id: &str,
-    field: &str,
-    label: &str,
-    from: &str,
-    to: &str,
-    time: &str,
-) {
-    db.execute(
-        "INSERT INTO todo_changelog (todo_id, field, label, from_val, to_val, time) VALUES (?1,?2,?3,?4,?5,?6)",
-        rusqlite::params![todo_id, field, label, from, to, time],
-    )
-    .ok();
-
-    // Keep only 50 most recent entries per todo
-    db.execute(
-        "DELETE FROM todo_changelog WHERE todo_id = ?1 AND id NOT IN (SELECT id FROM todo_changelog WHERE todo_id = ?1 ORDER BY id DESC LIMIT 50)",
-        [todo_id],
-    )
-    .ok();
-}
-
 pub async fn delete_todo(
     State(state): State<AppState>,
     user_id: ActiveUserId,
@@ -700,13 +971,15 @@ pub async fn restore_todo(
 
     let todo = db
         .query_row(
-            "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at FROM todos WHERE id = ?1",
+            "SELECT id, text, content, tab, quadrant, progress, completed_at, completed, due_date, deleted, assignee, tags, created_at, updated_at, deleted_at, recurrence, recurrence_prev_id, project_id, sort_order FROM todos WHERE id = ?1",
             [&id],
             row_to_todo,
         );
     match todo {
         Ok(mut t) => {
             t.changelog = load_changelog(&db, &id);
+            t.checklist = checklist::load_items(&db, &id);
+            attach_related(&db, &mut t);
             (
                 StatusCode::OK,
                 Json(TodoResponse {
@@ -737,6 +1010,9 @@ pub async fn permanent_delete_todo(
 ) -> (StatusCode, Json<SimpleResponse>) {
     let db = state.db.lock();
 
+    if collaboration::check_todo_owner(&db, &id, &user_id.0) {
+        attachments::remove_files(&db, &id);
+    }
     let rows = db
         .execute(
             "DELETE FROM todos WHERE id = ?1 AND user_id = ?2",
@@ -767,19 +1043,23 @@ pub async fn batch_update_todos(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Json(updates): Json<Vec<BatchUpdateItem>>,
-) -> (StatusCode, Json<SimpleResponse>) {
+) -> (StatusCode, Json<BatchUpdateResponse>) {
     if updates.len() > 200 {
         return (
             StatusCode::BAD_REQUEST,
-            Json(SimpleResponse {
+            Json(BatchUpdateResponse {
                 success: false,
                 message: Some("批量更新上限 200 条".into()),
+                batch_id: None,
             }),
         );
     }
     let db = state.db.lock();
     let now = chrono::Utc::now().to_rfc3339();
     let mut updated_count = 0;
+    // All items share one change set so the batch can be reverted as a unit
+    let changes = ChangeSet::new(&user_id.0, &now);
+    let mut changed_fields = 0;
 
     for item in &updates {
         // Verify ownership
@@ -794,6 +1074,8 @@ pub async fn batch_update_todos(
         if !owned {
             continue;
         }
+        let before = changelog::snapshot(&db, &item.id).unwrap_or_default();
+        let old_position = ordering::position(&db, &user_id.0, &item.id);
 
         if let Some(tab) = &item.tab {
             db.execute(
@@ -809,11 +1091,17 @@ pub async fn batch_update_todos(
             )
             .ok();
         }
-        if let Some(progress) = item.progress {
+        // Progress of a todo with a checklist is derived from its items
+        if let Some(progress) = item
+            .progress
+            .filter(|_| checklist::load_items(&db, &item.id).is_empty())
+        {
             let p = progress.min(100) as i32;
             let completed = if p == 100 { 1 } else { 0 };
             db.execute(
-                "UPDATE todos SET progress = ?1, completed = ?2, updated_at = ?3 WHERE id = ?4 AND user_id = ?5",
+                "UPDATE todos SET progress = ?1, completed = ?2, \
+                 completed_at = CASE WHEN ?2 = 1 THEN COALESCE(completed_at, ?3) ELSE NULL END, updated_at = ?3 \
+                 WHERE id = ?4 AND user_id = ?5",
                 rusqlite::params![p, completed, now, item.id, user_id.0],
             )
             .ok();
@@ -831,18 +1119,66 @@ pub async fn batch_update_todos(
             )
             .ok();
         }
+        // Drag-and-drop sends the drop position; a move without one goes to the end
+        if item.before.is_some() || item.after.is_some() {
+            if let Err(msg) = ordering::place(
+                &db,
+                &user_id.0,
+                &item.id,
+                item.before.as_deref(),
+                item.after.as_deref(),
+                &now,
+            ) {
+                eprintln!("[todos] batch reorder {} skipped: {}", item.id, msg);
+            }
+        } else if ordering::position(&db, &user_id.0, &item.id) != old_position {
+            ordering::place(&db, &user_id.0, &item.id, None, None, &now).ok();
+        }
+        let after = changelog::snapshot(&db, &item.id).unwrap_or_default();
+        changed_fields += changes.record_diff(&db, &item.id, &before, &after);
+        let completed = |s: &[(&str, String)]| s.iter().any(|(f, v)| *f == "completed" && v == "1");
+        if completed(&after) && !completed(&before) {
+            completion::on_completed(&db, &item.id, &now);
+        }
         updated_count += 1;
     }
 
     (
         StatusCode::OK,
-        Json(SimpleResponse {
+        Json(BatchUpdateResponse {
             success: true,
             message: Some(format!("已更新 {} 个任务", updated_count)),
+            batch_id: (changed_fields > 0).then_some(changes.id),
         }),
     )
 }
 
+/// Move a todo before or after a sibling in its tab and quadrant
+pub async fn move_todo(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<MoveTodoRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if ordering::position(&db, &user_id.0, &id).is_none() {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(msg) = ordering::place(
+        &db,
+        &user_id.0,
+        &id,
+        req.before.as_deref(),
+        req.after.as_deref(),
+        &now,
+    ) {
+        return todo_error(StatusCode::BAD_REQUEST, msg);
+    }
+
+    todo_result(&db, &id, &user_id.0, "任务已排序")
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CountsQuery {
     pub tab: String,
@@ -879,3 +1215,725 @@ pub async fn get_todo_counts(
         "counts": counts
     }))
 }
+
+/// Respond with the refreshed todo after a sub-resource change
+fn todo_result(
+    db: &rusqlite::Connection,
+    id: &str,
+    user_id: &str,
+    message: &str,
+) -> (StatusCode, Json<TodoResponse>) {
+    match fetch_todo(db, id, user_id) {
+        Ok(todo) => (
+            StatusCode::OK,
+            Json(TodoResponse {
+                success: true,
+                item: Some(todo),
+                message: Some(message.into()),
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[todos] refetch error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(TodoResponse {
+                    success: false,
+                    item: None,
+                    message: Some("读取任务数据失败".into()),
+                }),
+            )
+        }
+    }
+}
+
+fn todo_error(status: StatusCode, message: String) -> (StatusCode, Json<TodoResponse>) {
+    (
+        status,
+        Json(TodoResponse {
+            success: false,
+            item: None,
+            message: Some(message),
+        }),
+    )
+}
+
+pub async fn add_checklist_item(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<CreateChecklistItemRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(msg) = checklist::add_item(&db, &id, &req.text, &now) {
+        return todo_error(StatusCode::BAD_REQUEST, msg);
+    }
+    checklist::sync_progress(&db, &id, &ChangeSet::new(&user_id.0, &now));
+
+    todo_result(&db, &id, &user_id.0, "清单项已添加")
+}
+
+pub async fn update_checklist_item(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, item_id)): Path<(String, String)>,
+    Json(req): Json<UpdateChecklistItemRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    match checklist::update_item(&db, &id, &item_id, req.text.as_deref(), req.checked, &now) {
+        Ok(true) => {}
+        Ok(false) => {
+            return todo_error(StatusCode::NOT_FOUND, format!("清单项不存在: {}", item_id))
+        }
+        Err(msg) => return todo_error(StatusCode::BAD_REQUEST, msg),
+    }
+    checklist::sync_progress(&db, &id, &ChangeSet::new(&user_id.0, &now));
+
+    todo_result(&db, &id, &user_id.0, "清单项已更新")
+}
+
+pub async fn delete_checklist_item(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, item_id)): Path<(String, String)>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if !checklist::remove_item(&db, &id, &item_id, &now) {
+        return todo_error(StatusCode::NOT_FOUND, format!("清单项不存在: {}", item_id));
+    }
+    checklist::sync_progress(&db, &id, &ChangeSet::new(&user_id.0, &now));
+
+    todo_result(&db, &id, &user_id.0, "清单项已删除")
+}
+
+pub async fn reorder_checklist(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<ReorderChecklistRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(msg) = checklist::reorder_items(&db, &id, &req.ids, &now) {
+        return todo_error(StatusCode::BAD_REQUEST, msg);
+    }
+
+    todo_result(&db, &id, &user_id.0, "清单已排序")
+}
+
+pub async fn add_dependency(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<AddDependencyRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+    if !collaboration::check_todo_participant(&db, &req.blocker_id, &user_id.0) {
+        return todo_error(
+            StatusCode::NOT_FOUND,
+            format!("前置任务不存在: {}", req.blocker_id),
+        );
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(msg) = dependencies::add_dependency(&db, &id, &req.blocker_id, &user_id.0, &now) {
+        return todo_error(StatusCode::BAD_REQUEST, msg);
+    }
+
+    todo_result(&db, &id, &user_id.0, "前置任务已添加")
+}
+
+pub async fn remove_dependency(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, blocker_id)): Path<(String, String)>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if !dependencies::remove_dependency(&db, &id, &blocker_id, &now) {
+        return todo_error(
+            StatusCode::NOT_FOUND,
+            format!("前置任务不存在: {}", blocker_id),
+        );
+    }
+
+    todo_result(&db, &id, &user_id.0, "前置任务已移除")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// RFC 3339 timestamp
+    pub at: String,
+}
+
+/// GET /api/todos/{id}/history?at= — the todo's fields as they were at a moment
+pub async fn todo_history(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"success": false, "message": format!("任务不存在: {}", id)})),
+        );
+    }
+    let Some(at) = history::parse_time(&query.at) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"success": false, "message": "无效的时间"})),
+        );
+    };
+
+    let created_at: Option<String> = db
+        .query_row("SELECT created_at FROM todos WHERE id = ?1", [&id], |r| {
+            r.get(0)
+        })
+        .ok();
+    if created_at
+        .as_deref()
+        .and_then(history::parse_time)
+        .is_some_and(|c| c > at)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"success": false, "message": "该时间点任务尚未创建"})),
+        );
+    }
+
+    match history::rewind(&db, &id, &history::RevertTarget::Time(at)) {
+        Some(rewind) => (
+            StatusCode::OK,
+            Json(json!({
+                "success": true,
+                "id": id,
+                "at": at.to_rfc3339(),
+                "item": history::state_json(&rewind.state),
+                "complete": rewind.complete,
+            })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"success": false, "message": format!("任务不存在: {}", id)})),
+        ),
+    }
+}
+
+/// POST /api/todos/{id}/revert — rewind to a changelog entry or a timestamp
+pub async fn revert_todo(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<RevertRequest>,
+) -> (StatusCode, Json<TodoResponse>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return todo_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+
+    let target = match (req.entry_id, req.at.as_deref()) {
+        (Some(entry_id), _) => {
+            let exists: bool = db
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM todo_changelog WHERE id = ?1 AND todo_id = ?2",
+                    rusqlite::params![entry_id, id],
+                    |r| r.get(0),
+                )
+                .unwrap_or(false);
+            if !exists {
+                return todo_error(
+                    StatusCode::NOT_FOUND,
+                    format!("变更记录不存在: {}", entry_id),
+                );
+            }
+            history::RevertTarget::Entry(entry_id)
+        }
+        (None, Some(at)) => match history::parse_time(at) {
+            Some(t) => history::RevertTarget::Time(t),
+            None => return todo_error(StatusCode::BAD_REQUEST, "无效的时间".into()),
+        },
+        (None, None) => return todo_error(StatusCode::BAD_REQUEST, "请指定 entry_id 或 at".into()),
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    match history::revert_todo(
+        &db,
+        &id,
+        &user_id.0,
+        &target,
+        &ChangeSet::new(&user_id.0, &now),
+    ) {
+        Ok(0) => todo_result(&db, &id, &user_id.0, "没有需要恢复的变更"),
+        Ok(_) => todo_result(&db, &id, &user_id.0, "已恢复到所选版本"),
+        Err(msg) => todo_error(StatusCode::NOT_FOUND, msg),
+    }
+}
+
+/// POST /api/todos/batch/{batch_id}/revert — undo one change set across todos
+pub async fn revert_batch(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(batch_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    let changes = ChangeSet::new(&user_id.0, &now);
+    let result = history::revert_batch(&db, &batch_id, &user_id.0, &changes);
+    if result["reverted"] == 0 && result["conflicts"].as_array().is_none_or(|c| c.is_empty()) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"success": false, "message": "没有可撤销的变更"})),
+        );
+    }
+
+    let mut body = result;
+    body["success"] = json!(true);
+    (StatusCode::OK, Json(body))
+}
+
+// ─── Attachments ───
+
+/// GET /api/todos/{id}/attachments
+pub async fn list_attachments(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"success": false, "message": format!("任务不存在: {}", id)})),
+        );
+    }
+    (
+        StatusCode::OK,
+        Json(json!({"success": true, "items": attachments::load(&db, &id)})),
+    )
+}
+
+/// POST /api/todos/{id}/attachments — multipart upload, one file per field.
+/// Files go to the todo owner's upload directory so collaborators' uploads
+/// are cleaned up with the owner's data.
+pub async fn upload_attachments(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    mut multipart: Multipart,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (owner, mut count) = {
+        let db = state.db.lock();
+        if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"success": false, "message": format!("任务不存在: {}", id)})),
+            );
+        }
+        (
+            collaboration::get_todo_owner(&db, &id).unwrap_or_else(|| user_id.0.clone()),
+            attachments::count(&db, &id),
+        )
+    };
+
+    let upload_dir = format!("{}/todos", crate::routes::expenses::upload_dir(&owner));
+    std::fs::create_dir_all(&upload_dir).ok();
+
+    let mut uploaded: Vec<TodoAttachment> = Vec::new();
+    let mut rejected: Vec<serde_json::Value> = Vec::new();
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        // Keep only the last path component of the client's file name
+        let filename = field
+            .file_name()
+            .unwrap_or("file")
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or("file")
+            .chars()
+            .take(200)
+            .collect::<String>();
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        if count >= attachments::MAX_ATTACHMENTS {
+            rejected.push(json!({
+                "filename": filename,
+                "reason": format!("每个任务最多 {} 个附件", attachments::MAX_ATTACHMENTS),
+            }));
+            continue;
+        }
+
+        let data = match field.bytes().await {
+            Ok(d) => d,
+            Err(_) => {
+                rejected.push(json!({"filename": filename, "reason": "上传失败"}));
+                continue;
+            }
+        };
+        if data.is_empty() {
+            rejected.push(json!({"filename": filename, "reason": "文件为空"}));
+            continue;
+        }
+        if data.len() > attachments::MAX_FILE_SIZE {
+            rejected.push(json!({
+                "filename": filename,
+                "reason": format!("文件不能超过 {}MB", attachments::MAX_FILE_SIZE / 1_000_000),
+            }));
+            continue;
+        }
+
+        let attachment_id = uuid::Uuid::new_v4().to_string();
+        let storage_path = format!(
+            "{}/{}.{}",
+            upload_dir,
+            attachment_id,
+            attachments::storage_ext(&filename)
+        );
+        if let Err(e) = std::fs::write(&storage_path, &data) {
+            eprintln!("[todos] attachment write error: {}", e);
+            rejected.push(json!({"filename": filename, "reason": "保存失败"}));
+            continue;
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let db = state.db.lock();
+        let result = db.execute(
+            "INSERT INTO todo_attachments (id, todo_id, user_id, filename, storage_path, file_size, mime_type, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                attachment_id,
+                id,
+                user_id.0,
+                filename,
+                storage_path,
+                data.len() as i64,
+                content_type,
+                now
+            ],
+        );
+        match result {
+            Ok(_) => {
+                changelog::insert_changelog(
+                    &db,
+                    &id,
+                    "attachment",
+                    Todo::field_label("attachment"),
+                    "",
+                    &filename,
+                    &now,
+                );
+                count += 1;
+                uploaded.push(TodoAttachment {
+                    id: attachment_id,
+                    todo_id: id.clone(),
+                    filename,
+                    file_size: data.len() as i64,
+                    mime_type: content_type,
+                    user_id: user_id.0.clone(),
+                    created_at: now,
+                    storage_path,
+                });
+            }
+            Err(e) => {
+                eprintln!("[todos] attachment insert error: {}", e);
+                std::fs::remove_file(&storage_path).ok();
+                rejected.push(json!({"filename": filename, "reason": "保存失败"}));
+            }
+        }
+    }
+
+    let status = if uploaded.is_empty() && !rejected.is_empty() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+    (
+        status,
+        Json(json!({
+            "success": !uploaded.is_empty() || rejected.is_empty(),
+            "items": uploaded,
+            "count": uploaded.len(),
+            "rejected": rejected,
+        })),
+    )
+}
+
+/// GET /api/todos/{id}/attachments/{attachment_id} — download the file
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path((id, attachment_id)): Path<(String, String)>,
+) -> Response {
+    let attachment = {
+        let db = state.db.lock();
+        if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+            return (StatusCode::NOT_FOUND, "Not found").into_response();
+        }
+        match attachments::get(&db, &id, &attachment_id) {
+            Some(a) => a,
+            None => return (StatusCode::NOT_FOUND, "Not found").into_response(),
+        }
+    };
+
+    match tokio::fs::read(&attachment.storage_path).await {
+        Ok(data) => {
+            // Only well-known types are shown inline; everything else downloads
+            let (content_type, disposition) = if attachments::is_inline(&attachment.mime_type) {
+                (attachment.mime_type.clone(), "inline")
+            } else {
+                ("application/octet-stream".to_string(), "attachment")
+            };
+            (
+                StatusCode::OK,
+                [
+                    (http::header::CONTENT_TYPE, content_type),
+                    (
+                        http::header::CONTENT_DISPOSITION,
+                        format!(
+                            "{}; filename*=UTF-8''{}",
+                            disposition,
+                            urlencoding::encode(&attachment.filename)
+                        ),
+                    ),
+                    (http::header::CACHE_CONTROL, "private, max-age=86400".into()),
+                    (http::header::X_CONTENT_TYPE_OPTIONS, "nosniff".into()),
+                ],
+                Body::from(data),
+            )
+                .into_response()
+        }
+        Err(_) => (StatusCode::NOT_FOUND, "File not found").into_response(),
+    }
+}
+
+/// DELETE /api/todos/{id}/attachments/{attachment_id} — uploader or owner only
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, attachment_id)): Path<(String, String)>,
+) -> (StatusCode, Json<SimpleResponse>) {
+    let db = state.db.lock();
+    let attachment = match attachments::get(&db, &id, &attachment_id) {
+        Some(a) if collaboration::check_todo_participant(&db, &id, &user_id.0) => a,
+        _ => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(SimpleResponse {
+                    success: false,
+                    message: Some("附件不存在".into()),
+                }),
+            )
+        }
+    };
+    if attachment.user_id != user_id.0 && !collaboration::check_todo_owner(&db, &id, &user_id.0) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(SimpleResponse {
+                success: false,
+                message: Some("只有上传者或任务创建者可以删除附件".into()),
+            }),
+        );
+    }
+
+    db.execute(
+        "DELETE FROM todo_attachments WHERE id = ?1",
+        [&attachment_id],
+    )
+    .ok();
+    std::fs::remove_file(&attachment.storage_path).ok();
+    let now = chrono::Utc::now().to_rfc3339();
+    changelog::insert_changelog(
+        &db,
+        &id,
+        "attachment",
+        Todo::field_label("attachment"),
+        &attachment.filename,
+        "",
+        &now,
+    );
+
+    (
+        StatusCode::OK,
+        Json(SimpleResponse {
+            success: true,
+            message: Some("附件已删除".into()),
+        }),
+    )
+}
+
+// ─── Comments ───
+
+fn comment_error(status: StatusCode, message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (status, Json(json!({"success": false, "message": message})))
+}
+
+/// GET /api/todos/{id}/comments
+pub async fn list_comments(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let db = state.db.lock();
+    if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+        return comment_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+    }
+    (
+        StatusCode::OK,
+        Json(json!({"success": true, "items": comments::load(&db, &id)})),
+    )
+}
+
+/// POST /api/todos/{id}/comments — notifies the other participants
+pub async fn create_comment(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<CommentRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (comment, pushes) = {
+        let db = state.db.lock();
+        if !collaboration::check_todo_participant(&db, &id, &user_id.0) {
+            return comment_error(StatusCode::NOT_FOUND, format!("任务不存在: {}", id));
+        }
+        let body = match comments::validate(&req.body) {
+            Ok(b) => b,
+            Err(msg) => return comment_error(StatusCode::BAD_REQUEST, msg),
+        };
+
+        let comment_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+        let mentions = comments::find_mentions(&db, &id, &user_id.0, body);
+        if let Err(e) = db.execute(
+            "INSERT INTO todo_comments (id, todo_id, user_id, body, mentions, created_at, updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            rusqlite::params![
+                comment_id,
+                id,
+                user_id.0,
+                body,
+                serde_json::to_string(&mentions).unwrap_or_default(),
+                now
+            ],
+        ) {
+            eprintln!("[todos] create comment DB error: {}", e);
+            return comment_error(StatusCode::INTERNAL_SERVER_ERROR, "评论失败".into());
+        }
+
+        let Some(comment) = comments::get(&db, &id, &comment_id) else {
+            return comment_error(StatusCode::INTERNAL_SERVER_ERROR, "评论失败".into());
+        };
+        let pushes = comments::notify(&db, &comment, None, &now);
+        (comment, pushes)
+    };
+
+    tokio::spawn(push::send_to_users(state.db.clone(), pushes));
+    (
+        StatusCode::OK,
+        Json(json!({"success": true, "item": comment})),
+    )
+}
+
+/// PUT /api/todos/{id}/comments/{comment_id} — author only; newly
+/// mentioned participants are notified
+pub async fn update_comment(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, comment_id)): Path<(String, String)>,
+    Json(req): Json<CommentRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (comment, pushes) = {
+        let db = state.db.lock();
+        let existing = match comments::get(&db, &id, &comment_id) {
+            Some(c) if collaboration::check_todo_participant(&db, &id, &user_id.0) => c,
+            _ => return comment_error(StatusCode::NOT_FOUND, "评论不存在".into()),
+        };
+        if existing.user_id != user_id.0 {
+            return comment_error(StatusCode::FORBIDDEN, "只能编辑自己的评论".into());
+        }
+        let body = match comments::validate(&req.body) {
+            Ok(b) => b,
+            Err(msg) => return comment_error(StatusCode::BAD_REQUEST, msg),
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mentions = comments::find_mentions(&db, &id, &user_id.0, body);
+        db.execute(
+            "UPDATE todo_comments SET body = ?1, mentions = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![
+                body,
+                serde_json::to_string(&mentions).unwrap_or_default(),
+                now,
+                comment_id
+            ],
+        )
+        .ok();
+
+        let Some(comment) = comments::get(&db, &id, &comment_id) else {
+            return comment_error(StatusCode::NOT_FOUND, "评论不存在".into());
+        };
+        let previous: Vec<String> = existing.mentions.into_iter().map(|m| m.user_id).collect();
+        let pushes = comments::notify(&db, &comment, Some(&previous), &now);
+        (comment, pushes)
+    };
+
+    tokio::spawn(push::send_to_users(state.db.clone(), pushes));
+    (
+        StatusCode::OK,
+        Json(json!({"success": true, "item": comment})),
+    )
+}
+
+/// DELETE /api/todos/{id}/comments/{comment_id} — author or todo owner
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((id, comment_id)): Path<(String, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let db = state.db.lock();
+    let comment = match comments::get(&db, &id, &comment_id) {
+        Some(c) if collaboration::check_todo_participant(&db, &id, &user_id.0) => c,
+        _ => return comment_error(StatusCode::NOT_FOUND, "评论不存在".into()),
+    };
+    if comment.user_id != user_id.0 && !collaboration::check_todo_owner(&db, &id, &user_id.0) {
+        return comment_error(
+            StatusCode::FORBIDDEN,
+            "只有评论者或任务创建者可以删除评论".into(),
+        );
+    }
+
+    db.execute("DELETE FROM todo_comments WHERE id = ?1", [&comment_id])
+        .ok();
+    (
+        StatusCode::OK,
+        Json(json!({"success": true, "message": "评论已删除"})),
+    )
+}
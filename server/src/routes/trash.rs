@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::trash::*;
+use crate::services::trash;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct TrashResponse {
+    pub success: bool,
+    pub items: Vec<TrashItem>,
+    pub retention_days: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashActionResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purged: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Validate an optional `?type=` filter
+fn type_filter(query: &TrashQuery) -> Result<Option<&str>, String> {
+    match query.item_type.as_deref().filter(|t| !t.is_empty()) {
+        Some(t) if !trash::TYPES.contains(&t) => Err(format!(
+            "无效的类型: {}（可选: {}）",
+            t,
+            trash::TYPES.join(", ")
+        )),
+        t => Ok(t),
+    }
+}
+
+fn action_error(status: StatusCode, message: String) -> (StatusCode, Json<TrashActionResponse>) {
+    (
+        status,
+        Json(TrashActionResponse {
+            success: false,
+            purged: None,
+            message: Some(message),
+        }),
+    )
+}
+
+// ─── List trash ───
+
+pub async fn list_trash(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Query(query): Query<TrashQuery>,
+) -> (StatusCode, Json<TrashResponse>) {
+    let db = state.db.lock();
+    let retention_days = trash::retention_days(&db, &user_id.0);
+    match type_filter(&query) {
+        Ok(item_type) => (
+            StatusCode::OK,
+            Json(TrashResponse {
+                success: true,
+                items: trash::list(&db, &user_id.0, item_type),
+                retention_days,
+                message: None,
+            }),
+        ),
+        Err(msg) => (
+            StatusCode::BAD_REQUEST,
+            Json(TrashResponse {
+                success: false,
+                items: Vec::new(),
+                retention_days,
+                message: Some(msg),
+            }),
+        ),
+    }
+}
+
+// ─── Restore item ───
+
+pub async fn restore_item(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path((item_type, id)): Path<(String, String)>,
+) -> (StatusCode, Json<TrashActionResponse>) {
+    if !trash::TYPES.contains(&item_type.as_str()) {
+        return action_error(
+            StatusCode::BAD_REQUEST,
+            format!("无效的类型: {}", item_type),
+        );
+    }
+    let db = state.db.lock();
+    let now = chrono::Utc::now().to_rfc3339();
+    if !trash::restore(&db, &user_id.0, &item_type, &id, &now) {
+        return action_error(StatusCode::NOT_FOUND, "回收站中没有该项".into());
+    }
+    (
+        StatusCode::OK,
+        Json(TrashActionResponse {
+            success: true,
+            purged: None,
+            message: Some("已恢复".into()),
+        }),
+    )
+}
+
+// ─── Empty trash ───
+
+pub async fn empty_trash(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Query(query): Query<TrashQuery>,
+) -> (StatusCode, Json<TrashActionResponse>) {
+    let item_type = match type_filter(&query) {
+        Ok(t) => t,
+        Err(msg) => return action_error(StatusCode::BAD_REQUEST, msg),
+    };
+    let db = state.db.lock();
+    let purged = trash::empty(&db, &user_id.0, item_type);
+    (
+        StatusCode::OK,
+        Json(TrashActionResponse {
+            success: true,
+            purged: Some(purged),
+            message: Some(format!("已永久删除 {} 项", purged)),
+        }),
+    )
+}
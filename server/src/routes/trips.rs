@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Multipart, Path, State},
-    http::StatusCode,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -11,6 +11,8 @@ use serde_json::json;
 use crate::auth::{check_guest_ai_quota, ActiveUserId, UserId};
 use crate::models::trip::*;
 use crate::services::claude::ClaudeClient;
+use crate::services::etag;
+use crate::services::pagination::{self, PagePlan, PageQuery, SortField, SortKey};
 use crate::state::AppState;
 
 // ===== Permission helpers =====
@@ -20,7 +22,7 @@ fn check_trip_access(db: &Connection, trip_id: &str, user_id: &str) -> (bool, bo
     // Check if owner
     let is_owner: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id = ?1 AND user_id = ?2",
+            "SELECT COUNT(*) FROM trips WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
             rusqlite::params![trip_id, user_id],
             |row| row.get::<_, i64>(0),
         )
@@ -34,7 +36,8 @@ fn check_trip_access(db: &Connection, trip_id: &str, user_id: &str) -> (bool, bo
     // Check if collaborator
     let role: Option<String> = db
         .query_row(
-            "SELECT role FROM trip_collaborators WHERE trip_id = ?1 AND user_id = ?2",
+            "SELECT tc.role FROM trip_collaborators tc JOIN trips t ON t.id = tc.trip_id \
+             WHERE tc.trip_id = ?1 AND tc.user_id = ?2 AND t.deleted = 0",
             rusqlite::params![trip_id, user_id],
             |row| row.get(0),
         )
@@ -93,11 +96,49 @@ fn build_reimburse_summary(db: &Connection, trip_id: &str) -> ReimburseSummary {
     summary
 }
 
+/// Sort options of `GET /api/trips`; latest departure first by default
+const TRIP_SORTS: [SortField<Trip>; 4] = [
+    SortField {
+        name: "date",
+        descending: true,
+        key: |t| vec![SortKey::text(&t.date_from)],
+        columns: &["date_from"],
+    },
+    SortField {
+        name: "created",
+        descending: true,
+        key: |t| vec![SortKey::text(&t.created_at)],
+        columns: &["created_at"],
+    },
+    SortField {
+        name: "updated",
+        descending: true,
+        key: |t| vec![SortKey::text(&t.updated_at)],
+        columns: &["updated_at"],
+    },
+    SortField {
+        name: "title",
+        descending: false,
+        key: |t| vec![SortKey::text(&t.title)],
+        columns: &["title"],
+    },
+];
+
 // ===== List trips =====
 pub async fn list_trips(
     State(state): State<AppState>,
     user_id: UserId,
+    Query(page_query): Query<PageQuery>,
 ) -> (StatusCode, Json<serde_json::Value>) {
+    let plan = match PagePlan::new(&page_query, &TRIP_SORTS) {
+        Ok(plan) => plan,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "success": false, "message": msg })),
+            )
+        }
+    };
     let db = state.db.lock();
 
     // Own trips + collaborated trips
@@ -108,7 +149,7 @@ pub async fn list_trips(
                (SELECT COALESCE(SUM(amount), 0) FROM trip_items WHERE trip_id = t.id) as total_amount,
                CASE WHEN t.user_id = ?1 THEN 1 ELSE 0 END as is_owner
         FROM trips t
-        WHERE t.user_id = ?1
+        WHERE t.user_id = ?1 AND t.deleted = 0
         UNION
         SELECT t.id, t.user_id, t.title, t.destination, t.date_from, t.date_to,
                t.purpose, t.notes, t.currency, t.created_at, t.updated_at,
@@ -117,13 +158,15 @@ pub async fn list_trips(
                0 as is_owner
         FROM trips t
         JOIN trip_collaborators tc ON tc.trip_id = t.id
-        WHERE tc.user_id = ?1
-        ORDER BY date_from DESC
+        WHERE tc.user_id = ?1 AND t.deleted = 0
     ";
 
-    let mut trips: Vec<Trip> = Vec::new();
-    if let Ok(mut stmt) = db.prepare(sql) {
-        if let Ok(rows) = stmt.query_map(rusqlite::params![user_id.0], |row| {
+    let result = pagination::query_page(
+        &db,
+        sql,
+        &[&user_id.0],
+        &plan,
+        |row| {
             Ok(Trip {
                 id: row.get(0)?,
                 user_id: row.get(1)?,
@@ -141,10 +184,19 @@ pub async fn list_trips(
                 reimburse_summary: ReimburseSummary::default(),
                 is_owner: row.get::<_, i64>(13).unwrap_or(0) != 0,
             })
-        }) {
-            trips = rows.filter_map(|r| r.ok()).collect();
+        },
+        |t| &t.id,
+    );
+    let (mut trips, page) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("[trips] list DB error: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "success": false, "message": "读取差旅失败" })),
+            );
         }
-    }
+    };
 
     // Fill reimburse summaries
     for trip in &mut trips {
@@ -153,7 +205,7 @@ pub async fn list_trips(
 
     (
         StatusCode::OK,
-        Json(json!({ "success": true, "trips": trips })),
+        Json(json!({ "success": true, "trips": trips, "page": page })),
     )
 }
 
@@ -211,21 +263,8 @@ pub async fn create_trip(
 }
 
 // ===== Get trip detail =====
-pub async fn get_trip(
-    State(state): State<AppState>,
-    user_id: UserId,
-    Path(id): Path<String>,
-) -> (StatusCode, Json<serde_json::Value>) {
-    let db = state.db.lock();
-
-    let (has_access, is_owner, _role) = check_trip_access(&db, &id, &user_id.0);
-    if !has_access {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(json!({ "success": false, "message": "行程不存在" })),
-        );
-    }
-
+/// Trip with its items, photos and collaborators
+fn load_trip_detail(db: &Connection, id: &str, is_owner: bool) -> Option<TripDetail> {
     let trip = db.query_row(
         "SELECT id, user_id, title, destination, date_from, date_to, purpose, notes, currency, created_at, updated_at,
                 (SELECT COUNT(*) FROM trip_items WHERE trip_id = t.id),
@@ -253,16 +292,8 @@ pub async fn get_trip(
         },
     );
 
-    let mut trip = match trip {
-        Ok(t) => t,
-        Err(_) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({ "success": false, "message": "行程不存在" })),
-            );
-        }
-    };
-    trip.reimburse_summary = build_reimburse_summary(&db, &id);
+    let mut trip = trip.ok()?;
+    trip.reimburse_summary = build_reimburse_summary(db, id);
 
     // Get items with photo counts
     let items: Vec<TripItem> = db
@@ -342,16 +373,38 @@ pub async fn get_trip(
         })
         .unwrap_or_default();
 
-    let detail = TripDetail {
+    Some(TripDetail {
         trip,
         items: items_with_photos,
         collaborators,
-    };
+    })
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({ "success": true, "trip": detail })),
-    )
+pub async fn get_trip(
+    State(state): State<AppState>,
+    user_id: UserId,
+    Path(id): Path<String>,
+) -> (StatusCode, HeaderMap, Json<serde_json::Value>) {
+    let db = state.db.lock();
+
+    let (has_access, is_owner, _role) = check_trip_access(&db, &id, &user_id.0);
+    let detail = if has_access {
+        load_trip_detail(&db, &id, is_owner)
+    } else {
+        None
+    };
+    match detail {
+        Some(detail) => (
+            StatusCode::OK,
+            etag::headers(&detail.trip.updated_at),
+            Json(json!({ "success": true, "trip": detail })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            HeaderMap::new(),
+            Json(json!({ "success": false, "message": "行程不存在" })),
+        ),
+    }
 }
 
 // ===== Update trip =====
@@ -359,24 +412,41 @@ pub async fn update_trip(
     State(state): State<AppState>,
     user_id: ActiveUserId,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<UpdateTripRequest>,
-) -> (StatusCode, Json<serde_json::Value>) {
+) -> (StatusCode, HeaderMap, Json<serde_json::Value>) {
     let db = state.db.lock();
 
     let (has_access, is_owner, _) = check_trip_access(&db, &id, &user_id.0);
     if !has_access {
         return (
             StatusCode::NOT_FOUND,
+            HeaderMap::new(),
             Json(json!({ "success": false, "message": "行程不存在" })),
         );
     }
     if !is_owner {
         return (
             StatusCode::FORBIDDEN,
+            HeaderMap::new(),
             Json(json!({ "success": false, "message": "只有创建者可以编辑行程" })),
         );
     }
 
+    if let Some(current) = load_trip_detail(&db, &id, is_owner) {
+        if !etag::matches(&headers, &current.trip.updated_at) {
+            return (
+                StatusCode::PRECONDITION_FAILED,
+                etag::headers(&current.trip.updated_at),
+                Json(json!({
+                    "success": false,
+                    "message": "行程已在其他地方被修改，请合并后重试",
+                    "trip": current,
+                })),
+            );
+        }
+    }
+
     let now = chrono::Utc::now().to_rfc3339();
     let mut sets = vec!["updated_at = ?1".to_string()];
     let mut idx = 2u32;
@@ -400,7 +470,7 @@ pub async fn update_trip(
 
     let sql = format!("UPDATE trips SET {} WHERE id = ?{}", sets.join(", "), idx);
 
-    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now)];
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now.clone())];
     if let Some(v) = &req.title {
         params.push(Box::new(v.clone()));
     }
@@ -426,11 +496,16 @@ pub async fn update_trip(
 
     let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
     match db.execute(&sql, param_refs.as_slice()) {
-        Ok(_) => (StatusCode::OK, Json(json!({ "success": true }))),
+        Ok(_) => (
+            StatusCode::OK,
+            etag::headers(&now),
+            Json(json!({ "success": true })),
+        ),
         Err(e) => {
             eprintln!("[Trip] update error: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
+                HeaderMap::new(),
                 Json(json!({ "success": false, "message": "更新失败" })),
             )
         }
@@ -459,30 +534,17 @@ pub async fn delete_trip(
         );
     }
 
-    // Collect photo paths for cleanup
-    let photos: Vec<String> = db
-        .prepare(
-            "SELECT tp.storage_path FROM trip_photos tp
-             JOIN trip_items ti ON ti.id = tp.item_id
-             WHERE ti.trip_id = ?1",
-        )
-        .and_then(|mut stmt| {
-            stmt.query_map(rusqlite::params![id], |row| row.get::<_, String>(0))
-                .map(|rows| rows.filter_map(|r| r.ok()).collect())
-        })
-        .unwrap_or_default();
-
-    match db.execute("DELETE FROM trips WHERE id = ?1", rusqlite::params![id]) {
+    // Photos stay until the trip is purged from the trash
+    let now = chrono::Utc::now().to_rfc3339();
+    match db.execute(
+        "UPDATE trips SET deleted = 1, deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![now, id],
+    ) {
         Ok(0) => (
             StatusCode::NOT_FOUND,
             Json(json!({ "success": false, "message": "行程不存在" })),
         ),
-        Ok(_) => {
-            for path in photos {
-                std::fs::remove_file(&path).ok();
-            }
-            (StatusCode::OK, Json(json!({ "success": true })))
-        }
+        Ok(_) => (StatusCode::OK, Json(json!({ "success": true }))),
         Err(e) => {
             eprintln!("[Trip] delete error: {}", e);
             (
@@ -760,14 +822,7 @@ pub async fn upload_item_photos(
             .unwrap_or_else(|_| user_id.0.clone());
     }
 
-    let upload_dir = format!(
-        "{}/uploads/{}",
-        std::env::var("DATABASE_PATH")
-            .unwrap_or_else(|_| "data/next.db".to_string())
-            .replace("/next.db", "")
-            .replace("\\next.db", ""),
-        owner_user_id
-    );
+    let upload_dir = crate::routes::expenses::upload_dir(&owner_user_id);
     std::fs::create_dir_all(&upload_dir).ok();
 
     let mut uploaded: Vec<TripPhoto> = Vec::new();
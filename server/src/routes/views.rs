@@ -0,0 +1,229 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::{ActiveUserId, UserId};
+use crate::models::view::*;
+use crate::services::time;
+use crate::services::todo_filter::TodoFilter;
+use crate::state::AppState;
+
+/// Max saved views per user
+const MAX_VIEWS: i64 = 50;
+
+#[derive(Debug, Serialize)]
+pub struct ViewsResponse {
+    pub success: bool,
+    pub items: Vec<SavedView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ViewResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<SavedView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn view_error(status: StatusCode, message: String) -> (StatusCode, Json<ViewResponse>) {
+    (
+        status,
+        Json(ViewResponse {
+            success: false,
+            item: None,
+            message: Some(message),
+        }),
+    )
+}
+
+/// Validate a view name and filter; returns the trimmed values
+fn validate(name: &str, filter: &str) -> Result<(String, String), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("视图名称不能为空".into());
+    }
+    if name.chars().count() > 50 {
+        return Err("视图名称不能超过 50 字符".into());
+    }
+    let filter = filter.trim();
+    TodoFilter::parse(filter, time::today())?;
+    Ok((name.to_string(), filter.to_string()))
+}
+
+fn load_view(db: &rusqlite::Connection, id: &str, user_id: &str) -> Option<SavedView> {
+    db.query_row(
+        "SELECT id, name, filter, created_at, updated_at FROM saved_views WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![id, user_id],
+        |row| {
+            Ok(SavedView {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                filter: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )
+    .ok()
+}
+
+// ─── List saved views ───
+
+pub async fn list_views(
+    State(state): State<AppState>,
+    user_id: UserId,
+) -> (StatusCode, Json<ViewsResponse>) {
+    let db = state.db.lock();
+    let mut stmt = db
+        .prepare(
+            "SELECT id, name, filter, created_at, updated_at FROM saved_views WHERE user_id = ?1 ORDER BY created_at ASC",
+        )
+        .unwrap();
+    let items: Vec<SavedView> = stmt
+        .query_map([&user_id.0], |row| {
+            Ok(SavedView {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                filter: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ViewsResponse {
+            success: true,
+            items,
+            message: None,
+        }),
+    )
+}
+
+// ─── Create saved view ───
+
+pub async fn create_view(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Json(req): Json<CreateViewRequest>,
+) -> (StatusCode, Json<ViewResponse>) {
+    let (name, filter) = match validate(&req.name, &req.filter) {
+        Ok(v) => v,
+        Err(msg) => return view_error(StatusCode::BAD_REQUEST, msg),
+    };
+
+    let db = state.db.lock();
+    let count: i64 = db
+        .query_row(
+            "SELECT COUNT(*) FROM saved_views WHERE user_id = ?1",
+            [&user_id.0],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+    if count >= MAX_VIEWS {
+        return view_error(
+            StatusCode::BAD_REQUEST,
+            format!("最多保存 {} 个视图", MAX_VIEWS),
+        );
+    }
+
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    if db
+        .execute(
+            "INSERT INTO saved_views (id, user_id, name, filter, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            rusqlite::params![id, user_id.0, name, filter, now],
+        )
+        .is_err()
+    {
+        return view_error(StatusCode::CONFLICT, format!("视图已存在: {}", name));
+    }
+
+    (
+        StatusCode::OK,
+        Json(ViewResponse {
+            success: true,
+            item: load_view(&db, &id, &user_id.0),
+            message: Some("视图已保存".into()),
+        }),
+    )
+}
+
+// ─── Update saved view ───
+
+pub async fn update_view(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateViewRequest>,
+) -> (StatusCode, Json<ViewResponse>) {
+    let db = state.db.lock();
+    let Some(view) = load_view(&db, &id, &user_id.0) else {
+        return view_error(StatusCode::NOT_FOUND, "视图不存在".into());
+    };
+
+    let (name, filter) = match validate(
+        req.name.as_deref().unwrap_or(&view.name),
+        req.filter.as_deref().unwrap_or(&view.filter),
+    ) {
+        Ok(v) => v,
+        Err(msg) => return view_error(StatusCode::BAD_REQUEST, msg),
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if db
+        .execute(
+            "UPDATE saved_views SET name = ?1, filter = ?2, updated_at = ?3 WHERE id = ?4 AND user_id = ?5",
+            rusqlite::params![name, filter, now, id, user_id.0],
+        )
+        .is_err()
+    {
+        return view_error(StatusCode::CONFLICT, format!("视图已存在: {}", name));
+    }
+
+    (
+        StatusCode::OK,
+        Json(ViewResponse {
+            success: true,
+            item: load_view(&db, &id, &user_id.0),
+            message: Some("视图已更新".into()),
+        }),
+    )
+}
+
+// ─── Delete saved view ───
+
+pub async fn delete_view(
+    State(state): State<AppState>,
+    user_id: ActiveUserId,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ViewResponse>) {
+    let db = state.db.lock();
+    let deleted = db
+        .execute(
+            "DELETE FROM saved_views WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )
+        .unwrap_or(0);
+    if deleted == 0 {
+        return view_error(StatusCode::NOT_FOUND, "视图不存在".into());
+    }
+
+    (
+        StatusCode::OK,
+        Json(ViewResponse {
+            success: true,
+            item: None,
+            message: Some("视图已删除".into()),
+        }),
+    )
+}
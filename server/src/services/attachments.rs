@@ -0,0 +1,82 @@
+use rusqlite::Connection;
+
+use crate::models::todo::TodoAttachment;
+
+/// Maximum size of one attached file
+pub const MAX_FILE_SIZE: usize = 20_000_000;
+
+/// Maximum number of attachments per todo
+pub const MAX_ATTACHMENTS: i64 = 20;
+
+fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<TodoAttachment> {
+    Ok(TodoAttachment {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        filename: row.get(2)?,
+        file_size: row.get(3)?,
+        mime_type: row.get(4)?,
+        user_id: row.get(5)?,
+        created_at: row.get(6)?,
+        storage_path: row.get(7)?,
+    })
+}
+
+/// A todo's attachments, oldest first
+pub fn load(db: &Connection, todo_id: &str) -> Vec<TodoAttachment> {
+    let mut stmt = match db.prepare(
+        "SELECT id, todo_id, filename, file_size, mime_type, user_id, created_at, storage_path \
+         FROM todo_attachments WHERE todo_id = ?1 ORDER BY created_at ASC, id ASC",
+    ) {
+        Ok(s) => s,
+        Err(_) => return Vec::new(),
+    };
+    stmt.query_map([todo_id], row_to_attachment)
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        .unwrap_or_default()
+}
+
+pub fn get(db: &Connection, todo_id: &str, attachment_id: &str) -> Option<TodoAttachment> {
+    db.query_row(
+        "SELECT id, todo_id, filename, file_size, mime_type, user_id, created_at, storage_path \
+         FROM todo_attachments WHERE id = ?1 AND todo_id = ?2",
+        [attachment_id, todo_id],
+        row_to_attachment,
+    )
+    .ok()
+}
+
+pub fn count(db: &Connection, todo_id: &str) -> i64 {
+    db.query_row(
+        "SELECT COUNT(*) FROM todo_attachments WHERE todo_id = ?1",
+        [todo_id],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
+/// File extension kept for storage; anything unusual falls back to `bin`
+pub fn storage_ext(filename: &str) -> String {
+    filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .filter(|ext| {
+            !ext.is_empty() && ext.len() <= 8 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .unwrap_or_else(|| "bin".into())
+}
+
+/// Whether a file can be shown in the browser rather than downloaded
+pub fn is_inline(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "application/pdf" | "text/plain"
+    )
+}
+
+/// Remove the stored files of a todo's attachments. Call before the todo
+/// row is deleted; the rows themselves go with it.
+pub fn remove_files(db: &Connection, todo_id: &str) {
+    for attachment in load(db, todo_id) {
+        std::fs::remove_file(&attachment.storage_path).ok();
+    }
+}
@@ -0,0 +1,155 @@
+use rusqlite::Connection;
+
+use crate::models::todo::{Quadrant, Recurrence, Todo};
+
+/// `changed_by` for edits made through the assistant's tools
+pub const ASSISTANT: &str = "assistant";
+
+/// `changed_by` for edits made by background jobs
+pub const SYSTEM: &str = "system";
+
+/// Fields whose changelog entries carry raw column values and can be replayed
+pub const REVERTIBLE_FIELDS: [&str; 10] = [
+    "text",
+    "content",
+    "tab",
+    "quadrant",
+    "progress",
+    "completed",
+    "due_date",
+    "assignee",
+    "tags",
+    "recurrence",
+];
+
+/// Append an entry to a todo's changelog, keeping the 50 most recent
+pub fn insert_changelog(
+    db: &Connection,
+    todo_id: &str,
+    field: &str,
+    label: &str,
+    from: &str,
+    to: &str,
+    time: &str,
+) {
+    db.execute(
+        "INSERT INTO todo_changelog (todo_id, field, label, from_val, to_val, time) VALUES (?1,?2,?3,?4,?5,?6)",
+        rusqlite::params![todo_id, field, label, from, to, time],
+    )
+    .ok();
+    prune(db, todo_id);
+}
+
+fn prune(db: &Connection, todo_id: &str) {
+    // Keep only 50 most recent entries per todo
+    db.execute(
+        "DELETE FROM todo_changelog WHERE todo_id = ?1 AND id NOT IN (SELECT id FROM todo_changelog WHERE todo_id = ?1 ORDER BY id DESC LIMIT 50)",
+        [todo_id],
+    )
+    .ok();
+}
+
+/// Human-readable form of a raw column value, as shown in the changelog
+pub fn display_value(field: &str, raw: &str) -> String {
+    match field {
+        "content" => "(已更新)".into(),
+        "quadrant" => Quadrant::parse(raw).label().into(),
+        "completed" => if raw == "1" { "已完成" } else { "未完成" }.into(),
+        "tags" => serde_json::from_str::<Vec<String>>(raw)
+            .unwrap_or_default()
+            .join(", "),
+        "recurrence" => Recurrence::parse(raw)
+            .map(|r| r.label())
+            .unwrap_or_default(),
+        _ => raw.to_string(),
+    }
+}
+
+/// Current raw values of the revertible fields (tab/quadrant from the owner's view)
+pub fn snapshot(db: &Connection, todo_id: &str) -> Option<Vec<(&'static str, String)>> {
+    db.query_row(
+        "SELECT text, COALESCE(content, ''), tab, quadrant, progress, completed, COALESCE(due_date, ''), \
+         COALESCE(assignee, ''), COALESCE(tags, '[]'), COALESCE(recurrence, '') FROM todos WHERE id = ?1",
+        [todo_id],
+        |r| {
+            Ok(vec![
+                ("text", r.get::<_, String>(0)?),
+                ("content", r.get::<_, String>(1)?),
+                ("tab", r.get::<_, String>(2)?),
+                ("quadrant", r.get::<_, String>(3)?),
+                ("progress", r.get::<_, i64>(4)?.to_string()),
+                ("completed", r.get::<_, i64>(5)?.to_string()),
+                ("due_date", r.get::<_, String>(6)?),
+                ("assignee", r.get::<_, String>(7)?),
+                ("tags", r.get::<_, String>(8)?),
+                ("recurrence", r.get::<_, String>(9)?),
+            ])
+        },
+    )
+    .ok()
+}
+
+/// A group of changelog entries written by one edit. Entries carry raw
+/// values so the group can later be reverted as a unit.
+pub struct ChangeSet<'a> {
+    pub id: String,
+    pub changed_by: &'a str,
+    pub time: &'a str,
+}
+
+impl<'a> ChangeSet<'a> {
+    pub fn new(changed_by: &'a str, time: &'a str) -> Self {
+        ChangeSet {
+            id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
+            changed_by,
+            time,
+        }
+    }
+
+    /// Record a change of a revertible field from one raw value to another
+    pub fn record(
+        &self,
+        db: &Connection,
+        todo_id: &str,
+        field: &str,
+        from_raw: &str,
+        to_raw: &str,
+    ) {
+        db.execute(
+            "INSERT INTO todo_changelog (todo_id, field, label, from_val, to_val, time, changed_by, from_raw, to_raw, batch_id) \
+             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10)",
+            rusqlite::params![
+                todo_id,
+                field,
+                Todo::field_label(field),
+                display_value(field, from_raw),
+                display_value(field, to_raw),
+                self.time,
+                self.changed_by,
+                from_raw,
+                to_raw,
+                self.id,
+            ],
+        )
+        .ok();
+        prune(db, todo_id);
+    }
+
+    /// Record every field that differs between two snapshots
+    pub fn record_diff(
+        &self,
+        db: &Connection,
+        todo_id: &str,
+        before: &[(&'static str, String)],
+        after: &[(&'static str, String)],
+    ) -> usize {
+        let mut count = 0;
+        for ((field, old), (_, new)) in before.iter().zip(after) {
+            if old != new {
+                self.record(db, todo_id, field, old, new);
+                count += 1;
+            }
+        }
+        count
+    }
+}
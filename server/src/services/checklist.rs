@@ -0,0 +1,290 @@
+use rusqlite::Connection;
+
+use crate::models::todo::{ChecklistItem, Todo};
+use crate::services::changelog::{insert_changelog, ChangeSet};
+use crate::services::completion;
+
+/// Maximum number of checklist items per todo
+pub const MAX_ITEMS: usize = 100;
+
+/// Load a todo's checklist in display order
+pub fn load_items(db: &Connection, todo_id: &str) -> Vec<ChecklistItem> {
+    let mut stmt = match db.prepare(
+        "SELECT id, text, checked, checked_at, sort_order FROM todo_checklist_items \
+         WHERE todo_id = ?1 ORDER BY sort_order ASC, created_at ASC",
+    ) {
+        Ok(s) => s,
+        Err(_) => return Vec::new(),
+    };
+    stmt.query_map([todo_id], |row| {
+        Ok(ChecklistItem {
+            id: row.get(0)?,
+            text: row.get(1)?,
+            checked: row.get::<_, i32>(2)? != 0,
+            checked_at: row.get(3)?,
+            sort_order: row.get(4)?,
+        })
+    })
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+fn count_items(db: &Connection, todo_id: &str) -> usize {
+    db.query_row(
+        "SELECT COUNT(*) FROM todo_checklist_items WHERE todo_id = ?1",
+        [todo_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0) as usize
+}
+
+/// Append an item to the end of a todo's checklist
+pub fn add_item(
+    db: &Connection,
+    todo_id: &str,
+    text: &str,
+    now: &str,
+) -> Result<ChecklistItem, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("清单项不能为空".into());
+    }
+    if text.len() > 500 {
+        return Err("清单项不能超过 500 字符".into());
+    }
+    if count_items(db, todo_id) >= MAX_ITEMS {
+        return Err(format!("每个任务最多 {} 个清单项", MAX_ITEMS));
+    }
+
+    let sort_order: f64 = db
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order), 0) + 1 FROM todo_checklist_items WHERE todo_id = ?1",
+            [todo_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(1.0);
+    let id = Todo::generate_id();
+
+    db.execute(
+        "INSERT INTO todo_checklist_items (id, todo_id, text, checked, checked_at, sort_order, created_at, updated_at) VALUES (?1,?2,?3,0,NULL,?4,?5,?5)",
+        rusqlite::params![id, todo_id, text, sort_order, now],
+    )
+    .map_err(|e| {
+        eprintln!("[checklist] add_item DB error: {}", e);
+        "数据库写入失败，请稍后重试".to_string()
+    })?;
+
+    insert_changelog(
+        db,
+        todo_id,
+        "checklist",
+        Todo::field_label("checklist"),
+        "",
+        text,
+        now,
+    );
+
+    Ok(ChecklistItem {
+        id,
+        text: text.to_string(),
+        checked: false,
+        checked_at: None,
+        sort_order,
+    })
+}
+
+/// Rename and/or (un)check an item. Returns false when the item does not exist.
+pub fn update_item(
+    db: &Connection,
+    todo_id: &str,
+    item_id: &str,
+    text: Option<&str>,
+    checked: Option<bool>,
+    now: &str,
+) -> Result<bool, String> {
+    let current = db.query_row(
+        "SELECT text, checked FROM todo_checklist_items WHERE id = ?1 AND todo_id = ?2",
+        rusqlite::params![item_id, todo_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)? != 0)),
+    );
+    let (old_text, old_checked) = match current {
+        Ok(c) => c,
+        Err(_) => return Ok(false),
+    };
+
+    let mut new_text = old_text.clone();
+    if let Some(t) = text {
+        let t = t.trim();
+        if t.is_empty() {
+            return Err("清单项不能为空".into());
+        }
+        if t.len() > 500 {
+            return Err("清单项不能超过 500 字符".into());
+        }
+        if t != old_text {
+            insert_changelog(
+                db,
+                todo_id,
+                "checklist",
+                Todo::field_label("checklist"),
+                &old_text,
+                t,
+                now,
+            );
+            new_text = t.to_string();
+        }
+    }
+
+    let mut new_checked = old_checked;
+    if let Some(c) = checked {
+        if c != old_checked {
+            let (from, to) = if c {
+                (format!("[ ] {}", new_text), format!("[x] {}", new_text))
+            } else {
+                (format!("[x] {}", new_text), format!("[ ] {}", new_text))
+            };
+            insert_changelog(
+                db,
+                todo_id,
+                "checklist",
+                Todo::field_label("checklist"),
+                &from,
+                &to,
+                now,
+            );
+            new_checked = c;
+        }
+    }
+
+    if new_text == old_text && new_checked == old_checked {
+        return Ok(true);
+    }
+
+    db.execute(
+        "UPDATE todo_checklist_items SET text = ?1, checked = ?2, \
+         checked_at = CASE WHEN ?2 = 1 THEN COALESCE(checked_at, ?3) ELSE NULL END, updated_at = ?3 \
+         WHERE id = ?4 AND todo_id = ?5",
+        rusqlite::params![new_text, new_checked as i32, now, item_id, todo_id],
+    )
+    .map_err(|e| {
+        eprintln!("[checklist] update_item DB error: {}", e);
+        "数据库写入失败，请稍后重试".to_string()
+    })?;
+
+    Ok(true)
+}
+
+/// Remove an item. Returns false when the item does not exist.
+pub fn remove_item(db: &Connection, todo_id: &str, item_id: &str, now: &str) -> bool {
+    let text: Option<String> = db
+        .query_row(
+            "SELECT text FROM todo_checklist_items WHERE id = ?1 AND todo_id = ?2",
+            rusqlite::params![item_id, todo_id],
+            |row| row.get(0),
+        )
+        .ok();
+    let Some(text) = text else {
+        return false;
+    };
+
+    db.execute(
+        "DELETE FROM todo_checklist_items WHERE id = ?1 AND todo_id = ?2",
+        rusqlite::params![item_id, todo_id],
+    )
+    .ok();
+    insert_changelog(
+        db,
+        todo_id,
+        "checklist",
+        Todo::field_label("checklist"),
+        &text,
+        "",
+        now,
+    );
+    true
+}
+
+/// Reorder a checklist. `ids` must list every item of the todo exactly once.
+pub fn reorder_items(
+    db: &Connection,
+    todo_id: &str,
+    ids: &[String],
+    now: &str,
+) -> Result<(), String> {
+    let existing: std::collections::HashSet<String> =
+        load_items(db, todo_id).into_iter().map(|i| i.id).collect();
+    let requested: std::collections::HashSet<String> = ids.iter().cloned().collect();
+    if requested.len() != ids.len() || requested != existing {
+        return Err("排序列表与清单项不一致".into());
+    }
+
+    for (idx, item_id) in ids.iter().enumerate() {
+        db.execute(
+            "UPDATE todo_checklist_items SET sort_order = ?1, updated_at = ?2 WHERE id = ?3 AND todo_id = ?4",
+            rusqlite::params![(idx + 1) as f64, now, item_id, todo_id],
+        )
+        .ok();
+    }
+    insert_changelog(
+        db,
+        todo_id,
+        "checklist",
+        Todo::field_label("checklist"),
+        "(已排序)",
+        "(已排序)",
+        now,
+    );
+    Ok(())
+}
+
+/// Re-derive the todo's progress from its checklist and persist it.
+/// Reaching 100% completes the todo; unchecking an item reopens it.
+/// Todos without checklist items are left untouched.
+pub fn sync_progress(db: &Connection, todo_id: &str, changes: &ChangeSet) {
+    let now = changes.time;
+    let items = load_items(db, todo_id);
+    let Some(new_progress) = ChecklistItem::derive_progress(&items) else {
+        return;
+    };
+
+    let current = db.query_row(
+        "SELECT progress, completed FROM todos WHERE id = ?1",
+        [todo_id],
+        |row| Ok((row.get::<_, i32>(0)? as u8, row.get::<_, i32>(1)? != 0)),
+    );
+    let Ok((old_progress, old_completed)) = current else {
+        return;
+    };
+
+    let new_completed = new_progress == 100;
+    if new_progress != old_progress {
+        changes.record(
+            db,
+            todo_id,
+            "progress",
+            &old_progress.to_string(),
+            &new_progress.to_string(),
+        );
+    }
+    if new_completed != old_completed {
+        changes.record(
+            db,
+            todo_id,
+            "completed",
+            if old_completed { "1" } else { "0" },
+            if new_completed { "1" } else { "0" },
+        );
+    }
+
+    db.execute(
+        "UPDATE todos SET progress = ?1, completed = ?2, \
+         completed_at = CASE WHEN ?2 = 1 THEN COALESCE(completed_at, ?3) ELSE NULL END, updated_at = ?3 \
+         WHERE id = ?4",
+        rusqlite::params![new_progress as i32, new_completed as i32, now, todo_id],
+    )
+    .ok();
+
+    if new_completed && !old_completed {
+        completion::on_completed(db, todo_id, now);
+    }
+}
@@ -0,0 +1,192 @@
+use rusqlite::Connection;
+use serde_json::json;
+
+use crate::models::todo::{CommentMention, TodoComment};
+use crate::services::collaboration;
+
+/// Maximum comment length in characters
+pub const MAX_BODY_CHARS: usize = 2000;
+
+fn row_to_comment(row: &rusqlite::Row) -> rusqlite::Result<(TodoComment, String)> {
+    let created_at: String = row.get(6)?;
+    let updated_at: String = row.get(7)?;
+    let display_name: Option<String> = row.get(3)?;
+    let username: Option<String> = row.get(8)?;
+    Ok((
+        TodoComment {
+            id: row.get(0)?,
+            todo_id: row.get(1)?,
+            user_id: row.get(2)?,
+            author_name: display_name
+                .filter(|n| !n.is_empty())
+                .or(username)
+                .unwrap_or_default(),
+            author_avatar: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+            body: row.get(5)?,
+            mentions: Vec::new(),
+            edited: updated_at != created_at,
+            created_at,
+            updated_at,
+        },
+        row.get(9)?,
+    ))
+}
+
+const SELECT: &str = "SELECT c.id, c.todo_id, c.user_id, u.display_name, u.avatar, c.body, c.created_at, c.updated_at, u.username, c.mentions \
+     FROM todo_comments c LEFT JOIN users u ON u.id = c.user_id";
+
+/// Resolve stored mention ids to names
+fn with_mentions(db: &Connection, (mut comment, mentions): (TodoComment, String)) -> TodoComment {
+    let ids: Vec<String> = serde_json::from_str(&mentions).unwrap_or_default();
+    comment.mentions = ids
+        .into_iter()
+        .map(|id| CommentMention {
+            name: user_name(db, &id),
+            user_id: id,
+        })
+        .collect();
+    comment
+}
+
+/// A todo's comments, oldest first
+pub fn load(db: &Connection, todo_id: &str) -> Vec<TodoComment> {
+    let rows: Vec<(TodoComment, String)> = db
+        .prepare(&format!(
+            "{} WHERE c.todo_id = ?1 ORDER BY c.created_at ASC, c.id ASC",
+            SELECT
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_map([todo_id], row_to_comment)
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+    rows.into_iter().map(|r| with_mentions(db, r)).collect()
+}
+
+pub fn get(db: &Connection, todo_id: &str, comment_id: &str) -> Option<TodoComment> {
+    db.query_row(
+        &format!("{} WHERE c.id = ?1 AND c.todo_id = ?2", SELECT),
+        [comment_id, todo_id],
+        row_to_comment,
+    )
+    .ok()
+    .map(|r| with_mentions(db, r))
+}
+
+fn user_name(db: &Connection, user_id: &str) -> String {
+    db.query_row(
+        "SELECT COALESCE(NULLIF(display_name, ''), username) FROM users WHERE id = ?1",
+        [user_id],
+        |r| r.get(0),
+    )
+    .unwrap_or_default()
+}
+
+/// Trim and validate a comment body
+pub fn validate(body: &str) -> Result<&str, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("评论不能为空".into());
+    }
+    if body.chars().count() > MAX_BODY_CHARS {
+        return Err(format!("评论不能超过 {} 字", MAX_BODY_CHARS));
+    }
+    Ok(body)
+}
+
+/// Participants other than the author mentioned as `@username` or
+/// `@display name`. A name only counts when it is not followed by more
+/// letters or digits, so `@ann` does not match `@anna`.
+pub fn find_mentions(db: &Connection, todo_id: &str, author: &str, body: &str) -> Vec<String> {
+    let body = body.to_lowercase();
+    let mut mentioned = Vec::new();
+    for user_id in collaboration::get_all_participants(db, todo_id) {
+        if user_id == author || mentioned.contains(&user_id) {
+            continue;
+        }
+        let names: Vec<String> = db
+            .query_row(
+                "SELECT username, COALESCE(display_name, '') FROM users WHERE id = ?1",
+                [&user_id],
+                |r| Ok(vec![r.get::<_, String>(0)?, r.get::<_, String>(1)?]),
+            )
+            .unwrap_or_default();
+        let hit = names.iter().filter(|n| !n.is_empty()).any(|name| {
+            let needle = format!("@{}", name.to_lowercase());
+            body.match_indices(&needle).any(|(i, _)| {
+                body[i + needle.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric())
+            })
+        });
+        if hit {
+            mentioned.push(user_id);
+        }
+    }
+    mentioned
+}
+
+/// Notify the other participants about a new comment, or only newly
+/// mentioned users after an edit. Returns Web Push payloads per recipient.
+pub fn notify(
+    db: &Connection,
+    comment: &TodoComment,
+    previous_mentions: Option<&[String]>,
+    now: &str,
+) -> Vec<(String, serde_json::Value)> {
+    let todo_text: String = db
+        .query_row(
+            "SELECT text FROM todos WHERE id = ?1",
+            [&comment.todo_id],
+            |r| r.get(0),
+        )
+        .unwrap_or_default();
+    let excerpt: String = comment.body.chars().take(80).collect();
+
+    let recipients: Vec<String> = match previous_mentions {
+        None => collaboration::get_all_participants(db, &comment.todo_id)
+            .into_iter()
+            .filter(|u| *u != comment.user_id)
+            .collect(),
+        Some(previous) => comment
+            .mentions
+            .iter()
+            .map(|m| m.user_id.clone())
+            .filter(|u| !previous.contains(u))
+            .collect(),
+    };
+
+    let mut pushes = Vec::new();
+    for user_id in recipients {
+        let mentioned = comment.mentions.iter().any(|m| m.user_id == user_id);
+        let (ntype, title) = if mentioned {
+            (
+                "todo_mention",
+                format!("{} 在「{}」中提到了你", comment.author_name, todo_text),
+            )
+        } else {
+            (
+                "todo_comment",
+                format!("{} 评论了「{}」", comment.author_name, todo_text),
+            )
+        };
+        let notif_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        db.execute(
+            "INSERT INTO notifications (id, user_id, type, title, body, todo_id, read, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7)",
+            rusqlite::params![notif_id, user_id, ntype, title, excerpt, comment.todo_id, now],
+        )
+        .ok();
+        pushes.push((
+            user_id,
+            json!({
+                "title": title,
+                "body": excerpt,
+                "type": ntype,
+                "todo_id": comment.todo_id,
+            }),
+        ));
+    }
+    pushes
+}
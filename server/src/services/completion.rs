@@ -0,0 +1,11 @@
+use rusqlite::Connection;
+
+use crate::services::{dependencies, recurrence};
+
+/// Side effects of a todo becoming completed: notify todos waiting on it and
+/// spawn the next occurrence of a recurring todo. Call only on the transition
+/// from open to completed. Returns the id of the spawned occurrence, if any.
+pub fn on_completed(db: &Connection, todo_id: &str, now: &str) -> Option<String> {
+    dependencies::notify_unblocked(db, todo_id, now);
+    recurrence::spawn_next(db, todo_id, now)
+}
@@ -1,6 +1,8 @@
 use chrono::Timelike;
 use rusqlite::Connection;
 
+use crate::services::{projects, templates};
+
 /// Sanitize user-generated text before injecting into AI prompts.
 /// Truncates to max_len, strips angle brackets and control chars.
 fn sanitize_for_prompt(text: &str, max_len: usize) -> String {
@@ -94,11 +96,22 @@ pub fn build_system_prompt_with_page(
 - "改/更新/进度/完成" → update_todo
 - "删掉/不要了" → delete_todo
 - "有哪些/多少任务" → query_todos 或 get_statistics
+- "这周/上个月做得怎么样""写个周报" → get_report，上周/上月传对应日期
 - "帮我整理/分类" → 先 query_todos 再 batch_update_todos
+- "改错了/撤销刚才的修改" → undo_todo_changes，传入之前 update_todo / batch_update_todos 返回的 batch_id
 - 创建任务时指定协作者 → create_todo 传入 collaborator
+- "每周都要做/每个工作日" 这类周期性任务 → create_todo 传入 recurrence，完成后会自动生成下一次
+- query_todos 返回 blocked_by 的任务还在等前置任务完成，建议用户先处理前置任务
+- 复杂条件（"这周到期的工作任务""逾期没做完的"）→ query_todos 传 filter，如 "tag:工作 due<7d !completed"、"overdue"；用户提到已保存的视图名时传 view
+- 提到某个项目 → create_todo / update_todo 传 project（项目名），查看项目里的任务 → query_todos 传 project；项目需用户先在应用里创建
+- "开始做/开始计时" → start_timer；"做完了/停止计时" → stop_timer，已有计时在跑时先停掉再开新的
+- "用模板/按模板建任务" → apply_template，传模板名和基准日期（如出发日、上线日）；用户要单独成项目时传 new_project
+
+### 搜索
+- "找一下/之前记过的…在哪" → search（跨待办、学习、记账、差旅），再按返回的 id 调用对应模块的工具
 
 ### 例行
-- "加一个例行/每天做" → create_routine
+- "加一个例行/每天做" → create_routine（"每周一三五"、"每周三次"、"隔天" 用 schedule 表示）
 - "例行有哪些/完成情况" → query_routines
 - "改一下那个例行" → 先 query_routines 找到 ID → update_routine
 - "删掉那个例行" → 先 query_routines 找到 ID → delete_routine
@@ -229,6 +242,40 @@ fn build_task_context(db: &Connection, user_id: &str) -> String {
     }
     ctx.push('\n');
 
+    // Open projects with their progress
+    let open_projects: Vec<String> = projects::list(db, user_id, None)
+        .into_iter()
+        .filter(|p| p.status == "active" || p.status == "paused")
+        .take(8)
+        .map(|p| {
+            let mut line = format!(
+                "{}（{}，{}/{} 完成",
+                p.name,
+                projects::status_label(&p.status),
+                p.progress.completed,
+                p.progress.total
+            );
+            if let Some(deadline) = &p.deadline {
+                line.push_str(&format!("，截止 {}", deadline));
+            }
+            if p.progress.overdue > 0 {
+                line.push_str(&format!("，{} 个逾期", p.progress.overdue));
+            }
+            line.push('）');
+            line
+        })
+        .collect();
+    if !open_projects.is_empty() {
+        ctx.push_str(&format!("- 项目: {}\n", open_projects.join("；")));
+    }
+    let template_names: Vec<String> = templates::list(db, user_id)
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    if !template_names.is_empty() {
+        ctx.push_str(&format!("- 任务模板: {}\n", template_names.join("、")));
+    }
+
     // Routine counts
     let routine_total: i64 = db
         .query_row(
@@ -254,7 +301,7 @@ fn build_task_context(db: &Connection, user_id: &str) -> String {
     // Review counts
     let review_total: i64 = db
         .query_row(
-            "SELECT COUNT(*) FROM reviews WHERE user_id=?1",
+            "SELECT COUNT(*) FROM reviews WHERE user_id=?1 AND deleted=0",
             [user_id],
             |r| r.get(0),
         )
@@ -278,7 +325,7 @@ fn build_task_context(db: &Connection, user_id: &str) -> String {
     // Expense summary (current month)
     let month_start = chrono::Local::now().format("%Y-%m-01").to_string();
     if let Ok(row) = db.query_row(
-        "SELECT COALESCE(SUM(amount), 0), COUNT(*) FROM expense_entries WHERE user_id=?1 AND date >= ?2",
+        "SELECT COALESCE(SUM(amount), 0), COUNT(*) FROM expense_entries WHERE user_id=?1 AND deleted=0 AND date >= ?2",
         rusqlite::params![user_id, month_start],
         |r| Ok((r.get::<_, f64>(0)?, r.get::<_, i64>(1)?)),
     ) {
@@ -290,14 +337,14 @@ fn build_task_context(db: &Connection, user_id: &str) -> String {
     // Trip count
     let trip_count: i64 = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE user_id=?1",
+            "SELECT COUNT(*) FROM trips WHERE user_id=?1 AND deleted=0",
             [user_id],
             |r| r.get(0),
         )
         .unwrap_or(0);
     let trip_collab: i64 = db
         .query_row(
-            "SELECT COUNT(*) FROM trip_collaborators WHERE user_id=?1",
+            "SELECT COUNT(*) FROM trip_collaborators tc JOIN trips t ON t.id = tc.trip_id WHERE tc.user_id=?1 AND t.deleted=0",
             [user_id],
             |r| r.get(0),
         )
@@ -429,7 +476,7 @@ fn build_page_context(
         }
         "expense" | "life" => {
             if let Ok(row) = db.query_row(
-                "SELECT amount, date, notes, tags, COALESCE(currency, 'CAD') FROM expense_entries WHERE id=?1 AND user_id=?2",
+                "SELECT amount, date, notes, tags, COALESCE(currency, 'CAD') FROM expense_entries WHERE id=?1 AND user_id=?2 AND deleted=0",
                 rusqlite::params![detail_id, user_id],
                 |r| Ok((
                     r.get::<_, f64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?,
@@ -445,7 +492,7 @@ fn build_page_context(
         }
         "trip" => {
             if let Ok(row) = db.query_row(
-                "SELECT title, destination, date_from, date_to FROM trips WHERE id=?1 AND (user_id=?2 OR id IN (SELECT trip_id FROM trip_collaborators WHERE user_id=?2))",
+                "SELECT title, destination, date_from, date_to FROM trips WHERE id=?1 AND deleted=0 AND (user_id=?2 OR id IN (SELECT trip_id FROM trip_collaborators WHERE user_id=?2))",
                 rusqlite::params![detail_id, user_id],
                 |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?)),
             ) {
@@ -0,0 +1,341 @@
+//! CSV imports from other task managers (Todoist, TickTick, spreadsheets).
+
+use chrono::NaiveDate;
+use rusqlite::Connection;
+
+use crate::models::import::{CsvMapping, CsvPreview, CsvRow};
+use crate::models::todo::{Quadrant, Todo};
+use crate::services::tool_executor::compute_tab_for_date;
+use crate::services::{changelog, ordering, tags};
+
+/// Header names recognised for each field, lowercased
+const TITLE_HEADERS: [&str; 7] = ["title", "content", "task", "name", "text", "标题", "任务"];
+const NOTES_HEADERS: [&str; 7] = [
+    "notes",
+    "note",
+    "description",
+    "details",
+    "content",
+    "备注",
+    "内容",
+];
+const DUE_HEADERS: [&str; 6] = [
+    "due",
+    "due date",
+    "due_date",
+    "date",
+    "deadline",
+    "截止日期",
+];
+const PRIORITY_HEADERS: [&str; 3] = ["priority", "prio", "优先级"];
+const TAGS_HEADERS: [&str; 4] = ["tags", "labels", "tag", "标签"];
+const COMPLETED_HEADERS: [&str; 6] = [
+    "completed",
+    "done",
+    "status",
+    "is completed",
+    "完成",
+    "状态",
+];
+
+const DEFAULT_QUADRANT: Quadrant = Quadrant::NotImportantNotUrgent;
+
+/// Pick a column for each field from well-known header names. A header
+/// already taken by an earlier field is not reused (Todoist's `CONTENT` is
+/// the title, not the notes).
+pub fn suggest_mapping(headers: &[String]) -> CsvMapping {
+    let mut used: Vec<usize> = Vec::new();
+    let mut pick = |names: &[&str]| {
+        let idx = names.iter().find_map(|name| {
+            (0..headers.len())
+                .find(|i| !used.contains(i) && headers[*i].trim().to_lowercase() == *name)
+        })?;
+        used.push(idx);
+        Some(headers[idx].clone())
+    };
+    CsvMapping {
+        title: pick(&TITLE_HEADERS),
+        notes: pick(&NOTES_HEADERS),
+        due: pick(&DUE_HEADERS),
+        priority: pick(&PRIORITY_HEADERS),
+        tags: pick(&TAGS_HEADERS),
+        completed: pick(&COMPLETED_HEADERS),
+    }
+}
+
+fn column(headers: &[String], name: &Option<String>) -> Result<Option<usize>, String> {
+    let Some(name) = name.as_deref().map(str::trim).filter(|n| !n.is_empty()) else {
+        return Ok(None);
+    };
+    headers
+        .iter()
+        .position(|h| h.trim().eq_ignore_ascii_case(name))
+        .map(Some)
+        .ok_or_else(|| format!("CSV 中没有名为 \"{}\" 的列", name))
+}
+
+/// Parse `content` with `mapping` (or the guessed one). Errors here are
+/// about the file or the mapping as a whole; row problems end up in
+/// `CsvRow::errors`.
+pub fn preview(content: &str, mapping: Option<&CsvMapping>) -> Result<CsvPreview, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("无法读取 CSV 表头: {}", e))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    if headers.iter().all(|h| h.is_empty()) {
+        return Err("CSV 文件为空".into());
+    }
+
+    let mapping = mapping
+        .cloned()
+        .unwrap_or_else(|| suggest_mapping(&headers));
+    let title = column(&headers, &mapping.title)?.ok_or("请指定标题所在的列")?;
+    let notes = column(&headers, &mapping.notes)?;
+    let due = column(&headers, &mapping.due)?;
+    let priority = column(&headers, &mapping.priority)?;
+    let tag_col = column(&headers, &mapping.tags)?;
+    let completed = column(&headers, &mapping.completed)?;
+    // Todoist mixes section and comment rows into its task export
+    let kind = headers.iter().position(|h| h.eq_ignore_ascii_case("type"));
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let row = i + 1;
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                rows.push(invalid_row(row, format!("无法解析: {}", e)));
+                continue;
+            }
+        };
+        let field = |idx: Option<usize>| idx.and_then(|i| record.get(i)).unwrap_or("");
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        if kind.is_some_and(|k| !matches!(field(Some(k)).to_lowercase().as_str(), "" | "task")) {
+            continue;
+        }
+        rows.push(parse_row(
+            row,
+            field(Some(title)),
+            field(notes),
+            field(due),
+            field(priority),
+            field(tag_col),
+            field(completed),
+        ));
+    }
+
+    let invalid = rows.iter().filter(|r| !r.errors.is_empty()).count();
+    Ok(CsvPreview {
+        headers,
+        mapping,
+        valid: rows.len() - invalid,
+        invalid,
+        rows,
+    })
+}
+
+fn invalid_row(row: usize, error: String) -> CsvRow {
+    CsvRow {
+        row,
+        text: String::new(),
+        content: String::new(),
+        due_date: None,
+        tab: "today".into(),
+        quadrant: DEFAULT_QUADRANT.as_str().into(),
+        tags: Vec::new(),
+        completed: false,
+        errors: vec![error],
+    }
+}
+
+fn parse_row(
+    row: usize,
+    title: &str,
+    notes: &str,
+    due: &str,
+    priority: &str,
+    tag_list: &str,
+    completed: &str,
+) -> CsvRow {
+    let mut errors = Vec::new();
+
+    if title.is_empty() {
+        errors.push("标题为空".to_string());
+    } else if title.chars().count() > 500 {
+        errors.push("标题不能超过 500 字符".to_string());
+    }
+    if notes.chars().count() > 10000 {
+        errors.push("备注不能超过 10000 字符".to_string());
+    }
+
+    let due_date = match parse_date(due) {
+        Ok(d) => d,
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    };
+    let quadrant = parse_priority(priority).unwrap_or_else(|e| {
+        errors.push(e);
+        DEFAULT_QUADRANT
+    });
+    let completed = parse_bool(completed).unwrap_or_else(|e| {
+        errors.push(e);
+        false
+    });
+
+    let mut tag_names: Vec<String> = Vec::new();
+    for raw in tag_list.split([',', ';', '|']) {
+        let raw = raw.trim().trim_start_matches(['#', '@']);
+        if raw.is_empty() {
+            continue;
+        }
+        match tags::validate_name(raw) {
+            Ok(name) if !tag_names.contains(&name) => tag_names.push(name),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("标签 \"{}\": {}", raw, e)),
+        }
+    }
+
+    CsvRow {
+        row,
+        text: title.to_string(),
+        content: notes.to_string(),
+        tab: due_date
+            .as_deref()
+            .map(compute_tab_for_date)
+            .unwrap_or("today")
+            .to_string(),
+        due_date,
+        quadrant: quadrant.as_str().to_string(),
+        tags: tag_names,
+        completed,
+        errors,
+    }
+}
+
+/// `2026-03-01`, `2026/3/1`, `2026.03.01`, or a timestamp starting with one
+fn parse_date(s: &str) -> Result<Option<String>, String> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let date_part = s.split(['T', ' ']).next().unwrap_or(s);
+    ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"]
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(date_part, fmt).ok())
+        .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+        .ok_or_else(|| format!("无法识别的日期: {}", s))
+}
+
+/// Quadrant slugs, `A`–`D`, Todoist-style `p1`–`p4` / `1`–`4` (1 highest),
+/// or high/medium/low words. Empty and `0` mean no priority.
+fn parse_priority(s: &str) -> Result<Quadrant, String> {
+    let lower = s.to_lowercase();
+    let idx = match lower.trim_start_matches('p') {
+        "" | "0" | "none" | "无" => return Ok(DEFAULT_QUADRANT),
+        "1" | "a" | "urgent" | "highest" | "紧急" => 0,
+        "2" | "b" | "high" | "高" => 1,
+        "3" | "c" | "medium" | "normal" | "中" => 2,
+        "4" | "d" | "low" | "低" => 3,
+        _ => {
+            return [
+                Quadrant::ImportantUrgent,
+                Quadrant::ImportantNotUrgent,
+                Quadrant::NotImportantUrgent,
+                Quadrant::NotImportantNotUrgent,
+            ]
+            .into_iter()
+            .find(|q| q.as_str() == lower)
+            .ok_or_else(|| format!("无法识别的优先级: {}", s))
+        }
+    };
+    Ok(match idx {
+        0 => Quadrant::ImportantUrgent,
+        1 => Quadrant::ImportantNotUrgent,
+        2 => Quadrant::NotImportantUrgent,
+        _ => Quadrant::NotImportantNotUrgent,
+    })
+}
+
+fn parse_bool(s: &str) -> Result<bool, String> {
+    match s.to_lowercase().as_str() {
+        "" | "false" | "no" | "n" | "0" | "open" | "todo" | "否" | "未完成" => Ok(false),
+        "true" | "yes" | "y" | "1" | "x" | "done" | "completed" | "是" | "完成" | "已完成" => {
+            Ok(true)
+        }
+        _ => Err(format!("无法识别的完成状态: {}", s)),
+    }
+}
+
+/// Insert the valid rows in one transaction; returns the new todo ids.
+/// `source` names the tool the file came from in the changelog entry.
+pub fn import(
+    db: &Connection,
+    user_id: &str,
+    rows: &[CsvRow],
+    source: Option<&str>,
+    now: &str,
+) -> Result<Vec<String>, String> {
+    let source = source
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("CSV");
+    db.execute_batch("BEGIN TRANSACTION")
+        .map_err(|_| "数据库繁忙，请稍后重试".to_string())?;
+    let result = (|| {
+        let mut ids = Vec::new();
+        for row in rows.iter().filter(|r| r.errors.is_empty()) {
+            let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+            let sort_order = ordering::append_key(db, user_id, &row.tab, &row.quadrant);
+            db.execute(
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, \
+                 assignee, tags, sort_order, created_at, updated_at, deleted) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, '', ?11, ?12, ?13, ?13, 0)",
+                rusqlite::params![
+                    id,
+                    user_id,
+                    row.text,
+                    row.content,
+                    row.tab,
+                    row.quadrant,
+                    if row.completed { 100 } else { 0 },
+                    row.completed as i32,
+                    row.completed.then_some(now),
+                    row.due_date,
+                    serde_json::to_string(&row.tags).unwrap_or_else(|_| "[]".into()),
+                    sort_order,
+                    now,
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[csv_import] import DB error: {}", e);
+                format!("第 {} 行导入失败", row.row)
+            })?;
+            changelog::insert_changelog(
+                db,
+                &id,
+                "imported",
+                Todo::field_label("imported"),
+                "",
+                &format!("{} 第 {} 行", source, row.row),
+                now,
+            );
+            ids.push(id);
+        }
+        Ok(ids)
+    })();
+
+    match &result {
+        Ok(_) => db.execute_batch("COMMIT").ok(),
+        Err(_) => db.execute_batch("ROLLBACK").ok(),
+    };
+    result
+}
@@ -0,0 +1,216 @@
+use rusqlite::Connection;
+
+use crate::models::todo::{Todo, TodoLink};
+use crate::services::changelog::insert_changelog;
+use crate::services::collaboration;
+
+/// Max blockers on a single todo
+pub const MAX_BLOCKERS: usize = 20;
+
+fn todo_text(db: &Connection, todo_id: &str) -> Option<String> {
+    db.query_row(
+        "SELECT text FROM todos WHERE id = ?1 AND deleted = 0",
+        [todo_id],
+        |r| r.get(0),
+    )
+    .ok()
+}
+
+fn query_links(db: &Connection, sql: &str, todo_id: &str) -> Vec<TodoLink> {
+    db.prepare(sql)
+        .and_then(|mut stmt| {
+            stmt.query_map([todo_id], |r| {
+                Ok(TodoLink {
+                    id: r.get(0)?,
+                    text: r.get(1)?,
+                    completed: r.get::<_, i32>(2)? != 0,
+                })
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Load (blocked_by, blocking) for a todo, skipping soft-deleted todos
+pub fn load_links(db: &Connection, todo_id: &str) -> (Vec<TodoLink>, Vec<TodoLink>) {
+    let blocked_by = query_links(
+        db,
+        "SELECT t.id, t.text, t.completed FROM todo_dependencies d JOIN todos t ON t.id = d.blocker_id \
+         WHERE d.todo_id = ?1 AND t.deleted = 0 ORDER BY d.created_at",
+        todo_id,
+    );
+    let blocking = query_links(
+        db,
+        "SELECT t.id, t.text, t.completed FROM todo_dependencies d JOIN todos t ON t.id = d.todo_id \
+         WHERE d.blocker_id = ?1 AND t.deleted = 0 ORDER BY d.created_at",
+        todo_id,
+    );
+    (blocked_by, blocking)
+}
+
+/// Fill in blocked_by / blocking / blocked on a loaded todo
+pub fn attach(db: &Connection, todo: &mut Todo) {
+    let (blocked_by, blocking) = load_links(db, &todo.id);
+    todo.blocked = blocked_by.iter().any(|l| !l.completed);
+    todo.blocked_by = blocked_by;
+    todo.blocking = blocking;
+}
+
+/// True if `todo_id` is reachable from `blocker_id` by following blockers,
+/// i.e. adding "todo_id is blocked by blocker_id" would close a cycle
+fn creates_cycle(db: &Connection, todo_id: &str, blocker_id: &str) -> bool {
+    let mut stack = vec![blocker_id.to_string()];
+    let mut seen = std::collections::HashSet::new();
+    while let Some(current) = stack.pop() {
+        if current == todo_id {
+            return true;
+        }
+        if !seen.insert(current.clone()) {
+            continue;
+        }
+        let next: Vec<String> = db
+            .prepare("SELECT blocker_id FROM todo_dependencies WHERE todo_id = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map([&current], |r| r.get(0))
+                    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            })
+            .unwrap_or_default();
+        stack.extend(next);
+    }
+    false
+}
+
+/// Record that `todo_id` cannot start until `blocker_id` is completed
+pub fn add_dependency(
+    db: &Connection,
+    todo_id: &str,
+    blocker_id: &str,
+    user_id: &str,
+    now: &str,
+) -> Result<(), String> {
+    if todo_id == blocker_id {
+        return Err("任务不能依赖自己".into());
+    }
+    let blocker_text = todo_text(db, blocker_id).ok_or("前置任务不存在")?;
+
+    let exists: bool = db
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM todo_dependencies WHERE todo_id = ?1 AND blocker_id = ?2",
+            rusqlite::params![todo_id, blocker_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(false);
+    if exists {
+        return Ok(());
+    }
+
+    let count: usize = db
+        .query_row(
+            "SELECT COUNT(*) FROM todo_dependencies WHERE todo_id = ?1",
+            [todo_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    if count >= MAX_BLOCKERS {
+        return Err(format!("前置任务最多 {} 个", MAX_BLOCKERS));
+    }
+    if creates_cycle(db, todo_id, blocker_id) {
+        return Err("不能形成循环依赖".into());
+    }
+
+    db.execute(
+        "INSERT INTO todo_dependencies (todo_id, blocker_id, created_by, created_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![todo_id, blocker_id, user_id, now],
+    )
+    .map_err(|e| {
+        eprintln!("[dependencies] add DB error: {}", e);
+        "数据库写入失败，请稍后重试".to_string()
+    })?;
+
+    insert_changelog(
+        db,
+        todo_id,
+        "dependency",
+        Todo::field_label("dependency"),
+        "",
+        &blocker_text,
+        now,
+    );
+    Ok(())
+}
+
+/// Remove a dependency. Returns false if it did not exist.
+pub fn remove_dependency(db: &Connection, todo_id: &str, blocker_id: &str, now: &str) -> bool {
+    let removed = db
+        .execute(
+            "DELETE FROM todo_dependencies WHERE todo_id = ?1 AND blocker_id = ?2",
+            rusqlite::params![todo_id, blocker_id],
+        )
+        .unwrap_or(0)
+        > 0;
+    if removed {
+        let blocker_text = todo_text(db, blocker_id).unwrap_or_default();
+        insert_changelog(
+            db,
+            todo_id,
+            "dependency",
+            Todo::field_label("dependency"),
+            &blocker_text,
+            "",
+            now,
+        );
+    }
+    removed
+}
+
+/// Notify participants of every open todo that was waiting on `blocker_id`
+pub fn notify_unblocked(db: &Connection, blocker_id: &str, now: &str) {
+    let Some(blocker_text) = todo_text(db, blocker_id) else {
+        return;
+    };
+    let dependents: Vec<(String, String)> = db
+        .prepare(
+            "SELECT t.id, t.text FROM todo_dependencies d JOIN todos t ON t.id = d.todo_id \
+             WHERE d.blocker_id = ?1 AND t.completed = 0 AND t.deleted = 0",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([blocker_id], |r| Ok((r.get(0)?, r.get(1)?)))
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+
+    for (todo_id, text) in dependents {
+        let remaining: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM todo_dependencies d JOIN todos t ON t.id = d.blocker_id \
+                 WHERE d.todo_id = ?1 AND t.completed = 0 AND t.deleted = 0",
+                [&todo_id],
+                |r| r.get(0),
+            )
+            .unwrap_or(0);
+        let (title, body) = if remaining == 0 {
+            (
+                "任务可以开始了".to_string(),
+                format!("「{}」已完成，「{}」可以开始了", blocker_text, text),
+            )
+        } else {
+            (
+                "前置任务已完成".to_string(),
+                format!(
+                    "「{}」已完成，「{}」还有 {} 个前置任务",
+                    blocker_text, text, remaining
+                ),
+            )
+        };
+
+        for user_id in collaboration::get_all_participants(db, &todo_id) {
+            let notif_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+            db.execute(
+                "INSERT INTO notifications (id, user_id, type, title, body, todo_id, read, created_at) \
+                 VALUES (?1, ?2, 'todo_unblocked', ?3, ?4, ?5, 0, ?6)",
+                rusqlite::params![notif_id, user_id, title, body, todo_id, now],
+            )
+            .ok();
+        }
+    }
+}
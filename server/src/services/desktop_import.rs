@@ -0,0 +1,569 @@
+//! Import of the desktop app's JSON files into a user's server data.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::models::import::*;
+use crate::models::review::{Frequency, FrequencyConfig};
+use crate::models::routine::RoutineSchedule;
+use crate::models::todo::Todo;
+use crate::services::changelog::{self, REVERTIBLE_FIELDS};
+use crate::services::{ordering, tags};
+
+const TABS: [&str; 3] = ["today", "week", "month"];
+const QUADRANTS: [&str; 4] = [
+    "important-urgent",
+    "important-not-urgent",
+    "not-important-urgent",
+    "not-important-not-urgent",
+];
+
+/// Import the files for `user_id`, or only plan it when `dry_run` is set.
+/// A real import is all-or-nothing.
+pub fn run(
+    db: &Connection,
+    user_id: &str,
+    req: &DesktopImportRequest,
+    now: &str,
+) -> Result<DesktopImportReport, String> {
+    let dry_run = req.dry_run;
+    if !dry_run {
+        db.execute_batch("BEGIN TRANSACTION")
+            .map_err(|_| "数据库繁忙，请稍后重试".to_string())?;
+    }
+    let result = (|| {
+        let mut report = DesktopImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut importer = Importer {
+            db,
+            user_id,
+            now,
+            dry_run,
+            seen: HashSet::new(),
+        };
+        for raw in req.todos.iter().flat_map(|f| &f.items) {
+            let entry = importer.todo(raw)?;
+            count(&mut report.todos, &entry);
+            report.items.push(entry);
+        }
+        for raw in req.routines.iter().flat_map(|f| &f.items) {
+            let entry = importer.routine(raw)?;
+            count(&mut report.routines, &entry);
+            report.items.push(entry);
+        }
+        for raw in req.reviews.iter().flat_map(|f| &f.items) {
+            let entry = importer.review(raw)?;
+            count(&mut report.reviews, &entry);
+            report.items.push(entry);
+        }
+        Ok(report)
+    })();
+
+    if !dry_run {
+        match &result {
+            Ok(_) => db.execute_batch("COMMIT").ok(),
+            Err(_) => db.execute_batch("ROLLBACK").ok(),
+        };
+    }
+    result
+}
+
+fn count(counts: &mut ImportCounts, entry: &ImportEntry) {
+    match entry.action.as_str() {
+        "create" => counts.created += 1,
+        "duplicate" => counts.duplicates += 1,
+        _ => counts.invalid += 1,
+    }
+}
+
+struct Importer<'a> {
+    db: &'a Connection,
+    user_id: &'a str,
+    now: &'a str,
+    dry_run: bool,
+    /// Duplicate keys of items already taken from these files, so repeats
+    /// inside one import are caught in a dry run too
+    seen: HashSet<String>,
+}
+
+fn entry(item_type: &str, id: &str, text: &str, action: &str) -> ImportEntry {
+    ImportEntry {
+        item_type: item_type.into(),
+        id: id.into(),
+        text: text.into(),
+        action: action.into(),
+        new_id: None,
+        existing_id: None,
+        differences: Vec::new(),
+        message: None,
+    }
+}
+
+/// Parse one raw item, or record why it can't be imported
+fn parse<T: DeserializeOwned>(item_type: &str, raw: &Value) -> Result<T, Box<ImportEntry>> {
+    serde_json::from_value(raw.clone()).map_err(|e| {
+        let id = raw["id"].as_str().unwrap_or_default();
+        let text = raw["text"].as_str().unwrap_or_default();
+        Box::new(invalid(item_type, id, text, format!("格式错误: {}", e)))
+    })
+}
+
+fn invalid(item_type: &str, id: &str, text: &str, message: String) -> ImportEntry {
+    let mut invalid = entry(item_type, id, text, "invalid");
+    invalid.message = Some(message);
+    invalid
+}
+
+/// Desktop timestamps are RFC 3339, or ISO without an offset in older
+/// files; both become RFC 3339 (offset-less ones read as UTC)
+fn timestamp(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(raw) {
+        return Some(t.to_rfc3339());
+    }
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()
+        .map(|t| t.and_utc().to_rfc3339())
+}
+
+fn opt_timestamp(raw: Option<&str>) -> Option<String> {
+    raw.filter(|s| !s.is_empty()).and_then(timestamp)
+}
+
+/// `YYYY-MM-DD` from a date or timestamp
+fn date(raw: Option<&str>) -> Option<String> {
+    let raw = raw?.trim();
+    let day = raw.get(..10)?;
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .ok()
+        .map(|_| day.to_string())
+}
+
+/// Raw column value of a desktop changelog value
+fn raw_value(field: &str, value: &Value) -> String {
+    match value {
+        Value::String(s) if field == "completed" => {
+            if matches!(s.to_lowercase().as_str(), "true" | "1") {
+                "1".into()
+            } else {
+                "0".into()
+            }
+        }
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => if *b { "1" } else { "0" }.into(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+impl Importer<'_> {
+    /// Whether `id` is free in `table` across all users
+    fn id_free(&self, table: &str, id: &str) -> bool {
+        !id.is_empty()
+            && self
+                .db
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {} WHERE id = ?1", table),
+                    [id],
+                    |r| r.get::<_, i64>(0),
+                )
+                .map(|n| n == 0)
+                .unwrap_or(false)
+    }
+
+    /// Keep the desktop id when it's free, otherwise mint one
+    fn claim_id(&self, table: &str, id: &str) -> String {
+        if self.id_free(table, id) {
+            id.to_string()
+        } else {
+            uuid::Uuid::new_v4().to_string()[..8].to_string()
+        }
+    }
+
+    /// Id of the first row matching `sql`
+    fn find(&self, sql: &str, params: impl rusqlite::Params) -> Option<String> {
+        self.db.query_row(sql, params, |r| r.get(0)).ok()
+    }
+
+    /// Register an item's duplicate keys; false if any was already taken
+    /// earlier in this import. Items without an id only count by content.
+    fn take(&mut self, item_type: &str, id: &str, content: &str) -> bool {
+        let by_id = id.is_empty() || self.seen.insert(format!("{}:id:{}", item_type, id));
+        let by_content = self.seen.insert(format!("{}:{}", item_type, content));
+        by_id && by_content
+    }
+
+    fn todo(&mut self, raw: &Value) -> Result<ImportEntry, String> {
+        let todo: DesktopTodo = match parse("todo", raw) {
+            Ok(t) => t,
+            Err(e) => return Ok(*e),
+        };
+        let text = todo.text.trim().to_string();
+        if text.is_empty() {
+            return Ok(invalid("todo", &todo.id, "", "标题不能为空".into()));
+        }
+        let tab = todo.tab.as_deref().unwrap_or("today").to_lowercase();
+        if !TABS.contains(&tab.as_str()) {
+            return Ok(invalid(
+                "todo",
+                &todo.id,
+                &text,
+                format!("无效的 tab: {}", tab),
+            ));
+        }
+        let quadrant = todo
+            .quadrant
+            .as_deref()
+            .unwrap_or("not-important-not-urgent")
+            .to_string();
+        if !QUADRANTS.contains(&quadrant.as_str()) {
+            return Ok(invalid(
+                "todo",
+                &todo.id,
+                &text,
+                format!("无效的象限: {}", quadrant),
+            ));
+        }
+        let created_at = timestamp(&todo.created_at).unwrap_or_else(|| self.now.to_string());
+        let updated_at = timestamp(&todo.updated_at).unwrap_or_else(|| created_at.clone());
+        let progress = todo.progress.clamp(0, 100);
+        let due_date = date(todo.due_date.as_deref());
+        let assignee = todo.assignee.clone().unwrap_or_default();
+        let mut todo_tags: Vec<String> = Vec::new();
+        for tag in todo.tags.iter().filter_map(|t| tags::validate_name(t).ok()) {
+            if !todo_tags.contains(&tag) {
+                todo_tags.push(tag);
+            }
+        }
+        let tags_json = serde_json::to_string(&todo_tags).unwrap_or_else(|_| "[]".into());
+
+        let mut result = entry("todo", &todo.id, &text, "create");
+        let existing = self
+            .find(
+                "SELECT id FROM todos WHERE user_id = ?1 AND id = ?2",
+                rusqlite::params![self.user_id, todo.id],
+            )
+            .or_else(|| {
+                self.find(
+                    "SELECT id FROM todos WHERE user_id = ?1 AND text = ?2 AND created_at = ?3",
+                    rusqlite::params![self.user_id, text, created_at],
+                )
+            });
+        let fresh = self.take("todo", &todo.id, &format!("{}\n{}", text, created_at));
+        if let Some(existing_id) = existing {
+            result.action = "duplicate".into();
+            result.differences = self.todo_differences(
+                &existing_id,
+                &[
+                    ("text", text.clone()),
+                    ("content", todo.content.clone()),
+                    ("tab", tab.clone()),
+                    ("quadrant", quadrant.clone()),
+                    ("progress", progress.to_string()),
+                    ("completed", if todo.completed { "1" } else { "0" }.into()),
+                    ("due_date", due_date.clone().unwrap_or_default()),
+                    ("assignee", assignee.clone()),
+                    ("tags", tags_json.clone()),
+                ],
+            );
+            result.existing_id = Some(existing_id);
+            return Ok(result);
+        }
+        if !fresh {
+            result.action = "duplicate".into();
+            result.message = Some("文件中重复出现".into());
+            return Ok(result);
+        }
+
+        let id = self.claim_id("todos", &todo.id);
+        if id != todo.id {
+            result.new_id = Some(id.clone());
+        }
+        if self.dry_run {
+            return Ok(result);
+        }
+
+        let sort_order = ordering::append_key(self.db, self.user_id, &tab, &quadrant);
+        let deleted_at = if todo.deleted {
+            opt_timestamp(todo.deleted_at.as_deref()).or_else(|| Some(self.now.to_string()))
+        } else {
+            None
+        };
+        self.db
+            .execute(
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, \
+                 assignee, tags, sort_order, created_at, updated_at, deleted, deleted_at) \
+                 VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)",
+                rusqlite::params![
+                    id,
+                    self.user_id,
+                    text,
+                    todo.content,
+                    tab,
+                    quadrant,
+                    progress,
+                    todo.completed as i32,
+                    opt_timestamp(todo.completed_at.as_deref()),
+                    due_date,
+                    assignee,
+                    tags_json,
+                    sort_order,
+                    created_at,
+                    updated_at,
+                    todo.deleted as i32,
+                    deleted_at,
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[import] todo insert DB error: {}", e);
+                "导入任务失败".to_string()
+            })?;
+
+        for change in &todo.changelog {
+            let field = change.field.as_str();
+            if field.is_empty() {
+                continue;
+            }
+            let from = raw_value(field, &change.old_value);
+            let to = raw_value(field, &change.new_value);
+            let revertible = REVERTIBLE_FIELDS.contains(&field);
+            self.db
+                .execute(
+                    "INSERT INTO todo_changelog (todo_id, field, label, from_val, to_val, time, changed_by, from_raw, to_raw) \
+                     VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9)",
+                    rusqlite::params![
+                        id,
+                        field,
+                        Todo::field_label(field),
+                        changelog::display_value(field, &from),
+                        changelog::display_value(field, &to),
+                        timestamp(&change.timestamp).unwrap_or_else(|| updated_at.clone()),
+                        self.user_id,
+                        revertible.then_some(&from),
+                        revertible.then_some(&to),
+                    ],
+                )
+                .map_err(|e| {
+                    eprintln!("[import] changelog insert DB error: {}", e);
+                    "导入变更记录失败".to_string()
+                })?;
+        }
+        Ok(result)
+    }
+
+    /// Fields of an existing todo that differ from the imported values
+    fn todo_differences(&self, id: &str, values: &[(&str, String)]) -> Vec<String> {
+        let current: Vec<String> = self
+            .db
+            .query_row(
+                "SELECT text, COALESCE(content, ''), tab, quadrant, COALESCE(progress, 0), completed, \
+                 COALESCE(due_date, ''), COALESCE(assignee, ''), COALESCE(tags, '[]') FROM todos WHERE id = ?1",
+                [id],
+                |r| {
+                    Ok(vec![
+                        r.get(0)?,
+                        r.get(1)?,
+                        r.get(2)?,
+                        r.get(3)?,
+                        r.get::<_, i64>(4)?.to_string(),
+                        r.get::<_, i64>(5)?.to_string(),
+                        r.get(6)?,
+                        r.get(7)?,
+                        r.get(8)?,
+                    ])
+                },
+            )
+            .unwrap_or_default();
+        values
+            .iter()
+            .zip(&current)
+            .filter(|((_, new), old)| new != *old)
+            .map(|((field, _), _)| field.to_string())
+            .collect()
+    }
+
+    fn routine(&mut self, raw: &Value) -> Result<ImportEntry, String> {
+        let routine: DesktopRoutine = match parse("routine", raw) {
+            Ok(r) => r,
+            Err(e) => return Ok(*e),
+        };
+        let text = routine.text.trim().to_string();
+        if text.is_empty() {
+            return Ok(invalid("routine", &routine.id, "", "内容不能为空".into()));
+        }
+
+        let mut result = entry("routine", &routine.id, &text, "create");
+        let existing = self.find(
+            "SELECT id FROM routines WHERE user_id = ?1 AND (id = ?2 OR text = ?3)",
+            rusqlite::params![self.user_id, routine.id, text],
+        );
+        let fresh = self.take("routine", &routine.id, &text);
+        if let Some(existing_id) = existing {
+            result.action = "duplicate".into();
+            result.existing_id = Some(existing_id);
+            return Ok(result);
+        }
+        if !fresh {
+            result.action = "duplicate".into();
+            result.message = Some("文件中重复出现".into());
+            return Ok(result);
+        }
+
+        let id = self.claim_id("routines", &routine.id);
+        if id != routine.id {
+            result.new_id = Some(id.clone());
+        }
+        if self.dry_run {
+            return Ok(result);
+        }
+        self.db
+            .execute(
+                "INSERT INTO routines (id, user_id, text, completed_today, last_completed_date, created_at, schedule) \
+                 VALUES (?1,?2,?3,?4,?5,?6,?7)",
+                rusqlite::params![
+                    id,
+                    self.user_id,
+                    text,
+                    routine.completed_today as i32,
+                    date(routine.last_completed_date.as_deref()),
+                    timestamp(&routine.created_at).unwrap_or_else(|| self.now.to_string()),
+                    routine
+                        .schedule
+                        .as_deref()
+                        .and_then(RoutineSchedule::parse)
+                        .unwrap_or(RoutineSchedule::Daily)
+                        .as_string(),
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[import] routine insert DB error: {}", e);
+                "导入日常任务失败".to_string()
+            })?;
+        Ok(result)
+    }
+
+    fn review(&mut self, raw: &Value) -> Result<ImportEntry, String> {
+        let review: DesktopReview = match parse("review", raw) {
+            Ok(r) => r,
+            Err(e) => return Ok(*e),
+        };
+        let text = review.text.trim().to_string();
+        if text.is_empty() {
+            return Ok(invalid("review", &review.id, "", "内容不能为空".into()));
+        }
+        let frequency = review.frequency.to_lowercase();
+        if serde_json::from_value::<Frequency>(Value::String(frequency.clone())).is_err() {
+            return Ok(invalid(
+                "review",
+                &review.id,
+                &text,
+                format!("无效的频率: {}", review.frequency),
+            ));
+        }
+        let config: FrequencyConfig = if review.frequency_config.is_null() {
+            FrequencyConfig::default()
+        } else {
+            match serde_json::from_value(review.frequency_config.clone()) {
+                Ok(c) => c,
+                Err(e) => {
+                    return Ok(invalid(
+                        "review",
+                        &review.id,
+                        &text,
+                        format!("无效的频率配置: {}", e),
+                    ))
+                }
+            }
+        };
+        let config_json = serde_json::to_string(&config).unwrap_or_else(|_| "{}".into());
+
+        let mut result = entry("review", &review.id, &text, "create");
+        let existing = self
+            .find(
+                "SELECT id FROM reviews WHERE user_id = ?1 AND id = ?2",
+                rusqlite::params![self.user_id, review.id],
+            )
+            .or_else(|| {
+                self.find(
+                    "SELECT id FROM reviews WHERE user_id = ?1 AND text = ?2 AND frequency = ?3 AND deleted = 0",
+                    rusqlite::params![self.user_id, text, frequency],
+                )
+            });
+        let fresh = self.take("review", &review.id, &format!("{}\n{}", text, frequency));
+        if let Some(existing_id) = existing {
+            result.action = "duplicate".into();
+            let current: Option<(String, String, String, String, i64)> = self
+                .db
+                .query_row(
+                    "SELECT frequency, COALESCE(frequency_config, '{}'), COALESCE(notes, ''), \
+                     COALESCE(category, ''), COALESCE(paused, 0) FROM reviews WHERE id = ?1",
+                    [&existing_id],
+                    |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+                )
+                .ok();
+            if let Some((freq, conf, notes, category, paused)) = current {
+                let conf: Value = serde_json::from_str(&conf).unwrap_or_default();
+                let new_conf: Value = serde_json::from_str(&config_json).unwrap_or_default();
+                for (field, differs) in [
+                    ("frequency", freq != frequency),
+                    ("frequency_config", conf != new_conf),
+                    ("notes", notes != review.notes),
+                    ("category", category != review.category),
+                    ("paused", (paused != 0) != review.paused),
+                ] {
+                    if differs {
+                        result.differences.push(field.into());
+                    }
+                }
+            }
+            result.existing_id = Some(existing_id);
+            return Ok(result);
+        }
+        if !fresh {
+            result.action = "duplicate".into();
+            result.message = Some("文件中重复出现".into());
+            return Ok(result);
+        }
+
+        let id = self.claim_id("reviews", &review.id);
+        if id != review.id {
+            result.new_id = Some(id.clone());
+        }
+        if self.dry_run {
+            return Ok(result);
+        }
+        let created_at = timestamp(&review.created_at).unwrap_or_else(|| self.now.to_string());
+        let updated_at = timestamp(&review.updated_at).unwrap_or_else(|| created_at.clone());
+        self.db
+            .execute(
+                "INSERT INTO reviews (id, user_id, text, frequency, frequency_config, notes, category, last_completed, \
+                 paused, created_at, updated_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11)",
+                rusqlite::params![
+                    id,
+                    self.user_id,
+                    text,
+                    frequency,
+                    config_json,
+                    review.notes,
+                    review.category,
+                    opt_timestamp(review.last_completed.as_deref())
+                        .or_else(|| date(review.last_completed.as_deref())),
+                    review.paused as i32,
+                    created_at,
+                    updated_at,
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[import] review insert DB error: {}", e);
+                "导入例行审视失败".to_string()
+            })?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,32 @@
+//! Optimistic concurrency for single-record edits.
+
+use axum::http::{header, HeaderMap, HeaderValue};
+
+/// Entity tag for a record version
+pub fn tag(updated_at: &str) -> String {
+    format!("\"{}\"", updated_at)
+}
+
+/// Response headers announcing a record version
+pub fn headers(updated_at: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&tag(updated_at)) {
+        headers.insert(header::ETAG, value);
+    }
+    headers
+}
+
+/// Whether the request's `If-Match` (if any) allows writing over `updated_at`
+pub fn matches(request: &HeaderMap, updated_at: &str) -> bool {
+    let Some(value) = request.get(header::IF_MATCH) else {
+        return true;
+    };
+    let Ok(value) = value.to_str() else {
+        return false;
+    };
+    let current = tag(updated_at);
+    value.split(',').map(str::trim).any(|t| {
+        // Weak tags are accepted too; some proxies weaken what they pass on
+        t == "*" || t.strip_prefix("W/").unwrap_or(t) == current
+    })
+}
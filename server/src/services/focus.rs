@@ -0,0 +1,262 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+use crate::models::focus::{FocusDay, FocusSession, FocusStats};
+use crate::services::time_tracking::timestamp;
+use crate::services::{collaboration, time};
+
+pub const DEFAULT_MINUTES: i64 = 25;
+pub const MAX_MINUTES: i64 = 180;
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+const SELECT: &str = "SELECT f.id, f.todo_id, COALESCE(t.text, ''), f.planned_minutes, f.started_at, f.ended_at, f.actual_secs, f.interrupted \
+     FROM focus_sessions f LEFT JOIN todos t ON t.id = f.todo_id";
+
+fn row_to_session(row: &rusqlite::Row, now: DateTime<Utc>) -> rusqlite::Result<FocusSession> {
+    let started_at: String = row.get(4)?;
+    let ended_at: Option<String> = row.get(5)?;
+    let actual_secs = match row.get::<_, Option<i64>>(6)? {
+        Some(secs) => secs,
+        None => parse_time(&started_at)
+            .map(|s| (now - s).num_seconds().max(0))
+            .unwrap_or(0),
+    };
+    Ok(FocusSession {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        todo_text: row.get(2)?,
+        planned_minutes: row.get(3)?,
+        running: ended_at.is_none(),
+        started_at,
+        ended_at,
+        actual_secs,
+        interrupted: row.get::<_, i64>(7)? != 0,
+    })
+}
+
+fn get(db: &Connection, session_id: &str, now: DateTime<Utc>) -> Option<FocusSession> {
+    db.query_row(&format!("{} WHERE f.id = ?1", SELECT), [session_id], |r| {
+        row_to_session(r, now)
+    })
+    .ok()
+}
+
+/// The user's running session, if any
+pub fn current(db: &Connection, user_id: &str, now: DateTime<Utc>) -> Option<FocusSession> {
+    db.query_row(
+        &format!("{} WHERE f.user_id = ?1 AND f.ended_at IS NULL", SELECT),
+        [user_id],
+        |r| row_to_session(r, now),
+    )
+    .ok()
+}
+
+/// Start a session. Only one session may run per user.
+pub fn start(
+    db: &Connection,
+    user_id: &str,
+    todo_id: Option<&str>,
+    planned_minutes: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<FocusSession, String> {
+    let planned = planned_minutes.unwrap_or(DEFAULT_MINUTES);
+    if !(1..=MAX_MINUTES).contains(&planned) {
+        return Err(format!("专注时长需在 1-{} 分钟之间", MAX_MINUTES));
+    }
+    let todo_id = todo_id.filter(|id| !id.is_empty());
+    if let Some(id) = todo_id {
+        if !collaboration::check_todo_participant(db, id, user_id) {
+            return Err(format!("任务不存在: {}", id));
+        }
+    }
+    if current(db, user_id, now).is_some() {
+        return Err("已有进行中的专注，请先结束".into());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    let ts = timestamp(now);
+    db.execute(
+        "INSERT INTO focus_sessions (id, user_id, todo_id, planned_minutes, started_at, created_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        rusqlite::params![id, user_id, todo_id, planned, ts],
+    )
+    .map_err(|e| {
+        eprintln!("[focus] start DB error: {}", e);
+        "开始专注失败".to_string()
+    })?;
+    get(db, &id, now).ok_or_else(|| "开始专注失败".into())
+}
+
+/// End the user's running session. Time past the planned length is not
+/// counted; stopping early marks the session interrupted unless told otherwise.
+pub fn stop(
+    db: &Connection,
+    user_id: &str,
+    interrupted: Option<bool>,
+    now: DateTime<Utc>,
+) -> Result<FocusSession, String> {
+    let session = current(db, user_id, now).ok_or("当前没有进行中的专注")?;
+    let planned_secs = session.planned_minutes * 60;
+    let actual = session.actual_secs.min(planned_secs);
+    let interrupted = interrupted.unwrap_or(actual < planned_secs);
+    db.execute(
+        "UPDATE focus_sessions SET ended_at = ?1, actual_secs = ?2, interrupted = ?3 WHERE id = ?4",
+        rusqlite::params![timestamp(now), actual, interrupted as i64, session.id],
+    )
+    .map_err(|e| {
+        eprintln!("[focus] stop DB error: {}", e);
+        "结束专注失败".to_string()
+    })?;
+    get(db, &session.id, now).ok_or_else(|| "结束专注失败".into())
+}
+
+/// Complete every running session whose planned length has run out and
+/// create a notification for it. Returns push payloads per user; called by
+/// the reminder poller.
+pub fn complete_due(db: &Connection, now: DateTime<Utc>) -> Vec<(String, Value)> {
+    let running: Vec<(String, String, i64, String, Option<String>, String)> = db
+        .prepare(
+            "SELECT f.id, f.user_id, f.planned_minutes, f.started_at, f.todo_id, COALESCE(t.text, '') \
+             FROM focus_sessions f LEFT JOIN todos t ON t.id = f.todo_id \
+             WHERE f.ended_at IS NULL",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?))
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+
+    let now_str = now.to_rfc3339();
+    let mut pushes = Vec::new();
+    for (id, user_id, planned, started_at, todo_id, todo_text) in running {
+        let Some(end) = parse_time(&started_at).map(|s| s + Duration::minutes(planned)) else {
+            continue;
+        };
+        if end > now {
+            continue;
+        }
+        let updated = db
+            .execute(
+                "UPDATE focus_sessions SET ended_at = ?1, actual_secs = ?2, interrupted = 0 \
+                 WHERE id = ?3 AND ended_at IS NULL",
+                rusqlite::params![timestamp(end), planned * 60, id],
+            )
+            .unwrap_or(0);
+        if updated == 0 {
+            continue;
+        }
+
+        let title = format!("专注 {} 分钟完成", planned);
+        let body = if todo_text.is_empty() {
+            "休息一下吧".to_string()
+        } else {
+            format!("「{}」，休息一下吧", todo_text)
+        };
+        let notif_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        db.execute(
+            "INSERT INTO notifications (id, user_id, type, title, body, todo_id, read, created_at) \
+             VALUES (?1, ?2, 'focus', ?3, ?4, ?5, 0, ?6)",
+            rusqlite::params![notif_id, user_id, title, body, todo_id, now_str],
+        )
+        .ok();
+        pushes.push((
+            user_id,
+            json!({
+                "title": title,
+                "body": body,
+                "type": "focus",
+                "session_id": id,
+                "todo_id": todo_id,
+            }),
+        ));
+    }
+    if !pushes.is_empty() {
+        println!("[focus] completed {} session(s)", pushes.len());
+    }
+    pushes
+}
+
+/// The user's sessions started in a UTC range, newest first
+pub fn list(
+    db: &Connection,
+    user_id: &str,
+    range: &(String, String),
+    now: DateTime<Utc>,
+) -> Vec<FocusSession> {
+    db.prepare(&format!(
+        "{} WHERE f.user_id = ?1 AND f.started_at >= ?2 AND f.started_at < ?3 \
+         ORDER BY f.started_at DESC",
+        SELECT
+    ))
+    .and_then(|mut stmt| {
+        stmt.query_map(rusqlite::params![user_id, range.0, range.1], |r| {
+            row_to_session(r, now)
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Delete one of the user's sessions
+pub fn delete(db: &Connection, user_id: &str, session_id: &str) -> bool {
+    db.execute(
+        "DELETE FROM focus_sessions WHERE id = ?1 AND user_id = ?2",
+        [session_id, user_id],
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+/// Totals per local (Asia/Shanghai) start day. Running sessions count
+/// towards minutes but not towards completed or interrupted.
+pub fn stats(
+    db: &Connection,
+    user_id: &str,
+    range: &(String, String),
+    now: DateTime<Utc>,
+) -> FocusStats {
+    let shanghai = time::shanghai();
+    let mut days: Vec<FocusDay> = Vec::new();
+    for session in list(db, user_id, range, now) {
+        let date = parse_time(&session.started_at)
+            .map(|t| t.with_timezone(&shanghai).date_naive().to_string())
+            .unwrap_or_default();
+        let idx = match days.iter().position(|d| d.date == date) {
+            Some(i) => i,
+            None => {
+                days.push(FocusDay {
+                    date,
+                    ..Default::default()
+                });
+                days.len() - 1
+            }
+        };
+        let day = &mut days[idx];
+        day.sessions += 1;
+        day.minutes += session.actual_secs / 60;
+        if !session.running {
+            if session.interrupted {
+                day.interrupted += 1;
+            } else {
+                day.completed += 1;
+            }
+        }
+    }
+    days.sort_by(|a, b| a.date.cmp(&b.date));
+
+    FocusStats {
+        minutes: days.iter().map(|d| d.minutes).sum(),
+        sessions: days.iter().map(|d| d.sessions).sum(),
+        completed: days.iter().map(|d| d.completed).sum(),
+        interrupted: days.iter().map(|d| d.interrupted).sum(),
+        days,
+    }
+}
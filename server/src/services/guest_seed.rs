@@ -441,6 +441,41 @@ pub fn cleanup_expired_guests(state: &AppState) {
             [guest_id],
         )
         .ok();
+        db.execute(
+            "DELETE FROM todo_checklist_items WHERE todo_id IN (SELECT id FROM todos WHERE user_id = ?1)",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM projects WHERE user_id = ?1",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM focus_sessions WHERE user_id = ?1",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM time_entries WHERE todo_id IN (SELECT id FROM todos WHERE user_id = ?1) OR user_id = ?1",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM todo_comments WHERE todo_id IN (SELECT id FROM todos WHERE user_id = ?1) OR user_id = ?1",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM todo_attachments WHERE todo_id IN (SELECT id FROM todos WHERE user_id = ?1) OR user_id = ?1",
+            [guest_id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM todo_dependencies WHERE todo_id IN (SELECT id FROM todos WHERE user_id = ?1) OR blocker_id IN (SELECT id FROM todos WHERE user_id = ?1)",
+            [guest_id],
+        )
+        .ok();
         db.execute("DELETE FROM todos WHERE user_id = ?1", [guest_id])
             .ok();
 
@@ -468,6 +503,10 @@ pub fn cleanup_expired_guests(state: &AppState) {
             [guest_id],
         )
         .ok();
+        db.execute("DELETE FROM saved_views WHERE user_id = ?1", [guest_id])
+            .ok();
+        db.execute("DELETE FROM todo_templates WHERE user_id = ?1", [guest_id])
+            .ok();
         db.execute("DELETE FROM user_settings WHERE user_id = ?1", [guest_id])
             .ok();
         db.execute("DELETE FROM sessions WHERE user_id = ?1", [guest_id])
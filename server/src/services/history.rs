@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+use crate::services::changelog::{self, ChangeSet, REVERTIBLE_FIELDS};
+use crate::services::collaboration;
+
+/// A changelog entry for a revertible field. Entries written before raw
+/// values were recorded have `to_raw = None` and cannot be replayed.
+struct RawEntry {
+    id: i64,
+    field: String,
+    from_raw: String,
+    to_raw: Option<String>,
+    time: Option<DateTime<Utc>>,
+    changed_by: Option<String>,
+}
+
+/// Where to rewind a todo to
+pub enum RevertTarget {
+    /// State right after this changelog entry was written
+    Entry(i64),
+    /// State at this moment
+    Time(DateTime<Utc>),
+}
+
+/// Result of rewinding: reconstructed raw values and whether the changelog
+/// covered every change in between
+pub struct Rewind {
+    pub state: Vec<(&'static str, String)>,
+    pub complete: bool,
+}
+
+pub fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Revertible entries for a todo, newest first. Tab/quadrant changes made by
+/// collaborators only touched their own view, so they are left out.
+fn raw_entries(db: &Connection, todo_id: &str) -> Vec<RawEntry> {
+    let owner = collaboration::get_todo_owner(db, todo_id).unwrap_or_default();
+    db.prepare(
+        "SELECT id, field, from_raw, to_raw, time, changed_by FROM todo_changelog \
+         WHERE todo_id = ?1 ORDER BY id DESC",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([todo_id], |r| {
+            Ok(RawEntry {
+                id: r.get(0)?,
+                field: r.get(1)?,
+                from_raw: r.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                to_raw: r.get(3)?,
+                time: parse_time(&r.get::<_, String>(4)?),
+                changed_by: r.get(5)?,
+            })
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect::<Vec<RawEntry>>())
+    })
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|e| {
+        REVERTIBLE_FIELDS.contains(&e.field.as_str())
+            && !(matches!(e.field.as_str(), "tab" | "quadrant")
+                && e.changed_by.as_deref().is_some_and(|u| {
+                    u != owner && collaboration::check_todo_collaborator(db, todo_id, u)
+                }))
+    })
+    .collect()
+}
+
+/// Reconstruct the raw field values of a todo at a changelog point by undoing
+/// every later change, newest first
+pub fn rewind(db: &Connection, todo_id: &str, target: &RevertTarget) -> Option<Rewind> {
+    let mut state = changelog::snapshot(db, todo_id)?;
+    let is_after = |e: &RawEntry| match target {
+        RevertTarget::Entry(id) => e.id > *id,
+        RevertTarget::Time(t) => e.time.is_none_or(|time| time > *t),
+    };
+
+    let entries = raw_entries(db, todo_id);
+    let mut complete = true;
+    for entry in entries.iter().take_while(|e| is_after(e)) {
+        if entry.to_raw.is_none() {
+            complete = false;
+            continue;
+        }
+        if let Some(slot) = state.iter_mut().find(|(f, _)| *f == entry.field) {
+            slot.1 = entry.from_raw.clone();
+        }
+    }
+
+    // Only the 50 most recent entries are kept; older history may be gone
+    if let RevertTarget::Time(t) = target {
+        let (count, oldest): (i64, Option<String>) = db
+            .query_row(
+                "SELECT COUNT(*), MIN(time) FROM todo_changelog WHERE todo_id = ?1",
+                [todo_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap_or((0, None));
+        if count >= 50
+            && oldest
+                .as_deref()
+                .and_then(parse_time)
+                .is_some_and(|o| o > *t)
+        {
+            complete = false;
+        }
+    }
+
+    Some(Rewind { state, complete })
+}
+
+/// Reconstructed state as JSON with typed values
+pub fn state_json(state: &[(&'static str, String)]) -> Value {
+    let mut obj = serde_json::Map::new();
+    for (field, raw) in state {
+        let value = match *field {
+            "progress" => json!(raw.parse::<i64>().unwrap_or(0)),
+            "completed" => json!(raw == "1"),
+            "tags" => serde_json::from_str(raw).unwrap_or_else(|_| json!([])),
+            "due_date" | "recurrence" if raw.is_empty() => Value::Null,
+            _ => json!(raw),
+        };
+        obj.insert(field.to_string(), value);
+    }
+    Value::Object(obj)
+}
+
+/// Write one raw field value back to the todos row
+fn write_field(db: &Connection, todo_id: &str, field: &str, raw: &str, now: &str) -> bool {
+    if !REVERTIBLE_FIELDS.contains(&field) {
+        return false;
+    }
+    let result = match field {
+        "progress" => db.execute(
+            "UPDATE todos SET progress = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![raw.parse::<i64>().unwrap_or(0), now, todo_id],
+        ),
+        "completed" => db.execute(
+            "UPDATE todos SET completed = ?1, \
+             completed_at = CASE WHEN ?1 = 1 THEN COALESCE(completed_at, ?2) ELSE NULL END, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![(raw == "1") as i32, now, todo_id],
+        ),
+        "due_date" | "recurrence" => db.execute(
+            &format!("UPDATE todos SET {} = ?1, updated_at = ?2 WHERE id = ?3", field),
+            rusqlite::params![Some(raw).filter(|r| !r.is_empty()), now, todo_id],
+        ),
+        _ => db.execute(
+            &format!("UPDATE todos SET {} = ?1, updated_at = ?2 WHERE id = ?3", field),
+            rusqlite::params![raw, now, todo_id],
+        ),
+    };
+    match result {
+        Ok(n) => n > 0,
+        Err(e) => {
+            eprintln!("[history] write_field DB error: {}", e);
+            false
+        }
+    }
+}
+
+/// Tab and quadrant on the todos row are the owner's; collaborators keep
+/// their own in `todo_collaborators`, so their reverts leave them alone
+fn may_write(db: &Connection, todo_id: &str, user_id: &str, field: &str) -> bool {
+    !matches!(field, "tab" | "quadrant")
+        || collaboration::get_todo_owner(db, todo_id).as_deref() == Some(user_id)
+}
+
+/// Rewind a todo to a changelog point on behalf of `user_id`. The revert is
+/// logged into `changes`, so it can be undone in turn. Returns the number of
+/// fields changed.
+pub fn revert_todo(
+    db: &Connection,
+    todo_id: &str,
+    user_id: &str,
+    target: &RevertTarget,
+    changes: &ChangeSet,
+) -> Result<usize, String> {
+    let current = changelog::snapshot(db, todo_id).ok_or("任务不存在")?;
+    let rewind = rewind(db, todo_id, target).ok_or("任务不存在")?;
+
+    let mut count = 0;
+    for ((field, old), (_, new)) in current.iter().zip(&rewind.state) {
+        if old != new
+            && may_write(db, todo_id, user_id, field)
+            && write_field(db, todo_id, field, new, changes.time)
+        {
+            changes.record(db, todo_id, field, old, new);
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Undo every change in a change set (e.g. one `batch_update_todos` call).
+/// Fields changed again since then are skipped and reported as conflicts.
+/// Only todos `user_id` participates in are touched.
+pub fn revert_batch(db: &Connection, batch_id: &str, user_id: &str, changes: &ChangeSet) -> Value {
+    let entries: Vec<(String, String, String, String)> = db
+        .prepare(
+            "SELECT todo_id, field, COALESCE(from_raw, ''), to_raw FROM todo_changelog \
+             WHERE batch_id = ?1 AND to_raw IS NOT NULL ORDER BY id DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([batch_id], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+
+    let mut reverted = 0;
+    let mut conflicts = Vec::new();
+    let mut todo_ids = Vec::new();
+    for (todo_id, field, from_raw, to_raw) in &entries {
+        if !collaboration::check_todo_participant(db, todo_id, user_id)
+            || !may_write(db, todo_id, user_id, field)
+        {
+            continue;
+        }
+        let current = changelog::snapshot(db, todo_id)
+            .and_then(|s| s.into_iter().find(|(f, _)| f == field).map(|(_, v)| v));
+        match current {
+            Some(value) if value != *to_raw => {
+                conflicts.push(json!({"todo_id": todo_id, "field": field}))
+            }
+            Some(_) if write_field(db, todo_id, field, from_raw, changes.time) => {
+                changes.record(db, todo_id, field, to_raw, from_raw);
+                reverted += 1;
+                if !todo_ids.contains(todo_id) {
+                    todo_ids.push(todo_id.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    json!({
+        "reverted": reverted,
+        "todo_ids": todo_ids,
+        "conflicts": conflicts,
+        "batch_id": changes.id,
+    })
+}
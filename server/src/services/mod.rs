@@ -1,7 +1,34 @@
+pub mod attachments;
+pub mod changelog;
+pub mod checklist;
 pub mod claude;
 pub mod collaboration;
+pub mod comments;
+pub mod completion;
 pub mod context;
+pub mod csv_import;
+pub mod dependencies;
+pub mod desktop_import;
+pub mod etag;
+pub mod focus;
 pub mod guest_seed;
+pub mod history;
+pub mod ordering;
+pub mod pagination;
+pub mod projects;
 pub mod push;
+pub mod recurrence;
 pub mod reminder_poller;
+pub mod reports;
+pub mod rollover;
+pub mod routine_stats;
+pub mod search;
+pub mod sync;
+pub mod tags;
+pub mod templates;
+pub mod time;
+pub mod time_tracking;
+pub mod todo_filter;
+pub mod todo_text;
 pub mod tool_executor;
+pub mod trash;
@@ -0,0 +1,160 @@
+//! Manual todo ordering with fractional keys.
+
+use rusqlite::Connection;
+
+/// Neighbours closer than this trigger a rebalance
+const MIN_GAP: f64 = 1e-6;
+
+/// One todo in a tab + quadrant list, as the user sees it
+struct Sibling {
+    id: String,
+    own: bool,
+    key: f64,
+}
+
+/// The user's tab and quadrant for a visible todo, own copy first
+pub fn position(db: &Connection, user_id: &str, todo_id: &str) -> Option<(String, String)> {
+    db.query_row(
+        "SELECT tab, quadrant FROM todos WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
+        rusqlite::params![todo_id, user_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .or_else(|_| {
+        db.query_row(
+            "SELECT tc.tab, tc.quadrant FROM todo_collaborators tc JOIN todos t ON t.id = tc.todo_id \
+             WHERE tc.todo_id = ?1 AND tc.user_id = ?2 AND tc.status = 'active' AND t.deleted = 0",
+            rusqlite::params![todo_id, user_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+    })
+    .ok()
+}
+
+/// Visible todos in one tab + quadrant, in display order
+fn siblings(db: &Connection, user_id: &str, tab: &str, quadrant: &str) -> Vec<Sibling> {
+    let mut items: Vec<(Sibling, String)> = Vec::new();
+    for (sql, own) in [
+        (
+            "SELECT id, COALESCE(sort_order, 0), created_at FROM todos \
+             WHERE user_id = ?1 AND tab = ?2 AND quadrant = ?3 AND deleted = 0",
+            true,
+        ),
+        (
+            "SELECT t.id, COALESCE(tc.sort_order, 0), t.created_at FROM todos t \
+             JOIN todo_collaborators tc ON t.id = tc.todo_id \
+             WHERE tc.user_id = ?1 AND tc.tab = ?2 AND tc.quadrant = ?3 \
+             AND tc.status = 'active' AND t.deleted = 0",
+            false,
+        ),
+    ] {
+        let rows: Vec<(String, f64, String)> = db
+            .prepare(sql)
+            .and_then(|mut stmt| {
+                stmt.query_map(rusqlite::params![user_id, tab, quadrant], |r| {
+                    Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+                })
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            })
+            .unwrap_or_default();
+        for (id, key, created_at) in rows {
+            // Own todos take priority, as in the list
+            if !items.iter().any(|(s, _)| s.id == id) {
+                items.push((Sibling { id, own, key }, created_at));
+            }
+        }
+    }
+    items.sort_by(|(a, ac), (b, bc)| a.key.total_cmp(&b.key).then_with(|| ac.cmp(bc)));
+    items.into_iter().map(|(s, _)| s).collect()
+}
+
+/// Store a sibling's key. Only the moved todo gets a new `updated_at`; a
+/// renumbered neighbour keeps its version, so edits based on it still match.
+fn write_key(db: &Connection, user_id: &str, sibling: &Sibling, touched: Option<&str>) {
+    if sibling.own {
+        db.execute(
+            "UPDATE todos SET sort_order = ?1, updated_at = COALESCE(?2, updated_at) WHERE id = ?3 AND user_id = ?4",
+            rusqlite::params![sibling.key, touched, sibling.id, user_id],
+        )
+        .ok();
+    } else {
+        db.execute(
+            "UPDATE todo_collaborators SET sort_order = ?1 WHERE todo_id = ?2 AND user_id = ?3",
+            rusqlite::params![sibling.key, sibling.id, user_id],
+        )
+        .ok();
+    }
+}
+
+/// Key for a new todo at the end of its list
+pub fn append_key(db: &Connection, user_id: &str, tab: &str, quadrant: &str) -> f64 {
+    siblings(db, user_id, tab, quadrant)
+        .last()
+        .map(|s| s.key.floor() + 1.0)
+        .unwrap_or(1.0)
+}
+
+/// Move a todo right before or after a sibling in its current tab and
+/// quadrant, or to the end when neither is given. Returns the new key.
+pub fn place(
+    db: &Connection,
+    user_id: &str,
+    todo_id: &str,
+    before: Option<&str>,
+    after: Option<&str>,
+    now: &str,
+) -> Result<f64, String> {
+    if before.is_some() && after.is_some() {
+        return Err("before 和 after 只能指定一个".into());
+    }
+    if before == Some(todo_id) || after == Some(todo_id) {
+        return Err("不能相对自身排序".into());
+    }
+    let (tab, quadrant) =
+        position(db, user_id, todo_id).ok_or_else(|| format!("任务不存在: {}", todo_id))?;
+
+    let mut list = siblings(db, user_id, &tab, &quadrant);
+    let Some(idx) = list.iter().position(|s| s.id == todo_id) else {
+        return Err(format!("任务不存在: {}", todo_id));
+    };
+    let mut moving = list.remove(idx);
+
+    // Slot in `list` the todo goes into
+    let slot = match before.or(after) {
+        Some(anchor) => {
+            let at = list
+                .iter()
+                .position(|s| s.id == anchor)
+                .ok_or_else(|| format!("目标任务不在同一列表: {}", anchor))?;
+            if before.is_some() {
+                at
+            } else {
+                at + 1
+            }
+        }
+        None => list.len(),
+    };
+    let prev = slot.checked_sub(1).map(|i| list[i].key);
+    let next = list.get(slot).map(|s| s.key);
+    let key = match (prev, next) {
+        (Some(p), Some(n)) => (p + n) / 2.0,
+        (Some(p), None) => p.floor() + 1.0,
+        (None, Some(n)) => n.ceil() - 1.0,
+        (None, None) => 1.0,
+    };
+    let crowded =
+        prev.is_some_and(|p| key - p < MIN_GAP) || next.is_some_and(|n| n - key < MIN_GAP);
+    if !crowded {
+        moving.key = key;
+        write_key(db, user_id, &moving, Some(now));
+        return Ok(key);
+    }
+
+    // Out of room between the neighbours: renumber the list in its new order
+    list.insert(slot, moving);
+    for (i, sibling) in list.iter_mut().enumerate() {
+        sibling.key = (i + 1) as f64;
+        let touched = (sibling.id == todo_id).then_some(now);
+        write_key(db, user_id, sibling, touched);
+    }
+    Ok((slot + 1) as f64)
+}
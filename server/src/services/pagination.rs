@@ -0,0 +1,271 @@
+use std::cmp::Ordering;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rusqlite::types::{ToSql, Value};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 200;
+
+/// Paging parameters shared by list endpoints:
+/// `?cursor=&limit=&sort=&order=asc|desc`, or `?all=1` for the whole list
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub all: Option<String>,
+}
+
+impl PageQuery {
+    fn unpaged(&self) -> bool {
+        matches!(self.all.as_deref(), Some("1" | "true"))
+    }
+}
+
+/// Paging metadata returned next to the items
+#[derive(Debug, Clone, Serialize)]
+pub struct PageInfo {
+    /// Pass back as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Number of items across all pages
+    pub total: usize,
+    /// Page size, absent when the whole list was returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    pub sort: &'static str,
+    pub order: &'static str,
+}
+
+/// One component of an item's sort key
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortKey {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl SortKey {
+    pub fn text(s: &str) -> Self {
+        SortKey::Text(s.to_string())
+    }
+
+    /// Two components that put missing values after present ones
+    pub fn nulls_last(s: Option<&str>) -> [Self; 2] {
+        [
+            SortKey::Int(s.is_none() as i64),
+            SortKey::text(s.unwrap_or_default()),
+        ]
+    }
+}
+
+/// A sort option of a list endpoint. Ties are broken by item id, so the
+/// order is total and a cursor always points at one place in the list.
+pub struct SortField<T> {
+    pub name: &'static str,
+    pub descending: bool,
+    pub key: fn(&T) -> Vec<SortKey>,
+    /// SQL expressions giving the same components as `key`, over the output
+    /// columns of the list's SELECT
+    pub columns: &'static [&'static str],
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    desc: bool,
+    key: Vec<SortKey>,
+    id: String,
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+fn decode_cursor(s: &str) -> Option<Cursor> {
+    let bytes = URL_SAFE_NO_PAD.decode(s).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn compare(a: (&[SortKey], &str), b: (&[SortKey], &str)) -> Ordering {
+    for (x, y) in a.0.iter().zip(b.0) {
+        match x.partial_cmp(y).unwrap_or(Ordering::Equal) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    a.1.cmp(b.1)
+}
+
+fn sql_value(key: &SortKey) -> Value {
+    match key {
+        SortKey::Int(n) => Value::Integer(*n),
+        SortKey::Float(f) => Value::Real(*f),
+        SortKey::Text(s) => Value::Text(s.clone()),
+    }
+}
+
+/// `SELECT COUNT(*)` over a list's SELECT, for `PageInfo::total`
+pub fn count_sql(select: &str) -> String {
+    format!("SELECT COUNT(*) FROM ({})", select)
+}
+
+/// A page request checked against a list's sort options. The first entry
+/// of `sorts` is the default. Errors are user-facing messages.
+pub struct PagePlan<'a, T> {
+    sort: &'a SortField<T>,
+    desc: bool,
+    after: Option<Cursor>,
+    limit: Option<usize>,
+}
+
+impl<'a, T> PagePlan<'a, T> {
+    pub fn new(query: &PageQuery, sorts: &'a [SortField<T>]) -> Result<Self, String> {
+        let sort = match query.sort.as_deref().filter(|s| !s.is_empty()) {
+            None => &sorts[0],
+            Some(name) => sorts.iter().find(|s| s.name == name).ok_or_else(|| {
+                let names: Vec<&str> = sorts.iter().map(|s| s.name).collect();
+                format!("不支持的排序字段: {}（可选: {}）", name, names.join(", "))
+            })?,
+        };
+        let desc = match query.order.as_deref() {
+            None | Some("") => sort.descending,
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => return Err(format!("排序方向只能是 asc 或 desc: {}", other)),
+        };
+        if query.unpaged() {
+            return Ok(PagePlan {
+                sort,
+                desc,
+                after: None,
+                limit: None,
+            });
+        }
+        let after = match query.cursor.as_deref().filter(|c| !c.is_empty()) {
+            None => None,
+            Some(raw) => Some(
+                decode_cursor(raw)
+                    .filter(|c| {
+                        c.sort == sort.name && c.desc == desc && c.key.len() == sort.columns.len()
+                    })
+                    .ok_or("分页游标无效或与排序方式不匹配")?,
+            ),
+        };
+        Ok(PagePlan {
+            sort,
+            desc,
+            after,
+            limit: Some(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)),
+        })
+    }
+
+    /// Wrap a list's SELECT so SQLite sorts it, skips up to the cursor and
+    /// returns at most one row more than a page. The SELECT's output must
+    /// include `id` and the sort columns; placeholders of the wrapper are
+    /// numbered from `first_param`.
+    pub fn sql(&self, select: &str, first_param: usize) -> (String, Vec<Value>) {
+        let direction = if self.desc { "DESC" } else { "ASC" };
+        let mut columns: Vec<&str> = self.sort.columns.to_vec();
+        columns.push("id");
+        let order = columns
+            .iter()
+            .map(|c| format!("{} {}", c, direction))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut sql = format!("SELECT * FROM ({})", select);
+        let mut params = Vec::new();
+        if let Some(cursor) = &self.after {
+            let placeholders: Vec<String> = (0..columns.len())
+                .map(|i| format!("?{}", first_param + i))
+                .collect();
+            sql.push_str(&format!(
+                " WHERE ({}) {} ({})",
+                columns.join(", "),
+                if self.desc { "<" } else { ">" },
+                placeholders.join(", ")
+            ));
+            params.extend(cursor.key.iter().map(sql_value));
+            params.push(Value::Text(cursor.id.clone()));
+        }
+        sql.push_str(&format!(" ORDER BY {}", order));
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit + 1));
+        }
+        (sql, params)
+    }
+
+    /// Cut the page out of rows fetched with `sql` — from one query or
+    /// merged from several — and describe it. `total` counts all pages.
+    pub fn page(&self, rows: Vec<T>, total: usize, id: fn(&T) -> &str) -> (Vec<T>, PageInfo) {
+        let desc = self.desc;
+        let ordered = |a: (&[SortKey], &str), b: (&[SortKey], &str)| {
+            let ord = compare(a, b);
+            if desc {
+                ord.reverse()
+            } else {
+                ord
+            }
+        };
+        let mut keyed: Vec<(Vec<SortKey>, T)> = rows
+            .into_iter()
+            .map(|item| ((self.sort.key)(&item), item))
+            .filter(|(key, item)| {
+                self.after
+                    .as_ref()
+                    .is_none_or(|c| ordered((key, id(item)), (&c.key, &c.id)) == Ordering::Greater)
+            })
+            .collect();
+        keyed.sort_by(|a, b| ordered((&a.0, id(&a.1)), (&b.0, id(&b.1))));
+
+        let mut info = PageInfo {
+            next_cursor: None,
+            total,
+            limit: self.limit,
+            sort: self.sort.name,
+            order: if desc { "desc" } else { "asc" },
+        };
+        if let Some(limit) = self.limit {
+            if keyed.len() > limit {
+                keyed.truncate(limit);
+                if let Some((key, item)) = keyed.last() {
+                    info.next_cursor = Some(encode_cursor(&Cursor {
+                        sort: self.sort.name.to_string(),
+                        desc,
+                        key: key.clone(),
+                        id: id(item).to_string(),
+                    }));
+                }
+            }
+        }
+        (keyed.into_iter().map(|(_, item)| item).collect(), info)
+    }
+}
+
+/// Run a list's SELECT one page at a time. `params` are the SELECT's own
+/// (numbered from ?1); `map` reads one row.
+pub fn query_page<T>(
+    db: &Connection,
+    select: &str,
+    params: &[&dyn ToSql],
+    plan: &PagePlan<T>,
+    map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+    id: fn(&T) -> &str,
+) -> rusqlite::Result<(Vec<T>, PageInfo)> {
+    let total: i64 = db.query_row(&count_sql(select), params, |r| r.get(0))?;
+    let (sql, extra) = plan.sql(select, params.len() + 1);
+    let mut all: Vec<&dyn ToSql> = params.to_vec();
+    all.extend(extra.iter().map(|v| v as &dyn ToSql));
+    let rows = db
+        .prepare(&sql)?
+        .query_map(all.as_slice(), map)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(plan.page(rows, total as usize, id))
+}
@@ -0,0 +1,324 @@
+use rusqlite::Connection;
+
+use crate::models::project::{
+    CreateProjectRequest, Project, ProjectProgress, UpdateProjectRequest,
+};
+use crate::services::{changelog, time};
+
+pub const STATUSES: [&str; 4] = ["active", "paused", "done", "archived"];
+pub const DEFAULT_COLOR: &str = "#4A90D9";
+
+/// Max projects per user
+pub const MAX_PROJECTS: i64 = 100;
+
+pub fn status_label(status: &str) -> &'static str {
+    match status {
+        "paused" => "暂停",
+        "done" => "已完成",
+        "archived" => "已归档",
+        _ => "进行中",
+    }
+}
+
+/// Validated, trimmed project fields
+struct ProjectFields {
+    name: String,
+    color: String,
+    status: String,
+    deadline: Option<String>,
+    description: String,
+}
+
+fn validate(
+    name: &str,
+    color: &str,
+    status: &str,
+    deadline: Option<&str>,
+    description: &str,
+) -> Result<ProjectFields, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("项目名称不能为空".into());
+    }
+    if name.chars().count() > 50 {
+        return Err("项目名称不能超过 50 字符".into());
+    }
+    let color = color.trim();
+    let valid_color = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid_color {
+        return Err(format!("颜色格式无效: {}（需为 #RRGGBB）", color));
+    }
+    if !STATUSES.contains(&status) {
+        return Err(format!(
+            "无效的项目状态: {}（可选: {}）",
+            status,
+            STATUSES.join(", ")
+        ));
+    }
+    let deadline = deadline.map(str::trim).filter(|d| !d.is_empty());
+    if let Some(d) = deadline {
+        if chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() {
+            return Err(format!("截止日期格式无效: {}", d));
+        }
+    }
+    if description.chars().count() > 2000 {
+        return Err("项目描述不能超过 2000 字符".into());
+    }
+    Ok(ProjectFields {
+        name: name.to_string(),
+        color: color.to_uppercase(),
+        status: status.to_string(),
+        deadline: deadline.map(String::from),
+        description: description.trim().to_string(),
+    })
+}
+
+const SELECT: &str =
+    "SELECT id, name, color, status, deadline, COALESCE(description, ''), created_at, updated_at FROM projects";
+
+fn row_to_project(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        status: row.get(3)?,
+        deadline: row.get(4)?,
+        description: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+        progress: ProjectProgress::default(),
+    })
+}
+
+/// Rollup over the project's todos
+pub fn progress(db: &Connection, project_id: &str) -> ProjectProgress {
+    let today = time::today().format("%Y-%m-%d").to_string();
+    let mut progress = db
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(completed), 0), \
+             COALESCE(SUM(completed = 0 AND COALESCE(due_date, '') != '' AND substr(due_date, 1, 10) < ?2), 0), \
+             COALESCE(ROUND(AVG(CASE WHEN completed = 1 THEN 100 ELSE progress END)), 0) \
+             FROM todos WHERE project_id = ?1 AND deleted = 0",
+            rusqlite::params![project_id, today],
+            |r| {
+                Ok(ProjectProgress {
+                    total: r.get(0)?,
+                    completed: r.get(1)?,
+                    overdue: r.get(2)?,
+                    percent: r.get::<_, f64>(3)? as i64,
+                    time_spent_secs: 0,
+                })
+            },
+        )
+        .unwrap_or_default();
+    progress.time_spent_secs = db
+        .query_row(
+            "SELECT COALESCE(SUM(duration_secs), 0) FROM time_entries \
+             WHERE todo_id IN (SELECT id FROM todos WHERE project_id = ?1 AND deleted = 0)",
+            [project_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    progress
+}
+
+/// One of the user's projects, with its progress
+pub fn get(db: &Connection, user_id: &str, project_id: &str) -> Option<Project> {
+    let mut project = db
+        .query_row(
+            &format!("{} WHERE id = ?1 AND user_id = ?2", SELECT),
+            [project_id, user_id],
+            row_to_project,
+        )
+        .ok()?;
+    project.progress = progress(db, &project.id);
+    Some(project)
+}
+
+/// The user's projects with progress. Without a status filter, archived
+/// projects are left out. Active first, then by deadline and name.
+pub fn list(db: &Connection, user_id: &str, status: Option<&str>) -> Vec<Project> {
+    let mut projects: Vec<Project> = db
+        .prepare(&format!(
+            "{} WHERE user_id = ?1 AND (?2 IS NULL AND status != 'archived' OR status = ?2) \
+             ORDER BY CASE status WHEN 'active' THEN 0 WHEN 'paused' THEN 1 WHEN 'done' THEN 2 ELSE 3 END, \
+             deadline IS NULL, deadline, name",
+            SELECT
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_map(rusqlite::params![user_id, status], row_to_project)
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+    for project in &mut projects {
+        project.progress = progress(db, &project.id);
+    }
+    projects
+}
+
+/// Find one of the user's projects by id or (case-insensitive) name;
+/// returns (id, name)
+pub fn resolve(db: &Connection, user_id: &str, key: &str) -> Option<(String, String)> {
+    db.query_row(
+        "SELECT id, name FROM projects WHERE user_id = ?1 AND (id = ?2 OR name = ?2 COLLATE NOCASE) \
+         ORDER BY id = ?2 DESC LIMIT 1",
+        rusqlite::params![user_id, key.trim()],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .ok()
+}
+
+pub fn name_of(db: &Connection, project_id: &str) -> Option<String> {
+    db.query_row(
+        "SELECT name FROM projects WHERE id = ?1",
+        [project_id],
+        |r| r.get(0),
+    )
+    .ok()
+}
+
+pub fn create(
+    db: &Connection,
+    user_id: &str,
+    req: &CreateProjectRequest,
+    now: &str,
+) -> Result<Project, String> {
+    let fields = validate(
+        &req.name,
+        req.color.as_deref().unwrap_or(DEFAULT_COLOR),
+        req.status.as_deref().unwrap_or("active"),
+        req.deadline.as_deref(),
+        req.description.as_deref().unwrap_or(""),
+    )?;
+    let count: i64 = db
+        .query_row(
+            "SELECT COUNT(*) FROM projects WHERE user_id = ?1",
+            [user_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    if count >= MAX_PROJECTS {
+        return Err(format!("最多创建 {} 个项目", MAX_PROJECTS));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    db.execute(
+        "INSERT INTO projects (id, user_id, name, color, status, deadline, description, created_at, updated_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+        rusqlite::params![
+            id,
+            user_id,
+            fields.name,
+            fields.color,
+            fields.status,
+            fields.deadline,
+            fields.description,
+            now
+        ],
+    )
+    .map_err(|_| format!("项目已存在: {}", fields.name))?;
+    get(db, user_id, &id).ok_or_else(|| "创建项目失败".into())
+}
+
+/// Update a project; the caller has checked that it exists
+pub fn update(
+    db: &Connection,
+    user_id: &str,
+    project: &Project,
+    req: &UpdateProjectRequest,
+    now: &str,
+) -> Result<Project, String> {
+    let fields = validate(
+        req.name.as_deref().unwrap_or(&project.name),
+        req.color.as_deref().unwrap_or(&project.color),
+        req.status.as_deref().unwrap_or(&project.status),
+        match &req.deadline {
+            Some(d) => Some(d.as_str()),
+            None => project.deadline.as_deref(),
+        },
+        req.description.as_deref().unwrap_or(&project.description),
+    )?;
+    db.execute(
+        "UPDATE projects SET name = ?1, color = ?2, status = ?3, deadline = ?4, description = ?5, updated_at = ?6 \
+         WHERE id = ?7 AND user_id = ?8",
+        rusqlite::params![
+            fields.name,
+            fields.color,
+            fields.status,
+            fields.deadline,
+            fields.description,
+            now,
+            project.id,
+            user_id
+        ],
+    )
+    .map_err(|_| format!("项目已存在: {}", fields.name))?;
+    get(db, user_id, &project.id).ok_or_else(|| "项目不存在".into())
+}
+
+/// Delete a project. Its todos stay, without a project.
+pub fn delete(db: &Connection, user_id: &str, project_id: &str) -> bool {
+    let deleted = db
+        .execute(
+            "DELETE FROM projects WHERE id = ?1 AND user_id = ?2",
+            [project_id, user_id],
+        )
+        .unwrap_or(0);
+    if deleted > 0 {
+        db.execute(
+            "UPDATE todos SET project_id = NULL WHERE project_id = ?1",
+            [project_id],
+        )
+        .ok();
+    }
+    deleted > 0
+}
+
+/// Move a todo into a project (or out, with None), logging the change.
+/// `project_key` is a project id or name owned by `owner_id`; returns
+/// the new project id.
+pub fn assign_todo(
+    db: &Connection,
+    todo_id: &str,
+    owner_id: &str,
+    project_key: Option<&str>,
+    now: &str,
+) -> Result<Option<String>, String> {
+    let target = match project_key.map(str::trim).filter(|k| !k.is_empty()) {
+        Some(key) => Some(resolve(db, owner_id, key).ok_or(format!("项目不存在: {}", key))?),
+        None => None,
+    };
+    let current: Option<String> = db
+        .query_row(
+            "SELECT project_id FROM todos WHERE id = ?1",
+            [todo_id],
+            |r| r.get(0),
+        )
+        .map_err(|_| format!("任务不存在: {}", todo_id))?;
+    let target_id = target.as_ref().map(|(id, _)| id.clone());
+    if current == target_id {
+        return Ok(target_id);
+    }
+
+    db.execute(
+        "UPDATE todos SET project_id = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![target_id, now, todo_id],
+    )
+    .map_err(|e| {
+        eprintln!("[projects] assign_todo DB error: {}", e);
+        "数据库写入失败，请稍后重试".to_string()
+    })?;
+    let from = current.as_deref().and_then(|id| name_of(db, id));
+    changelog::insert_changelog(
+        db,
+        todo_id,
+        "project",
+        "项目",
+        from.as_deref().unwrap_or(""),
+        target.as_ref().map(|(_, name)| name.as_str()).unwrap_or(""),
+        now,
+    );
+    Ok(target_id)
+}
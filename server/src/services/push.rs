@@ -221,3 +221,63 @@ impl std::fmt::Display for PushError {
         }
     }
 }
+
+/// Send one payload per user to all of their subscriptions. Expired
+/// subscriptions are removed. Does nothing when VAPID keys are not configured.
+/// The DB lock is only held while reading and cleaning up subscriptions.
+pub async fn send_to_users(
+    db: std::sync::Arc<parking_lot::Mutex<rusqlite::Connection>>,
+    messages: Vec<(String, serde_json::Value)>,
+) {
+    let Some(vapid) = VapidKeys::from_env() else {
+        return;
+    };
+
+    let targets: Vec<(String, PushSubscription, String)> = {
+        let db = db.lock();
+        let mut targets = Vec::new();
+        for (user_id, payload) in &messages {
+            let rows: Vec<(String, String, String)> = db
+                .prepare("SELECT endpoint, p256dh, auth FROM push_subscriptions WHERE user_id = ?1")
+                .and_then(|mut stmt| {
+                    stmt.query_map([user_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+                        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                })
+                .unwrap_or_default();
+            for (endpoint, p256dh, auth) in rows {
+                let (Ok(p256dh), Ok(auth)) = (
+                    URL_SAFE_NO_PAD.decode(&p256dh),
+                    URL_SAFE_NO_PAD.decode(&auth),
+                ) else {
+                    continue;
+                };
+                targets.push((
+                    endpoint.clone(),
+                    PushSubscription {
+                        endpoint,
+                        p256dh,
+                        auth,
+                    },
+                    payload.to_string(),
+                ));
+            }
+        }
+        targets
+    };
+
+    for (endpoint, sub, payload) in targets {
+        match send_push(&vapid, &sub, &payload).await {
+            Ok(()) => {}
+            Err(PushError::Gone) => {
+                db.lock()
+                    .execute(
+                        "DELETE FROM push_subscriptions WHERE endpoint = ?1",
+                        [&endpoint],
+                    )
+                    .ok();
+                println!("[push] removed expired subscription");
+            }
+            Err(e) => eprintln!("[push] error: {}", e),
+        }
+    }
+}
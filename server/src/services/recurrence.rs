@@ -0,0 +1,156 @@
+use chrono::{Datelike, NaiveDate};
+use rusqlite::Connection;
+
+use crate::models::todo::{clamp_month_day, Recurrence, Todo};
+use crate::services::changelog::insert_changelog;
+use crate::services::tool_executor::compute_tab_for_date;
+use crate::services::{ordering, time};
+
+/// Validate a recurrence rule from a request. Empty string means "no recurrence".
+pub fn normalize_rule(rule: &str) -> Result<Option<String>, String> {
+    let rule = rule.trim();
+    if rule.is_empty() {
+        return Ok(None);
+    }
+    Recurrence::parse(rule)
+        .map(|r| Some(r.as_string()))
+        .ok_or_else(|| format!("无效的重复规则: {}", rule))
+}
+
+/// Fields copied from a completed occurrence into the next one
+struct Occurrence {
+    user_id: String,
+    text: String,
+    content: String,
+    quadrant: String,
+    assignee: String,
+    tags: String,
+    recurrence: Option<String>,
+    due_date: Option<String>,
+    is_collaborative: i32,
+    recurrence_day: Option<u32>,
+}
+
+/// Spawn the next occurrence of a recurring todo once it is completed.
+/// Returns the new todo id, or None when the todo is not recurring, not
+/// completed, or its next occurrence already exists.
+pub fn spawn_next(db: &Connection, todo_id: &str, now: &str) -> Option<String> {
+    let src = db
+        .query_row(
+            "SELECT user_id, text, content, quadrant, assignee, tags, recurrence, due_date, is_collaborative, recurrence_day FROM todos WHERE id = ?1 AND completed = 1 AND deleted = 0",
+            [todo_id],
+            |r| {
+                Ok(Occurrence {
+                    user_id: r.get(0)?,
+                    text: r.get(1)?,
+                    content: r.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    quadrant: r.get(3)?,
+                    assignee: r.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    tags: r.get::<_, Option<String>>(5)?.unwrap_or_else(|| "[]".into()),
+                    recurrence: r.get(6)?,
+                    due_date: r.get(7)?,
+                    is_collaborative: r.get::<_, Option<i32>>(8)?.unwrap_or(0),
+                    recurrence_day: r.get(9)?,
+                })
+            },
+        )
+        .ok()?;
+    let rule = Recurrence::parse(src.recurrence.as_deref()?)?;
+
+    let already_spawned: bool = db
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM todos WHERE recurrence_prev_id = ?1 AND deleted = 0",
+            [todo_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(false);
+    if already_spawned {
+        return None;
+    }
+
+    // Next date follows the due date; missed occurrences are skipped
+    let today = time::today();
+    let base = src
+        .due_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .unwrap_or(today);
+    // A monthly series keeps its day across short months, unless this
+    // occurrence was moved to another day
+    let month_day = src
+        .recurrence_day
+        .filter(|d| clamp_month_day(base, *d) == base)
+        .unwrap_or(base.day());
+    let mut next = rule.next_after(base, month_day);
+    while next < today {
+        next = rule.next_after(next, month_day);
+    }
+    let next_str = next.format("%Y-%m-%d").to_string();
+    let tab = compute_tab_for_date(&next_str);
+
+    let new_id = Todo::generate_id();
+    let sort_order = ordering::append_key(db, &src.user_id, tab, &src.quadrant);
+    if let Err(e) = db.execute(
+        "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, due_date, assignee, tags, sort_order, created_at, updated_at, deleted, is_collaborative, recurrence, recurrence_prev_id, recurrence_day) VALUES (?1,?2,?3,?4,?5,?6,0,0,?7,?8,?9,?10,?11,?11,0,?12,?13,?14,?15)",
+        rusqlite::params![
+            new_id,
+            src.user_id,
+            src.text,
+            src.content,
+            tab,
+            src.quadrant,
+            next_str,
+            src.assignee,
+            src.tags,
+            sort_order,
+            now,
+            src.is_collaborative,
+            rule.as_string(),
+            todo_id,
+            month_day,
+        ],
+    ) {
+        eprintln!("[recurrence] spawn_next DB error: {}", e);
+        return None;
+    }
+
+    // Carry the checklist over, unchecked
+    db.execute(
+        "INSERT INTO todo_checklist_items (id, todo_id, text, checked, checked_at, sort_order, created_at, updated_at) \
+         SELECT lower(hex(randomblob(4))), ?1, text, 0, NULL, sort_order, ?2, ?2 FROM todo_checklist_items WHERE todo_id = ?3",
+        rusqlite::params![new_id, now, todo_id],
+    )
+    .ok();
+
+    // Keep collaborators on the new occurrence
+    let collaborators: Vec<(String, String)> = db
+        .prepare(
+            "SELECT user_id, quadrant FROM todo_collaborators WHERE todo_id = ?1 AND status = 'active'",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([todo_id], |r| Ok((r.get(0)?, r.get(1)?)))
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+    for (collab_user, collab_quadrant) in collaborators {
+        let tc_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+        let collab_order = ordering::append_key(db, &collab_user, tab, &collab_quadrant);
+        db.execute(
+            "INSERT INTO todo_collaborators (id, todo_id, user_id, tab, quadrant, status, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6, ?7)",
+            rusqlite::params![tc_id, new_id, collab_user, tab, collab_quadrant, collab_order, now],
+        )
+        .ok();
+    }
+
+    insert_changelog(
+        db,
+        todo_id,
+        "recurrence_spawn",
+        Todo::field_label("recurrence_spawn"),
+        &rule.label(),
+        &format!("下一次: {}", next_str),
+        now,
+    );
+
+    Some(new_id)
+}
@@ -5,6 +5,7 @@ use parking_lot::Mutex;
 use rusqlite::Connection;
 use std::sync::Arc;
 
+use crate::services::focus;
 use crate::services::push::{self, PushSubscription, VapidKeys};
 
 /// Data collected from DB under lock, used for async push after unlock
@@ -21,7 +22,8 @@ struct UserPushSub {
 }
 
 /// Spawn the reminder poller background task.
-/// Checks every 30 seconds for due reminders, triggers them, and sends Web Push.
+/// Checks every 30 seconds for due reminders and finished focus sessions,
+/// notifies the users, and sends Web Push.
 pub fn spawn_poller(db: Arc<Mutex<Connection>>) {
     tokio::spawn(async move {
         println!("[reminder_poller] started");
@@ -37,6 +39,12 @@ pub fn spawn_poller(db: Arc<Mutex<Connection>>) {
                     eprintln!("[reminder_poller] error: {}", e);
                 }
             }
+
+            // Focus sessions whose planned length has run out
+            let focus_done = focus::complete_due(&db.lock(), chrono::Utc::now());
+            if !focus_done.is_empty() {
+                push::send_to_users(db.clone(), focus_done).await;
+            }
         }
     });
 }
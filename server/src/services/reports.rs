@@ -0,0 +1,656 @@
+//! Weekly and monthly productivity reports.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use rusqlite::Connection;
+
+use crate::models::report::*;
+use crate::models::todo::Quadrant;
+use crate::services::{focus, routine_stats, time, time_tracking};
+
+const QUADRANTS: [Quadrant; 4] = [
+    Quadrant::ImportantUrgent,
+    Quadrant::ImportantNotUrgent,
+    Quadrant::NotImportantUrgent,
+    Quadrant::NotImportantNotUrgent,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Week,
+    Month,
+}
+
+impl Period {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "week" | "weekly" => Some(Period::Week),
+            "month" | "monthly" => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Period::Week => "周报",
+            Period::Month => "月报",
+        }
+    }
+
+    /// First and last day of the period containing `date`, or None if
+    /// it runs past the calendar limits
+    pub fn bounds(&self, date: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        match self {
+            Period::Week => {
+                let offset = date.weekday().num_days_from_monday() as u64;
+                let from = date.checked_sub_days(Days::new(offset))?;
+                Some((from, from.checked_add_days(Days::new(6))?))
+            }
+            Period::Month => {
+                let from = date.with_day(1)?;
+                let next = if from.month() == 12 {
+                    NaiveDate::from_ymd_opt(from.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(from.year(), from.month() + 1, 1)
+                };
+                Some((from, next?.pred_opt()?))
+            }
+        }
+    }
+}
+
+/// Period and day from query parameters, defaulting to this week
+pub fn resolve(period: Option<&str>, date: Option<&str>) -> Result<(Period, NaiveDate), String> {
+    let period = match period.filter(|p| !p.is_empty()) {
+        Some(p) => {
+            Period::parse(p).ok_or_else(|| format!("不支持的周期: {}（可选: week, month）", p))?
+        }
+        None => Period::Week,
+    };
+    let date = match date.filter(|d| !d.is_empty()) {
+        Some(d) => {
+            NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", d))?
+        }
+        None => time::today(),
+    };
+    Ok((period, date))
+}
+
+/// Start of a local day
+fn day_start(d: NaiveDate) -> DateTime<Utc> {
+    d.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(time::shanghai()).single())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_default()
+}
+
+fn local_day(t: DateTime<Utc>) -> NaiveDate {
+    t.with_timezone(&time::shanghai()).date_naive()
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| parse_date(s).map(day_start))
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Quadrant slug from a raw value or, in older changelog entries, a label
+fn quadrant_slug(s: &str) -> Option<&'static str> {
+    QUADRANTS
+        .iter()
+        .find(|q| q.as_str() == s || q.label() == s)
+        .map(|q| q.as_str())
+}
+
+fn quadrant_counts() -> BTreeMap<String, i64> {
+    QUADRANTS
+        .iter()
+        .map(|q| (q.as_str().to_string(), 0))
+        .collect()
+}
+
+struct TodoRow {
+    id: String,
+    quadrant: String,
+    created: Option<DateTime<Utc>>,
+    completed: Option<DateTime<Utc>>,
+    due: Option<NaiveDate>,
+}
+
+impl TodoRow {
+    fn open_at(&self, t: DateTime<Utc>) -> bool {
+        self.created.is_some_and(|c| c < t) && self.completed.is_none_or(|c| c >= t)
+    }
+}
+
+fn load_todos(db: &Connection, user_id: &str) -> Vec<TodoRow> {
+    db.prepare(
+        "SELECT id, quadrant, created_at, CASE WHEN completed = 1 THEN completed_at END, due_date \
+         FROM todos WHERE user_id = ?1 AND deleted = 0",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([user_id], |r| {
+            Ok(TodoRow {
+                id: r.get(0)?,
+                quadrant: r.get(1)?,
+                created: r.get::<_, String>(2).ok().as_deref().and_then(parse_time),
+                completed: r
+                    .get::<_, Option<String>>(3)?
+                    .as_deref()
+                    .and_then(parse_time),
+                due: r
+                    .get::<_, Option<String>>(4)?
+                    .as_deref()
+                    .and_then(parse_date),
+            })
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Quadrant changes of the user's todos, oldest first: (todo, time, from, to)
+fn load_quadrant_changes(
+    db: &Connection,
+    user_id: &str,
+) -> Vec<(String, DateTime<Utc>, String, String)> {
+    db.prepare(
+        "SELECT c.todo_id, c.time, COALESCE(c.from_raw, c.from_val, ''), COALESCE(c.to_raw, c.to_val, '') \
+         FROM todo_changelog c JOIN todos t ON t.id = c.todo_id \
+         WHERE t.user_id = ?1 AND t.deleted = 0 AND c.field = 'quadrant' ORDER BY c.id",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([user_id], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+            ))
+        })
+        .map(|rows| {
+            rows.filter_map(|r| r.ok())
+                .filter_map(|(id, time, from, to)| Some((id, parse_time(&time)?, from, to)))
+                .collect()
+        })
+    })
+    .unwrap_or_default()
+}
+
+fn todo_report(
+    todos: &[TodoRow],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> TodoReport {
+    let in_period = |t: &Option<DateTime<Utc>>| t.is_some_and(|t| t >= start && t < end);
+    let done: Vec<&TodoRow> = todos.iter().filter(|t| in_period(&t.completed)).collect();
+
+    let lead_times: Vec<i64> = done
+        .iter()
+        .filter_map(|t| Some((t.completed? - t.created?).num_minutes().max(0)))
+        .collect();
+    let avg_lead_time_hours = (!lead_times.is_empty()).then(|| {
+        let avg = lead_times.iter().sum::<i64>() as f64 / lead_times.len() as f64 / 60.0;
+        (avg * 10.0).round() / 10.0
+    });
+
+    // Overdue as of the end of the period, or now for the current one
+    let cutoff = end.min(now);
+    let cutoff_day = local_day(cutoff);
+    TodoReport {
+        created: todos.iter().filter(|t| in_period(&t.created)).count() as i64,
+        completed: done.len() as i64,
+        avg_lead_time_hours,
+        overdue: todos
+            .iter()
+            .filter(|t| t.open_at(cutoff) && t.due.is_some_and(|d| d < cutoff_day))
+            .count() as i64,
+        completed_late: done
+            .iter()
+            .filter(|t| matches!((t.due, t.completed), (Some(due), Some(c)) if local_day(c) > due))
+            .count() as i64,
+    }
+}
+
+fn quadrant_report(
+    todos: &[TodoRow],
+    changes: &[(String, DateTime<Utc>, String, String)],
+    from: NaiveDate,
+    last: NaiveDate,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> QuadrantReport {
+    let start = day_start(from);
+    let mut report = QuadrantReport {
+        completed: quadrant_counts(),
+        moved_in: quadrant_counts(),
+        days: Vec::new(),
+    };
+    for todo in todos {
+        if todo.completed.is_some_and(|c| c >= start && c < end) {
+            if let Some(q) = quadrant_slug(&todo.quadrant) {
+                *report.completed.entry(q.into()).or_default() += 1;
+            }
+        }
+    }
+
+    let mut history: HashMap<&str, Vec<(DateTime<Utc>, &str)>> = HashMap::new();
+    for (id, time, from_q, to_q) in changes {
+        history.entry(id).or_default().push((*time, from_q));
+        if *time >= start && *time < end {
+            if let Some(q) = quadrant_slug(to_q) {
+                *report.moved_in.entry(q.into()).or_default() += 1;
+            }
+        }
+    }
+
+    // A todo's quadrant at time t is the "from" of its first change after
+    // t, or its current quadrant if it hasn't moved since
+    for day in from.iter_days().take_while(|d| *d <= last) {
+        let at = day.succ_opt().map_or(end, day_start).min(now);
+        let mut counts = quadrant_counts();
+        for todo in todos.iter().filter(|t| t.open_at(at)) {
+            let quadrant = history
+                .get(todo.id.as_str())
+                .and_then(|h| h.iter().find(|(time, _)| *time >= at))
+                .map(|(_, q)| *q)
+                .unwrap_or(&todo.quadrant);
+            if let Some(q) = quadrant_slug(quadrant) {
+                *counts.entry(q.into()).or_default() += 1;
+            }
+        }
+        report.days.push(QuadrantDay {
+            date: day.to_string(),
+            counts,
+        });
+    }
+    report
+}
+
+fn habit_report(mut items: Vec<HabitItem>) -> HabitReport {
+    items.retain(|i| i.due > 0 || i.done > 0);
+    let done: i64 = items.iter().map(|i| i.done).sum();
+    let due: i64 = items.iter().map(|i| i.due).sum();
+    HabitReport {
+        done,
+        due,
+        rate: (due > 0).then(|| (done.min(due) as f64 * 1000.0 / due as f64).round() / 10.0),
+        items,
+    }
+}
+
+/// Each routine the user owns or shares was due on its scheduled days
+/// from its creation; done counts the due days it was ticked
+fn routine_report(db: &Connection, user_id: &str, from: NaiveDate, last: NaiveDate) -> HabitReport {
+    let routines: Vec<(String, String, String)> = db
+        .prepare(
+            "SELECT id, text, created_at FROM routines WHERE user_id = ?1 \
+             UNION SELECT r.id, r.text, r.created_at FROM routines r \
+             JOIN routine_collaborators rc ON rc.routine_id = r.id \
+             WHERE rc.user_id = ?1 AND rc.status = 'active' ORDER BY 3",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([user_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+
+    let items = routines
+        .into_iter()
+        .map(|(id, text, created_at)| {
+            let first = parse_time(&created_at)
+                .map(local_day)
+                .map_or(from, |c| c.max(from));
+            let (due, done) = routine_stats::History::load(db, &id, user_id)
+                .filter(|_| first <= last)
+                .map_or((0, 0), |h| h.due_and_done(first, last));
+            HabitItem {
+                id,
+                text,
+                done,
+                due,
+            }
+        })
+        .collect();
+    habit_report(items)
+}
+
+/// The stretch of time a review is due once in: a day, ISO week, month or year
+fn review_slot(frequency: &str, d: NaiveDate) -> (i32, u32) {
+    match frequency {
+        "daily" => (d.year(), d.ordinal()),
+        "weekly" => (d.iso_week().year(), d.iso_week().week()),
+        "monthly" => (d.year(), d.month()),
+        _ => (d.year(), 0),
+    }
+}
+
+/// Each active review was due once per daily/weekly/monthly/yearly slot
+/// overlapping the period; done counts the slots with a completion.
+/// `end` is the start of the day after `last`.
+fn review_report(
+    db: &Connection,
+    user_id: &str,
+    from: NaiveDate,
+    last: NaiveDate,
+    end: DateTime<Utc>,
+) -> HabitReport {
+    let reviews: Vec<(String, String, String, String)> = db
+        .prepare(
+            "SELECT id, text, frequency, created_at FROM reviews \
+             WHERE user_id = ?1 AND deleted = 0 AND paused = 0 ORDER BY created_at",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([user_id], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+    let start = day_start(from);
+
+    let items = reviews
+        .into_iter()
+        .map(|(id, text, frequency, created_at)| {
+            let first = parse_time(&created_at)
+                .map(local_day)
+                .map_or(from, |c| c.max(from));
+            let due: HashSet<(i32, u32)> = first
+                .iter_days()
+                .take_while(|d| *d <= last)
+                .map(|d| review_slot(&frequency, d))
+                .collect();
+            let done: HashSet<(i32, u32)> = db
+                .prepare("SELECT completed_at FROM review_completions WHERE review_id = ?1")
+                .and_then(|mut stmt| {
+                    stmt.query_map([&id], |r| r.get::<_, String>(0))
+                        .map(|rows| {
+                            rows.filter_map(|r| r.ok())
+                                .filter_map(|t| parse_time(&t))
+                                .filter(|t| *t >= start && *t < end)
+                                .map(|t| review_slot(&frequency, local_day(t)))
+                                .collect()
+                        })
+                })
+                .unwrap_or_default();
+            HabitItem {
+                id,
+                text,
+                done: done.len() as i64,
+                due: due.len() as i64,
+            }
+        })
+        .collect();
+    habit_report(items)
+}
+
+/// Report for the period containing `date`. Days after today count
+/// towards nothing that is "due".
+pub fn build(
+    db: &Connection,
+    user_id: &str,
+    period: Period,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> Result<Report, String> {
+    let out_of_range = || "日期超出范围".to_string();
+    let next_start = |d: NaiveDate| {
+        d.checked_add_days(Days::new(1))
+            .map(day_start)
+            .ok_or_else(out_of_range)
+    };
+    let (from, to) = period.bounds(date).ok_or_else(out_of_range)?;
+    let last = to.min(local_day(now));
+    let (start, end) = (day_start(from), next_start(to)?);
+    let last_end = next_start(last)?;
+    let range = time_tracking::day_range(Some(&from.to_string()), Some(&to.to_string()))?;
+
+    let todos = load_todos(db, user_id);
+    let changes = load_quadrant_changes(db, user_id);
+
+    Ok(Report {
+        period: period.as_str().to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        todos: todo_report(&todos, start, end, now),
+        quadrants: quadrant_report(&todos, &changes, from, last, last_end, now),
+        routines: routine_report(db, user_id, from, last),
+        reviews: review_report(db, user_id, from, last, last_end),
+        focus: focus::stats(db, user_id, &range, now),
+    })
+}
+
+// ─── Rendering ───
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Some(ReportFormat::Markdown),
+            "html" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "text/markdown; charset=utf-8",
+            ReportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    pub fn file_name(&self, report: &Report) -> String {
+        let ext = match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        };
+        format!("next-{}-{}.{}", report.period, report.from, ext)
+    }
+}
+
+/// A heading with optional summary lines and a table, rendered as either
+/// format
+struct Section {
+    title: String,
+    lines: Vec<String>,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn section(title: &str) -> Section {
+    Section {
+        title: title.to_string(),
+        lines: Vec::new(),
+        headers: Vec::new(),
+        rows: Vec::new(),
+    }
+}
+
+fn rate(r: &HabitReport) -> String {
+    match r.rate {
+        Some(rate) => format!("完成率 {}%（{}/{}）", rate, r.done.min(r.due), r.due),
+        None => "本期没有需要完成的项目".to_string(),
+    }
+}
+
+fn sections(report: &Report) -> Vec<Section> {
+    let t = &report.todos;
+    let mut todos = section("任务");
+    todos.headers = vec!["指标".into(), "数值".into()];
+    todos.rows = vec![
+        vec!["新建".into(), t.created.to_string()],
+        vec!["完成".into(), t.completed.to_string()],
+        vec![
+            "平均完成用时".into(),
+            t.avg_lead_time_hours
+                .map_or("-".into(), |h| format!("{} 小时", h)),
+        ],
+        vec!["逾期未完成".into(), t.overdue.to_string()],
+        vec!["逾期后完成".into(), t.completed_late.to_string()],
+    ];
+
+    let q = &report.quadrants;
+    let mut quadrants = section("象限分布");
+    quadrants.headers = vec![
+        "象限".into(),
+        "完成".into(),
+        "移入".into(),
+        "期末未完成".into(),
+    ];
+    let closing = q.days.last();
+    for quadrant in QUADRANTS {
+        let slug = quadrant.as_str();
+        quadrants.rows.push(vec![
+            quadrant.label().into(),
+            q.completed.get(slug).copied().unwrap_or(0).to_string(),
+            q.moved_in.get(slug).copied().unwrap_or(0).to_string(),
+            closing
+                .and_then(|d| d.counts.get(slug))
+                .copied()
+                .unwrap_or(0)
+                .to_string(),
+        ]);
+    }
+
+    let mut daily = section("每日未完成任务");
+    daily.headers = std::iter::once("日期".to_string())
+        .chain(QUADRANTS.iter().map(|q| q.label().to_string()))
+        .collect();
+    for day in &q.days {
+        daily.rows.push(
+            std::iter::once(day.date.clone())
+                .chain(
+                    QUADRANTS
+                        .iter()
+                        .map(|q| day.counts.get(q.as_str()).copied().unwrap_or(0).to_string()),
+                )
+                .collect(),
+        );
+    }
+
+    let habit = |title: &str, name: &str, r: &HabitReport| {
+        let mut s = section(title);
+        s.lines.push(rate(r));
+        if !r.items.is_empty() {
+            s.headers = vec![name.into(), "完成".into(), "应完成".into()];
+            s.rows = r
+                .items
+                .iter()
+                .map(|i| vec![i.text.clone(), i.done.to_string(), i.due.to_string()])
+                .collect();
+        }
+        s
+    };
+
+    let mut focus = section("专注");
+    focus.lines.push(format!(
+        "{} 分钟，{} 次（完成 {}，中断 {}）",
+        report.focus.minutes,
+        report.focus.sessions,
+        report.focus.completed,
+        report.focus.interrupted
+    ));
+
+    vec![
+        todos,
+        quadrants,
+        daily,
+        habit("日常任务", "日常任务", &report.routines),
+        habit("例行审视", "审视项", &report.reviews),
+        focus,
+    ]
+}
+
+fn title(report: &Report) -> String {
+    let period = Period::parse(&report.period).unwrap_or(Period::Week);
+    format!("{} {} ~ {}", period.title(), report.from, report.to)
+}
+
+fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render(report: &Report, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Markdown => {
+            let mut out = format!("# {}\n", title(report));
+            for s in sections(report) {
+                out.push_str(&format!("\n## {}\n\n", s.title));
+                for line in &s.lines {
+                    out.push_str(&format!("{}\n\n", line));
+                }
+                if s.headers.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!("| {} |\n", s.headers.join(" | ")));
+                out.push_str(&format!("|{}\n", "---|".repeat(s.headers.len())));
+                for row in &s.rows {
+                    let cells: Vec<String> = row.iter().map(|c| md_cell(c)).collect();
+                    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+            }
+            out
+        }
+        ReportFormat::Html => {
+            let title = html_escape(&title(report));
+            let mut out = format!(
+                "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+                 <style>body{{font-family:sans-serif;max-width:720px;margin:2em auto;color:#333}}\
+                 table{{border-collapse:collapse;margin:0.5em 0 1.5em}}\
+                 th,td{{border:1px solid #ddd;padding:4px 10px;text-align:left}}th{{background:#f5f5f5}}</style>\n\
+                 </head>\n<body>\n<h1>{}</h1>\n",
+                title, title
+            );
+            for s in sections(report) {
+                out.push_str(&format!("<h2>{}</h2>\n", html_escape(&s.title)));
+                for line in &s.lines {
+                    out.push_str(&format!("<p>{}</p>\n", html_escape(line)));
+                }
+                if s.headers.is_empty() {
+                    continue;
+                }
+                out.push_str("<table>\n<tr>");
+                for h in &s.headers {
+                    out.push_str(&format!("<th>{}</th>", html_escape(h)));
+                }
+                out.push_str("</tr>\n");
+                for row in &s.rows {
+                    out.push_str("<tr>");
+                    for c in row {
+                        out.push_str(&format!("<td>{}</td>", html_escape(c)));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+            out.push_str("</body>\n</html>\n");
+            out
+        }
+    }
+}
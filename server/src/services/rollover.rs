@@ -0,0 +1,134 @@
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use rusqlite::Connection;
+use std::sync::Arc;
+
+use crate::models::todo::Quadrant;
+use crate::services::changelog::{ChangeSet, SYSTEM};
+use crate::services::time;
+use crate::services::tool_executor::compute_tab_for_date;
+
+/// Spawn the horizon rollover task.
+/// Every hour, pulls open todos into a nearer tab as their due date approaches.
+pub fn spawn_rollover(db: Arc<Mutex<Connection>>) {
+    tokio::spawn(async move {
+        println!("[rollover] started");
+        loop {
+            let moved = run_once(&db.lock());
+            if moved > 0 {
+                println!("[rollover] updated {} todos", moved);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
+        }
+    });
+}
+
+/// Distance of a tab from today; other tabs are never touched
+fn horizon(tab: &str) -> Option<u8> {
+    match tab {
+        "today" => Some(0),
+        "week" => Some(1),
+        "month" => Some(2),
+        _ => None,
+    }
+}
+
+/// Whether the job already moved this todo's field to `value`. A user who
+/// moves the todo back afterwards is not overridden again.
+fn already_applied(db: &Connection, todo_id: &str, field: &str, value: &str) -> bool {
+    db.query_row(
+        "SELECT COUNT(*) > 0 FROM todo_rollovers WHERE todo_id = ?1 AND field = ?2 AND value = ?3",
+        rusqlite::params![todo_id, field, value],
+        |r| r.get(0),
+    )
+    .unwrap_or(false)
+}
+
+/// Remember a move for `already_applied`; unlike the changelog, never pruned
+fn mark_applied(db: &Connection, todo_id: &str, field: &str, value: &str) {
+    db.execute(
+        "INSERT OR IGNORE INTO todo_rollovers (todo_id, field, value) VALUES (?1, ?2, ?3)",
+        rusqlite::params![todo_id, field, value],
+    )
+    .ok();
+}
+
+/// Single pass over open, dated todos of users who have not opted out.
+/// Tabs only move towards today; overdue todos are also moved to the urgent
+/// quadrant for users who enabled `escalate_overdue`. Only the owner's view
+/// is changed. All moves of one pass share a change set. Returns the number
+/// of todos changed.
+pub fn run_once(db: &Connection) -> usize {
+    let today = time::today();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    type Row = (String, String, String, String, bool);
+    let rows: Vec<Row> = match db.prepare(
+        "SELECT t.id, t.tab, t.quadrant, t.due_date, COALESCE(s.escalate_overdue, 0) FROM todos t \
+         LEFT JOIN user_settings s ON s.user_id = t.user_id \
+         WHERE t.deleted = 0 AND t.completed = 0 AND t.due_date IS NOT NULL AND t.due_date != '' \
+         AND COALESCE(s.auto_rollover, 1) = 1",
+    ) {
+        Ok(mut stmt) => stmt
+            .query_map([], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            .unwrap_or_default(),
+        Err(e) => {
+            eprintln!("[rollover] query error: {}", e);
+            return 0;
+        }
+    };
+
+    let changes = ChangeSet::new(SYSTEM, &now);
+    let mut updated = 0;
+    for (id, tab, quadrant, due_date, escalate) in rows {
+        let Some(due) = due_date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        let overdue = due < today;
+        let mut changed = false;
+
+        let target = if overdue {
+            "today"
+        } else {
+            compute_tab_for_date(&due.format("%Y-%m-%d").to_string())
+        };
+        if let (Some(from), Some(to)) = (horizon(&tab), horizon(target)) {
+            if to < from && !already_applied(db, &id, "tab", target) {
+                db.execute(
+                    "UPDATE todos SET tab = ?1, updated_at = ?2 WHERE id = ?3",
+                    rusqlite::params![target, now, id],
+                )
+                .ok();
+                changes.record(db, &id, "tab", &tab, target);
+                mark_applied(db, &id, "tab", target);
+                changed = true;
+            }
+        }
+
+        if overdue && escalate {
+            if let Some(urgent) = Quadrant::parse(&quadrant).escalated() {
+                if !already_applied(db, &id, "quadrant", urgent.as_str()) {
+                    db.execute(
+                        "UPDATE todos SET quadrant = ?1, updated_at = ?2 WHERE id = ?3",
+                        rusqlite::params![urgent.as_str(), now, id],
+                    )
+                    .ok();
+                    changes.record(db, &id, "quadrant", &quadrant, urgent.as_str());
+                    mark_applied(db, &id, "quadrant", urgent.as_str());
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            updated += 1;
+        }
+    }
+    updated
+}
@@ -0,0 +1,280 @@
+//! Streaks, completion rates and heatmap data for routines.
+
+use std::collections::BTreeSet;
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::Connection;
+
+use crate::models::routine::{week_start, HeatmapDay, RoutineSchedule, RoutineStats};
+use crate::services::time;
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Local day a routine was created on; `created_at` is RFC 3339
+pub fn created_on(created_at: &str) -> Option<NaiveDate> {
+    chrono::DateTime::parse_from_rfc3339(created_at)
+        .map(|t| t.with_timezone(&chrono::Local).date_naive())
+        .ok()
+        .or_else(|| parse_date(created_at))
+}
+
+/// Days the user ticked the routine
+pub fn completion_dates(db: &Connection, routine_id: &str, user_id: &str) -> BTreeSet<NaiveDate> {
+    db.prepare(
+        "SELECT completed_date FROM routine_completions WHERE routine_id = ?1 AND user_id = ?2",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([routine_id, user_id], |r| r.get::<_, String>(0))
+            .map(|rows| {
+                rows.filter_map(|r| r.ok())
+                    .filter_map(|d| parse_date(&d))
+                    .collect()
+            })
+    })
+    .unwrap_or_default()
+}
+
+/// One person's record of a routine
+pub struct History {
+    pub schedule: RoutineSchedule,
+    /// First day the routine could be done
+    pub anchor: NaiveDate,
+    pub dates: BTreeSet<NaiveDate>,
+}
+
+impl History {
+    /// Schedule and completions of a routine for `user_id`
+    pub fn load(db: &Connection, routine_id: &str, user_id: &str) -> Option<Self> {
+        let (schedule, created_at): (String, String) = db
+            .query_row(
+                "SELECT COALESCE(schedule, 'daily'), created_at FROM routines WHERE id = ?1",
+                [routine_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .ok()?;
+        let dates = completion_dates(db, routine_id, user_id);
+        let anchor = created_on(&created_at)
+            .or_else(|| dates.first().copied())
+            .unwrap_or_else(time::local_today);
+        Some(History {
+            schedule: RoutineSchedule::parse(&schedule).unwrap_or(RoutineSchedule::Daily),
+            anchor,
+            dates,
+        })
+    }
+
+    pub fn is_due(&self, date: NaiveDate) -> bool {
+        self.schedule.is_due(date, self.anchor, &self.dates)
+    }
+
+    /// (current, longest). Today not being done yet doesn't break the
+    /// current streak, nor does an unfinished week for a weekly quota.
+    pub fn streaks(&self, today: NaiveDate) -> (i64, i64) {
+        let Some(&first) = self.dates.first() else {
+            return (0, 0);
+        };
+        let (mut run, mut longest) = (0, 0);
+        if let RoutineSchedule::TimesPerWeek(n) = self.schedule {
+            let this_week = week_start(today);
+            let mut week = week_start(first);
+            while week <= this_week {
+                let count = self
+                    .dates
+                    .range(week..week + Duration::days(7))
+                    .filter(|d| **d <= today)
+                    .count() as i64;
+                if count >= n as i64 || week == this_week {
+                    run += count;
+                } else {
+                    run = 0;
+                }
+                longest = longest.max(run);
+                week += Duration::days(7);
+            }
+            return (run, longest);
+        }
+
+        let mut day = first;
+        while day <= today {
+            if self.schedule.is_scheduled(day, self.anchor) {
+                if self.dates.contains(&day) {
+                    run += 1;
+                    longest = longest.max(run);
+                } else if day < today {
+                    run = 0;
+                }
+            }
+            day += Duration::days(1);
+        }
+        (run, longest)
+    }
+
+    /// (due, done) between two days, inclusive. A times-per-week routine
+    /// is due N times in each week, fewer in weeks cut short by the range.
+    pub fn due_and_done(&self, from: NaiveDate, to: NaiveDate) -> (i64, i64) {
+        let (mut due, mut done) = (0, 0);
+        if let RoutineSchedule::TimesPerWeek(n) = self.schedule {
+            let mut week = week_start(from);
+            while week <= to {
+                let start = week.max(from);
+                let end = (week + Duration::days(6)).min(to);
+                let week_due = ((end - start).num_days() + 1).min(n as i64);
+                due += week_due;
+                done += (self.dates.range(start..=end).count() as i64).min(week_due);
+                week += Duration::days(7);
+            }
+            return (due, done);
+        }
+
+        let mut day = from;
+        while day <= to {
+            if self.schedule.is_scheduled(day, self.anchor) {
+                due += 1;
+                if self.dates.contains(&day) {
+                    done += 1;
+                }
+            }
+            day += Duration::days(1);
+        }
+        (due, done)
+    }
+
+    /// Percentage of due days done in the `days` ending today, starting no
+    /// earlier than the routine itself
+    fn rate(&self, today: NaiveDate, days: i64) -> Option<f64> {
+        let from = (today - Duration::days(days - 1)).max(self.anchor);
+        let (due, done) = self.due_and_done(from, today);
+        (due > 0).then(|| (done as f64 * 1000.0 / due as f64).round() / 10.0)
+    }
+
+    pub fn stats(&self, routine_id: &str, today: NaiveDate) -> RoutineStats {
+        let (current_streak, longest_streak) = self.streaks(today);
+        RoutineStats {
+            routine_id: routine_id.to_string(),
+            schedule: self.schedule.as_string(),
+            current_streak,
+            longest_streak,
+            total: self.dates.range(..=today).count() as i64,
+            rate_7: self.rate(today, 7),
+            rate_30: self.rate(today, 30),
+            rate_365: self.rate(today, 365),
+            last_completed_date: self
+                .dates
+                .range(..=today)
+                .next_back()
+                .map(|d| d.to_string()),
+        }
+    }
+
+    /// One entry per day from `from` to `to`, inclusive
+    pub fn heatmap(&self, from: NaiveDate, to: NaiveDate) -> Vec<HeatmapDay> {
+        std::iter::successors(Some(from), |day| day.succ_opt())
+            .take_while(|day| *day <= to)
+            .map(|day| HeatmapDay {
+                date: day.to_string(),
+                done: self.dates.contains(&day),
+                scheduled: day >= self.anchor && self.schedule.is_scheduled(day, self.anchor),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn history(schedule: &str, anchor: &str, list: &[&str]) -> History {
+        History {
+            schedule: RoutineSchedule::parse(schedule).unwrap(),
+            anchor: date(anchor),
+            dates: list.iter().map(|d| date(d)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_streaks() {
+        let today = date("2026-03-10");
+        let mut h = history(
+            "daily",
+            "2026-03-01",
+            &[
+                "2026-03-01",
+                "2026-03-02",
+                "2026-03-03",
+                "2026-03-04",
+                "2026-03-07",
+                "2026-03-08",
+                "2026-03-09",
+            ],
+        );
+        // Today still open: the streak runs up to yesterday
+        assert_eq!(h.streaks(today), (3, 4));
+        // A missed yesterday breaks it
+        assert_eq!(h.streaks(date("2026-03-11")), (0, 4));
+
+        h.dates.insert(today);
+        assert_eq!(h.streaks(today), (4, 4));
+        assert_eq!(history("daily", "2026-03-01", &[]).streaks(today), (0, 0));
+    }
+
+    #[test]
+    fn test_streaks_skip_rest_days() {
+        // Mondays, Wednesdays and Fridays; 2026-03-02 is a Monday
+        let h = history(
+            "weekdays:1,3,5",
+            "2026-03-01",
+            &["2026-03-02", "2026-03-04", "2026-03-06", "2026-03-09"],
+        );
+        assert_eq!(h.streaks(date("2026-03-10")), (4, 4));
+        // Wednesday missed by Thursday
+        assert_eq!(h.streaks(date("2026-03-12")), (0, 4));
+
+        let h = history(
+            "every_other_day",
+            "2026-03-01",
+            &["2026-03-01", "2026-03-03", "2026-03-05"],
+        );
+        assert_eq!(h.streaks(date("2026-03-07")), (3, 3));
+        assert_eq!(h.streaks(date("2026-03-08")), (0, 3));
+
+        // Three times a week: the unfinished week doesn't break it
+        let h = history(
+            "weekly:3",
+            "2026-03-01",
+            &["2026-03-02", "2026-03-05", "2026-03-07", "2026-03-09"],
+        );
+        assert_eq!(h.streaks(date("2026-03-11")), (4, 4));
+        // Once the second week falls short, its one day no longer counts
+        assert_eq!(h.streaks(date("2026-03-16")), (0, 3));
+    }
+
+    #[test]
+    fn test_rates() {
+        let today = date("2026-03-10");
+        let h = history(
+            "daily",
+            "2026-01-01",
+            &["2026-03-04", "2026-03-08", "2026-03-10"],
+        );
+        assert_eq!(h.rate(today, 7), Some(42.9));
+        // Created three days ago: only those days were due
+        let h = history("daily", "2026-03-08", &["2026-03-08", "2026-03-10"]);
+        assert_eq!(h.rate(today, 30), Some(66.7));
+        // Twice a week over two full weeks
+        let h = history(
+            "weekly:2",
+            "2026-01-01",
+            &["2026-03-02", "2026-03-03", "2026-03-04", "2026-03-12"],
+        );
+        assert_eq!(
+            h.due_and_done(date("2026-03-02"), date("2026-03-15")),
+            (4, 3)
+        );
+    }
+}
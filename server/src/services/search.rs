@@ -0,0 +1,253 @@
+use rusqlite::Connection;
+
+/// Modules covered by the search index, in display order
+pub const MODULES: [&str; 4] = ["todo", "english", "expense", "trip"];
+
+/// Longest accepted query (in characters)
+pub const MAX_QUERY_CHARS: usize = 100;
+
+// Highlight markers; rendered to <mark> (HTML) or stripped (plain text)
+const MARK_OPEN: char = '\u{1}';
+const MARK_CLOSE: char = '\u{2}';
+
+/// Rows visible to a user: own rows, plus collaborative todos and shared
+/// trips, minus expenses and trips in the trash
+const ACCESS_FILTER: &str = "(user_id = ?1 \
+     OR (module = 'todo' AND item_id IN (SELECT todo_id FROM todo_collaborators WHERE user_id = ?1 AND status = 'active')) \
+     OR (module = 'trip' AND parent_id IN (SELECT trip_id FROM trip_collaborators WHERE user_id = ?1))) \
+     AND NOT (module = 'expense' AND parent_id IN (SELECT id FROM expense_entries WHERE deleted = 1)) \
+     AND NOT (module = 'trip' AND parent_id IN (SELECT id FROM trips WHERE deleted = 1))";
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub module: String,
+    pub id: String,
+    pub parent_id: String,
+    /// Title with highlight markers
+    pub title: String,
+    /// Body excerpt with highlight markers
+    pub snippet: String,
+    /// bm25 score, lower is better (0 for substring fallback matches)
+    pub score: f64,
+}
+
+impl SearchHit {
+    pub fn to_json(&self, html: bool) -> serde_json::Value {
+        let render = if html { to_html } else { to_plain };
+        serde_json::json!({
+            "module": self.module,
+            "id": self.id,
+            "parent_id": self.parent_id,
+            "title": render(&self.title),
+            "snippet": render(&self.snippet),
+            "score": self.score,
+        })
+    }
+}
+
+/// Escape HTML and turn highlight markers into <mark> tags
+pub fn to_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            MARK_OPEN => out.push_str("<mark>"),
+            MARK_CLOSE => out.push_str("</mark>"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Strip highlight markers
+pub fn to_plain(s: &str) -> String {
+    s.chars()
+        .filter(|c| *c != MARK_OPEN && *c != MARK_CLOSE)
+        .collect()
+}
+
+/// Search one module. Terms of 3+ characters go through the trigram FTS index
+/// with bm25 ranking; shorter terms (common for Chinese words) fall back to a
+/// substring scan of the same index.
+pub fn search_module(
+    db: &Connection,
+    user_id: &str,
+    query: &str,
+    module: &str,
+    limit: usize,
+) -> Vec<SearchHit> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    if terms.iter().all(|t| t.chars().count() >= 3) {
+        match_search(db, user_id, &terms, module, limit)
+    } else {
+        substring_search(db, user_id, &terms, module, limit)
+    }
+}
+
+fn match_search(
+    db: &Connection,
+    user_id: &str,
+    terms: &[&str],
+    module: &str,
+    limit: usize,
+) -> Vec<SearchHit> {
+    let fts_query = terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let sql = format!(
+        "SELECT module, item_id, parent_id, \
+         highlight(search_index, 0, char(1), char(2)), \
+         snippet(search_index, 1, char(1), char(2), '…', 16), \
+         bm25(search_index, 10.0, 1.0) AS score \
+         FROM search_index WHERE search_index MATCH ?2 AND module = ?3 AND {} \
+         ORDER BY score LIMIT ?4",
+        ACCESS_FILTER
+    );
+    let mut stmt = match db.prepare(&sql) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[search] prepare failed: {}", e);
+            return Vec::new();
+        }
+    };
+    stmt.query_map(
+        rusqlite::params![user_id, fts_query, module, limit as i64],
+        |row| {
+            Ok(SearchHit {
+                module: row.get(0)?,
+                id: row.get(1)?,
+                parent_id: row.get(2)?,
+                title: row.get(3)?,
+                snippet: row.get(4)?,
+                score: row.get(5)?,
+            })
+        },
+    )
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+fn substring_search(
+    db: &Connection,
+    user_id: &str,
+    terms: &[&str],
+    module: &str,
+    limit: usize,
+) -> Vec<SearchHit> {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> =
+        vec![Box::new(user_id.to_string()), Box::new(module.to_string())];
+    for (i, term) in terms.iter().enumerate() {
+        let escaped = term
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        conditions.push(format!(
+            "(title LIKE ?{idx} ESCAPE '\\' OR body LIKE ?{idx} ESCAPE '\\')",
+            idx = i + 3
+        ));
+        params.push(Box::new(format!("%{}%", escaped)));
+    }
+    params.push(Box::new(limit as i64));
+    let sql = format!(
+        "SELECT module, item_id, parent_id, title, body FROM search_index \
+         WHERE module = ?2 AND {} AND {} LIMIT ?{}",
+        ACCESS_FILTER,
+        conditions.join(" AND "),
+        terms.len() + 3
+    );
+
+    let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let mut stmt = match db.prepare(&sql) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[search] prepare failed: {}", e);
+            return Vec::new();
+        }
+    };
+    stmt.query_map(param_refs.as_slice(), |row| {
+        let title: String = row.get(3)?;
+        let body: String = row.get(4)?;
+        Ok(SearchHit {
+            module: row.get(0)?,
+            id: row.get(1)?,
+            parent_id: row.get(2)?,
+            title: mark_terms(&title, terms),
+            snippet: mark_terms(&excerpt(&body, terms, 32), terms),
+            score: 0.0,
+        })
+    })
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+/// Wrap every case-insensitive occurrence of the terms in highlight markers
+fn mark_terms(text: &str, terms: &[&str]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text.to_lowercase().chars().collect();
+    // Lowercasing can change length for some scripts; skip highlighting then
+    if chars.len() != lower.len() {
+        return text.to_string();
+    }
+
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let t: Vec<char> = term.to_lowercase().chars().collect();
+        if t.is_empty() || t.len() > lower.len() {
+            continue;
+        }
+        for start in 0..=(lower.len() - t.len()) {
+            if lower[start..start + t.len()] == t[..] {
+                marked[start..start + t.len()]
+                    .iter_mut()
+                    .for_each(|m| *m = true);
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(text.len() + 8);
+    for (i, c) in chars.iter().enumerate() {
+        if marked[i] && (i == 0 || !marked[i - 1]) {
+            out.push(MARK_OPEN);
+        }
+        out.push(*c);
+        if marked[i] && (i + 1 == chars.len() || !marked[i + 1]) {
+            out.push(MARK_CLOSE);
+        }
+    }
+    out
+}
+
+/// A window of `width` characters around the first term found in `text`
+fn excerpt(text: &str, terms: &[&str], width: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width {
+        return text.to_string();
+    }
+    let lower = text.to_lowercase();
+    let first = terms
+        .iter()
+        .filter_map(|t| lower.find(&t.to_lowercase()))
+        .min()
+        .map(|byte_idx| lower[..byte_idx].chars().count())
+        .unwrap_or(0);
+    let start = first.saturating_sub(width / 4).min(chars.len() - width);
+    let mut out: String = chars[start..start + width].iter().collect();
+    if start > 0 {
+        out.insert(0, '…');
+    }
+    if start + width < chars.len() {
+        out.push('…');
+    }
+    out
+}
@@ -0,0 +1,883 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde_json::{Map, Value};
+
+use crate::models::review::FrequencyConfig;
+use crate::models::routine::RoutineSchedule;
+use crate::models::sync::{SyncChange, SyncMutation, SyncResult};
+use crate::services::changelog::{self, ChangeSet};
+use crate::services::{collaboration, completion, ordering};
+
+pub const TYPES: [&str; 5] = ["todo", "routine", "review", "reminder", "expense"];
+pub const DEFAULT_LIMIT: usize = 500;
+pub const MAX_LIMIT: usize = 1000;
+pub const MAX_MUTATIONS: usize = 200;
+
+/// Integer columns returned as JSON booleans
+const BOOL_COLUMNS: [&str; 6] = [
+    "completed",
+    "deleted",
+    "paused",
+    "completed_today",
+    "ai_processed",
+    "is_collaborative",
+];
+/// Text columns holding JSON
+const JSON_COLUMNS: [&str; 2] = ["tags", "frequency_config"];
+
+const TABS: [&str; 3] = ["today", "week", "month"];
+const QUADRANTS: [&str; 4] = [
+    "important-urgent",
+    "important-not-urgent",
+    "not-important-urgent",
+    "not-important-not-urgent",
+];
+const FREQUENCIES: [&str; 4] = ["daily", "weekly", "monthly", "yearly"];
+
+/// Accepted shape of a pushed field
+#[derive(Clone, Copy)]
+enum Kind {
+    /// Non-empty text up to N characters
+    Title(usize),
+    Text(usize),
+    OneOf(&'static [&'static str]),
+    Int(i64, i64),
+    /// Positive number
+    Amount,
+    Real,
+    Bool,
+    /// YYYY-MM-DD
+    Date,
+    /// RFC 3339 timestamp
+    Time,
+    Tags,
+    FrequencyConfig,
+    /// Routine schedule, stored in canonical form
+    Schedule,
+}
+
+struct Field {
+    name: &'static str,
+    kind: Kind,
+    nullable: bool,
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        nullable: false,
+    }
+}
+
+const fn nullable(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        nullable: true,
+    }
+}
+
+/// How a deleted record is represented
+#[derive(Clone, Copy, PartialEq)]
+enum Delete {
+    /// `deleted = 1`, goes to the trash
+    Flag,
+    /// Reminders are cancelled rather than removed
+    Cancel,
+    Hard,
+}
+
+/// Storage of a synced record type and the fields clients may write
+struct Spec {
+    table: &'static str,
+    fields: &'static [Field],
+    required: &'static [&'static str],
+    has_updated_at: bool,
+    delete: Delete,
+}
+
+const TODO_FIELDS: [Field; 10] = [
+    field("text", Kind::Title(500)),
+    field("content", Kind::Text(10000)),
+    field("tab", Kind::OneOf(&TABS)),
+    field("quadrant", Kind::OneOf(&QUADRANTS)),
+    field("progress", Kind::Int(0, 100)),
+    field("completed", Kind::Bool),
+    nullable("due_date", Kind::Date),
+    field("assignee", Kind::Text(100)),
+    field("tags", Kind::Tags),
+    field("sort_order", Kind::Real),
+];
+const ROUTINE_FIELDS: [Field; 4] = [
+    field("text", Kind::Title(500)),
+    field("schedule", Kind::Schedule),
+    field("completed_today", Kind::Bool),
+    nullable("last_completed_date", Kind::Date),
+];
+const REVIEW_FIELDS: [Field; 7] = [
+    field("text", Kind::Title(500)),
+    field("frequency", Kind::OneOf(&FREQUENCIES)),
+    field("frequency_config", Kind::FrequencyConfig),
+    field("notes", Kind::Text(5000)),
+    field("category", Kind::Text(100)),
+    field("paused", Kind::Bool),
+    nullable("last_completed", Kind::Date),
+];
+const REMINDER_FIELDS: [Field; 4] = [
+    field("text", Kind::Title(500)),
+    field("remind_at", Kind::Time),
+    nullable("related_todo_id", Kind::Text(64)),
+    nullable("repeat", Kind::Text(50)),
+];
+const EXPENSE_FIELDS: [Field; 5] = [
+    field("amount", Kind::Amount),
+    field("date", Kind::Date),
+    field("notes", Kind::Text(5000)),
+    field("tags", Kind::Tags),
+    field("currency", Kind::Text(10)),
+];
+
+fn spec(item_type: &str) -> Option<Spec> {
+    match item_type {
+        "todo" => Some(Spec {
+            table: "todos",
+            fields: &TODO_FIELDS,
+            required: &["text"],
+            has_updated_at: true,
+            delete: Delete::Flag,
+        }),
+        "routine" => Some(Spec {
+            table: "routines",
+            fields: &ROUTINE_FIELDS,
+            required: &["text"],
+            has_updated_at: false,
+            delete: Delete::Hard,
+        }),
+        "review" => Some(Spec {
+            table: "reviews",
+            fields: &REVIEW_FIELDS,
+            required: &["text", "frequency"],
+            has_updated_at: true,
+            delete: Delete::Flag,
+        }),
+        "reminder" => Some(Spec {
+            table: "reminders",
+            fields: &REMINDER_FIELDS,
+            required: &["text", "remind_at"],
+            has_updated_at: false,
+            delete: Delete::Cancel,
+        }),
+        "expense" => Some(Spec {
+            table: "expense_entries",
+            fields: &EXPENSE_FIELDS,
+            required: &["amount", "date"],
+            has_updated_at: true,
+            delete: Delete::Flag,
+        }),
+        _ => None,
+    }
+}
+
+// ─── Reading ───
+
+/// Highest sequence number so far; the cursor of an up-to-date client
+pub fn current_seq(db: &Connection) -> i64 {
+    db.query_row("SELECT COALESCE(MAX(seq), 0) FROM sync_changes", [], |r| {
+        r.get(0)
+    })
+    .unwrap_or(0)
+}
+
+/// Version of one record: its latest change, or 0 if it has not changed
+/// since the log started
+pub fn record_seq(db: &Connection, item_type: &str, id: &str) -> i64 {
+    db.query_row(
+        "SELECT COALESCE(MAX(seq), 0) FROM sync_changes WHERE record_type = ?1 AND record_id = ?2",
+        rusqlite::params![item_type, id],
+        |r| r.get(0),
+    )
+    .unwrap_or(0)
+}
+
+fn query_record(db: &Connection, sql: &str, id: &str, user_id: &str) -> Option<Value> {
+    let mut stmt = db.prepare(sql).ok()?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+    stmt.query_row(rusqlite::params![id, user_id], |row| {
+        let mut record = Map::new();
+        for (i, name) in names.iter().enumerate() {
+            let value = match row.get::<_, SqlValue>(i)? {
+                SqlValue::Null => Value::Null,
+                SqlValue::Integer(n) if BOOL_COLUMNS.contains(&name.as_str()) => {
+                    Value::Bool(n != 0)
+                }
+                SqlValue::Integer(n) => Value::from(n),
+                SqlValue::Real(f) => Value::from(f),
+                SqlValue::Text(s) if JSON_COLUMNS.contains(&name.as_str()) => {
+                    serde_json::from_str(&s).unwrap_or(Value::String(s))
+                }
+                SqlValue::Text(s) => Value::String(s),
+                SqlValue::Blob(_) => Value::Null,
+            };
+            // Later columns (a collaborator's own tab, quadrant…) win
+            record.insert(name.clone(), value);
+        }
+        Ok(Value::Object(record))
+    })
+    .ok()
+}
+
+/// The record as the user sees it, or None once it is deleted or no longer
+/// shared with them
+pub fn load(db: &Connection, user_id: &str, item_type: &str, id: &str) -> Option<Value> {
+    match item_type {
+        "todo" => query_record(
+            db,
+            "SELECT * FROM todos WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
+            id,
+            user_id,
+        )
+        .or_else(|| {
+            query_record(
+                db,
+                "SELECT t.*, tc.tab, tc.quadrant, tc.sort_order FROM todos t \
+                 JOIN todo_collaborators tc ON t.id = tc.todo_id \
+                 WHERE t.id = ?1 AND tc.user_id = ?2 AND tc.status = 'active' AND t.deleted = 0",
+                id,
+                user_id,
+            )
+        }),
+        "routine" => query_record(
+            db,
+            "SELECT * FROM routines WHERE id = ?1 AND user_id = ?2",
+            id,
+            user_id,
+        )
+        .or_else(|| {
+            query_record(
+                db,
+                "SELECT r.* FROM routines r JOIN routine_collaborators rc ON rc.routine_id = r.id \
+                 WHERE r.id = ?1 AND rc.user_id = ?2 AND rc.status = 'active'",
+                id,
+                user_id,
+            )
+        }),
+        "review" => query_record(
+            db,
+            "SELECT * FROM reviews WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
+            id,
+            user_id,
+        ),
+        "reminder" => query_record(
+            db,
+            "SELECT * FROM reminders WHERE id = ?1 AND user_id = ?2 AND status != 'cancelled'",
+            id,
+            user_id,
+        ),
+        "expense" => query_record(
+            db,
+            "SELECT * FROM expense_entries WHERE id = ?1 AND user_id = ?2 AND deleted = 0",
+            id,
+            user_id,
+        ),
+        _ => None,
+    }
+}
+
+/// Ids of every live record of a type the user can see
+fn visible_ids(db: &Connection, user_id: &str, item_type: &str) -> Vec<String> {
+    let sql = match item_type {
+        "todo" => {
+            "SELECT id FROM todos WHERE user_id = ?1 AND deleted = 0 \
+             UNION SELECT t.id FROM todos t JOIN todo_collaborators tc ON t.id = tc.todo_id \
+             WHERE tc.user_id = ?1 AND tc.status = 'active' AND t.deleted = 0"
+        }
+        "routine" => {
+            "SELECT id FROM routines WHERE user_id = ?1 \
+             UNION SELECT routine_id FROM routine_collaborators WHERE user_id = ?1 AND status = 'active'"
+        }
+        "review" => "SELECT id FROM reviews WHERE user_id = ?1 AND deleted = 0",
+        "reminder" => "SELECT id FROM reminders WHERE user_id = ?1 AND status != 'cancelled'",
+        "expense" => "SELECT id FROM expense_entries WHERE user_id = ?1 AND deleted = 0",
+        _ => return Vec::new(),
+    };
+    db.prepare(sql)
+        .and_then(|mut stmt| {
+            stmt.query_map([user_id], |r| r.get(0))
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Records changed after `since`, oldest change first, plus the cursor for
+/// the next call and whether more changes are waiting. Without `since`
+/// every live record is returned as created.
+pub fn changes(
+    db: &Connection,
+    user_id: &str,
+    since: Option<i64>,
+    limit: usize,
+) -> (Vec<SyncChange>, i64, bool) {
+    let latest = current_seq(db);
+    let Some(since) = since else {
+        let mut items = Vec::new();
+        for item_type in TYPES {
+            for id in visible_ids(db, user_id, item_type) {
+                let Some(record) = load(db, user_id, item_type, &id) else {
+                    continue;
+                };
+                items.push(SyncChange {
+                    item_type: item_type.to_string(),
+                    seq: record_seq(db, item_type, &id),
+                    id,
+                    op: "created".into(),
+                    record: Some(record),
+                });
+            }
+        }
+        return (items, latest, false);
+    };
+
+    // Shared records change under their owner's id, so match them by record
+    let mut rows: Vec<(String, String, i64, bool)> = db
+        .prepare(
+            "SELECT record_type, record_id, MAX(seq) AS last, MAX(op = 'insert' OR COALESCE(created_seq, 0) > ?2) \
+             FROM sync_changes \
+             WHERE seq > ?2 AND (user_id = ?1 \
+                OR (record_type = 'todo' AND record_id IN (SELECT todo_id FROM todo_collaborators WHERE user_id = ?1)) \
+                OR (record_type = 'routine' AND record_id IN (SELECT routine_id FROM routine_collaborators WHERE user_id = ?1))) \
+             GROUP BY record_type, record_id ORDER BY last LIMIT ?3",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(
+                rusqlite::params![user_id, since, (limit + 1) as i64],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default();
+    let has_more = rows.len() > limit;
+    rows.truncate(limit);
+    let cursor = if has_more {
+        rows.last().map(|r| r.2).unwrap_or(latest)
+    } else {
+        latest.max(since)
+    };
+
+    let items = rows
+        .into_iter()
+        .map(|(item_type, id, seq, inserted)| {
+            let record = load(db, user_id, &item_type, &id);
+            let op = match (&record, inserted) {
+                (None, _) => "deleted",
+                (Some(_), true) => "created",
+                (Some(_), false) => "updated",
+            };
+            SyncChange {
+                item_type,
+                id,
+                op: op.into(),
+                seq,
+                record,
+            }
+        })
+        .collect();
+    (items, cursor, has_more)
+}
+
+/// Collapse the log to the latest change per record and user. The kept
+/// row remembers when the record was inserted, so it still reads as created
+/// for clients that have not seen it yet.
+pub fn compact(db: &Connection) {
+    db.execute_batch(
+        "UPDATE sync_changes SET created_seq = (
+            SELECT MIN(CASE WHEN o.op = 'insert' THEN o.seq ELSE o.created_seq END)
+            FROM sync_changes o WHERE o.record_type = sync_changes.record_type
+                AND o.record_id = sync_changes.record_id AND o.user_id = sync_changes.user_id)
+         WHERE op != 'insert'
+            AND seq IN (SELECT MAX(seq) FROM sync_changes GROUP BY record_type, record_id, user_id);
+         DELETE FROM sync_changes WHERE seq NOT IN
+            (SELECT MAX(seq) FROM sync_changes GROUP BY record_type, record_id, user_id);",
+    )
+    .unwrap_or_else(|e| eprintln!("[sync] compact failed: {}", e));
+}
+
+// ─── Writing ───
+
+fn outcome(
+    db: &Connection,
+    user_id: &str,
+    item_type: &str,
+    id: Option<&str>,
+    status: &str,
+    message: Option<String>,
+) -> SyncResult {
+    let known = status == "applied" || status == "conflict";
+    SyncResult {
+        item_type: item_type.to_string(),
+        id: id.map(str::to_string),
+        status: status.into(),
+        seq: id.filter(|_| known).map(|id| record_seq(db, item_type, id)),
+        record: id
+            .filter(|_| known)
+            .and_then(|id| load(db, user_id, item_type, id)),
+        message,
+    }
+}
+
+/// Check pushed fields against the type's spec and convert them for SQLite
+fn validate(
+    spec: &Spec,
+    fields: &Map<String, Value>,
+    creating: bool,
+) -> Result<Vec<(&'static str, SqlValue)>, String> {
+    let mut values = Vec::new();
+    for (name, value) in fields {
+        let field = spec
+            .fields
+            .iter()
+            .find(|f| f.name == name)
+            .ok_or_else(|| format!("不支持的字段: {}", name))?;
+        let invalid = || format!("字段格式不正确: {}", name);
+        if value.is_null() {
+            if !field.nullable {
+                return Err(invalid());
+            }
+            values.push((field.name, SqlValue::Null));
+            continue;
+        }
+        let converted = match field.kind {
+            Kind::Title(max) | Kind::Text(max) => {
+                let s = value.as_str().ok_or_else(invalid)?;
+                if s.chars().count() > max {
+                    return Err(format!("{} 不能超过 {} 字符", name, max));
+                }
+                if matches!(field.kind, Kind::Title(_)) && s.trim().is_empty() {
+                    return Err(format!("{} 不能为空", name));
+                }
+                SqlValue::Text(s.to_string())
+            }
+            Kind::OneOf(allowed) => {
+                let s = value.as_str().filter(|s| allowed.contains(s));
+                SqlValue::Text(s.ok_or_else(invalid)?.to_string())
+            }
+            Kind::Int(lo, hi) => {
+                let n = value.as_i64().filter(|n| (lo..=hi).contains(n));
+                SqlValue::Integer(n.ok_or_else(invalid)?)
+            }
+            Kind::Amount => {
+                SqlValue::Real(value.as_f64().filter(|n| *n > 0.0).ok_or_else(invalid)?)
+            }
+            Kind::Real => SqlValue::Real(value.as_f64().ok_or_else(invalid)?),
+            Kind::Bool => SqlValue::Integer(value.as_bool().ok_or_else(invalid)? as i64),
+            Kind::Date => {
+                let s = value
+                    .as_str()
+                    .filter(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok());
+                SqlValue::Text(s.ok_or_else(invalid)?.to_string())
+            }
+            Kind::Time => {
+                let s = value
+                    .as_str()
+                    .filter(|s| chrono::DateTime::parse_from_rfc3339(s).is_ok());
+                SqlValue::Text(s.ok_or_else(invalid)?.to_string())
+            }
+            Kind::Tags => {
+                let tags: Vec<String> =
+                    serde_json::from_value(value.clone()).map_err(|_| invalid())?;
+                SqlValue::Text(serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into()))
+            }
+            Kind::FrequencyConfig => {
+                let config: FrequencyConfig =
+                    serde_json::from_value(value.clone()).map_err(|_| invalid())?;
+                SqlValue::Text(serde_json::to_string(&config).unwrap_or_else(|_| "{}".into()))
+            }
+            Kind::Schedule => value
+                .as_str()
+                .and_then(RoutineSchedule::parse)
+                .map(|s| SqlValue::Text(s.as_string()))
+                .ok_or_else(invalid)?,
+        };
+        values.push((field.name, converted));
+    }
+    if creating {
+        if let Some(missing) = spec
+            .required
+            .iter()
+            .find(|r| !values.iter().any(|(n, v)| n == *r && *v != SqlValue::Null))
+        {
+            return Err(format!("缺少字段: {}", missing));
+        }
+    } else if values.is_empty() {
+        return Err("没有要修改的字段".into());
+    }
+    Ok(values)
+}
+
+fn value_of<'a>(values: &'a [(&str, SqlValue)], name: &str) -> Option<&'a SqlValue> {
+    values.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+}
+
+/// Progress and completion move together, as in the batch update
+fn derive_todo_values(values: &mut Vec<(&'static str, SqlValue)>) {
+    if let (Some(SqlValue::Integer(p)), None) =
+        (value_of(values, "progress"), value_of(values, "completed"))
+    {
+        let done = (*p == 100) as i64;
+        values.push(("completed", SqlValue::Integer(done)));
+    }
+}
+
+/// Stamp or clear completed_at to match the completed flag
+fn sync_completed_at(db: &Connection, id: &str, now: &str) {
+    db.execute(
+        "UPDATE todos SET completed_at = CASE WHEN completed = 1 THEN COALESCE(completed_at, ?1) ELSE NULL END \
+         WHERE id = ?2",
+        rusqlite::params![now, id],
+    )
+    .ok();
+}
+
+fn valid_client_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn create(db: &Connection, user_id: &str, spec: &Spec, m: &SyncMutation, now: &str) -> SyncResult {
+    let item_type = m.item_type.as_str();
+    let id = match m.id.as_deref() {
+        Some(id) if !valid_client_id(id) => {
+            return outcome(
+                db,
+                user_id,
+                item_type,
+                None,
+                "invalid",
+                Some("无效的 ID".into()),
+            )
+        }
+        Some(id) => id.to_string(),
+        None => uuid::Uuid::new_v4().to_string()[..8].to_string(),
+    };
+    let owner: Option<String> = db
+        .query_row(
+            &format!("SELECT user_id FROM {} WHERE id = ?1", spec.table),
+            [&id],
+            |r| r.get(0),
+        )
+        .ok();
+    match owner {
+        // Already there, e.g. a retried push: hand back the server copy
+        Some(owner) if owner == user_id => {
+            return outcome(
+                db,
+                user_id,
+                item_type,
+                Some(&id),
+                "conflict",
+                Some("记录已存在".into()),
+            )
+        }
+        Some(_) => {
+            return outcome(
+                db,
+                user_id,
+                item_type,
+                Some(&id),
+                "invalid",
+                Some("ID 已被占用".into()),
+            )
+        }
+        None => {}
+    }
+
+    let mut values = match validate(spec, &m.fields, true) {
+        Ok(v) => v,
+        Err(msg) => return outcome(db, user_id, item_type, Some(&id), "invalid", Some(msg)),
+    };
+    if item_type == "todo" {
+        derive_todo_values(&mut values);
+        if value_of(&values, "sort_order").is_none() {
+            let text = |name: &str, default: &str| match value_of(&values, name) {
+                Some(SqlValue::Text(s)) => s.clone(),
+                _ => default.to_string(),
+            };
+            let key = ordering::append_key(
+                db,
+                user_id,
+                &text("tab", "today"),
+                &text("quadrant", "not-important-not-urgent"),
+            );
+            values.push(("sort_order", SqlValue::Real(key)));
+        }
+    }
+
+    let mut columns = vec!["id", "user_id", "created_at"];
+    let mut params = vec![
+        SqlValue::Text(id.clone()),
+        SqlValue::Text(user_id.to_string()),
+        SqlValue::Text(now.to_string()),
+    ];
+    if spec.has_updated_at {
+        columns.push("updated_at");
+        params.push(SqlValue::Text(now.to_string()));
+    }
+    for (name, value) in values {
+        columns.push(name);
+        params.push(value);
+    }
+    let placeholders: Vec<String> = (1..=params.len()).map(|i| format!("?{}", i)).collect();
+    if let Err(e) = db.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            spec.table,
+            columns.join(", "),
+            placeholders.join(", ")
+        ),
+        rusqlite::params_from_iter(params),
+    ) {
+        eprintln!("[sync] create {} DB error: {}", item_type, e);
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(&id),
+            "invalid",
+            Some("写入失败".into()),
+        );
+    }
+    if item_type == "todo" {
+        sync_completed_at(db, &id, now);
+    }
+    outcome(db, user_id, item_type, Some(&id), "applied", None)
+}
+
+fn update(
+    db: &Connection,
+    user_id: &str,
+    spec: &Spec,
+    m: &SyncMutation,
+    id: &str,
+    now: &str,
+) -> SyncResult {
+    let item_type = m.item_type.as_str();
+    let mut values = match validate(spec, &m.fields, false) {
+        Ok(v) => v,
+        Err(msg) => return outcome(db, user_id, item_type, Some(id), "invalid", Some(msg)),
+    };
+    let is_todo = item_type == "todo";
+    let before = if is_todo {
+        derive_todo_values(&mut values);
+        changelog::snapshot(db, id).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let old_position = ordering::position(db, user_id, id);
+    let moves_itself = value_of(&values, "sort_order").is_some();
+
+    let mut sets: Vec<String> = Vec::new();
+    let mut params: Vec<SqlValue> = Vec::new();
+    for (name, value) in values {
+        params.push(value);
+        sets.push(format!("{} = ?{}", name, params.len()));
+    }
+    if spec.has_updated_at {
+        params.push(SqlValue::Text(now.to_string()));
+        sets.push(format!("updated_at = ?{}", params.len()));
+    }
+    params.push(SqlValue::Text(id.to_string()));
+    params.push(SqlValue::Text(user_id.to_string()));
+    if let Err(e) = db.execute(
+        &format!(
+            "UPDATE {} SET {} WHERE id = ?{} AND user_id = ?{}",
+            spec.table,
+            sets.join(", "),
+            params.len() - 1,
+            params.len()
+        ),
+        rusqlite::params_from_iter(params),
+    ) {
+        eprintln!("[sync] update {} DB error: {}", item_type, e);
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(id),
+            "invalid",
+            Some("写入失败".into()),
+        );
+    }
+
+    if is_todo {
+        sync_completed_at(db, id, now);
+        // A todo moved to another list goes to its end, as in the batch update
+        if !moves_itself && ordering::position(db, user_id, id) != old_position {
+            ordering::place(db, user_id, id, None, None, now).ok();
+        }
+        let after = changelog::snapshot(db, id).unwrap_or_default();
+        ChangeSet::new(user_id, now).record_diff(db, id, &before, &after);
+        let completed = |s: &[(&str, String)]| s.iter().any(|(f, v)| *f == "completed" && v == "1");
+        if completed(&after) && !completed(&before) {
+            completion::on_completed(db, id, now);
+        }
+    }
+    outcome(db, user_id, item_type, Some(id), "applied", None)
+}
+
+fn delete(
+    db: &Connection,
+    user_id: &str,
+    spec: &Spec,
+    item_type: &str,
+    id: &str,
+    now: &str,
+) -> SyncResult {
+    let result = match spec.delete {
+        Delete::Flag => db.execute(
+            &format!(
+                "UPDATE {} SET deleted = 1, deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND user_id = ?3",
+                spec.table
+            ),
+            rusqlite::params![now, id, user_id],
+        ),
+        Delete::Cancel => db.execute(
+            &format!(
+                "UPDATE {} SET status = 'cancelled', acknowledged_at = ?1 WHERE id = ?2 AND user_id = ?3",
+                spec.table
+            ),
+            rusqlite::params![now, id, user_id],
+        ),
+        Delete::Hard => db.execute(
+            &format!("DELETE FROM {} WHERE id = ?1 AND user_id = ?2", spec.table),
+            rusqlite::params![id, user_id],
+        ),
+    };
+    if let Err(e) = result {
+        eprintln!("[sync] delete {} DB error: {}", item_type, e);
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(id),
+            "invalid",
+            Some("删除失败".into()),
+        );
+    }
+    if item_type == "routine" {
+        db.execute(
+            "DELETE FROM routine_collaborators WHERE routine_id = ?1",
+            [id],
+        )
+        .ok();
+        db.execute(
+            "DELETE FROM routine_completions WHERE routine_id = ?1",
+            [id],
+        )
+        .ok();
+    }
+    outcome(db, user_id, item_type, Some(id), "applied", None)
+}
+
+/// Apply one queued offline mutation to the user's own records
+pub fn apply(db: &Connection, user_id: &str, m: &SyncMutation, now: &str) -> SyncResult {
+    let item_type = m.item_type.as_str();
+    let id = m.id.as_deref();
+    let Some(spec) = spec(item_type) else {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            id,
+            "invalid",
+            Some(format!("未知类型: {}", item_type)),
+        );
+    };
+    if m.op == "create" {
+        return create(db, user_id, &spec, m, now);
+    }
+    if m.op != "update" && m.op != "delete" {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            id,
+            "invalid",
+            Some(format!("未知操作: {}", m.op)),
+        );
+    }
+    let Some(id) = id else {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            None,
+            "invalid",
+            Some("缺少 id".into()),
+        );
+    };
+
+    let owned: bool = db
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) > 0 FROM {} WHERE id = ?1 AND user_id = ?2",
+                spec.table
+            ),
+            rusqlite::params![id, user_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(false);
+    if !owned {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(id),
+            "not_found",
+            Some("记录不存在".into()),
+        );
+    }
+    if m.base_seq
+        .is_some_and(|base| record_seq(db, item_type, id) > base)
+    {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(id),
+            "conflict",
+            Some("服务器上的版本已更新".into()),
+        );
+    }
+
+    let live = load(db, user_id, item_type, id).is_some();
+    if m.op == "delete" {
+        if !live {
+            // Deleting twice is fine
+            return outcome(db, user_id, item_type, Some(id), "applied", None);
+        }
+        if item_type == "todo" && collaboration::is_todo_collaborative(db, id) {
+            return outcome(
+                db,
+                user_id,
+                item_type,
+                Some(id),
+                "invalid",
+                Some("协作任务需要协作者确认，请联网后删除".into()),
+            );
+        }
+        return delete(db, user_id, &spec, item_type, id, now);
+    }
+    if !live {
+        return outcome(
+            db,
+            user_id,
+            item_type,
+            Some(id),
+            "conflict",
+            Some("记录已被删除".into()),
+        );
+    }
+    update(db, user_id, &spec, m, id, now)
+}
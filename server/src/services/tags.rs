@@ -0,0 +1,166 @@
+use rusqlite::Connection;
+
+use crate::models::tag::{TagChange, TagUsage};
+use crate::services::changelog::ChangeSet;
+
+fn parse_tags(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+/// Trimmed tag name, or an error if it is empty or too long
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("标签不能为空".into());
+    }
+    if name.chars().count() > 50 {
+        return Err("标签不能超过 50 字符".into());
+    }
+    Ok(name.to_string())
+}
+
+fn tag_rows(db: &Connection, sql: &str, user_id: &str) -> Vec<(String, String)> {
+    db.prepare(sql)
+        .and_then(|mut stmt| {
+            stmt.query_map([user_id], |r| Ok((r.get(0)?, r.get(1)?)))
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Every tag on the user's live todos and expense entries, most used first
+pub fn usage(db: &Connection, user_id: &str) -> Vec<TagUsage> {
+    let mut tags: Vec<TagUsage> = Vec::new();
+    let mut count = |raw: &str, is_todo: bool| {
+        for name in parse_tags(raw) {
+            let idx = match tags.iter().position(|t| t.name == name) {
+                Some(i) => i,
+                None => {
+                    tags.push(TagUsage {
+                        name,
+                        todos: 0,
+                        expenses: 0,
+                        total: 0,
+                    });
+                    tags.len() - 1
+                }
+            };
+            let tag = &mut tags[idx];
+            if is_todo {
+                tag.todos += 1;
+            } else {
+                tag.expenses += 1;
+            }
+            tag.total += 1;
+        }
+    };
+    for (_, raw) in tag_rows(
+        db,
+        "SELECT id, COALESCE(tags, '[]') FROM todos WHERE user_id = ?1 AND deleted = 0",
+        user_id,
+    ) {
+        count(&raw, true);
+    }
+    for (_, raw) in tag_rows(
+        db,
+        "SELECT id, COALESCE(tags, '[]') FROM expense_entries WHERE user_id = ?1 AND deleted = 0",
+        user_id,
+    ) {
+        count(&raw, false);
+    }
+    // Stable sort keeps first-seen order among equals
+    tags.sort_by_key(|t| std::cmp::Reverse(t.total));
+    tags
+}
+
+/// Replace the tags in `sources` with `target` (or drop them when `target`
+/// is None) on all of the user's todos and expense entries, trashed ones
+/// included. Duplicates left by a merge are collapsed. Todo edits go into
+/// one revertible changelog batch. All or nothing.
+pub fn replace(
+    db: &Connection,
+    user_id: &str,
+    sources: &[String],
+    target: Option<&str>,
+    now: &str,
+) -> Result<TagChange, String> {
+    if sources.is_empty() {
+        return Err("请指定要修改的标签".into());
+    }
+    let rewrite = |tags: &[String]| -> Option<Vec<String>> {
+        if !tags.iter().any(|t| sources.contains(t)) {
+            return None;
+        }
+        let mut out: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = match (sources.contains(tag), target) {
+                (true, Some(t)) => t.to_string(),
+                (true, None) => continue,
+                (false, _) => tag.clone(),
+            };
+            if !out.contains(&tag) {
+                out.push(tag);
+            }
+        }
+        Some(out)
+    };
+
+    db.execute_batch("BEGIN TRANSACTION")
+        .map_err(|_| "数据库繁忙，请稍后重试".to_string())?;
+    let result = (|| {
+        let changes = ChangeSet::new(user_id, now);
+        let mut change = TagChange::default();
+        for (id, raw) in tag_rows(
+            db,
+            "SELECT id, COALESCE(tags, '[]') FROM todos WHERE user_id = ?1",
+            user_id,
+        ) {
+            let Some(tags) = rewrite(&parse_tags(&raw)) else {
+                continue;
+            };
+            let new_raw = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into());
+            db.execute(
+                "UPDATE todos SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![new_raw, now, id],
+            )
+            .map_err(|e| {
+                eprintln!("[tags] todo update DB error: {}", e);
+                "更新任务标签失败".to_string()
+            })?;
+            changes.record(db, &id, "tags", &raw, &new_raw);
+            change.todos += 1;
+        }
+        for (id, raw) in tag_rows(
+            db,
+            "SELECT id, COALESCE(tags, '[]') FROM expense_entries WHERE user_id = ?1",
+            user_id,
+        ) {
+            let Some(tags) = rewrite(&parse_tags(&raw)) else {
+                continue;
+            };
+            db.execute(
+                "UPDATE expense_entries SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![
+                    serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into()),
+                    now,
+                    id
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[tags] expense update DB error: {}", e);
+                "更新记账标签失败".to_string()
+            })?;
+            change.expenses += 1;
+        }
+        if change.todos > 0 {
+            change.batch_id = Some(changes.id.clone());
+        }
+        Ok(change)
+    })();
+
+    match &result {
+        Ok(_) => db.execute_batch("COMMIT").ok(),
+        Err(_) => db.execute_batch("ROLLBACK").ok(),
+    };
+    result
+}
@@ -0,0 +1,289 @@
+use chrono::{Duration, NaiveDate};
+use rusqlite::Connection;
+
+use crate::models::project::CreateProjectRequest;
+use crate::models::template::{TemplateItem, TodoTemplate};
+use crate::models::todo::Quadrant;
+use crate::services::tool_executor::compute_tab_for_date;
+use crate::services::{checklist, ordering, projects};
+
+/// Max templates per user
+pub const MAX_TEMPLATES: i64 = 50;
+
+/// Max todos in one template
+pub const MAX_ITEMS: usize = 50;
+
+/// Furthest a due offset may reach from the anchor date
+const MAX_OFFSET_DAYS: i64 = 3650;
+
+/// Validated, trimmed template fields
+pub struct TemplateFields {
+    pub name: String,
+    pub description: String,
+    pub items: Vec<TemplateItem>,
+}
+
+pub fn validate(
+    name: &str,
+    description: &str,
+    items: &[TemplateItem],
+) -> Result<TemplateFields, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("模板名称不能为空".into());
+    }
+    if name.chars().count() > 50 {
+        return Err("模板名称不能超过 50 字符".into());
+    }
+    if description.chars().count() > 2000 {
+        return Err("模板描述不能超过 2000 字符".into());
+    }
+    if items.is_empty() {
+        return Err("模板至少需要一个任务".into());
+    }
+    if items.len() > MAX_ITEMS {
+        return Err(format!("每个模板最多 {} 个任务", MAX_ITEMS));
+    }
+
+    let mut cleaned = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let n = i + 1;
+        let text = item.text.trim();
+        if text.is_empty() {
+            return Err(format!("第 {} 个任务标题为空", n));
+        }
+        if text.len() > 500 {
+            return Err(format!("第 {} 个任务标题不能超过 500 字符", n));
+        }
+        if item.content.len() > 10000 {
+            return Err(format!("第 {} 个任务内容不能超过 10000 字符", n));
+        }
+        if item
+            .due_offset_days
+            .is_some_and(|d| d.abs() > MAX_OFFSET_DAYS)
+        {
+            return Err(format!("第 {} 个任务的截止偏移超出范围", n));
+        }
+        if Quadrant::parse(&item.quadrant).as_str() != item.quadrant {
+            return Err(format!("第 {} 个任务的象限无效: {}", n, item.quadrant));
+        }
+        let checklist: Vec<String> = item
+            .checklist
+            .iter()
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+        if checklist.len() > checklist::MAX_ITEMS {
+            return Err(format!(
+                "第 {} 个任务最多 {} 个清单项",
+                n,
+                checklist::MAX_ITEMS
+            ));
+        }
+        cleaned.push(TemplateItem {
+            text: text.to_string(),
+            content: item.content.clone(),
+            due_offset_days: item.due_offset_days,
+            quadrant: item.quadrant.clone(),
+            tags: item
+                .tags
+                .iter()
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            checklist,
+        });
+    }
+    Ok(TemplateFields {
+        name: name.to_string(),
+        description: description.trim().to_string(),
+        items: cleaned,
+    })
+}
+
+const SELECT: &str =
+    "SELECT id, name, COALESCE(description, ''), items, created_at, updated_at FROM todo_templates";
+
+fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<TodoTemplate> {
+    let items: String = row.get(3)?;
+    Ok(TodoTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        items: serde_json::from_str(&items).unwrap_or_default(),
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+pub fn list(db: &Connection, user_id: &str) -> Vec<TodoTemplate> {
+    db.prepare(&format!(
+        "{} WHERE user_id = ?1 ORDER BY name COLLATE NOCASE",
+        SELECT
+    ))
+    .and_then(|mut stmt| {
+        stmt.query_map([user_id], row_to_template)
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// One of the user's templates, by id or (case-insensitive) name
+pub fn find(db: &Connection, user_id: &str, key: &str) -> Option<TodoTemplate> {
+    db.query_row(
+        &format!(
+            "{} WHERE user_id = ?1 AND (id = ?2 OR name = ?2 COLLATE NOCASE) ORDER BY id = ?2 DESC LIMIT 1",
+            SELECT
+        ),
+        rusqlite::params![user_id, key.trim()],
+        row_to_template,
+    )
+    .ok()
+}
+
+/// Store a validated template. A taken name gets a numbered suffix when
+/// `rename_on_conflict` is set (used for templates received from friends).
+pub fn insert(
+    db: &Connection,
+    id: &str,
+    user_id: &str,
+    fields: &TemplateFields,
+    rename_on_conflict: bool,
+    now: &str,
+) -> Result<TodoTemplate, String> {
+    let count: i64 = db
+        .query_row(
+            "SELECT COUNT(*) FROM todo_templates WHERE user_id = ?1",
+            [user_id],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    if count >= MAX_TEMPLATES {
+        return Err(format!("最多保存 {} 个模板", MAX_TEMPLATES));
+    }
+
+    let name = &fields.name;
+    let items_json = serde_json::to_string(&fields.items).unwrap_or_else(|_| "[]".into());
+    let mut candidate = name.clone();
+    for n in 2.. {
+        let inserted = db.execute(
+            "INSERT INTO todo_templates (id, user_id, name, description, items, created_at, updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            rusqlite::params![id, user_id, candidate, fields.description, items_json, now],
+        );
+        match inserted {
+            Ok(_) => break,
+            Err(_) if rename_on_conflict && n < 100 => candidate = format!("{} ({})", name, n),
+            Err(_) => return Err(format!("模板已存在: {}", name)),
+        }
+    }
+    find(db, user_id, id).ok_or_else(|| "保存模板失败".into())
+}
+
+/// Result of instantiating a template
+pub struct Applied {
+    pub todo_ids: Vec<String>,
+    pub project_id: Option<String>,
+    pub project_name: Option<String>,
+}
+
+/// Create the template's todos, due dates counted from `anchor`. The todos
+/// go into an existing project, a newly created one (whose deadline is the
+/// latest due date), or none. All or nothing.
+pub fn apply(
+    db: &Connection,
+    user_id: &str,
+    template: &TodoTemplate,
+    anchor: NaiveDate,
+    project_key: Option<&str>,
+    new_project: Option<&str>,
+    now: &str,
+) -> Result<Applied, String> {
+    let project_key = project_key.map(str::trim).filter(|k| !k.is_empty());
+    let new_project = new_project.map(str::trim).filter(|k| !k.is_empty());
+    if project_key.is_some() && new_project.is_some() {
+        return Err("project_id 与 new_project 只能传一个".into());
+    }
+    let due_dates: Vec<Option<String>> = template
+        .items
+        .iter()
+        .map(|item| {
+            item.due_offset_days
+                .map(|d| (anchor + Duration::days(d)).format("%Y-%m-%d").to_string())
+        })
+        .collect();
+
+    db.execute_batch("BEGIN TRANSACTION")
+        .map_err(|_| "数据库繁忙，请稍后重试".to_string())?;
+    let result = (|| {
+        let project = match (project_key, new_project) {
+            (Some(key), _) => {
+                Some(projects::resolve(db, user_id, key).ok_or(format!("项目不存在: {}", key))?)
+            }
+            (None, Some(name)) => {
+                let req = CreateProjectRequest {
+                    name: name.to_string(),
+                    color: None,
+                    status: None,
+                    deadline: due_dates.iter().flatten().max().cloned(),
+                    description: None,
+                };
+                let project = projects::create(db, user_id, &req, now)?;
+                Some((project.id, project.name))
+            }
+            (None, None) => None,
+        };
+        let project_id = project.as_ref().map(|(id, _)| id.clone());
+
+        let mut todo_ids = Vec::with_capacity(template.items.len());
+        for (item, due_date) in template.items.iter().zip(&due_dates) {
+            let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+            let tab = due_date
+                .as_deref()
+                .map(compute_tab_for_date)
+                .unwrap_or("today");
+            // Appended one by one, so the items keep the template's order
+            let sort_order = ordering::append_key(db, user_id, tab, item.quadrant.as_str());
+            db.execute(
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, due_date, assignee, tags, \
+                 sort_order, created_at, updated_at, deleted, project_id) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 0, ?7, '', ?8, ?9, ?10, ?10, 0, ?11)",
+                rusqlite::params![
+                    id,
+                    user_id,
+                    item.text,
+                    item.content,
+                    tab,
+                    item.quadrant,
+                    due_date,
+                    serde_json::to_string(&item.tags).unwrap_or_else(|_| "[]".into()),
+                    sort_order,
+                    now,
+                    project_id,
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[templates] apply DB error: {}", e);
+                "创建任务失败".to_string()
+            })?;
+            for text in &item.checklist {
+                checklist::add_item(db, &id, text, now).map_err(|e| {
+                    eprintln!("[templates] apply checklist error: {}", e);
+                    "创建清单失败".to_string()
+                })?;
+            }
+            todo_ids.push(id);
+        }
+        Ok(Applied {
+            todo_ids,
+            project_name: project.map(|(_, name)| name),
+            project_id,
+        })
+    })();
+
+    match &result {
+        Ok(_) => db.execute_batch("COMMIT").ok(),
+        Err(_) => db.execute_batch("ROLLBACK").ok(),
+    };
+    result
+}
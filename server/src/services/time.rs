@@ -0,0 +1,16 @@
+use chrono::{FixedOffset, NaiveDate, Utc};
+
+/// The app's timezone (Asia/Shanghai)
+pub fn shanghai() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+/// Today's date in the app's timezone
+pub fn today() -> NaiveDate {
+    Utc::now().with_timezone(&shanghai()).date_naive()
+}
+
+/// Today's date on the server's clock; routines tick over on it
+pub fn local_today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
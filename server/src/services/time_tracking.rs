@@ -0,0 +1,309 @@
+use chrono::{DateTime, Days, Duration, NaiveDate, SecondsFormat, Utc};
+use rusqlite::Connection;
+
+use crate::models::time_entry::{ManualEntryRequest, TimeEntry, TimeReportRow};
+use crate::services::{collaboration, time};
+
+/// Longest span a single manual entry may cover
+pub const MAX_ENTRY_SECS: i64 = 24 * 3600;
+
+/// Entry timestamps are stored as UTC with second precision, so they
+/// compare correctly as strings
+pub fn timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+const SELECT: &str = "SELECT e.id, e.todo_id, COALESCE(t.text, ''), e.started_at, e.ended_at, e.duration_secs, e.note, e.source \
+     FROM time_entries e LEFT JOIN todos t ON t.id = e.todo_id";
+
+fn row_to_entry(row: &rusqlite::Row, now: DateTime<Utc>) -> rusqlite::Result<TimeEntry> {
+    let started_at: String = row.get(3)?;
+    let ended_at: Option<String> = row.get(4)?;
+    let duration_secs = match row.get::<_, Option<i64>>(5)? {
+        Some(secs) => secs,
+        None => parse_time(&started_at)
+            .map(|s| (now - s).num_seconds().max(0))
+            .unwrap_or(0),
+    };
+    Ok(TimeEntry {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        todo_text: row.get(2)?,
+        running: ended_at.is_none(),
+        started_at,
+        ended_at,
+        duration_secs,
+        note: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+        source: row.get(7)?,
+    })
+}
+
+fn get(db: &Connection, entry_id: &str, now: DateTime<Utc>) -> Option<TimeEntry> {
+    db.query_row(&format!("{} WHERE e.id = ?1", SELECT), [entry_id], |r| {
+        row_to_entry(r, now)
+    })
+    .ok()
+}
+
+/// The user's running timer, if any
+pub fn running(db: &Connection, user_id: &str, now: DateTime<Utc>) -> Option<TimeEntry> {
+    db.query_row(
+        &format!("{} WHERE e.user_id = ?1 AND e.ended_at IS NULL", SELECT),
+        [user_id],
+        |r| row_to_entry(r, now),
+    )
+    .ok()
+}
+
+/// Start a timer on a todo. Only one timer may run per user.
+pub fn start(
+    db: &Connection,
+    user_id: &str,
+    todo_id: &str,
+    note: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<TimeEntry, String> {
+    if !collaboration::check_todo_participant(db, todo_id, user_id) {
+        return Err(format!("任务不存在: {}", todo_id));
+    }
+    if let Some(current) = running(db, user_id, now) {
+        return Err(format!("「{}」正在计时，请先停止", current.todo_text));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    let ts = timestamp(now);
+    db.execute(
+        "INSERT INTO time_entries (id, user_id, todo_id, started_at, note, source, created_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, 'timer', ?4)",
+        rusqlite::params![id, user_id, todo_id, ts, note.unwrap_or("").trim()],
+    )
+    .map_err(|e| {
+        eprintln!("[time] start DB error: {}", e);
+        "开始计时失败".to_string()
+    })?;
+    get(db, &id, now).ok_or_else(|| "开始计时失败".into())
+}
+
+/// Stop the user's running timer
+pub fn stop(db: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<TimeEntry, String> {
+    let current = running(db, user_id, now).ok_or("当前没有进行中的计时")?;
+    db.execute(
+        "UPDATE time_entries SET ended_at = ?1, duration_secs = ?2 WHERE id = ?3",
+        rusqlite::params![timestamp(now), current.duration_secs, current.id],
+    )
+    .map_err(|e| {
+        eprintln!("[time] stop DB error: {}", e);
+        "停止计时失败".to_string()
+    })?;
+    get(db, &current.id, now).ok_or_else(|| "停止计时失败".into())
+}
+
+/// Record time after the fact. The span must be in the past and must not
+/// overlap the user's other entries, including a running timer.
+pub fn add_manual(
+    db: &Connection,
+    user_id: &str,
+    req: &ManualEntryRequest,
+    now: DateTime<Utc>,
+) -> Result<TimeEntry, String> {
+    if !collaboration::check_todo_participant(db, &req.todo_id, user_id) {
+        return Err(format!("任务不存在: {}", req.todo_id));
+    }
+    let start = parse_time(&req.started_at).ok_or("开始时间格式无效")?;
+    let end = match (req.ended_at.as_deref(), req.minutes) {
+        (Some(e), _) => parse_time(e).ok_or("结束时间格式无效")?,
+        // Checked up front: out-of-range minutes would overflow the duration
+        (None, Some(m)) if m <= 0 => return Err("结束时间必须晚于开始时间".into()),
+        (None, Some(m)) if m > MAX_ENTRY_SECS / 60 => return Err("单条记录不能超过 24 小时".into()),
+        (None, Some(m)) => start + Duration::minutes(m),
+        (None, None) => return Err("请提供结束时间或分钟数".into()),
+    };
+    let secs = (end - start).num_seconds();
+    if secs <= 0 {
+        return Err("结束时间必须晚于开始时间".into());
+    }
+    if secs > MAX_ENTRY_SECS {
+        return Err("单条记录不能超过 24 小时".into());
+    }
+    if end > now {
+        return Err("不能记录未来的时间".into());
+    }
+
+    let (start_ts, end_ts) = (timestamp(start), timestamp(end));
+    let overlapping: bool = db
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM time_entries WHERE user_id = ?1 \
+             AND started_at < ?2 AND COALESCE(ended_at, ?3) > ?4",
+            rusqlite::params![user_id, end_ts, timestamp(now), start_ts],
+            |r| r.get(0),
+        )
+        .unwrap_or(false);
+    if overlapping {
+        return Err("与已有的计时记录重叠".into());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+    db.execute(
+        "INSERT INTO time_entries (id, user_id, todo_id, started_at, ended_at, duration_secs, note, source, created_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'manual', ?8)",
+        rusqlite::params![
+            id,
+            user_id,
+            req.todo_id,
+            start_ts,
+            end_ts,
+            secs,
+            req.note.as_deref().unwrap_or("").trim(),
+            timestamp(now)
+        ],
+    )
+    .map_err(|e| {
+        eprintln!("[time] manual entry DB error: {}", e);
+        "保存失败".to_string()
+    })?;
+    get(db, &id, now).ok_or_else(|| "保存失败".into())
+}
+
+/// Delete one of the user's own entries
+pub fn delete(db: &Connection, user_id: &str, entry_id: &str) -> bool {
+    db.execute(
+        "DELETE FROM time_entries WHERE id = ?1 AND user_id = ?2",
+        [entry_id, user_id],
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+/// Time spent on a todo by all participants, running timers included
+pub fn total_secs(db: &Connection, todo_id: &str, now: DateTime<Utc>) -> i64 {
+    db.prepare("SELECT started_at, duration_secs FROM time_entries WHERE todo_id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map([todo_id], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, Option<i64>>(1)?))
+            })
+            .map(|rows| {
+                rows.filter_map(|r| r.ok())
+                    .map(|(started_at, secs)| {
+                        secs.unwrap_or_else(|| {
+                            parse_time(&started_at)
+                                .map(|s| (now - s).num_seconds().max(0))
+                                .unwrap_or(0)
+                        })
+                    })
+                    .sum()
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// UTC bounds of a range of local (Asia/Shanghai) days, end exclusive.
+/// Defaults to the last 7 days.
+pub fn day_range(from: Option<&str>, to: Option<&str>) -> Result<(String, String), String> {
+    let parse = |s: &str| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", s))
+    };
+    let out_of_range = || "日期超出范围".to_string();
+    let today = time::today();
+    let to = match to {
+        Some(s) => parse(s)?,
+        None => today,
+    };
+    let from = match from {
+        Some(s) => parse(s)?,
+        None => to.checked_sub_days(Days::new(6)).ok_or_else(out_of_range)?,
+    };
+    if from > to {
+        return Err("开始日期不能晚于结束日期".into());
+    }
+    let shanghai = time::shanghai();
+    let bound = |d: NaiveDate| {
+        d.and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(shanghai).single())
+            .map(|t| timestamp(t.with_timezone(&Utc)))
+            .ok_or_else(out_of_range)
+    };
+    let end = to.checked_add_days(Days::new(1)).ok_or_else(out_of_range)?;
+    Ok((bound(from)?, bound(end)?))
+}
+
+/// The user's entries in a UTC range, newest first
+pub fn list(
+    db: &Connection,
+    user_id: &str,
+    todo_id: Option<&str>,
+    range: &(String, String),
+    now: DateTime<Utc>,
+) -> Vec<TimeEntry> {
+    db.prepare(&format!(
+        "{} WHERE e.user_id = ?1 AND e.started_at >= ?2 AND e.started_at < ?3 \
+         AND (?4 IS NULL OR e.todo_id = ?4) ORDER BY e.started_at DESC",
+        SELECT
+    ))
+    .and_then(|mut stmt| {
+        stmt.query_map(rusqlite::params![user_id, range.0, range.1, todo_id], |r| {
+            row_to_entry(r, now)
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Totals per local start day, or per todo tag. An entry counts towards
+/// every tag of its todo; untagged time is grouped under "".
+pub fn report(
+    db: &Connection,
+    user_id: &str,
+    range: &(String, String),
+    by_tag: bool,
+    now: DateTime<Utc>,
+) -> Vec<TimeReportRow> {
+    let shanghai = time::shanghai();
+    let mut rows: Vec<TimeReportRow> = Vec::new();
+    for entry in list(db, user_id, None, range, now) {
+        let keys: Vec<String> = if by_tag {
+            let tags: Vec<String> = db
+                .query_row(
+                    "SELECT COALESCE(tags, '[]') FROM todos WHERE id = ?1",
+                    [&entry.todo_id],
+                    |r| r.get::<_, String>(0),
+                )
+                .ok()
+                .and_then(|t| serde_json::from_str(&t).ok())
+                .unwrap_or_default();
+            if tags.is_empty() {
+                vec![String::new()]
+            } else {
+                tags
+            }
+        } else {
+            vec![parse_time(&entry.started_at)
+                .map(|t| t.with_timezone(&shanghai).date_naive().to_string())
+                .unwrap_or_default()]
+        };
+        for key in keys {
+            match rows.iter_mut().find(|r| r.key == key) {
+                Some(row) => {
+                    row.seconds += entry.duration_secs;
+                    row.entries += 1;
+                }
+                None => rows.push(TimeReportRow {
+                    key,
+                    seconds: entry.duration_secs,
+                    entries: 1,
+                }),
+            }
+        }
+    }
+    if by_tag {
+        rows.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.key.cmp(&b.key)));
+    } else {
+        rows.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+    rows
+}
@@ -0,0 +1,403 @@
+//! Todo filter expressions, e.g. `tag:work quadrant:important-* due<7d !completed assignee:Tom`.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{types::ToSql, Connection};
+
+/// Longest accepted filter expression (in characters)
+pub const MAX_FILTER_CHARS: usize = 500;
+const MAX_TERMS: usize = 20;
+
+/// Column prefixes for the query a filter is compiled into. Shared fields
+/// live on the todo row; tab and quadrant come from the viewer's own view
+/// (the collaborator row for shared todos).
+pub struct Columns {
+    pub todo: &'static str,
+    pub view: &'static str,
+}
+
+/// Own todos: `FROM todos`
+pub const OWN_COLUMNS: Columns = Columns {
+    todo: "todos.",
+    view: "todos.",
+};
+
+/// Collaborative todos: `FROM todos t JOIN todo_collaborators tc`
+pub const COLLAB_COLUMNS: Columns = Columns {
+    todo: "t.",
+    view: "tc.",
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Cmp {
+    fn sql(self) -> &'static str {
+        match self {
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+            Cmp::Eq => "=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Clause {
+    Tag(String),
+    Quadrant(String),
+    Tab(String),
+    Assignee(Option<String>),
+    Project(Option<String>),
+    Due(Cmp, NaiveDate),
+    HasDue(bool),
+    Progress(Cmp, i64),
+    Completed,
+    Blocked,
+    Overdue(NaiveDate),
+    Recurring,
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    clause: Clause,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TodoFilter {
+    terms: Vec<Term>,
+}
+
+/// Split on whitespace, keeping double-quoted runs together. The flag marks
+/// tokens that start with a quote, which are always plain text.
+fn tokenize(input: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted_start = false;
+    let mut in_quotes = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if current.is_empty() && !in_quotes {
+                    quoted_start = true;
+                }
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push((std::mem::take(&mut current), quoted_start));
+                }
+                quoted_start = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push((current, quoted_start));
+    }
+    tokens
+}
+
+fn parse_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let err = || format!("无效的日期: {}", value);
+    match value {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Duration::days(1)),
+        "yesterday" => return Ok(today - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(d);
+    }
+    let unit_start = value.char_indices().last().map(|(i, _)| i).unwrap_or(0);
+    let (num, unit) = value.split_at(unit_start);
+    let n: i64 = num.parse().map_err(|_| err())?;
+    let days = match unit {
+        "d" => n,
+        "w" => n.checked_mul(7).ok_or_else(err)?,
+        _ => return Err(err()),
+    };
+    if !(-3650..=3650).contains(&days) {
+        return Err(err());
+    }
+    Ok(today + Duration::days(days))
+}
+
+/// Split `key<op>value`, e.g. `due<=7d` → ("due", Le, "7d")
+fn split_operator(token: &str) -> Option<(&str, Cmp, &str)> {
+    let pos = token.find([':', '<', '>', '='])?;
+    let key = &token[..pos];
+    let rest = &token[pos..];
+    let (cmp, len) = if rest.starts_with("<=") {
+        (Cmp::Le, 2)
+    } else if rest.starts_with(">=") {
+        (Cmp::Ge, 2)
+    } else if rest.starts_with('<') {
+        (Cmp::Lt, 1)
+    } else if rest.starts_with('>') {
+        (Cmp::Gt, 1)
+    } else {
+        (Cmp::Eq, 1)
+    };
+    Some((key, cmp, &rest[len..]))
+}
+
+fn parse_term(token: &str, today: NaiveDate) -> Result<Clause, String> {
+    let Some((key, cmp, value)) = split_operator(token) else {
+        return Ok(match token.to_lowercase().as_str() {
+            "completed" | "done" => Clause::Completed,
+            "blocked" => Clause::Blocked,
+            "overdue" => Clause::Overdue(today),
+            "recurring" => Clause::Recurring,
+            _ => Clause::Text(token.to_string()),
+        });
+    };
+    let key = key.to_lowercase();
+    if value.is_empty() {
+        return Err(format!("筛选条件缺少值: {}", token));
+    }
+    let only_eq = |clause: Clause| {
+        if cmp == Cmp::Eq {
+            Ok(clause)
+        } else {
+            Err(format!("{} 只支持 \":\"", key))
+        }
+    };
+
+    match key.as_str() {
+        "tag" => only_eq(Clause::Tag(value.to_string())),
+        "quadrant" | "q" => only_eq(Clause::Quadrant(value.to_lowercase())),
+        "tab" => only_eq(Clause::Tab(value.to_lowercase())),
+        "assignee" => only_eq(Clause::Assignee(match value {
+            "none" => None,
+            v => Some(v.to_string()),
+        })),
+        "project" => only_eq(Clause::Project(match value {
+            "none" => None,
+            v => Some(v.to_string()),
+        })),
+        "due" => match (cmp, value) {
+            (Cmp::Eq, "none") => Ok(Clause::HasDue(false)),
+            (Cmp::Eq, "any") => Ok(Clause::HasDue(true)),
+            _ => Ok(Clause::Due(cmp, parse_date(value, today)?)),
+        },
+        "progress" => match value.parse::<i64>() {
+            Ok(n) if (0..=100).contains(&n) => Ok(Clause::Progress(cmp, n)),
+            _ => Err(format!("无效的进度: {}", value)),
+        },
+        "is" => parse_term(value, today).and_then(|c| match c {
+            Clause::Text(_) => Err(format!("未知状态: {}", value)),
+            c => Ok(c),
+        }),
+        "" => Ok(Clause::Text(token.to_string())),
+        _ => Err(format!("未知筛选条件: {}", key)),
+    }
+}
+
+impl TodoFilter {
+    /// Parse a filter expression. Relative dates resolve against `today`.
+    pub fn parse(input: &str, today: NaiveDate) -> Result<Self, String> {
+        if input.chars().count() > MAX_FILTER_CHARS {
+            return Err("筛选条件过长".into());
+        }
+        let tokens = tokenize(input);
+        if tokens.len() > MAX_TERMS {
+            return Err(format!("筛选条件最多 {} 项", MAX_TERMS));
+        }
+
+        let mut terms = Vec::with_capacity(tokens.len());
+        for (token, quoted) in tokens {
+            let (negated, body) = match token.strip_prefix('!') {
+                Some(rest) if !quoted && !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+            let clause = if quoted {
+                Clause::Text(body.to_string())
+            } else {
+                parse_term(body, today)?
+            };
+            terms.push(Term { negated, clause });
+        }
+        Ok(TodoFilter { terms })
+    }
+
+    /// Compile into SQL conditions (to be ANDed) with parameters numbered
+    /// from `?{first_param}`
+    pub fn to_sql(&self, cols: &Columns, first_param: usize) -> (Vec<String>, Vec<Box<dyn ToSql>>) {
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+        let conditions = self
+            .terms
+            .iter()
+            .map(|term| {
+                let sql = clause_sql(&term.clause, cols, first_param, &mut params);
+                if term.negated {
+                    format!("NOT COALESCE(({}), 0)", sql)
+                } else {
+                    format!("({})", sql)
+                }
+            })
+            .collect();
+        (conditions, params)
+    }
+}
+
+fn like_pattern(s: &str) -> String {
+    format!(
+        "%{}%",
+        s.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
+fn clause_sql(
+    clause: &Clause,
+    cols: &Columns,
+    first_param: usize,
+    params: &mut Vec<Box<dyn ToSql>>,
+) -> String {
+    let t = cols.todo;
+    let v = cols.view;
+    let mut bind = |value: Box<dyn ToSql>| {
+        params.push(value);
+        format!("?{}", first_param + params.len() - 1)
+    };
+    let due = format!("substr(COALESCE({}due_date, ''), 1, 10)", t);
+
+    match clause {
+        Clause::Tag(tag) => format!(
+            "EXISTS (SELECT 1 FROM json_each({}tags) WHERE value = {} COLLATE NOCASE)",
+            t,
+            bind(Box::new(tag.clone()))
+        ),
+        Clause::Quadrant(p) => format!("{}quadrant GLOB {}", v, bind(Box::new(p.clone()))),
+        Clause::Tab(p) => format!("{}tab GLOB {}", v, bind(Box::new(p.clone()))),
+        Clause::Assignee(Some(a)) => format!(
+            "{}assignee = {} COLLATE NOCASE",
+            t,
+            bind(Box::new(a.clone()))
+        ),
+        Clause::Assignee(None) => format!("COALESCE({}assignee, '') = ''", t),
+        Clause::Project(Some(p)) => format!(
+            "EXISTS (SELECT 1 FROM projects p WHERE p.id = {t}project_id AND (p.id = {k} OR p.name = {k} COLLATE NOCASE))",
+            k = bind(Box::new(p.clone()))
+        ),
+        Clause::Project(None) => format!("COALESCE({}project_id, '') = ''", t),
+        Clause::Due(cmp, date) => format!(
+            "{due} != '' AND {due} {} {}",
+            cmp.sql(),
+            bind(Box::new(date.format("%Y-%m-%d").to_string()))
+        ),
+        Clause::HasDue(true) => format!("{} != ''", due),
+        Clause::HasDue(false) => format!("{} = ''", due),
+        Clause::Progress(cmp, n) => format!("{}progress {} {}", t, cmp.sql(), bind(Box::new(*n))),
+        Clause::Completed => format!("{}completed = 1", t),
+        Clause::Blocked => format!(
+            "EXISTS (SELECT 1 FROM todo_dependencies d JOIN todos b ON b.id = d.blocker_id \
+             WHERE d.todo_id = {}id AND b.completed = 0 AND b.deleted = 0)",
+            t
+        ),
+        Clause::Overdue(today) => format!(
+            "{t}completed = 0 AND {due} != '' AND {due} < {}",
+            bind(Box::new(today.format("%Y-%m-%d").to_string()))
+        ),
+        Clause::Recurring => format!("COALESCE({}recurrence, '') != ''", t),
+        Clause::Text(s) => {
+            let p = bind(Box::new(like_pattern(s)));
+            format!("{t}text LIKE {p} ESCAPE '\\' OR COALESCE({t}content, '') LIKE {p} ESCAPE '\\'")
+        }
+    }
+}
+
+/// Look up a saved view's filter by id or name
+pub fn saved_view_filter(db: &Connection, user_id: &str, key: &str) -> Option<String> {
+    db.query_row(
+        "SELECT filter FROM saved_views WHERE user_id = ?1 AND (id = ?2 OR name = ?2) \
+         ORDER BY id = ?2 DESC LIMIT 1",
+        rusqlite::params![user_id, key],
+        |r| r.get(0),
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_example() {
+        let f = TodoFilter::parse(
+            "tag:work quadrant:important-* due<7d !completed assignee:Tom",
+            day("2025-03-10"),
+        )
+        .unwrap();
+        assert_eq!(
+            f.terms,
+            vec![
+                Term {
+                    negated: false,
+                    clause: Clause::Tag("work".into())
+                },
+                Term {
+                    negated: false,
+                    clause: Clause::Quadrant("important-*".into())
+                },
+                Term {
+                    negated: false,
+                    clause: Clause::Due(Cmp::Lt, day("2025-03-17"))
+                },
+                Term {
+                    negated: true,
+                    clause: Clause::Completed
+                },
+                Term {
+                    negated: false,
+                    clause: Clause::Assignee(Some("Tom".into()))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_quotes_and_errors() {
+        let today = day("2025-03-10");
+        let f = TodoFilter::parse("tag:\"deep work\" \"a:b\" due:none", today).unwrap();
+        assert_eq!(f.terms[0].clause, Clause::Tag("deep work".into()));
+        assert_eq!(f.terms[1].clause, Clause::Text("a:b".into()));
+        assert_eq!(f.terms[2].clause, Clause::HasDue(false));
+
+        let f = TodoFilter::parse("project:\"Home move\" !project:none", today).unwrap();
+        assert_eq!(f.terms[0].clause, Clause::Project(Some("Home move".into())));
+        assert_eq!(f.terms[1].clause, Clause::Project(None));
+        assert!(f.terms[1].negated);
+
+        assert!(TodoFilter::parse("color:red", today).is_err());
+        assert!(TodoFilter::parse("due<soon", today).is_err());
+        assert!(TodoFilter::parse("tag>work", today).is_err());
+        assert!(TodoFilter::parse("progress>101", today).is_err());
+        // Huge offsets are rejected instead of overflowing
+        assert!(TodoFilter::parse("due<2635249153387078803w", today).is_err());
+        assert!(TodoFilter::parse("due<-9223372036854775808d", today).is_err());
+    }
+
+    #[test]
+    fn test_to_sql_params() {
+        let f = TodoFilter::parse("tag:a !report", day("2025-03-10")).unwrap();
+        let (conds, params) = f.to_sql(&COLLAB_COLUMNS, 3);
+        assert_eq!(params.len(), 2);
+        assert!(conds[0].contains("json_each(t.tags)") && conds[0].contains("?3"));
+        assert!(conds[1].starts_with("NOT COALESCE") && conds[1].contains("?4"));
+    }
+}
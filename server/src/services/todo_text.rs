@@ -0,0 +1,516 @@
+//! todo.txt and Markdown checklists for moving todos in and out of plain-text tools.
+
+use chrono::NaiveDate;
+use rusqlite::Connection;
+
+use crate::models::import::TextTodo;
+use crate::models::todo::Quadrant;
+use crate::services::tool_executor::compute_tab_for_date;
+use crate::services::{ordering, projects, tags};
+
+const TABS: [&str; 3] = ["today", "week", "month"];
+
+/// Quadrants in priority order, A to D
+const QUADRANTS: [Quadrant; 4] = [
+    Quadrant::ImportantUrgent,
+    Quadrant::ImportantNotUrgent,
+    Quadrant::NotImportantUrgent,
+    Quadrant::NotImportantNotUrgent,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    TodoTxt,
+    Markdown,
+}
+
+impl TextFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "todotxt" | "todo.txt" | "txt" => Some(TextFormat::TodoTxt),
+            "markdown" | "md" => Some(TextFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            TextFormat::TodoTxt => "todo.txt",
+            TextFormat::Markdown => "todos.md",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TextFormat::TodoTxt => "text/plain; charset=utf-8",
+            TextFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+}
+
+fn priority(quadrant: &str) -> char {
+    let idx = QUADRANTS
+        .iter()
+        .position(|q| q.as_str() == quadrant)
+        .unwrap_or(3);
+    (b'A' + idx as u8) as char
+}
+
+/// Priorities past D are the least important quadrant
+fn quadrant_for(priority: char) -> &'static str {
+    let idx = (priority as u8).saturating_sub(b'A').min(3) as usize;
+    QUADRANTS[idx].as_str()
+}
+
+/// A tag or project name as one token
+fn word(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+fn is_date(s: &str) -> bool {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()
+}
+
+// ─── Export ───
+
+struct Row {
+    text: String,
+    tab: String,
+    quadrant: String,
+    completed: bool,
+    completed_at: Option<String>,
+    due_date: Option<String>,
+    tags: Vec<String>,
+    project: Option<String>,
+    created_at: String,
+}
+
+fn load(db: &Connection, user_id: &str, tab: Option<&str>, include_completed: bool) -> Vec<Row> {
+    db.prepare(
+        "SELECT t.text, t.tab, t.quadrant, t.completed, t.completed_at, t.due_date, COALESCE(t.tags, '[]'), \
+         p.name, t.created_at FROM todos t LEFT JOIN projects p ON p.id = t.project_id \
+         WHERE t.user_id = ?1 AND t.deleted = 0 AND (?2 IS NULL OR t.tab = ?2) AND (?3 OR t.completed = 0) \
+         ORDER BY t.completed, t.sort_order, t.created_at",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(rusqlite::params![user_id, tab, include_completed], |r| {
+            let tags: String = r.get(6)?;
+            Ok(Row {
+                text: r.get(0)?,
+                tab: r.get(1)?,
+                quadrant: r.get(2)?,
+                completed: r.get::<_, i64>(3)? != 0,
+                completed_at: r.get(4)?,
+                due_date: r.get(5)?,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                project: r.get(7)?,
+                created_at: r.get(8)?,
+            })
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Tokens after the text, shared by both formats
+fn trailing_tokens(row: &Row, tag_sigil: char) -> Vec<String> {
+    let mut tokens = Vec::new();
+    if let Some(project) = &row.project {
+        tokens.push(format!("+{}", word(project)));
+    }
+    for tag in &row.tags {
+        tokens.push(format!("{}{}", tag_sigil, word(tag)));
+    }
+    tokens.push(format!("tab:{}", row.tab));
+    if let Some(due) = row.due_date.as_deref().filter(|d| !d.is_empty()) {
+        tokens.push(format!("due:{}", due));
+    }
+    tokens
+}
+
+fn todotxt_line(row: &Row) -> String {
+    let created = row.created_at.get(..10).unwrap_or_default();
+    let mut parts = Vec::new();
+    if row.completed {
+        parts.push("x".to_string());
+        if let Some(done) = row.completed_at.as_deref().and_then(|d| d.get(..10)) {
+            parts.push(done.to_string());
+        }
+    } else {
+        parts.push(format!("({})", priority(&row.quadrant)));
+    }
+    if is_date(created) {
+        parts.push(created.to_string());
+    }
+    parts.push(row.text.replace('\n', " "));
+    parts.extend(trailing_tokens(row, '@'));
+    // Completed tasks drop the leading priority; todo.txt keeps it as pri:X
+    if row.completed {
+        parts.push(format!("pri:{}", priority(&row.quadrant)));
+    }
+    parts.join(" ")
+}
+
+fn markdown_item(row: &Row) -> String {
+    let mut parts = vec![
+        format!("- [{}]", if row.completed { "x" } else { " " }),
+        row.text.replace('\n', " "),
+    ];
+    parts.extend(trailing_tokens(row, '#'));
+    if row.completed {
+        if let Some(done) = row.completed_at.as_deref().and_then(|d| d.get(..10)) {
+            parts.push(format!("done:{}", done));
+        }
+    }
+    parts.join(" ")
+}
+
+/// The user's live todos in `format`
+pub fn export(
+    db: &Connection,
+    user_id: &str,
+    format: TextFormat,
+    tab: Option<&str>,
+    include_completed: bool,
+) -> String {
+    let rows = load(db, user_id, tab, include_completed);
+    match format {
+        TextFormat::TodoTxt => {
+            let mut rows: Vec<&Row> = rows.iter().collect();
+            // Open tasks by priority, then the done ones
+            rows.sort_by_key(|r| (r.completed, priority(&r.quadrant)));
+            rows.iter().map(|r| todotxt_line(r) + "\n").collect()
+        }
+        TextFormat::Markdown => {
+            let mut out = String::from("# Next 待办\n");
+            for quadrant in QUADRANTS {
+                let items: Vec<&Row> = rows
+                    .iter()
+                    .filter(|r| r.quadrant == quadrant.as_str())
+                    .collect();
+                if items.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!(
+                    "\n## {} ({})\n\n",
+                    quadrant.label(),
+                    quadrant.as_str()
+                ));
+                for row in items {
+                    out.push_str(&markdown_item(row));
+                    out.push('\n');
+                }
+            }
+            out
+        }
+    }
+}
+
+// ─── Import ───
+
+fn blank(line: usize) -> TextTodo {
+    TextTodo {
+        line,
+        text: String::new(),
+        tab: String::new(),
+        quadrant: Quadrant::NotImportantNotUrgent.as_str().into(),
+        tags: Vec::new(),
+        project: None,
+        due_date: None,
+        completed: false,
+        completed_on: None,
+        created_on: None,
+    }
+}
+
+/// Take the inline tokens out of `words`, filling `todo`; the rest is the text
+fn read_tokens<'a>(todo: &mut TextTodo, words: impl Iterator<Item = &'a str>, tag_sigil: char) {
+    let mut text = Vec::new();
+    for w in words {
+        let sigil = w.chars().next().unwrap_or(' ');
+        let rest = &w[sigil.len_utf8()..];
+        if sigil == tag_sigil && !rest.is_empty() {
+            if let Ok(tag) = tags::validate_name(&rest.replace('_', " ")) {
+                if !todo.tags.contains(&tag) {
+                    todo.tags.push(tag);
+                }
+                continue;
+            }
+        }
+        if sigil == '+' && !rest.is_empty() {
+            let name = rest.replace('_', " ");
+            if todo.project.is_none() {
+                todo.project = Some(name);
+            } else if let Ok(tag) = tags::validate_name(&name) {
+                todo.tags.push(tag);
+            }
+            continue;
+        }
+        match w.split_once(':') {
+            Some(("tab", v)) if TABS.contains(&v) => todo.tab = v.to_string(),
+            Some(("due", v)) if is_date(v) => todo.due_date = Some(v.to_string()),
+            Some(("done", v)) if is_date(v) => todo.completed_on = Some(v.to_string()),
+            Some(("pri", v)) if v.len() == 1 && v.chars().all(|c| c.is_ascii_uppercase()) => {
+                todo.quadrant = quadrant_for(v.chars().next().unwrap_or('D')).into()
+            }
+            _ => text.push(w),
+        }
+    }
+    todo.text = text.join(" ");
+}
+
+fn parse_todotxt_line(line: usize, raw: &str) -> Option<TextTodo> {
+    let raw = raw.trim();
+    if raw.is_empty() || raw.starts_with('#') {
+        return None;
+    }
+    let mut todo = blank(line);
+    let mut words = raw.split_whitespace().peekable();
+    if words.peek() == Some(&"x") {
+        words.next();
+        todo.completed = true;
+        if let Some(d) = words.next_if(|w| is_date(w)) {
+            todo.completed_on = Some(d.to_string());
+        }
+    } else if let Some(p) = words.next_if(|w| {
+        let b = w.as_bytes();
+        b.len() == 3 && b[0] == b'(' && b[1].is_ascii_uppercase() && b[2] == b')'
+    }) {
+        todo.quadrant = quadrant_for(p.as_bytes()[1] as char).into();
+    }
+    if let Some(d) = words.next_if(|w| is_date(w)) {
+        todo.created_on = Some(d.to_string());
+    }
+    read_tokens(&mut todo, words, '@');
+    Some(todo)
+}
+
+/// Quadrant named by a Markdown heading, by slug or label
+fn heading_quadrant(heading: &str) -> Option<&'static str> {
+    QUADRANTS
+        .iter()
+        .find(|q| heading.contains(q.as_str()))
+        .or_else(|| QUADRANTS.iter().find(|q| heading.contains(q.label())))
+        .map(|q| q.as_str())
+}
+
+fn parse_markdown(content: &str) -> Vec<TextTodo> {
+    let mut quadrant = Quadrant::NotImportantNotUrgent.as_str();
+    let mut items = Vec::new();
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.starts_with('#') {
+            if let Some(q) = heading_quadrant(line) {
+                quadrant = q;
+            }
+            continue;
+        }
+        // Checklist items only: `- [ ] text`, `* [x] text`, `1. [ ] text`
+        let Some(item) = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .or_else(|| line.strip_prefix("+ "))
+            .or_else(|| {
+                let (n, rest) = line.split_once(". ")?;
+                n.chars().all(|c| c.is_ascii_digit()).then_some(rest)
+            })
+        else {
+            continue;
+        };
+        let item = item.trim_start();
+        let (completed, rest) = if let Some(rest) = item.strip_prefix("[ ]") {
+            (false, rest)
+        } else if let Some(rest) = item
+            .strip_prefix("[x]")
+            .or_else(|| item.strip_prefix("[X]"))
+        {
+            (true, rest)
+        } else {
+            continue;
+        };
+        let mut todo = blank(i + 1);
+        todo.quadrant = quadrant.into();
+        todo.completed = completed;
+        read_tokens(&mut todo, rest.split_whitespace(), '#');
+        items.push(todo);
+    }
+    items
+}
+
+/// Todos in a todo.txt or Markdown file; items without text are dropped
+pub fn parse(format: TextFormat, content: &str) -> Vec<TextTodo> {
+    let items = match format {
+        TextFormat::TodoTxt => content
+            .lines()
+            .enumerate()
+            .filter_map(|(i, l)| parse_todotxt_line(i + 1, l))
+            .collect(),
+        TextFormat::Markdown => parse_markdown(content),
+    };
+    items
+        .into_iter()
+        .filter(|t| !t.text.is_empty())
+        .map(|mut t| {
+            if t.tab.is_empty() {
+                t.tab = t
+                    .due_date
+                    .as_deref()
+                    .map(compute_tab_for_date)
+                    .unwrap_or("today")
+                    .to_string();
+            }
+            if !t.completed {
+                t.completed_on = None;
+            }
+            t
+        })
+        .collect()
+}
+
+/// Point `+project` at one of the user's projects, or fall back to a tag
+pub fn resolve_projects(db: &Connection, user_id: &str, items: &mut [TextTodo]) {
+    for todo in items {
+        let Some(name) = todo.project.take() else {
+            continue;
+        };
+        match projects::resolve(db, user_id, &name) {
+            Some((_, name)) => todo.project = Some(name),
+            None => {
+                if let Ok(tag) = tags::validate_name(&name) {
+                    if !todo.tags.contains(&tag) {
+                        todo.tags.push(tag);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Create the parsed todos, all or nothing; returns their ids
+pub fn import(
+    db: &Connection,
+    user_id: &str,
+    items: &[TextTodo],
+    now: &str,
+) -> Result<Vec<String>, String> {
+    db.execute_batch("BEGIN TRANSACTION")
+        .map_err(|_| "数据库繁忙，请稍后重试".to_string())?;
+    let result = (|| {
+        let mut ids = Vec::with_capacity(items.len());
+        for todo in items {
+            let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+            let project_id = todo
+                .project
+                .as_deref()
+                .and_then(|p| projects::resolve(db, user_id, p))
+                .map(|(id, _)| id);
+            // A task finished before today can't have been created today
+            let created_at = todo
+                .created_on
+                .as_deref()
+                .or(todo.completed_on.as_deref())
+                .map(|d| format!("{}T00:00:00+00:00", d))
+                .unwrap_or_else(|| now.to_string());
+            let completed_at = todo.completed.then(|| {
+                todo.completed_on
+                    .as_deref()
+                    .map(|d| format!("{}T00:00:00+00:00", d))
+                    .unwrap_or_else(|| now.to_string())
+            });
+            let sort_order = ordering::append_key(db, user_id, &todo.tab, &todo.quadrant);
+            db.execute(
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, \
+                 assignee, tags, sort_order, created_at, updated_at, deleted, project_id) \
+                 VALUES (?1, ?2, ?3, '', ?4, ?5, ?6, ?7, ?8, ?9, '', ?10, ?11, ?12, ?13, 0, ?14)",
+                rusqlite::params![
+                    id,
+                    user_id,
+                    todo.text,
+                    todo.tab,
+                    todo.quadrant,
+                    if todo.completed { 100 } else { 0 },
+                    todo.completed as i32,
+                    completed_at,
+                    todo.due_date,
+                    serde_json::to_string(&todo.tags).unwrap_or_else(|_| "[]".into()),
+                    sort_order,
+                    created_at,
+                    now,
+                    project_id,
+                ],
+            )
+            .map_err(|e| {
+                eprintln!("[todo_text] import DB error: {}", e);
+                format!("第 {} 行导入失败", todo.line)
+            })?;
+            ids.push(id);
+        }
+        Ok(ids)
+    })();
+
+    match &result {
+        Ok(_) => db.execute_batch("COMMIT").ok(),
+        Err(_) => db.execute_batch("ROLLBACK").ok(),
+    };
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_todotxt() {
+        let items = parse(
+            TextFormat::TodoTxt,
+            "(B) 2026-01-05 Call mom +Family @phone_calls due:2026-01-20 tab:week\n\
+             \n\
+             x 2026-01-06 2026-01-01 File taxes @money pri:A\n\
+             plain task ratio:3:1\n",
+        );
+        assert_eq!(items.len(), 3);
+
+        let call = &items[0];
+        assert_eq!(call.line, 1);
+        assert_eq!(call.text, "Call mom");
+        assert_eq!(call.quadrant, "important-not-urgent");
+        assert_eq!(call.tab, "week");
+        assert_eq!(call.tags, vec!["phone calls".to_string()]);
+        assert_eq!(call.project.as_deref(), Some("Family"));
+        assert_eq!(call.due_date.as_deref(), Some("2026-01-20"));
+        assert_eq!(call.created_on.as_deref(), Some("2026-01-05"));
+
+        let taxes = &items[1];
+        assert!(taxes.completed);
+        assert_eq!(taxes.completed_on.as_deref(), Some("2026-01-06"));
+        assert_eq!(taxes.created_on.as_deref(), Some("2026-01-01"));
+        assert_eq!(taxes.quadrant, "important-urgent");
+
+        let plain = &items[2];
+        assert_eq!(plain.text, "plain task ratio:3:1");
+        assert_eq!(plain.quadrant, "not-important-not-urgent");
+        assert_eq!(plain.tab, "today");
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let items = parse(
+            TextFormat::Markdown,
+            "# Next 待办\n\n## 优先处理 (important-urgent)\n\n\
+             - [ ] Ship release #work\n\
+             - just a note\n\n\
+             ## 待分类\n\n\
+             * [x] Reply to Ann done:2026-02-01\n\
+             1. [ ] Book flights +Trip\n",
+        );
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].text, "Ship release");
+        assert_eq!(items[0].quadrant, "important-urgent");
+        assert_eq!(items[0].tags, vec!["work".to_string()]);
+        assert!(items[1].completed);
+        assert_eq!(items[1].quadrant, "not-important-urgent");
+        assert_eq!(items[1].completed_on.as_deref(), Some("2026-02-01"));
+        assert_eq!(items[2].project.as_deref(), Some("Trip"));
+        assert_eq!(items[2].line, 11);
+    }
+}
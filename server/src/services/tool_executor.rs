@@ -1,6 +1,14 @@
 use rusqlite::Connection;
 use serde_json::{json, Value};
 
+use crate::models::routine::RoutineSchedule;
+use crate::services::changelog::{self, ChangeSet};
+use crate::services::todo_filter::{self, TodoFilter};
+use crate::services::{
+    checklist, completion, dependencies, focus, history, ordering, projects, recurrence, reports,
+    routine_stats, search, templates, time, time_tracking,
+};
+
 /// Ensure collaboration tables exist (idempotent)
 fn ensure_collab_tables(db: &Connection) {
     db.execute_batch(
@@ -69,6 +77,10 @@ pub fn execute_tool(db: &Connection, user_id: &str, tool_name: &str, input: &Val
         "restore_todo" => tool_restore_todo(db, user_id, input),
         "query_todos" => tool_query_todos(db, user_id, input),
         "batch_update_todos" => tool_batch_update_todos(db, user_id, input),
+        "undo_todo_changes" => tool_undo_todo_changes(db, user_id, input),
+        "start_timer" => tool_start_timer(db, user_id, input),
+        "stop_timer" => tool_stop_timer(db, user_id),
+        "apply_template" => tool_apply_template(db, user_id, input),
         "create_routine" => tool_create_routine(db, user_id, input),
         "query_routines" => tool_query_routines(db, user_id, input),
         "update_routine" => tool_update_routine(db, user_id, input),
@@ -78,7 +90,9 @@ pub fn execute_tool(db: &Connection, user_id: &str, tool_name: &str, input: &Val
         "update_review" => tool_update_review(db, user_id, input),
         "delete_review" => tool_delete_review(db, user_id, input),
         "get_statistics" => tool_get_statistics(db, user_id, input),
+        "get_report" => tool_get_report(db, user_id, input),
         "get_current_datetime" => tool_get_current_datetime(),
+        "search" => tool_search(db, user_id, input),
         "create_english_scenario" => tool_create_english_scenario(db, user_id, input),
         "query_english_scenarios" => tool_query_english_scenarios(db, user_id, input),
         "update_english_scenario" => tool_update_english_scenario(db, user_id, input),
@@ -120,7 +134,10 @@ pub fn tool_definitions() -> Vec<Value> {
                     "due_date": {"type": "string", "description": "截止日期 YYYY-MM-DD"},
                     "assignee": {"type": "string", "description": "负责人"},
                     "tags": {"type": "array", "items": {"type": "string"}, "description": "标签"},
-                    "collaborator": {"type": "string", "description": "协作者用户ID（需为好友）"}
+                    "checklist": {"type": "array", "items": {"type": "string"}, "description": "子任务清单，每项一个步骤；有清单时进度由勾选情况自动计算"},
+                    "recurrence": {"type": "string", "description": "重复规则：daily / weekly / monthly / weekdays / every:N（每 N 天）；完成后自动生成下一次。传空字符串取消重复"},
+                    "collaborator": {"type": "string", "description": "协作者用户ID（需为好友）"},
+                    "project": {"type": "string", "description": "所属项目名称（需已存在）"}
                 },
                 "required": ["text"]
             }
@@ -137,7 +154,13 @@ pub fn tool_definitions() -> Vec<Value> {
                     "quadrant": {"type": "string", "enum": ["important-urgent", "important-not-urgent", "not-important-urgent", "not-important-not-urgent"]},
                     "progress": {"type": "integer", "minimum": 0, "maximum": 100},
                     "due_date": {"type": "string"},
-                    "completed": {"type": "boolean"}
+                    "completed": {"type": "boolean"},
+                    "recurrence": {"type": "string", "description": "重复规则：daily / weekly / monthly / weekdays / every:N（每 N 天）；完成后自动生成下一次。传空字符串取消重复"},
+                    "checklist_add": {"type": "array", "items": {"type": "string"}, "description": "追加的清单项"},
+                    "checklist_check": {"type": "array", "items": {"type": "string"}, "description": "勾选的清单项ID"},
+                    "checklist_uncheck": {"type": "array", "items": {"type": "string"}, "description": "取消勾选的清单项ID"},
+                    "checklist_remove": {"type": "array", "items": {"type": "string"}, "description": "删除的清单项ID"},
+                    "project": {"type": "string", "description": "移入的项目名称；传空字符串移出项目（仅任务创建者）"}
                 },
                 "required": ["id"]
             }
@@ -175,7 +198,10 @@ pub fn tool_definitions() -> Vec<Value> {
                     "completed": {"type": "boolean", "description": "按完成状态过滤"},
                     "keyword": {"type": "string", "description": "按关键词搜索标题"},
                     "assignee": {"type": "string", "description": "按负责人过滤"},
-                    "tag": {"type": "string", "description": "按标签过滤"}
+                    "tag": {"type": "string", "description": "按标签过滤"},
+                    "filter": {"type": "string", "description": "筛选表达式，空格分隔、同时满足。如 \"tag:work quadrant:important-* due<7d !completed assignee:Tom\"。支持 tag: quadrant: tab: assignee: project: due(< <= > >= :，值为 YYYY-MM-DD/today/7d/none) progress>=N，状态 completed/blocked/overdue/recurring，! 取反，其他词匹配标题"},
+                    "view": {"type": "string", "description": "已保存视图的名称，与 filter 同时生效"},
+                    "project": {"type": "string", "description": "按项目名称过滤"}
                 }
             }
         }),
@@ -204,13 +230,59 @@ pub fn tool_definitions() -> Vec<Value> {
                 "required": ["updates"]
             }
         }),
+        json!({
+            "name": "undo_todo_changes",
+            "description": "撤销之前对任务的修改。传入 update_todo 返回的 batch_id 或 batch_update_todos 返回的 batch_ids",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "batch_ids": {"type": "array", "items": {"type": "string"}, "description": "要撤销的修改批次 ID"}
+                },
+                "required": ["batch_ids"]
+            }
+        }),
+        json!({
+            "name": "start_timer",
+            "description": "开始为某个任务计时。同一时间只能有一个计时，已有计时时需先 stop_timer",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "todo_id": {"type": "string", "description": "任务ID"},
+                    "note": {"type": "string", "description": "备注（可选）"}
+                },
+                "required": ["todo_id"]
+            }
+        }),
+        json!({
+            "name": "stop_timer",
+            "description": "停止当前进行中的计时，返回本次时长和任务累计时长",
+            "input_schema": {
+                "type": "object",
+                "properties": {}
+            }
+        }),
+        json!({
+            "name": "apply_template",
+            "description": "按用户保存的任务模板批量创建任务。各任务截止日期 = 基准日期 + 模板中的偏移天数",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "template": {"type": "string", "description": "模板名称或ID"},
+                    "anchor_date": {"type": "string", "description": "基准日期 YYYY-MM-DD（可选，默认今天）"},
+                    "project": {"type": "string", "description": "放入已有项目（名称或ID，可选）"},
+                    "new_project": {"type": "string", "description": "为这批任务新建项目的名称（可选，与 project 二选一）"}
+                },
+                "required": ["template"]
+            }
+        }),
         json!({
             "name": "create_routine",
-            "description": "创建一个例行任务（每天重复）",
+            "description": "创建一个例行任务，默认每天重复，也可以只在每周固定几天、每周 N 次或隔天",
             "input_schema": {
                 "type": "object",
                 "properties": {
-                    "text": {"type": "string", "description": "例行任务内容"}
+                    "text": {"type": "string", "description": "例行任务内容"},
+                    "schedule": {"type": "string", "description": "日程：daily（每天，默认）、weekdays:1,3,5（每周一三五，1=周一）、weekly:3（每周任意 3 天）、every_other_day（隔天）"}
                 },
                 "required": ["text"]
             }
@@ -230,7 +302,7 @@ pub fn tool_definitions() -> Vec<Value> {
         }),
         json!({
             "name": "get_statistics",
-            "description": "获取用户的任务统计数据",
+            "description": "获取用户的任务统计数据，包括同期的专注（番茄钟）分钟数和次数",
             "input_schema": {
                 "type": "object",
                 "properties": {
@@ -239,6 +311,18 @@ pub fn tool_definitions() -> Vec<Value> {
                 "required": ["period"]
             }
         }),
+        json!({
+            "name": "get_report",
+            "description": "生成周报或月报：新建与完成的任务数、平均完成用时、逾期数、象限分布变化、日常任务和例行审视的完成率、专注时长",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "period": {"type": "string", "enum": ["week", "month"], "description": "报告周期"},
+                    "date": {"type": "string", "description": "周期内任意一天 YYYY-MM-DD，默认今天（上周/上月请传对应日期）"}
+                },
+                "required": ["period"]
+            }
+        }),
         json!({
             "name": "get_current_datetime",
             "description": "获取当前日期和时间",
@@ -247,6 +331,18 @@ pub fn tool_definitions() -> Vec<Value> {
                 "properties": {}
             }
         }),
+        json!({
+            "name": "search",
+            "description": "全文搜索任务、学习场景、记账备注/明细和差旅明细，按相关度返回",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "搜索关键词，多个词用空格分隔"},
+                    "module": {"type": "string", "enum": ["todo", "english", "expense", "trip"], "description": "只搜索某个模块，不传则全部搜索"}
+                },
+                "required": ["query"]
+            }
+        }),
         json!({
             "name": "create_english_scenario",
             "description": "创建一个学习场景（支持英语、编程、职场、生活等分类），创建后会自动生成学习内容",
@@ -321,7 +417,7 @@ pub fn tool_definitions() -> Vec<Value> {
         // ─── Routine tools ───
         json!({
             "name": "query_routines",
-            "description": "查询例行任务列表",
+            "description": "查询例行任务列表，含日程 schedule、今天是否需要做 due_today、当前连续天数 current_streak 和最长连续天数 longest_streak（休息日不中断连续）",
             "input_schema": {
                 "type": "object",
                 "properties": {
@@ -331,14 +427,15 @@ pub fn tool_definitions() -> Vec<Value> {
         }),
         json!({
             "name": "update_routine",
-            "description": "更新例行任务的文本",
+            "description": "更新例行任务的文本或日程",
             "input_schema": {
                 "type": "object",
                 "properties": {
                     "id": {"type": "string", "description": "例行任务ID"},
-                    "text": {"type": "string", "description": "新的文本内容"}
+                    "text": {"type": "string", "description": "新的文本内容"},
+                    "schedule": {"type": "string", "description": "日程：daily（每天，默认）、weekdays:1,3,5（每周一三五，1=周一）、weekly:3（每周任意 3 天）、every_other_day（隔天）"}
                 },
-                "required": ["id", "text"]
+                "required": ["id"]
             }
         }),
         json!({
@@ -640,34 +737,71 @@ fn tool_create_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
         .unwrap_or_default();
     let collaborator = input["collaborator"].as_str();
     let now = chrono::Utc::now().to_rfc3339();
+    let recurrence_rule =
+        match recurrence::normalize_rule(input["recurrence"].as_str().unwrap_or("")) {
+            Ok(r) => r,
+            Err(msg) => return json!({"error": msg}),
+        };
 
     if let Some(collab_id) = collaborator {
         if !check_friendship(db, user_id, collab_id) {
             return json!({"error": "协作者不是你的好友"});
         }
     }
+    let project = match input["project"].as_str().filter(|p| !p.trim().is_empty()) {
+        Some(key) => match projects::resolve(db, user_id, key) {
+            Some(p) => Some(p),
+            None => {
+                return json!({"error": format!("项目不存在: {}", key), "projects": project_names(db, user_id)})
+            }
+        },
+        None => None,
+    };
+    let project_id = project.as_ref().map(|(id, _)| id.clone());
 
     let is_collab = if collaborator.is_some() { 1 } else { 0 };
+    let sort_order = ordering::append_key(db, user_id, tab, quadrant);
 
     let result = db.execute(
-        "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, due_date, assignee, tags, sort_order, created_at, updated_at, is_collaborative) VALUES (?1, ?2, ?3, '', ?4, ?5, 0, 0, ?6, ?7, ?8, 0.0, ?9, ?10, ?11)",
-        rusqlite::params![id, user_id, text, tab, quadrant, due_date, assignee, serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into()), now, now, is_collab],
+        "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, due_date, assignee, tags, sort_order, created_at, updated_at, is_collaborative, recurrence, project_id) VALUES (?1, ?2, ?3, '', ?4, ?5, 0, 0, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        rusqlite::params![id, user_id, text, tab, quadrant, due_date, assignee, serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into()), sort_order, now, now, is_collab, recurrence_rule, project_id],
     );
 
     if let Some(collab_id) = collaborator {
         if result.is_ok() {
             let tc_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+            let collab_order = ordering::append_key(db, collab_id, tab, quadrant);
             db.execute(
-                "INSERT INTO todo_collaborators (id, todo_id, user_id, tab, quadrant, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6)",
-                rusqlite::params![tc_id, id, collab_id, tab, quadrant, now],
+                "INSERT INTO todo_collaborators (id, todo_id, user_id, tab, quadrant, sort_order, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'active', ?7)",
+                rusqlite::params![tc_id, id, collab_id, tab, quadrant, collab_order, now],
             ).ok();
         }
     }
 
+    let mut checklist_items = Vec::new();
+    if result.is_ok() {
+        if let Some(arr) = input["checklist"].as_array() {
+            for t in arr.iter().filter_map(|v| v.as_str()) {
+                if let Ok(item) = checklist::add_item(db, &id, t, &now) {
+                    checklist_items.push(item);
+                }
+            }
+        }
+    }
+
     match result {
         Ok(_) => {
             let mut resp =
                 json!({"success": true, "id": id, "text": text, "tab": tab, "quadrant": quadrant});
+            if !checklist_items.is_empty() {
+                resp["checklist"] = json!(checklist_items);
+            }
+            if let Some(rule) = &recurrence_rule {
+                resp["recurrence"] = json!(rule);
+            }
+            if let Some((_, name)) = &project {
+                resp["project"] = json!(name);
+            }
             if let Some(cid) = collaborator {
                 resp["collaborative"] = json!(true);
                 resp["collaborator_name"] = json!(get_user_display_name(db, cid));
@@ -708,6 +842,43 @@ fn tool_update_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
         return json!({"error": "Task not found"});
     }
 
+    // Edits made by the assistant are logged as one change set so they can be undone
+    let now = chrono::Utc::now().to_rfc3339();
+    let changes = ChangeSet::new(changelog::ASSISTANT, &now);
+
+    // Validate everything before the first write so a bad input changes nothing
+    let project = input["project"].as_str();
+    if let Some(key) = project {
+        if !is_owner {
+            return json!({"error": "只有任务创建者可以设置项目"});
+        }
+        let key = key.trim();
+        if !key.is_empty() && projects::resolve(db, user_id, key).is_none() {
+            return json!({
+                "error": format!("项目不存在: {}", key),
+                "projects": project_names(db, user_id)
+            });
+        }
+    }
+    let recurrence = match input["recurrence"].as_str().filter(|_| is_owner) {
+        Some(v) => match recurrence::normalize_rule(v) {
+            Ok(rule) => Some(rule),
+            Err(msg) => return json!({"error": msg}),
+        },
+        None => None,
+    };
+
+    // Projects belong to the owner; an empty name moves the todo out
+    let project_moved = match project {
+        Some(key) => match projects::assign_todo(db, id, user_id, Some(key), &now) {
+            Ok(_) => true,
+            Err(msg) => return json!({"error": msg, "projects": project_names(db, user_id)}),
+        },
+        None => false,
+    };
+    let checklist_changed = apply_checklist_ops(db, id, input, &changes);
+    let has_checklist = !checklist::load_items(db, id).is_empty();
+
     let mut sets = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
     let mut idx = 1;
@@ -733,9 +904,15 @@ fn tool_update_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
             params.push(Box::new(v.to_string()));
             idx += 1;
         }
+        if let Some(rule) = recurrence {
+            sets.push(format!("recurrence=?{}", idx));
+            params.push(Box::new(rule));
+            idx += 1;
+        }
     }
 
-    if let Some(v) = input["progress"].as_i64() {
+    // Progress of a todo with a checklist is derived from its items
+    if let Some(v) = input["progress"].as_i64().filter(|_| !has_checklist) {
         sets.push(format!("progress=?{}", idx));
         params.push(Box::new(v));
         idx += 1;
@@ -758,12 +935,17 @@ fn tool_update_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
     }
 
     if sets.is_empty() {
+        if checklist_changed {
+            return checklist_result(db, id);
+        }
+        if project_moved {
+            return json!({"success": true, "id": id});
+        }
         return json!({"success": true, "message": "Nothing to update"});
     }
 
-    let now = chrono::Utc::now().to_rfc3339();
     sets.push(format!("updated_at=?{}", idx));
-    params.push(Box::new(now));
+    params.push(Box::new(now.clone()));
     idx += 1;
 
     let sql = if is_owner {
@@ -783,11 +965,91 @@ fn tool_update_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
         s
     };
 
+    let before = changelog::snapshot(db, id).unwrap_or_default();
+    let was_completed: bool = db
+        .query_row("SELECT completed FROM todos WHERE id=?1", [id], |r| {
+            r.get(0)
+        })
+        .unwrap_or(false);
+
     let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-    match db.execute(&sql, param_refs.as_slice()) {
+    let mut result = match db.execute(&sql, param_refs.as_slice()) {
+        Ok(_) if checklist_changed => checklist_result(db, id),
         Ok(_) => json!({"success": true, "id": id}),
-        Err(e) => json!({"error": format!("Update failed: {}", e)}),
+        Err(e) => return json!({"error": format!("Update failed: {}", e)}),
+    };
+
+    if let Some(after) = changelog::snapshot(db, id) {
+        if changes.record_diff(db, id, &before, &after) > 0 {
+            result["batch_id"] = json!(changes.id);
+        }
+    }
+
+    let completed_now: bool = db
+        .query_row("SELECT completed FROM todos WHERE id=?1", [id], |r| {
+            r.get(0)
+        })
+        .unwrap_or(false);
+    if completed_now && !was_completed {
+        if let Some(next_id) = completion::on_completed(db, id, &chrono::Utc::now().to_rfc3339()) {
+            result["next_occurrence_id"] = json!(next_id);
+        }
+    }
+    result
+}
+
+/// Apply checklist_add/check/uncheck/remove from an update_todo call.
+/// Returns true when anything changed.
+fn apply_checklist_ops(db: &Connection, todo_id: &str, input: &Value, changes: &ChangeSet) -> bool {
+    let now = chrono::Utc::now().to_rfc3339();
+    let strs = |key: &str| -> Vec<String> {
+        input[key]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut changed = false;
+    for text in strs("checklist_add") {
+        changed |= checklist::add_item(db, todo_id, &text, &now).is_ok();
     }
+    for item_id in strs("checklist_check") {
+        changed |=
+            checklist::update_item(db, todo_id, &item_id, None, Some(true), &now).unwrap_or(false);
+    }
+    for item_id in strs("checklist_uncheck") {
+        changed |=
+            checklist::update_item(db, todo_id, &item_id, None, Some(false), &now).unwrap_or(false);
+    }
+    for item_id in strs("checklist_remove") {
+        changed |= checklist::remove_item(db, todo_id, &item_id, &now);
+    }
+
+    if changed {
+        checklist::sync_progress(db, todo_id, changes);
+    }
+    changed
+}
+
+fn checklist_result(db: &Connection, todo_id: &str) -> Value {
+    let (progress, completed): (i64, bool) = db
+        .query_row(
+            "SELECT progress, completed FROM todos WHERE id=?1",
+            [todo_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap_or((0, false));
+    json!({
+        "success": true,
+        "id": todo_id,
+        "progress": progress,
+        "completed": completed,
+        "checklist": checklist::load_items(db, todo_id)
+    })
 }
 
 fn tool_delete_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
@@ -853,6 +1115,30 @@ fn tool_restore_todo(db: &Connection, user_id: &str, input: &Value) -> Value {
 fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
     ensure_collab_tables(db);
 
+    let mut expression = String::new();
+    if let Some(view) = input["view"].as_str() {
+        match todo_filter::saved_view_filter(db, user_id, view) {
+            Some(f) => expression.push_str(&f),
+            None => return json!({"error": format!("视图不存在: {}", view)}),
+        }
+    }
+    if let Some(filter) = input["filter"].as_str() {
+        expression.push(' ');
+        expression.push_str(filter);
+    }
+    if let Some(project) = input["project"].as_str() {
+        match projects::resolve(db, user_id, project) {
+            Some((project_id, _)) => expression.push_str(&format!(" project:{}", project_id)),
+            None => {
+                return json!({"error": format!("项目不存在: {}", project), "projects": project_names(db, user_id)})
+            }
+        }
+    }
+    let filter = match TodoFilter::parse(&expression, time::today()) {
+        Ok(f) => f,
+        Err(msg) => return json!({"error": msg}),
+    };
+
     let mut conditions = vec!["user_id=?1".to_string(), "deleted=0".to_string()];
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(user_id.to_string())];
     let mut idx = 2;
@@ -887,9 +1173,13 @@ fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
         params.push(Box::new(format!("%\"{}\"", tag)));
         let _ = idx;
     }
+    let (filter_conditions, filter_params) =
+        filter.to_sql(&todo_filter::OWN_COLUMNS, params.len() + 1);
+    conditions.extend(filter_conditions);
+    params.extend(filter_params);
 
     let sql = format!(
-        "SELECT id, text, tab, quadrant, progress, completed, due_date, assignee, tags FROM todos WHERE {} ORDER BY sort_order ASC LIMIT 30",
+        "SELECT id, text, tab, quadrant, progress, completed, due_date, assignee, tags, recurrence, project_id FROM todos WHERE {} ORDER BY sort_order ASC LIMIT 30",
         conditions.join(" AND ")
     );
 
@@ -909,7 +1199,9 @@ fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
             "completed": row.get::<_, bool>(5)?,
             "due_date": row.get::<_, Option<String>>(6)?,
             "assignee": row.get::<_, String>(7)?,
-            "tags": row.get::<_, String>(8)?
+            "tags": row.get::<_, String>(8)?,
+            "recurrence": row.get::<_, Option<String>>(9)?,
+            "project_id": row.get::<_, Option<String>>(10)?
         }))
     }) {
         Ok(r) => r,
@@ -948,9 +1240,13 @@ fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
         collab_params.push(Box::new(format!("%{}%", keyword)));
         let _ = cidx;
     }
+    let (filter_conditions, filter_params) =
+        filter.to_sql(&todo_filter::COLLAB_COLUMNS, collab_params.len() + 1);
+    collab_conditions.extend(filter_conditions);
+    collab_params.extend(filter_params);
 
     let collab_sql = format!(
-        "SELECT t.id, t.text, tc.tab, tc.quadrant, t.progress, t.completed, t.due_date, t.assignee, t.tags
+        "SELECT t.id, t.text, tc.tab, tc.quadrant, t.progress, t.completed, t.due_date, t.assignee, t.tags, t.recurrence, t.project_id
          FROM todos t
          JOIN todo_collaborators tc ON t.id = tc.todo_id
          WHERE {} LIMIT 20",
@@ -971,6 +1267,8 @@ fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
                 "due_date": row.get::<_, Option<String>>(6)?,
                 "assignee": row.get::<_, String>(7)?,
                 "tags": row.get::<_, String>(8)?,
+                "recurrence": row.get::<_, Option<String>>(9)?,
+                "project_id": row.get::<_, Option<String>>(10)?,
                 "collaborative": true
             }))
         }) {
@@ -980,9 +1278,38 @@ fn tool_query_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
         }
     }
 
+    for item in &mut items {
+        let todo_id = item["id"].as_str().unwrap_or("").to_string();
+        let checklist = checklist::load_items(db, &todo_id);
+        if !checklist.is_empty() {
+            item["checklist"] = json!(checklist);
+        }
+        let (blocked_by, _) = dependencies::load_links(db, &todo_id);
+        if blocked_by.iter().any(|l| !l.completed) {
+            item["blocked_by"] = json!(blocked_by);
+        }
+        let project = item["project_id"]
+            .as_str()
+            .and_then(|p| projects::name_of(db, p));
+        if let Some(obj) = item.as_object_mut() {
+            obj.remove("project_id");
+        }
+        if let Some(name) = project {
+            item["project"] = json!(name);
+        }
+    }
+
     json!({"success": true, "count": items.len(), "items": items})
 }
 
+/// Names of the user's projects that are not archived, for error hints
+fn project_names(db: &Connection, user_id: &str) -> Vec<String> {
+    projects::list(db, user_id, None)
+        .into_iter()
+        .map(|p| p.name)
+        .collect()
+}
+
 fn tool_batch_update_todos(db: &Connection, user_id: &str, input: &Value) -> Value {
     let updates = match input["updates"].as_array() {
         Some(u) => u,
@@ -990,14 +1317,125 @@ fn tool_batch_update_todos(db: &Connection, user_id: &str, input: &Value) -> Val
     };
 
     let mut success_count = 0;
+    let mut batch_ids = Vec::new();
     for update in updates {
         let result = tool_update_todo(db, user_id, update);
         if result["success"].as_bool().unwrap_or(false) {
             success_count += 1;
         }
+        if let Some(batch_id) = result["batch_id"].as_str() {
+            batch_ids.push(batch_id.to_string());
+        }
+    }
+
+    json!({"success": true, "updated": success_count, "total": updates.len(), "batch_ids": batch_ids})
+}
+
+fn tool_undo_todo_changes(db: &Connection, user_id: &str, input: &Value) -> Value {
+    let batch_ids: Vec<&str> = input["batch_ids"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if batch_ids.is_empty() {
+        return json!({"error": "batch_ids is required"});
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let changes = ChangeSet::new(changelog::ASSISTANT, &now);
+    let mut reverted = 0;
+    let mut conflicts = Vec::new();
+    // Newest change sets first, so fields touched twice end at the oldest value
+    for batch_id in batch_ids.iter().rev() {
+        let result = history::revert_batch(db, batch_id, user_id, &changes);
+        reverted += result["reverted"].as_i64().unwrap_or(0);
+        if let Some(c) = result["conflicts"].as_array() {
+            conflicts.extend(c.iter().cloned());
+        }
+    }
+
+    json!({"success": true, "reverted": reverted, "conflicts": conflicts, "batch_id": changes.id})
+}
+
+fn tool_start_timer(db: &Connection, user_id: &str, input: &Value) -> Value {
+    let todo_id = match input["todo_id"].as_str() {
+        Some(id) if !id.is_empty() => id,
+        _ => return json!({"error": "todo_id is required"}),
+    };
+    match time_tracking::start(
+        db,
+        user_id,
+        todo_id,
+        input["note"].as_str(),
+        chrono::Utc::now(),
+    ) {
+        Ok(entry) => json!({
+            "success": true,
+            "entry_id": entry.id,
+            "todo_id": entry.todo_id,
+            "text": entry.todo_text,
+            "started_at": entry.started_at,
+        }),
+        Err(msg) => json!({"error": msg}),
     }
+}
 
-    json!({"success": true, "updated": success_count, "total": updates.len()})
+fn tool_stop_timer(db: &Connection, user_id: &str) -> Value {
+    let now = chrono::Utc::now();
+    match time_tracking::stop(db, user_id, now) {
+        Ok(entry) => json!({
+            "success": true,
+            "entry_id": entry.id,
+            "todo_id": entry.todo_id,
+            "text": entry.todo_text,
+            "minutes": entry.duration_secs / 60,
+            "total_minutes": time_tracking::total_secs(db, &entry.todo_id, now) / 60,
+        }),
+        Err(msg) => json!({"error": msg}),
+    }
+}
+
+fn tool_apply_template(db: &Connection, user_id: &str, input: &Value) -> Value {
+    let key = match input["template"].as_str() {
+        Some(k) if !k.trim().is_empty() => k,
+        _ => return json!({"error": "template is required"}),
+    };
+    let Some(template) = templates::find(db, user_id, key) else {
+        let names: Vec<String> = templates::list(db, user_id)
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        return json!({"error": format!("模板不存在: {}", key), "templates": names});
+    };
+    let anchor = match input["anchor_date"].as_str().filter(|d| !d.is_empty()) {
+        Some(d) => match chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return json!({"error": format!("Invalid anchor_date: {}", d)}),
+        },
+        None => time::today(),
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    match templates::apply(
+        db,
+        user_id,
+        &template,
+        anchor,
+        input["project"].as_str(),
+        input["new_project"].as_str(),
+        &now,
+    ) {
+        Ok(applied) => json!({
+            "success": true,
+            "template": template.name,
+            "anchor_date": anchor.format("%Y-%m-%d").to_string(),
+            "created": applied.todo_ids.len(),
+            "todo_ids": applied.todo_ids,
+            "project": applied.project_name,
+        }),
+        Err(msg) if msg.starts_with("项目不存在") => {
+            json!({"error": msg, "projects": project_names(db, user_id)})
+        }
+        Err(msg) => json!({"error": msg}),
+    }
 }
 
 fn tool_create_routine(db: &Connection, user_id: &str, input: &Value) -> Value {
@@ -1005,14 +1443,19 @@ fn tool_create_routine(db: &Connection, user_id: &str, input: &Value) -> Value {
         Some(t) if !t.is_empty() => t,
         _ => return json!({"error": "text is required"}),
     };
+    let schedule = match input["schedule"].as_str().map(RoutineSchedule::parse) {
+        None => RoutineSchedule::Daily,
+        Some(Some(s)) => s,
+        Some(None) => return json!({"error": "Invalid schedule"}),
+    };
     let id = uuid::Uuid::new_v4().to_string()[..8].to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
     match db.execute(
-        "INSERT INTO routines (id, user_id, text, completed_today, created_at) VALUES (?1, ?2, ?3, 0, ?4)",
-        rusqlite::params![id, user_id, text, now],
+        "INSERT INTO routines (id, user_id, text, completed_today, created_at, schedule) VALUES (?1, ?2, ?3, 0, ?4, ?5)",
+        rusqlite::params![id, user_id, text, now, schedule.as_string()],
     ) {
-        Ok(_) => json!({"success": true, "id": id, "text": text}),
+        Ok(_) => json!({"success": true, "id": id, "text": text, "schedule": schedule.label()}),
         Err(e) => json!({"error": format!("Failed to create routine: {}", e)}),
     }
 }
@@ -1085,16 +1528,47 @@ fn tool_get_statistics(db: &Connection, user_id: &str, input: &Value) -> Value {
         0
     };
 
+    // Focus sessions over the same period, counted in local days up to today
+    let today = time::today();
+    let focus_from = match period {
+        "today" => Some(today),
+        "week" => Some(today - chrono::Duration::days(6)),
+        "month" => Some(today - chrono::Duration::days(29)),
+        _ => None,
+    };
+    let focus_range = match focus_from {
+        Some(from) => time_tracking::day_range(Some(&from.to_string()), None).unwrap_or_default(),
+        // Whole history: every timestamp sorts between these
+        None => (String::new(), "~".to_string()),
+    };
+    let focus_stats = focus::stats(db, user_id, &focus_range, chrono::Utc::now());
+
     json!({
         "period": period,
         "total": total,
         "completed": completed,
         "pending": total - completed,
         "overdue": overdue,
-        "completion_rate": format!("{}%", completion_rate)
+        "completion_rate": format!("{}%", completion_rate),
+        "focus": {
+            "minutes": focus_stats.minutes,
+            "sessions": focus_stats.sessions,
+            "completed": focus_stats.completed,
+            "interrupted": focus_stats.interrupted,
+            "days": focus_stats.days,
+        }
     })
 }
 
+fn tool_get_report(db: &Connection, user_id: &str, input: &Value) -> Value {
+    let report = reports::resolve(input["period"].as_str(), input["date"].as_str())
+        .and_then(|(period, date)| reports::build(db, user_id, period, date, chrono::Utc::now()));
+    match report {
+        Ok(report) => json!({"success": true, "report": report}),
+        Err(msg) => json!({"error": msg}),
+    }
+}
+
 fn tool_get_current_datetime() -> Value {
     let now = chrono::Local::now();
     json!({
@@ -1105,6 +1579,29 @@ fn tool_get_current_datetime() -> Value {
     })
 }
 
+fn tool_search(db: &Connection, user_id: &str, input: &Value) -> Value {
+    let query = input["query"].as_str().unwrap_or("").trim();
+    if query.is_empty() {
+        return json!({"error": "query is required"});
+    }
+    if query.chars().count() > search::MAX_QUERY_CHARS {
+        return json!({"error": "query is too long"});
+    }
+    let modules: Vec<&str> = match input["module"].as_str() {
+        Some(m) if search::MODULES.contains(&m) => vec![m],
+        Some(m) => return json!({"error": format!("Unknown module: {}", m)}),
+        None => search::MODULES.to_vec(),
+    };
+
+    let results: Vec<Value> = modules
+        .into_iter()
+        .flat_map(|m| search::search_module(db, user_id, query, m, 10))
+        .map(|h| h.to_json(false))
+        .collect();
+
+    json!({"success": true, "count": results.len(), "results": results})
+}
+
 fn tool_create_english_scenario(db: &Connection, user_id: &str, input: &Value) -> Value {
     let title = match input["title"].as_str() {
         Some(t) if !t.is_empty() => t,
@@ -1282,7 +1779,19 @@ fn tool_query_routines(db: &Connection, user_id: &str, input: &Value) -> Value {
         Err(e) => return json!({"error": format!("Query failed: {}", e)}),
     };
 
-    let items: Vec<Value> = rows.flatten().collect();
+    let mut items: Vec<Value> = rows.flatten().collect();
+    let today = time::local_today();
+    for item in &mut items {
+        let id = item["id"].as_str().unwrap_or_default().to_string();
+        let Some(history) = routine_stats::History::load(db, &id, user_id) else {
+            continue;
+        };
+        let (current, longest) = history.streaks(today);
+        item["schedule"] = json!(history.schedule.label());
+        item["due_today"] = json!(history.is_due(today));
+        item["current_streak"] = json!(current);
+        item["longest_streak"] = json!(longest);
+    }
     let done = items
         .iter()
         .filter(|i| i["completed_today"].as_bool().unwrap_or(false))
@@ -1295,17 +1804,27 @@ fn tool_update_routine(db: &Connection, user_id: &str, input: &Value) -> Value {
         Some(i) => i,
         None => return json!({"error": "id is required"}),
     };
-    let text = match input["text"].as_str() {
-        Some(t) if !t.is_empty() => t,
-        _ => return json!({"error": "text is required"}),
+    let text = input["text"].as_str().filter(|t| !t.is_empty());
+    let schedule = match input["schedule"].as_str().map(RoutineSchedule::parse) {
+        None => None,
+        Some(Some(s)) => Some(s),
+        Some(None) => return json!({"error": "Invalid schedule"}),
     };
+    if text.is_none() && schedule.is_none() {
+        return json!({"error": "text or schedule is required"});
+    }
 
     match db.execute(
-        "UPDATE routines SET text=?1 WHERE id=?2 AND user_id=?3",
-        rusqlite::params![text, id, user_id],
+        "UPDATE routines SET text=COALESCE(?1, text), schedule=COALESCE(?2, schedule) WHERE id=?3 AND user_id=?4",
+        rusqlite::params![text, schedule.as_ref().map(|s| s.as_string()), id, user_id],
     ) {
         Ok(0) => json!({"error": "Routine not found or not owned by you"}),
-        Ok(_) => json!({"success": true, "id": id, "text": text}),
+        Ok(_) => json!({
+            "success": true,
+            "id": id,
+            "text": text,
+            "schedule": schedule.map(|s| s.label())
+        }),
         Err(e) => json!({"error": format!("Update failed: {}", e)}),
     }
 }
@@ -1332,7 +1851,7 @@ fn tool_query_reviews(db: &Connection, user_id: &str, input: &Value) -> Value {
     let keyword = input["keyword"].as_str();
     let frequency = input["frequency"].as_str();
 
-    let mut conditions = vec!["user_id=?1".to_string()];
+    let mut conditions = vec!["user_id=?1".to_string(), "deleted=0".to_string()];
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(user_id.to_string())];
     let mut idx = 1;
 
@@ -1426,7 +1945,7 @@ fn tool_update_review(db: &Connection, user_id: &str, input: &Value) -> Value {
     params.push(Box::new(now));
 
     let sql = format!(
-        "UPDATE reviews SET {} WHERE id=?1 AND user_id=?{}",
+        "UPDATE reviews SET {} WHERE id=?1 AND user_id=?{} AND deleted=0",
         sets.join(", "),
         idx + 1
     );
@@ -1448,8 +1967,8 @@ fn tool_delete_review(db: &Connection, user_id: &str, input: &Value) -> Value {
     };
 
     match db.execute(
-        "DELETE FROM reviews WHERE id=?1 AND user_id=?2",
-        rusqlite::params![id, user_id],
+        "UPDATE reviews SET deleted=1, deleted_at=?1, updated_at=?1 WHERE id=?2 AND user_id=?3 AND deleted=0",
+        rusqlite::params![chrono::Utc::now().to_rfc3339(), id, user_id],
     ) {
         Ok(0) => json!({"error": "Review not found or not owned by you"}),
         Ok(_) => json!({"success": true, "id": id}),
@@ -1494,7 +2013,7 @@ fn tool_create_expense(db: &Connection, user_id: &str, input: &Value) -> Value {
 }
 
 fn tool_query_expenses(db: &Connection, user_id: &str, input: &Value) -> Value {
-    let mut conditions = vec!["user_id=?1".to_string()];
+    let mut conditions = vec!["user_id=?1".to_string(), "deleted=0".to_string()];
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(user_id.to_string())];
     let mut idx = 1;
 
@@ -1604,7 +2123,7 @@ fn tool_update_expense(db: &Connection, user_id: &str, input: &Value) -> Value {
     params.push(Box::new(now));
 
     let sql = format!(
-        "UPDATE expense_entries SET {} WHERE id=?1 AND user_id=?{}",
+        "UPDATE expense_entries SET {} WHERE id=?1 AND user_id=?{} AND deleted=0",
         sets.join(", "),
         idx + 1
     );
@@ -1626,8 +2145,8 @@ fn tool_delete_expense(db: &Connection, user_id: &str, input: &Value) -> Value {
     };
 
     match db.execute(
-        "DELETE FROM expense_entries WHERE id=?1 AND user_id=?2",
-        rusqlite::params![id, user_id],
+        "UPDATE expense_entries SET deleted=1, deleted_at=?1, updated_at=?1 WHERE id=?2 AND user_id=?3 AND deleted=0",
+        rusqlite::params![chrono::Utc::now().to_rfc3339(), id, user_id],
     ) {
         Ok(0) => json!({"error": "Expense not found or not owned by you"}),
         Ok(_) => json!({"success": true, "id": id}),
@@ -1658,7 +2177,7 @@ fn tool_get_expense_summary(db: &Connection, user_id: &str, input: &Value) -> Va
     });
 
     if let Ok(mut stmt) = db.prepare(
-        "SELECT COALESCE(currency, 'CAD'), SUM(amount), COUNT(*) FROM expense_entries WHERE user_id=?1 AND date >= ?2 AND date <= ?3 GROUP BY COALESCE(currency, 'CAD')",
+        "SELECT COALESCE(currency, 'CAD'), SUM(amount), COUNT(*) FROM expense_entries WHERE user_id=?1 AND deleted=0 AND date >= ?2 AND date <= ?3 GROUP BY COALESCE(currency, 'CAD')",
     ) {
         let mut by_currency = json!({});
         if let Ok(rows) = stmt.query_map(
@@ -1683,7 +2202,7 @@ fn tool_get_expense_summary(db: &Connection, user_id: &str, input: &Value) -> Va
 
     // Top tags
     if let Ok(mut stmt) = db
-        .prepare("SELECT tags FROM expense_entries WHERE user_id=?1 AND date >= ?2 AND date <= ?3")
+        .prepare("SELECT tags FROM expense_entries WHERE user_id=?1 AND deleted=0 AND date >= ?2 AND date <= ?3")
     {
         let mut tag_totals: std::collections::HashMap<String, (f64, i64)> =
             std::collections::HashMap::new();
@@ -1695,7 +2214,7 @@ fn tool_get_expense_summary(db: &Connection, user_id: &str, input: &Value) -> Va
         }
         // Simpler: query with amount
         if let Ok(mut stmt2) = db.prepare(
-            "SELECT tags, amount FROM expense_entries WHERE user_id=?1 AND date >= ?2 AND date <= ?3",
+            "SELECT tags, amount FROM expense_entries WHERE user_id=?1 AND deleted=0 AND date >= ?2 AND date <= ?3",
         ) {
             if let Ok(rows) = stmt2.query_map(
                 rusqlite::params![user_id, date_from, date_to],
@@ -2022,13 +2541,13 @@ fn tool_query_trips(db: &Connection, user_id: &str, _input: &Value) -> Value {
                (SELECT COUNT(*) FROM trip_items WHERE trip_id = t.id),
                (SELECT COALESCE(SUM(amount), 0) FROM trip_items WHERE trip_id = t.id),
                1 as is_owner
-        FROM trips t WHERE t.user_id = ?1
+        FROM trips t WHERE t.user_id = ?1 AND t.deleted = 0
         UNION ALL
         SELECT t.id, t.title, t.destination, t.date_from, t.date_to, t.currency,
                (SELECT COUNT(*) FROM trip_items WHERE trip_id = t.id),
                (SELECT COALESCE(SUM(amount), 0) FROM trip_items WHERE trip_id = t.id),
                0 as is_owner
-        FROM trips t JOIN trip_collaborators tc ON tc.trip_id = t.id WHERE tc.user_id = ?1
+        FROM trips t JOIN trip_collaborators tc ON tc.trip_id = t.id WHERE tc.user_id = ?1 AND t.deleted = 0
         ORDER BY date_from DESC
     ";
 
@@ -2062,7 +2581,7 @@ fn tool_get_trip_detail(db: &Connection, user_id: &str, input: &Value) -> Value
     // Check access
     let is_owner: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
             rusqlite::params![id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2070,7 +2589,7 @@ fn tool_get_trip_detail(db: &Connection, user_id: &str, input: &Value) -> Value
         > 0;
     let is_collab: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trip_collaborators WHERE trip_id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trip_collaborators tc JOIN trips t ON t.id = tc.trip_id WHERE tc.trip_id=?1 AND tc.user_id=?2 AND t.deleted=0",
             rusqlite::params![id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2158,7 +2677,7 @@ fn tool_update_trip(db: &Connection, user_id: &str, input: &Value) -> Value {
 
     let owns: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
             rusqlite::params![id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2191,7 +2710,7 @@ fn tool_update_trip(db: &Connection, user_id: &str, input: &Value) -> Value {
     maybe!("currency", "currency");
 
     let sql = format!(
-        "UPDATE trips SET {} WHERE id=?{} AND user_id=?{}",
+        "UPDATE trips SET {} WHERE id=?{} AND user_id=?{} AND deleted=0",
         sets.join(","),
         idx,
         idx + 1
@@ -2213,8 +2732,8 @@ fn tool_delete_trip(db: &Connection, user_id: &str, input: &Value) -> Value {
         None => return json!({"error": "id is required"}),
     };
     match db.execute(
-        "DELETE FROM trips WHERE id=?1 AND user_id=?2",
-        rusqlite::params![id, user_id],
+        "UPDATE trips SET deleted=1, deleted_at=?1, updated_at=?1 WHERE id=?2 AND user_id=?3 AND deleted=0",
+        rusqlite::params![chrono::Utc::now().to_rfc3339(), id, user_id],
     ) {
         Ok(0) => json!({"error": "Trip not found or not owned by you"}),
         Ok(_) => json!({"success": true, "message": "行程已删除"}),
@@ -2231,7 +2750,7 @@ fn tool_create_trip_item(db: &Connection, user_id: &str, input: &Value) -> Value
     // Check access (owner or editor)
     let owns: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
             rusqlite::params![trip_id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2295,7 +2814,7 @@ fn tool_update_trip_item(db: &Connection, user_id: &str, input: &Value) -> Value
 
     let owns: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
             rusqlite::params![trip_id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2382,7 +2901,7 @@ fn tool_delete_trip_item(db: &Connection, user_id: &str, input: &Value) -> Value
 
     let owns: bool = db
         .query_row(
-            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+            "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
             rusqlite::params![trip_id, user_id],
             |r| r.get::<_, i64>(0),
         )
@@ -2406,7 +2925,7 @@ fn tool_get_trip_summary(db: &Connection, user_id: &str, input: &Value) -> Value
         // Single trip summary
         let owns: bool = db
             .query_row(
-                "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2",
+                "SELECT COUNT(*) FROM trips WHERE id=?1 AND user_id=?2 AND deleted=0",
                 rusqlite::params![tid, user_id],
                 |r| r.get::<_, i64>(0),
             )
@@ -2414,7 +2933,7 @@ fn tool_get_trip_summary(db: &Connection, user_id: &str, input: &Value) -> Value
             > 0;
         let is_collab: bool = db
             .query_row(
-                "SELECT COUNT(*) FROM trip_collaborators WHERE trip_id=?1 AND user_id=?2",
+                "SELECT COUNT(*) FROM trip_collaborators tc JOIN trips t ON t.id = tc.trip_id WHERE tc.trip_id=?1 AND tc.user_id=?2 AND t.deleted=0",
                 rusqlite::params![tid, user_id],
                 |r| r.get::<_, i64>(0),
             )
@@ -2460,14 +2979,14 @@ fn tool_get_trip_summary(db: &Connection, user_id: &str, input: &Value) -> Value
         // All trips summary
         let trip_count: i64 = db
             .query_row(
-                "SELECT COUNT(*) FROM trips WHERE user_id=?1",
+                "SELECT COUNT(*) FROM trips WHERE user_id=?1 AND deleted=0",
                 rusqlite::params![user_id],
                 |r| r.get(0),
             )
             .unwrap_or(0);
         let total: f64 = db
             .query_row(
-                "SELECT COALESCE(SUM(ti.amount), 0) FROM trip_items ti JOIN trips t ON t.id = ti.trip_id WHERE t.user_id=?1",
+                "SELECT COALESCE(SUM(ti.amount), 0) FROM trip_items ti JOIN trips t ON t.id = ti.trip_id WHERE t.user_id=?1 AND t.deleted=0",
                 rusqlite::params![user_id], |r| r.get(0),
             )
             .unwrap_or(0.0);
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+
+use crate::models::trash::TrashItem;
+use crate::services::attachments;
+
+/// Retention for users who never changed the setting
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+pub const MAX_RETENTION_DAYS: i64 = 365;
+
+pub const TYPES: [&str; 4] = ["todo", "expense", "trip", "review"];
+
+/// (table, title expression) for a trash item type
+fn table_of(item_type: &str) -> Option<(&'static str, &'static str)> {
+    match item_type {
+        "todo" => Some(("todos", "text")),
+        "expense" => Some((
+            "expense_entries",
+            "COALESCE(NULLIF(notes, ''), printf('%.2f', amount))",
+        )),
+        "trip" => Some(("trips", "title")),
+        "review" => Some(("reviews", "text")),
+        _ => None,
+    }
+}
+
+pub fn retention_days(db: &Connection, user_id: &str) -> i64 {
+    db.query_row(
+        "SELECT COALESCE(trash_retention_days, ?2) FROM user_settings WHERE user_id = ?1",
+        rusqlite::params![user_id, DEFAULT_RETENTION_DAYS],
+        |r| r.get(0),
+    )
+    .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// The user's trashed items, most recently deleted first
+pub fn list(db: &Connection, user_id: &str, item_type: Option<&str>) -> Vec<TrashItem> {
+    let retention = retention_days(db, user_id);
+    let mut items: Vec<TrashItem> = Vec::new();
+    for kind in TYPES {
+        if item_type.is_some_and(|t| t != kind) {
+            continue;
+        }
+        let (table, title) = table_of(kind).unwrap_or_default();
+        let rows: Vec<TrashItem> = db
+            .prepare(&format!(
+                "SELECT id, {}, COALESCE(deleted_at, updated_at) FROM {} WHERE user_id = ?1 AND deleted = 1",
+                title, table
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([user_id], |r| {
+                    let deleted_at: String = r.get(2)?;
+                    let purge_at = parse_time(&deleted_at)
+                        .filter(|_| retention > 0)
+                        .map(|t| (t + Duration::days(retention)).to_rfc3339());
+                    Ok(TrashItem {
+                        item_type: kind.to_string(),
+                        id: r.get(0)?,
+                        title: r.get(1)?,
+                        deleted_at,
+                        purge_at,
+                    })
+                })
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            })
+            .unwrap_or_default();
+        items.extend(rows);
+    }
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+/// Bring one of the user's trashed items back
+pub fn restore(db: &Connection, user_id: &str, item_type: &str, id: &str, now: &str) -> bool {
+    let Some((table, _)) = table_of(item_type) else {
+        return false;
+    };
+    db.execute(
+        &format!(
+            "UPDATE {} SET deleted = 0, deleted_at = NULL, updated_at = ?1 \
+             WHERE id = ?2 AND user_id = ?3 AND deleted = 1",
+            table
+        ),
+        rusqlite::params![now, id, user_id],
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+/// Permanently delete rows of one type, removing their files from disk.
+/// Child rows go with them through ON DELETE CASCADE.
+fn purge(db: &Connection, item_type: &str, ids: &[String]) -> usize {
+    let Some((table, _)) = table_of(item_type) else {
+        return 0;
+    };
+    let mut purged = 0;
+    for id in ids {
+        let files: Vec<String> = match item_type {
+            "expense" => db
+                .prepare("SELECT storage_path FROM expense_photos WHERE entry_id = ?1")
+                .and_then(|mut stmt| {
+                    stmt.query_map([id], |r| r.get(0))
+                        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                })
+                .unwrap_or_default(),
+            "trip" => db
+                .prepare(
+                    "SELECT tp.storage_path FROM trip_photos tp \
+                     JOIN trip_items ti ON ti.id = tp.item_id WHERE ti.trip_id = ?1",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_map([id], |r| r.get(0))
+                        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        if item_type == "todo" {
+            attachments::remove_files(db, id);
+        }
+        match db.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [id]) {
+            Ok(n) if n > 0 => {
+                for path in files {
+                    std::fs::remove_file(&path).ok();
+                }
+                purged += 1;
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[trash] purge {} {} failed: {}", item_type, id, e),
+        }
+    }
+    purged
+}
+
+/// Permanently delete everything in the user's trash, or only one type.
+/// Returns how many items were removed.
+pub fn empty(db: &Connection, user_id: &str, item_type: Option<&str>) -> usize {
+    let mut purged = 0;
+    for kind in TYPES {
+        if item_type.is_some_and(|t| t != kind) {
+            continue;
+        }
+        let ids: Vec<String> = list(db, user_id, Some(kind))
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        purged += purge(db, kind, &ids);
+    }
+    purged
+}
+
+/// Purge trashed items older than their owner's retention. Called from the
+/// periodic cleanup task.
+pub fn purge_expired(db: &Connection, now: DateTime<Utc>) -> usize {
+    let mut purged = 0;
+    for kind in TYPES {
+        let (table, _) = table_of(kind).unwrap_or_default();
+        let expired: Vec<String> = db
+            .prepare(&format!(
+                "SELECT x.id, COALESCE(x.deleted_at, x.updated_at), COALESCE(s.trash_retention_days, ?1) \
+                 FROM {} x LEFT JOIN user_settings s ON s.user_id = x.user_id WHERE x.deleted = 1",
+                table
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([DEFAULT_RETENTION_DAYS], |r| {
+                    Ok((
+                        r.get::<_, String>(0)?,
+                        r.get::<_, String>(1)?,
+                        r.get::<_, i64>(2)?,
+                    ))
+                })
+                .map(|rows| rows.filter_map(|r| r.ok()).collect::<Vec<_>>())
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, deleted_at, days)| {
+                *days > 0
+                    && parse_time(deleted_at).is_some_and(|t| t + Duration::days(*days) <= now)
+            })
+            .map(|(id, _, _)| id)
+            .collect();
+        purged += purge(db, kind, &expired);
+    }
+    if purged > 0 {
+        println!("[trash] purged {} expired item(s)", purged);
+    }
+    purged
+}
@@ -322,6 +322,3743 @@ async fn test_user_isolation() {
     assert_eq!(body["items"].as_array().unwrap().len(), 0);
 }
 
+#[tokio::test]
+async fn test_checklist_drives_progress() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "kate", "Kate1234");
+
+    // Create with two checklist items
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({
+                "text": "Pack",
+                "checklist": ["Passport", "Charger"]
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+    let items = body["item"]["checklist"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 2);
+
+    // Check the first item → 50%
+    let app = build_app(state.clone());
+    let req = Request::put(format!(
+        "/api/todos/{}/checklist/{}",
+        todo_id,
+        items[0]["id"].as_str().unwrap()
+    ))
+    .header("content-type", "application/json")
+    .header("cookie", auth_cookie(&token))
+    .body(Body::from(
+        serde_json::to_string(&serde_json::json!({ "checked": true })).unwrap(),
+    ))
+    .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["progress"], 50);
+    assert_eq!(body["item"]["completed"], false);
+
+    // Check the second item → completed
+    let app = build_app(state.clone());
+    let req = Request::put(format!(
+        "/api/todos/{}/checklist/{}",
+        todo_id,
+        items[1]["id"].as_str().unwrap()
+    ))
+    .header("content-type", "application/json")
+    .header("cookie", auth_cookie(&token))
+    .body(Body::from(
+        serde_json::to_string(&serde_json::json!({ "checked": true })).unwrap(),
+    ))
+    .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["progress"], 100);
+    assert_eq!(body["item"]["completed"], true);
+    assert!(body["item"]["changelog"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["field"] == "checklist"));
+
+    // Batch progress leaves a checklist's derived progress alone, and stamps
+    // completion on a plain todo
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(r#"{"text":"Plain"}"#))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let plain_id = body["item"]["id"].as_str().unwrap().to_string();
+    let app = build_app(state.clone());
+    let req = Request::put("/api/todos/batch")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::json!([
+                { "id": todo_id, "progress": 0 },
+                { "id": plain_id, "progress": 100 }
+            ])
+            .to_string(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    for id in [&todo_id, &plain_id] {
+        let app = build_app(state.clone());
+        let req = Request::get(format!("/api/todos/{}", id))
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(app, req).await;
+        assert_eq!(body["item"]["progress"], 100);
+        assert_eq!(body["item"]["completed"], true);
+        assert!(body["item"]["completed_at"].is_string());
+    }
+
+    // Another user cannot touch the checklist
+    let (_, other) = create_test_user(&state, "leo", "Leoo1234");
+    let app = build_app(state);
+    let req = Request::post(format!("/api/todos/{}/checklist", todo_id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&other))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "text": "Sneaky" })).unwrap(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_recurring_todo_spawns_next() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "mia", "Miaa1234");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({
+                "text": "Take out bins",
+                "due_date": "2099-01-05",
+                "recurrence": "weekly"
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["recurrence"], "weekly");
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    // Complete, reopen and complete again: only one next occurrence
+    for completed in [true, false, true] {
+        let app = build_app(state.clone());
+        let req = Request::put(format!("/api/todos/{}", todo_id))
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({ "completed": completed })).unwrap(),
+            ))
+            .unwrap();
+        let (status, _) = send(app, req).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let app = build_app(state);
+    let req = Request::get("/api/todos")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    let next = items
+        .iter()
+        .find(|t| t["recurrence_prev_id"] == todo_id.as_str())
+        .expect("next occurrence");
+    assert_eq!(next["due_date"], "2099-01-12");
+    assert_eq!(next["completed"], false);
+    assert_eq!(next["recurrence"], "weekly");
+}
+
+#[tokio::test]
+async fn test_monthly_recurrence_keeps_day_of_month() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "mona", "Mona1234");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::json!({
+                "text": "Pay rent",
+                "due_date": "2099-01-31",
+                "recurrence": "monthly"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let mut todo_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    // Jan 31 → Feb 28 → Mar 31, not Mar 28
+    for expected in ["2099-02-28", "2099-03-31"] {
+        let app = build_app(state.clone());
+        let req = Request::put(format!("/api/todos/{}", todo_id))
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(r#"{"completed":true}"#))
+            .unwrap();
+        let (status, _) = send(app, req).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let app = build_app(state.clone());
+        let req = Request::get("/api/todos?filter=!completed")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(app, req).await;
+        let next = &body["items"][0];
+        assert_eq!(next["recurrence_prev_id"], todo_id.as_str());
+        assert_eq!(next["due_date"], expected);
+        todo_id = next["id"].as_str().unwrap().to_string();
+    }
+}
+
+#[tokio::test]
+async fn test_revert_past_recurring_completion() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "rita", "Rita1234");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({
+                "text": "Water plants",
+                "due_date": "2099-01-05",
+                "recurrence": "weekly"
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let id = body["item"]["id"].as_str().unwrap().to_string();
+    let before = chrono::Utc::now().to_rfc3339();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(r#"{"completed":true}"#))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let fields: Vec<&str> = body["item"]["changelog"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["field"].as_str())
+        .collect();
+    assert!(fields.contains(&"recurrence_spawn"));
+    assert!(!fields.contains(&"recurrence"));
+
+    // Spawning the next occurrence doesn't make the history incomplete
+    let app = build_app(state.clone());
+    let req = Request::get(format!(
+        "/api/todos/{}/history?at={}",
+        id,
+        before.replace('+', "%2B")
+    ))
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["complete"], true);
+    assert_eq!(body["item"]["completed"], false);
+    assert_eq!(body["item"]["recurrence"], "weekly");
+
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/todos/{}/revert", id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(serde_json::json!({ "at": before }).to_string()))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["completed"], false);
+    assert_eq!(body["item"]["recurrence"], "weekly");
+}
+
+#[tokio::test]
+async fn test_tool_update_todo_validates_before_writing() {
+    use next_server::services::tool_executor::execute_tool;
+
+    let state = test_state();
+    let (user_id, _) = create_test_user(&state, "olga", "Olga1234");
+    let db = state.db.lock();
+    let result = execute_tool(
+        &db,
+        &user_id,
+        "create_todo",
+        &serde_json::json!({"text": "Pack"}),
+    );
+    let id = result["id"].as_str().unwrap().to_string();
+    let checklist_count = || -> i64 {
+        db.query_row(
+            "SELECT COUNT(*) FROM todo_checklist_items WHERE todo_id = ?1",
+            [&id],
+            |r| r.get(0),
+        )
+        .unwrap()
+    };
+
+    for bad in [
+        serde_json::json!({"id": id, "checklist_add": ["Tent"], "recurrence": "fortnightly"}),
+        serde_json::json!({"id": id, "checklist_add": ["Tent"], "project": "Nowhere"}),
+    ] {
+        let result = execute_tool(&db, &user_id, "update_todo", &bad);
+        assert!(result["error"].is_string());
+        assert_eq!(checklist_count(), 0);
+    }
+
+    let result = execute_tool(
+        &db,
+        &user_id,
+        "update_todo",
+        &serde_json::json!({"id": id, "checklist_add": ["Tent"], "recurrence": "weekly"}),
+    );
+    assert!(result["error"].is_null());
+    assert_eq!(checklist_count(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_recurrence_rejected() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "nick", "Nick1234");
+
+    let app = build_app(state);
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({
+                "text": "Odd",
+                "recurrence": "fortnightly"
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_todo_dependencies() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "nora", "Nora1234");
+
+    let mut ids = Vec::new();
+    for text in ["Design", "Build"] {
+        let app = build_app(state.clone());
+        let req = Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({ "text": text })).unwrap(),
+            ))
+            .unwrap();
+        let (_, body) = send(app, req).await;
+        ids.push(body["item"]["id"].as_str().unwrap().to_string());
+    }
+    let (design, build) = (&ids[0], &ids[1]);
+
+    // Build is blocked by Design
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/todos/{}/dependencies", build))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "blocker_id": design })).unwrap(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["blocked"], true);
+    assert_eq!(body["item"]["blocked_by"][0]["id"], design.as_str());
+
+    // The reverse edge would form a cycle
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/todos/{}/dependencies", design))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "blocker_id": build })).unwrap(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos/{}", design))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["blocking"][0]["id"], build.as_str());
+
+    // Completing the blocker unblocks Build and notifies its owner
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", design))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "completed": true })).unwrap(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos/{}", build))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["blocked"], false);
+
+    let app = build_app(state);
+    let req = Request::get("/api/notifications/unread")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert!(items
+        .iter()
+        .any(|n| n["type"] == "todo_unblocked" && n["todo_id"] == build.as_str()));
+}
+
+#[tokio::test]
+async fn test_todo_filter_and_saved_views() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "olive", "Olive1234");
+
+    for (text, quadrant, tags, assignee) in [
+        ("Ship release", "important-urgent", vec!["work"], "Tom"),
+        ("Plan offsite", "important-not-urgent", vec!["work"], "Ann"),
+        ("Buy flowers", "important-urgent", vec!["home"], "Tom"),
+    ] {
+        let app = build_app(state.clone());
+        let req = Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({
+                    "text": text,
+                    "quadrant": quadrant,
+                    "tags": tags,
+                    "assignee": assignee,
+                    "due_date": "2000-01-01"
+                }))
+                .unwrap(),
+            ))
+            .unwrap();
+        send(app, req).await;
+    }
+
+    let app = build_app(state.clone());
+    let req = Request::get(
+        "/api/todos?filter=tag:work%20quadrant:important-*%20assignee:tom%20!completed%20overdue",
+    )
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["text"], "Ship release");
+
+    let app = build_app(state.clone());
+    let req = Request::get("/api/todos?filter=color:red")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Saved view, combined with an ad-hoc filter
+    let app = build_app(state.clone());
+    let req = Request::post("/api/views")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({
+                "name": "Urgent",
+                "filter": "quadrant:important-urgent"
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let view_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos?view={}&filter=tag:home", view_id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["text"], "Buy flowers");
+
+    let app = build_app(state);
+    let req = Request::post("/api/views")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "name": "Bad", "filter": "due<soon" }))
+                .unwrap(),
+        ))
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_todo_history_and_revert() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "paula", "Paula1234");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "text": "Draft", "tags": ["a"] })).unwrap(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let id = body["item"]["id"].as_str().unwrap().to_string();
+
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "text": "Final", "tags": ["b"] })).unwrap(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let first_edit = body["item"]["changelog"][0]["id"].as_i64().unwrap();
+    let before_second_edit = chrono::Utc::now().to_rfc3339();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "text": "Oops", "progress": 40 })).unwrap(),
+        ))
+        .unwrap();
+    send(app, req).await;
+
+    // Point-in-time view between the two edits
+    let app = build_app(state.clone());
+    let req = Request::get(format!(
+        "/api/todos/{}/history?at={}",
+        id,
+        before_second_edit.replace('+', "%2B")
+    ))
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["text"], "Final");
+    assert_eq!(body["item"]["progress"], 0);
+    assert_eq!(body["item"]["tags"], serde_json::json!(["b"]));
+    assert_eq!(body["complete"], true);
+
+    // Revert to the first edit
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/todos/{}/revert", id))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!({ "entry_id": first_edit })).unwrap(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["text"], "Final");
+    assert_eq!(body["item"]["progress"], 0);
+
+    // Batch updates can be undone as a unit
+    let app = build_app(state.clone());
+    let req = Request::put("/api/todos/batch")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(
+            serde_json::to_string(&serde_json::json!([{ "id": id, "quadrant": "important-urgent", "completed": true }]))
+                .unwrap(),
+        ))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/todos/batch/{}/revert", batch_id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["reverted"], 2);
+
+    let app = build_app(state);
+    let req = Request::get(format!("/api/todos/{}", id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["completed"], false);
+    assert_eq!(body["item"]["quadrant"], "not-important-not-urgent");
+}
+
+#[tokio::test]
+async fn test_collaborator_revert_keeps_owner_placement() {
+    let state = test_state();
+    let (_, owner_token) = create_test_user(&state, "quinn", "Quinn1234");
+    let (collab_id, collab_token) = create_test_user(&state, "ravi", "Ravi12345");
+
+    let request = |method: &str, uri: String, token: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        request(
+            "POST",
+            "/api/todos".into(),
+            &owner_token,
+            serde_json::json!({"text": "Plan trip"}),
+        ),
+    )
+    .await;
+    let id = body["item"]["id"].as_str().unwrap().to_string();
+    state
+        .db
+        .lock()
+        .execute(
+            "INSERT INTO todo_collaborators (id, todo_id, user_id, created_at) VALUES ('tc1', ?1, ?2, '2026-01-01T00:00:00Z')",
+            rusqlite::params![id, collab_id],
+        )
+        .unwrap();
+    let before = chrono::Utc::now().to_rfc3339();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        request(
+            "PUT",
+            format!("/api/todos/{}", id),
+            &owner_token,
+            serde_json::json!({"text": "Plan the trip", "quadrant": "important-urgent"}),
+        ),
+    )
+    .await;
+    assert_eq!(body["item"]["quadrant"], "important-urgent");
+
+    // The collaborator's revert undoes the text but not where the owner filed it
+    let (status, _) = send(
+        build_app(state.clone()),
+        request(
+            "POST",
+            format!("/api/todos/{}/revert", id),
+            &collab_token,
+            serde_json::json!({ "at": before }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(
+        build_app(state.clone()),
+        request(
+            "GET",
+            format!("/api/todos/{}", id),
+            &owner_token,
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(body["item"]["text"], "Plan trip");
+    assert_eq!(body["item"]["quadrant"], "important-urgent");
+
+    // Same for undoing the owner's batch
+    let (_, body) = send(
+        build_app(state.clone()),
+        request(
+            "PUT",
+            "/api/todos/batch".into(),
+            &owner_token,
+            serde_json::json!([{ "id": id, "quadrant": "not-important-urgent" }]),
+        ),
+    )
+    .await;
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        build_app(state.clone()),
+        request(
+            "POST",
+            format!("/api/todos/batch/{}/revert", batch_id),
+            &collab_token,
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = send(
+        build_app(state),
+        request(
+            "GET",
+            format!("/api/todos/{}", id),
+            &owner_token,
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(body["item"]["quadrant"], "not-important-urgent");
+}
+
+// ──────────────────── Search ────────────────────
+
+#[tokio::test]
+async fn test_todo_pagination() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "paula", "Paula1234");
+
+    for text in ["Echo", "Alpha", "Delta", "Bravo", "Charlie"] {
+        let app = build_app(state.clone());
+        let req = Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({ "text": text })).unwrap(),
+            ))
+            .unwrap();
+        send(app, req).await;
+    }
+
+    // Walk the pages with the returned cursor
+    let mut texts = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut uri = "/api/todos?sort=text&limit=2".to_string();
+        if let Some(c) = &cursor {
+            uri.push_str(&format!("&cursor={}", c));
+        }
+        let app = build_app(state.clone());
+        let req = Request::get(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["page"]["total"], 5);
+        assert_eq!(body["page"]["limit"], 2);
+        assert!(body["items"].as_array().unwrap().len() <= 2);
+        for item in body["items"].as_array().unwrap() {
+            texts.push(item["text"].as_str().unwrap().to_string());
+        }
+        match body["page"]["next_cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(texts, ["Alpha", "Bravo", "Charlie", "Delta", "Echo"]);
+
+    // Descending order, and the unpaged opt-in
+    let app = build_app(state.clone());
+    let req = Request::get("/api/todos?sort=text&order=desc&limit=1")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["items"][0]["text"], "Echo");
+
+    let app = build_app(state.clone());
+    let req = Request::get("/api/todos?all=1&limit=1")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 5);
+    assert!(body["page"]["next_cursor"].is_null());
+
+    let app = build_app(state.clone());
+    let req = Request::get("/api/todos?sort=color")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Cursors from another sort are rejected
+    let app = build_app(state.clone());
+    let req = Request::get(format!(
+        "/api/todos?sort=created&cursor={}",
+        cursor.unwrap()
+    ))
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_pagination_merges_shared_todos_and_filters_in_sql() {
+    let state = test_state();
+    let (_, owner_token) = create_test_user(&state, "olga", "Olga1234");
+    let (reader_id, reader_token) = create_test_user(&state, "rita", "Rita1234");
+
+    let post = |token: &str, uri: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    for (i, text) in ["Bravo", "Delta"].iter().enumerate() {
+        let req = post(
+            &owner_token,
+            "/api/todos",
+            serde_json::json!({ "text": text }),
+        );
+        let (_, body) = send(build_app(state.clone()), req).await;
+        state
+            .db
+            .lock()
+            .execute(
+                "INSERT INTO todo_collaborators (id, todo_id, user_id, created_at) VALUES (?1, ?2, ?3, '2026-01-01T00:00:00Z')",
+                rusqlite::params![format!("tc{}", i), body["item"]["id"].as_str().unwrap(), reader_id],
+            )
+            .unwrap();
+    }
+    for text in ["Alpha", "Charlie", "Echo"] {
+        let req = post(
+            &reader_token,
+            "/api/todos",
+            serde_json::json!({ "text": text }),
+        );
+        send(build_app(state.clone()), req).await;
+    }
+
+    // Own and shared todos interleave across pages
+    let mut texts = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut uri = "/api/todos?sort=text&limit=2".to_string();
+        if let Some(c) = &cursor {
+            uri.push_str(&format!("&cursor={}", c));
+        }
+        let req = Request::get(uri)
+            .header("cookie", auth_cookie(&reader_token))
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(build_app(state.clone()), req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["page"]["total"], 5);
+        for item in body["items"].as_array().unwrap() {
+            texts.push(item["text"].as_str().unwrap().to_string());
+        }
+        match body["page"]["next_cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(texts, ["Alpha", "Bravo", "Charlie", "Delta", "Echo"]);
+
+    // The tag filter counts toward the page total
+    for (date, tags) in [
+        ("2026-10-01", serde_json::json!(["lunch"])),
+        ("2026-10-02", serde_json::json!(["taxi"])),
+        ("2026-10-03", serde_json::json!(["lunch", "work"])),
+    ] {
+        let req = post(
+            &reader_token,
+            "/api/expenses",
+            serde_json::json!({ "amount": 10.0, "date": date, "tags": tags }),
+        );
+        let (status, _) = send(build_app(state.clone()), req).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let req = Request::get("/api/expenses?tags=lunch,work&limit=1")
+        .header("cookie", auth_cookie(&reader_token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["page"]["total"], 2);
+    assert_eq!(body["entries"][0]["date"], "2026-10-03");
+    let req = Request::get(format!(
+        "/api/expenses?tags=lunch,work&limit=1&cursor={}",
+        body["page"]["next_cursor"].as_str().unwrap()
+    ))
+    .header("cookie", auth_cookie(&reader_token))
+    .body(Body::empty())
+    .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(body["entries"][0]["date"], "2026-10-01");
+    assert!(body["page"]["next_cursor"].is_null());
+
+    // Bad page parameters come back with a message on every list
+    let req = Request::get("/api/reminders?sort=color")
+        .header("cookie", auth_cookie(&reader_token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["success"], false);
+    assert!(body["message"].as_str().unwrap().contains("color"));
+}
+
+#[tokio::test]
+async fn test_horizon_rollover() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "rolf", "Rolf1234");
+    let (_, other_token) = create_test_user(&state, "rhea", "Rhea1234");
+    let today = next_server::services::time::today();
+    let yesterday = (today - chrono::Duration::days(1)).to_string();
+    let far = (today + chrono::Duration::days(90)).to_string();
+
+    let app = build_app(state.clone());
+    let req = Request::put("/api/settings")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(r#"{"escalate_overdue":true}"#))
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["settings"]["auto_rollover"], true);
+    assert_eq!(body["settings"]["escalate_overdue"], true);
+
+    let app = build_app(state.clone());
+    let req = Request::put("/api/settings")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&other_token))
+        .body(Body::from(r#"{"auto_rollover":false}"#))
+        .unwrap();
+    send(app, req).await;
+
+    let mut ids = Vec::new();
+    for (tok, due, tab) in [
+        (&token, today.to_string(), "month"),
+        (&token, yesterday.clone(), "week"),
+        (&token, far, "today"),
+        (&other_token, yesterday, "month"),
+    ] {
+        let app = build_app(state.clone());
+        let req = Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(tok))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({
+                    "text": format!("Due {}", due),
+                    "tab": tab,
+                    "quadrant": "important-not-urgent",
+                    "due_date": due
+                }))
+                .unwrap(),
+            ))
+            .unwrap();
+        let (_, body) = send(app, req).await;
+        ids.push(body["item"]["id"].as_str().unwrap().to_string());
+    }
+
+    let moved = next_server::services::rollover::run_once(&state.db.lock());
+    assert_eq!(moved, 2);
+
+    let get = |id: &str, tok: &str| {
+        Request::get(format!("/api/todos/{}", id))
+            .header("cookie", auth_cookie(tok))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (_, body) = send(build_app(state.clone()), get(&ids[0], &token)).await;
+    assert_eq!(body["item"]["tab"], "today");
+    assert_eq!(body["item"]["quadrant"], "important-not-urgent");
+    assert_eq!(body["item"]["changelog"][0]["changed_by"], "system");
+
+    // Overdue: pulled to today and escalated
+    let (_, body) = send(build_app(state.clone()), get(&ids[1], &token)).await;
+    assert_eq!(body["item"]["tab"], "today");
+    assert_eq!(body["item"]["quadrant"], "important-urgent");
+
+    // Far-off todos are never pushed out; opted-out users are left alone
+    let (_, body) = send(build_app(state.clone()), get(&ids[2], &token)).await;
+    assert_eq!(body["item"]["tab"], "today");
+    let (_, body) = send(build_app(state.clone()), get(&ids[3], &other_token)).await;
+    assert_eq!(body["item"]["tab"], "month");
+
+    // Moving a todo back by hand sticks
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", ids[0]))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(r#"{"tab":"month"}"#))
+        .unwrap();
+    send(app, req).await;
+    assert_eq!(
+        next_server::services::rollover::run_once(&state.db.lock()),
+        0
+    );
+
+    // ...even once the job's changelog entry has been pruned away
+    for i in 0..60 {
+        let app = build_app(state.clone());
+        let req = Request::put(format!("/api/todos/{}", ids[0]))
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::json!({ "text": format!("Due soon {}", i) }).to_string(),
+            ))
+            .unwrap();
+        send(app, req).await;
+    }
+    {
+        let db = state.db.lock();
+        let system_entries: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM todo_changelog WHERE todo_id = ?1 AND changed_by = 'system'",
+                [&ids[0]],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(system_entries, 0);
+        assert_eq!(next_server::services::rollover::run_once(&db), 0);
+    }
+}
+
+#[tokio::test]
+async fn test_todo_attachments() {
+    let dir = std::env::temp_dir().join(format!("next-attach-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("DATABASE_PATH", dir.join("next.db").to_str().unwrap());
+
+    let state = test_state();
+    let (_, owner_token) = create_test_user(&state, "ada", "Ada12345");
+    let (collab_id, collab_token) = create_test_user(&state, "cy", "Cy123456");
+    let (_, stranger_token) = create_test_user(&state, "sal", "Sal12345");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&owner_token))
+        .body(Body::from(r#"{"text":"Review contract"}"#))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+    state
+        .db
+        .lock()
+        .execute(
+            "INSERT INTO todo_collaborators (id, todo_id, user_id, created_at) VALUES ('tc1', ?1, ?2, '2026-01-01T00:00:00Z')",
+            rusqlite::params![todo_id, collab_id],
+        )
+        .unwrap();
+
+    let upload = |token: &str, name: &str, mime: &str, data: &str| {
+        let boundary = "XBOUNDARYX";
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{n}\"\r\nContent-Type: {m}\r\n\r\n{d}\r\n--{b}--\r\n",
+            b = boundary,
+            n = name,
+            m = mime,
+            d = data
+        );
+        Request::post(format!("/api/todos/{}/attachments", todo_id))
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // Collaborators can upload; strangers cannot
+    let (status, body) = send(
+        build_app(state.clone()),
+        upload(&collab_token, "notes.txt", "text/plain", "hello"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["count"], 1);
+    let attachment_id = body["items"][0]["id"].as_str().unwrap().to_string();
+    assert!(body["items"][0].get("storage_path").is_none());
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        upload(&stranger_token, "x.txt", "text/plain", "nope"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        upload(&owner_token, "empty.txt", "text/plain", ""),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Count shows on the todo, file downloads with its original name
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos/{}", todo_id))
+        .header("cookie", auth_cookie(&owner_token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["item"]["attachment_count"], 1);
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!(
+        "/api/todos/{}/attachments/{}",
+        todo_id, attachment_id
+    ))
+    .header("cookie", auth_cookie(&owner_token))
+    .body(Body::empty())
+    .unwrap();
+    let resp = tower::ServiceExt::oneshot(app, req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()["content-disposition"],
+        "inline; filename*=UTF-8''notes.txt"
+    );
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&bytes[..], b"hello");
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!(
+        "/api/todos/{}/attachments/{}",
+        todo_id, attachment_id
+    ))
+    .header("cookie", auth_cookie(&stranger_token))
+    .body(Body::empty())
+    .unwrap();
+    let resp = tower::ServiceExt::oneshot(app, req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // The owner may delete a collaborator's upload
+    let app = build_app(state.clone());
+    let req = Request::delete(format!(
+        "/api/todos/{}/attachments/{}",
+        todo_id, attachment_id
+    ))
+    .header("cookie", auth_cookie(&owner_token))
+    .body(Body::empty())
+    .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos/{}/attachments", todo_id))
+        .header("cookie", auth_cookie(&collab_token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn test_todo_comments() {
+    let state = test_state();
+    let (_, owner_token) = create_test_user(&state, "maya", "Maya1234");
+    let (collab_id, collab_token) = create_test_user(&state, "leo", "Leo12345");
+    let (_, other_token) = create_test_user(&state, "leona", "Leona123");
+    let (_, stranger_token) = create_test_user(&state, "zed", "Zed12345");
+
+    let app = build_app(state.clone());
+    let req = Request::post("/api/todos")
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&owner_token))
+        .body(Body::from(r#"{"text":"Plan launch"}"#))
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+    {
+        let db = state.db.lock();
+        db.execute(
+            "INSERT INTO todo_collaborators (id, todo_id, user_id, created_at) VALUES ('tc1', ?1, ?2, '2026-01-01T00:00:00Z')",
+            rusqlite::params![todo_id, collab_id],
+        )
+        .unwrap();
+    }
+
+    let comment = |token: &str, body: &str| {
+        Request::post(format!("/api/todos/{}/comments", todo_id))
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(serde_json::json!({ "body": body }).to_string()))
+            .unwrap()
+    };
+    let unread = |token: &str| {
+        Request::get("/api/notifications/unread")
+            .header("cookie", auth_cookie(token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Mentions only resolve to participants, and need a full name match
+    let (status, body) = send(
+        build_app(state.clone()),
+        comment(&owner_token, "@leo can you check? cc @leona @zed"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let comment_id = body["item"]["id"].as_str().unwrap().to_string();
+    let mentions = body["item"]["mentions"].as_array().unwrap();
+    assert_eq!(mentions.len(), 1);
+    assert_eq!(mentions[0]["name"], "leo");
+
+    let (_, body) = send(build_app(state.clone()), unread(&collab_token)).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["items"][0]["type"], "todo_mention");
+    assert_eq!(body["items"][0]["todo_id"], todo_id.as_str());
+    let (_, body) = send(build_app(state.clone()), unread(&other_token)).await;
+    assert_eq!(body["count"], 0);
+
+    // Replies notify the other participants
+    let (_, body) = send(build_app(state.clone()), comment(&collab_token, "On it")).await;
+    let reply_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(build_app(state.clone()), unread(&owner_token)).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["items"][0]["type"], "todo_comment");
+
+    let (status, _) = send(build_app(state.clone()), comment(&stranger_token, "hi")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(build_app(state.clone()), comment(&owner_token, "   ")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only the author edits
+    let edit = |token: &str, id: &str, body: &str| {
+        Request::put(format!("/api/todos/{}/comments/{}", todo_id, id))
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(serde_json::json!({ "body": body }).to_string()))
+            .unwrap()
+    };
+    let (status, _) = send(
+        build_app(state.clone()),
+        edit(&collab_token, &comment_id, "hijack"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = send(
+        build_app(state.clone()),
+        edit(&collab_token, &reply_id, "On it, @maya"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["edited"], true);
+    let (_, body) = send(build_app(state.clone()), unread(&owner_token)).await;
+    assert_eq!(body["count"], 2);
+
+    // The owner can remove any comment
+    let app = build_app(state.clone());
+    let req = Request::delete(format!("/api/todos/{}/comments/{}", todo_id, reply_id))
+        .header("cookie", auth_cookie(&owner_token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = build_app(state.clone());
+    let req = Request::get(format!("/api/todos/{}/comments", todo_id))
+        .header("cookie", auth_cookie(&collab_token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["author_name"], "maya");
+}
+
+#[tokio::test]
+async fn test_time_tracking() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "maya", "Maya1234");
+
+    let create = |text: &str, tags: &str| {
+        Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(format!(
+                r#"{{"text":"{}","tags":{}}}"#,
+                text, tags
+            )))
+            .unwrap()
+    };
+    let post = |uri: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| {
+        Request::get(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        create("Write report", r#"["work"]"#),
+    )
+    .await;
+    let report_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(build_app(state.clone()), create("Read book", "[]")).await;
+    let book_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    // Only one timer at a time
+    let (status, body) = send(
+        build_app(state.clone()),
+        post("/api/time/start", serde_json::json!({"todo_id": report_id})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["running"], true);
+    let (status, _) = send(
+        build_app(state.clone()),
+        post("/api/time/start", serde_json::json!({"todo_id": book_id})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, body) = send(build_app(state.clone()), get("/api/time/current")).await;
+    assert_eq!(body["item"]["todo_id"], report_id.as_str());
+
+    // Manual entries may not overlap the running timer
+    let now = chrono::Utc::now();
+    let minutes_ago = |m: i64| (now - chrono::Duration::minutes(m)).to_rfc3339();
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            "/api/time/entries",
+            serde_json::json!({"todo_id": book_id, "started_at": minutes_ago(5), "minutes": 10}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        post("/api/time/stop", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["running"], false);
+    let (status, _) = send(
+        build_app(state.clone()),
+        post("/api/time/stop", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        post(
+            "/api/time/entries",
+            serde_json::json!({"todo_id": book_id, "started_at": minutes_ago(120), "minutes": 30}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["duration_secs"], 1800);
+    assert_eq!(body["item"]["source"], "manual");
+    let manual_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            "/api/time/entries",
+            serde_json::json!({"todo_id": book_id, "started_at": minutes_ago(100), "minutes": 30}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            "/api/time/entries",
+            serde_json::json!({"todo_id": book_id, "started_at": minutes_ago(60), "ended_at": minutes_ago(90)}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Out-of-range minutes are rejected rather than overflowing
+    for minutes in [i64::MAX, i64::MIN, 24 * 60 + 1] {
+        let (status, body) = send(
+            build_app(state.clone()),
+            post(
+                "/api/time/entries",
+                serde_json::json!({"todo_id": book_id, "started_at": minutes_ago(3000), "minutes": minutes}),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+    }
+
+    // Totals show up on the todo and in the reports
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/todos/{}", book_id)),
+    )
+    .await;
+    assert_eq!(body["item"]["time_spent_secs"], 1800);
+
+    let (_, body) = send(build_app(state.clone()), get("/api/time/entries")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 2);
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/time/entries?todo_id={}", book_id)),
+    )
+    .await;
+    assert_eq!(body["total_secs"], 1800);
+
+    let (_, body) = send(build_app(state.clone()), get("/api/time/report?group=tag")).await;
+    let rows = body["items"].as_array().unwrap();
+    assert!(rows.iter().any(|r| r["key"] == "" && r["seconds"] == 1800));
+    assert!(rows.iter().any(|r| r["key"] == "work"));
+    let (_, body) = send(build_app(state.clone()), get("/api/time/report")).await;
+    assert_eq!(body["group"], "day");
+    assert!(body["total_secs"].as_i64().unwrap() >= 1800);
+    let (status, _) = send(build_app(state.clone()), get("/api/time/report?group=week")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Dates at the calendar limits are rejected rather than overflowing
+    for uri in [
+        "/api/time/report?to=%2B262142-12-31",
+        "/api/time/entries?to=%2B262142-12-31",
+        "/api/time/entries?to=-262143-01-03",
+    ] {
+        let (status, _) = send(build_app(state.clone()), get(uri)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        Request::delete(format!("/api/time/entries/{}", manual_id))
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The assistant tools drive the same timer
+    {
+        use next_server::services::tool_executor::execute_tool;
+        let db = state.db.lock();
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "start_timer",
+            &serde_json::json!({"todo_id": book_id}),
+        );
+        assert_eq!(result["success"], true);
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "start_timer",
+            &serde_json::json!({"todo_id": report_id}),
+        );
+        assert!(result["error"].is_string());
+        let result = execute_tool(&db, &user_id, "stop_timer", &serde_json::json!({}));
+        assert_eq!(result["success"], true);
+        assert_eq!(result["todo_id"], book_id.as_str());
+    }
+}
+
+#[tokio::test]
+async fn test_focus_sessions() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "maya", "Maya1234");
+
+    let post = |uri: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| {
+        Request::get(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        post("/api/todos", serde_json::json!({"text": "Write report"})),
+    )
+    .await;
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            "/api/focus/start",
+            serde_json::json!({"planned_minutes": 500}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(
+        build_app(state.clone()),
+        post("/api/focus/start", serde_json::json!({"todo_id": todo_id})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["planned_minutes"], 25);
+    assert_eq!(body["item"]["running"], true);
+    let (status, _) = send(
+        build_app(state.clone()),
+        post("/api/focus/start", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Stopping early counts as an interruption
+    let (status, body) = send(
+        build_app(state.clone()),
+        post("/api/focus/stop", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["interrupted"], true);
+    assert_eq!(body["item"]["running"], false);
+
+    // A session that has run its course is completed by the poller path
+    let (_, body) = send(
+        build_app(state.clone()),
+        post(
+            "/api/focus/start",
+            serde_json::json!({"todo_id": todo_id, "planned_minutes": 30}),
+        ),
+    )
+    .await;
+    let session_id = body["item"]["id"].as_str().unwrap().to_string();
+    {
+        let db = state.db.lock();
+        let started = (chrono::Utc::now() - chrono::Duration::minutes(40))
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        db.execute(
+            "UPDATE focus_sessions SET started_at = ?1 WHERE id = ?2",
+            rusqlite::params![started, session_id],
+        )
+        .unwrap();
+        let pushes = next_server::services::focus::complete_due(&db, chrono::Utc::now());
+        assert_eq!(pushes.len(), 1);
+        assert_eq!(pushes[0].0, user_id);
+        assert!(next_server::services::focus::complete_due(&db, chrono::Utc::now()).is_empty());
+    }
+    let (_, body) = send(build_app(state.clone()), get("/api/focus/current")).await;
+    assert!(body["item"].is_null());
+    let (_, body) = send(build_app(state.clone()), get("/api/notifications/unread")).await;
+    let items = body["items"].as_array().unwrap();
+    assert!(items
+        .iter()
+        .any(|n| n["type"] == "focus" && n["todo_id"] == todo_id.as_str()));
+
+    let (_, body) = send(build_app(state.clone()), get("/api/focus/sessions")).await;
+    let sessions = body["items"].as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    let done = sessions
+        .iter()
+        .find(|s| s["id"] == session_id.as_str())
+        .unwrap();
+    assert_eq!(done["actual_secs"], 1800);
+    assert_eq!(done["interrupted"], false);
+
+    let (_, body) = send(build_app(state.clone()), get("/api/focus/stats")).await;
+    assert_eq!(body["stats"]["sessions"], 2);
+    assert_eq!(body["stats"]["completed"], 1);
+    assert_eq!(body["stats"]["interrupted"], 1);
+    assert!(body["stats"]["minutes"].as_i64().unwrap() >= 30);
+
+    {
+        let db = state.db.lock();
+        let result = next_server::services::tool_executor::execute_tool(
+            &db,
+            &user_id,
+            "get_statistics",
+            &serde_json::json!({"period": "week"}),
+        );
+        assert!(result["focus"]["minutes"].as_i64().unwrap() >= 30);
+        assert_eq!(result["focus"]["completed"], 1);
+    }
+}
+
+#[tokio::test]
+async fn test_projects() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "maya", "Maya1234");
+    let (collab_id, collab_token) = create_test_user(&state, "leo", "Leo12345");
+
+    let json_req = |method: &str, uri: &str, token: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| {
+        Request::get(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/projects",
+            &token,
+            serde_json::json!({"name": "Move", "color": "red"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/projects",
+            &token,
+            serde_json::json!({"name": "Home move", "color": "#12ab34", "deadline": "2026-12-01"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["color"], "#12AB34");
+    assert_eq!(body["item"]["status"], "active");
+    let project_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/projects",
+            &token,
+            serde_json::json!({"name": "Home move"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Todos link by project id or name
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/todos",
+            &token,
+            serde_json::json!({"text": "Book movers", "project_id": "home move"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["project_id"], project_id.as_str());
+    let movers_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/todos",
+            &token,
+            serde_json::json!({"text": "x", "project_id": "nope"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/todos",
+            &token,
+            serde_json::json!({"text": "Pack books"}),
+        ),
+    )
+    .await;
+    let books_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            &format!("/api/todos/{}", books_id),
+            &token,
+            serde_json::json!({"project_id": project_id, "progress": 50}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["project_id"], project_id.as_str());
+    let changelog = body["item"]["changelog"].as_array().unwrap();
+    assert!(changelog
+        .iter()
+        .any(|c| c["field"] == "project" && c["new_value"] == "Home move"));
+
+    // Only the owner files a shared todo under a project
+    {
+        let db = state.db.lock();
+        db.execute(
+            "INSERT INTO todo_collaborators (id, todo_id, user_id, created_at) VALUES ('tc1', ?1, ?2, '2026-01-01T00:00:00Z')",
+            rusqlite::params![books_id, collab_id],
+        )
+        .unwrap();
+    }
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            &format!("/api/todos/{}", books_id),
+            &collab_token,
+            serde_json::json!({"project_id": ""}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            &format!("/api/todos/{}", movers_id),
+            &token,
+            serde_json::json!({"completed": true}),
+        ),
+    )
+    .await;
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/projects/{}", project_id)),
+    )
+    .await;
+    assert_eq!(body["item"]["progress"]["total"], 2);
+    assert_eq!(body["item"]["progress"]["completed"], 1);
+    assert_eq!(body["item"]["progress"]["percent"], 75);
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        get("/api/todos?all=1&filter=project:%22home%20move%22%20!completed"),
+    )
+    .await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], books_id.as_str());
+
+    // Status changes and archiving
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            &format!("/api/projects/{}", project_id),
+            &token,
+            serde_json::json!({"status": "archived", "deadline": ""}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["item"]["deadline"].is_null());
+    let (_, body) = send(build_app(state.clone()), get("/api/projects")).await;
+    assert!(body["items"].as_array().unwrap().is_empty());
+    let (_, body) = send(
+        build_app(state.clone()),
+        get("/api/projects?status=archived"),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+
+    // Assistant tools understand projects
+    {
+        use next_server::services::tool_executor::execute_tool;
+        let db = state.db.lock();
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "create_todo",
+            &serde_json::json!({"text": "Change address", "project": "Home Move"}),
+        );
+        assert_eq!(result["project"], "Home move");
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "create_todo",
+            &serde_json::json!({"text": "x", "project": "Garden"}),
+        );
+        assert!(result["error"].is_string());
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "query_todos",
+            &serde_json::json!({"project": "home move"}),
+        );
+        assert_eq!(result["count"], 3);
+        assert!(result["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|t| t["project"] == "Home move"));
+        let result = execute_tool(
+            &db,
+            &user_id,
+            "update_todo",
+            &serde_json::json!({"id": movers_id, "project": ""}),
+        );
+        assert_eq!(result["success"], true);
+    }
+
+    // Deleting a project keeps its todos
+    let (status, _) = send(
+        build_app(state.clone()),
+        Request::delete(format!("/api/projects/{}", project_id))
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/todos/{}", books_id)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["item"]["project_id"].is_null());
+}
+
+#[tokio::test]
+async fn test_template_items_append_in_order() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "tess", "Tess1234");
+    let post = |uri: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            "/api/todos",
+            serde_json::json!({"text": "Existing", "tab": "today", "quadrant": "important-urgent"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(
+        build_app(state.clone()),
+        post(
+            "/api/templates",
+            serde_json::json!({
+                "name": "Morning",
+                "items": [
+                    {"text": "First", "quadrant": "important-urgent"},
+                    {"text": "Second", "quadrant": "important-urgent"},
+                    {"text": "Third", "quadrant": "important-urgent"}
+                ]
+            }),
+        ),
+    )
+    .await;
+    let template_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        build_app(state.clone()),
+        post(
+            &format!("/api/templates/{}/apply", template_id),
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The template's items follow the existing todo, in template order
+    let req = Request::get("/api/todos?tab=today")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    let texts: Vec<&str> = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(texts, ["Existing", "First", "Second", "Third"]);
+}
+
+#[tokio::test]
+async fn test_todo_templates() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "nora", "Nora1234");
+    let (friend_id, friend_token) = create_test_user(&state, "omar", "Omar1234");
+
+    let json_req = |method: &str, uri: &str, token: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str, token: &str| {
+        Request::get(uri)
+            .header("cookie", auth_cookie(token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/templates",
+            &token,
+            serde_json::json!({"name": "Trip", "items": [{"text": "Pack", "quadrant": "urgent"}]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/templates",
+            &token,
+            serde_json::json!({
+                "name": "Trip prep",
+                "items": [
+                    {"text": "Book hotel", "due_offset_days": -14, "quadrant": "important-not-urgent", "tags": ["travel"]},
+                    {"text": "Pack", "due_offset_days": -1, "checklist": ["Passport", "Charger", " "]},
+                    {"text": "Expense report", "due_offset_days": 3}
+                ]
+            }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let template_id = body["item"]["id"].as_str().unwrap().to_string();
+    assert_eq!(
+        body["item"]["items"][1]["checklist"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        body["item"]["items"][2]["quadrant"],
+        "not-important-not-urgent"
+    );
+
+    // Apply against an anchor date, into a new project
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/templates/{}/apply", template_id),
+            &token,
+            serde_json::json!({"anchor_date": "2026-11-20", "new_project": "Lisbon"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let todo_ids: Vec<String> = body["todo_ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(todo_ids.len(), 3);
+    let project_id = body["project_id"].as_str().unwrap().to_string();
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/todos/{}", todo_ids[0]), &token),
+    )
+    .await;
+    assert_eq!(body["item"]["due_date"], "2026-11-06");
+    assert_eq!(body["item"]["quadrant"], "important-not-urgent");
+    assert_eq!(body["item"]["tags"], serde_json::json!(["travel"]));
+    assert_eq!(body["item"]["project_id"], project_id.as_str());
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/todos/{}", todo_ids[1]), &token),
+    )
+    .await;
+    assert_eq!(body["item"]["due_date"], "2026-11-19");
+    assert_eq!(body["item"]["checklist"].as_array().unwrap().len(), 2);
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/projects/{}", project_id), &token),
+    )
+    .await;
+    assert_eq!(body["item"]["name"], "Lisbon");
+    assert_eq!(body["item"]["deadline"], "2026-11-23");
+    assert_eq!(body["item"]["progress"]["total"], 3);
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/templates/{}/apply", template_id),
+            &token,
+            serde_json::json!({"anchor_date": "20/11/2026"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // A missing project leaves nothing behind
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/templates/{}/apply", template_id),
+            &token,
+            serde_json::json!({"project_id": "nope"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    {
+        let db = state.db.lock();
+        let count: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM todos WHERE user_id = ?1",
+                [&user_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    // Share with a friend, who accepts a copy
+    {
+        let db = state.db.lock();
+        db.execute(
+            "INSERT INTO friendships (id, requester_id, addressee_id, status, created_at, updated_at) VALUES ('fs1', ?1, ?2, 'accepted', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z')",
+            rusqlite::params![user_id, friend_id],
+        )
+        .unwrap();
+    }
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/share",
+            &token,
+            serde_json::json!({"friend_id": friend_id, "item_type": "template", "item_id": template_id}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(
+        build_app(state.clone()),
+        get("/api/share/inbox", &friend_token),
+    )
+    .await;
+    let share_id = body["items"][0]["id"].as_str().unwrap().to_string();
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/share/{}/accept", share_id),
+            &friend_token,
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let copy_id = body["new_id"].as_str().unwrap().to_string();
+    let (status, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/templates/{}", copy_id), &friend_token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["name"], "Trip prep");
+    assert_eq!(body["item"]["items"].as_array().unwrap().len(), 3);
+
+    // The assistant applies templates by name
+    {
+        use next_server::services::tool_executor::execute_tool;
+        let db = state.db.lock();
+        let result = execute_tool(
+            &db,
+            &friend_id,
+            "apply_template",
+            &serde_json::json!({"template": "trip prep", "anchor_date": "2026-12-01"}),
+        );
+        assert_eq!(result["success"], true);
+        assert_eq!(result["created"], 3);
+        let result = execute_tool(
+            &db,
+            &friend_id,
+            "apply_template",
+            &serde_json::json!({"template": "Camping"}),
+        );
+        assert_eq!(result["templates"], serde_json::json!(["Trip prep"]));
+    }
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "DELETE",
+            &format!("/api/templates/{}", template_id),
+            &token,
+            serde_json::json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(build_app(state.clone()), get("/api/templates", &token)).await;
+    assert!(body["items"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_tag_management() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "pia", "Pia12345");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| {
+        Request::get(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let mut todo_ids = Vec::new();
+    for tags in [
+        serde_json::json!(["work", "urgent"]),
+        serde_json::json!(["Work"]),
+        serde_json::json!(["home"]),
+    ] {
+        let (_, body) = send(
+            build_app(state.clone()),
+            json_req(
+                "POST",
+                "/api/todos",
+                serde_json::json!({"text": "t", "tags": tags}),
+            ),
+        )
+        .await;
+        todo_ids.push(body["item"]["id"].as_str().unwrap().to_string());
+    }
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/expenses",
+            serde_json::json!({"amount": 12.5, "date": "2026-10-01", "tags": ["work", "lunch"]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send(build_app(state.clone()), get("/api/tags")).await;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items[0]["name"], "work");
+    assert_eq!(items[0]["todos"], 1);
+    assert_eq!(items[0]["expenses"], 1);
+    assert_eq!(items.len(), 5);
+    let (_, body) = send(build_app(state.clone()), get("/api/expenses/tags")).await;
+    assert_eq!(body["tags"], serde_json::json!(["work", "lunch"]));
+
+    // Merge the duplicate spelling; a todo carrying both keeps one
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/tags/merge",
+            serde_json::json!({"sources": ["Work", "urgent"], "into": "work"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"]["todos"], 2);
+    assert_eq!(body["result"]["expenses"], 0);
+    assert!(body["result"]["batch_id"].is_string());
+    let (_, body) = send(
+        build_app(state.clone()),
+        get(&format!("/api/todos/{}", todo_ids[0])),
+    )
+    .await;
+    assert_eq!(body["item"]["tags"], serde_json::json!(["work"]));
+    assert!(body["item"]["changelog"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["field"] == "tags"));
+
+    // Rename reaches both tables
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req("PUT", "/api/tags/work", serde_json::json!({"name": "job"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"]["todos"], 2);
+    assert_eq!(body["result"]["expenses"], 1);
+    {
+        let db = state.db.lock();
+        let tags: String = db
+            .query_row(
+                "SELECT tags FROM expense_entries WHERE user_id = ?1",
+                [&user_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(tags, r#"["job","lunch"]"#);
+    }
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req("PUT", "/api/tags/nope", serde_json::json!({"name": "x"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req("PUT", "/api/tags/job", serde_json::json!({"name": "  "})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req("DELETE", "/api/tags/home", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"]["todos"], 1);
+    let (_, body) = send(build_app(state.clone()), get("/api/tags")).await;
+    let names: Vec<&str> = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["job", "lunch"]);
+}
+
+#[tokio::test]
+async fn test_trash_retention() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "quinn", "Quinn123");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let bare = |method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/expenses",
+            serde_json::json!({"amount": 8.0, "date": "2026-10-01", "notes": "Coffee"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let expense_id = body["entry"]["id"].as_str().unwrap().to_string();
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/trips",
+            serde_json::json!({"title": "Lisbon", "date_from": "2026-11-01", "date_to": "2026-11-05"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let trip_id = body["trip"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/reviews",
+            serde_json::json!({"text": "Budget check", "frequency": "daily"}),
+        ),
+    )
+    .await;
+    let review_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    for uri in [
+        format!("/api/expenses/{}", expense_id),
+        format!("/api/trips/{}", trip_id),
+        format!("/api/reviews/{}", review_id),
+    ] {
+        let (status, _) = send(build_app(state.clone()), bare("DELETE", &uri)).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+
+    // Gone from the normal lists, present in the trash
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/expenses")).await;
+    assert_eq!(body["entries"].as_array().unwrap().len(), 0);
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/trips")).await;
+    assert_eq!(body["trips"].as_array().unwrap().len(), 0);
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/reviews")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+    let (status, body) = send(build_app(state.clone()), bare("GET", "/api/trash")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["retention_days"], 30);
+    assert_eq!(body["items"].as_array().unwrap().len(), 3);
+    assert!(body["items"][0]["purge_at"].is_string());
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("GET", "/api/trash?type=expense"),
+    )
+    .await;
+    assert_eq!(body["items"][0]["title"], "Coffee");
+    let (status, _) = send(
+        build_app(state.clone()),
+        bare("GET", "/api/trash?type=bogus"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Restore the trip
+    let (status, _) = send(
+        build_app(state.clone()),
+        bare("POST", &format!("/api/trash/trip/{}/restore", trip_id)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/trips")).await;
+    assert_eq!(body["trips"].as_array().unwrap().len(), 1);
+    let (status, _) = send(
+        build_app(state.clone()),
+        bare("POST", &format!("/api/trash/trip/{}/restore", trip_id)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Retention bounds
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            "/api/settings",
+            serde_json::json!({"trash_retention_days": 366}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 0 keeps items forever
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            "/api/settings",
+            serde_json::json!({"trash_retention_days": 0}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let later = chrono::Utc::now() + chrono::Duration::days(400);
+    {
+        let db = state.db.lock();
+        assert_eq!(next_server::services::trash::purge_expired(&db, later), 0);
+    }
+
+    // A week's retention purges the expense and review once it has passed
+    send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            "/api/settings",
+            serde_json::json!({"trash_retention_days": 7}),
+        ),
+    )
+    .await;
+    {
+        let db = state.db.lock();
+        let soon = chrono::Utc::now() + chrono::Duration::days(3);
+        assert_eq!(next_server::services::trash::purge_expired(&db, soon), 0);
+        assert_eq!(next_server::services::trash::purge_expired(&db, later), 2);
+        let left: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM expense_entries WHERE user_id = ?1",
+                [&user_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+
+    // Emptying the trash removes the rest permanently
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("DELETE", &format!("/api/trips/{}", trip_id)),
+    )
+    .await;
+    assert_eq!(body["success"], true);
+    let (status, body) = send(build_app(state.clone()), bare("DELETE", "/api/trash")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["purged"], 1);
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/trash")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+    assert_eq!(body["retention_days"], 7);
+}
+
+#[tokio::test]
+async fn test_todo_manual_order() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "rene", "Rene1234");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let order = |body: &serde_json::Value, quadrant: &str| -> Vec<String> {
+        body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|t| t["quadrant"] == quadrant)
+            .map(|t| t["text"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let list = || {
+        Request::get("/api/todos?tab=today")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let mut ids = std::collections::HashMap::new();
+    for text in ["a", "b", "c"] {
+        let (_, body) = send(
+            build_app(state.clone()),
+            json_req(
+                "POST",
+                "/api/todos",
+                serde_json::json!({"text": text, "tab": "today", "quadrant": "important-urgent"}),
+            ),
+        )
+        .await;
+        ids.insert(text, body["item"]["id"].as_str().unwrap().to_string());
+    }
+    let (_, body) = send(build_app(state.clone()), list()).await;
+    assert_eq!(order(&body, "important-urgent"), ["a", "b", "c"]);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/todos/{}/move", ids["c"]),
+            serde_json::json!({"before": ids["a"]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["item"]["sort_order"].as_f64().unwrap() < 1.0);
+    send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/todos/{}/move", ids["a"]),
+            serde_json::json!({"after": ids["b"]}),
+        ),
+    )
+    .await;
+    let (_, body) = send(build_app(state.clone()), list()).await;
+    assert_eq!(order(&body, "important-urgent"), ["c", "b", "a"]);
+    let updated_at = |body: &serde_json::Value, text: &str| {
+        body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["text"] == text)
+            .unwrap()["updated_at"]
+            .clone()
+    };
+    let c_version = updated_at(&body, "c");
+
+    // Keep dropping into the same gap until it has to be rebalanced
+    let (mut moving, mut anchor) = ("a", "b");
+    for _ in 0..40 {
+        let (status, _) = send(
+            build_app(state.clone()),
+            json_req(
+                "POST",
+                &format!("/api/todos/{}/move", ids[moving]),
+                serde_json::json!({"before": ids[anchor]}),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        std::mem::swap(&mut moving, &mut anchor);
+    }
+    let (_, body) = send(build_app(state.clone()), list()).await;
+    assert_eq!(order(&body, "important-urgent"), ["c", "b", "a"]);
+    let keys: Vec<f64> = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["sort_order"].as_f64().unwrap())
+        .collect();
+    assert!(keys.windows(2).all(|w| w[1] - w[0] > 1e-6));
+    // Renumbering a neighbour leaves its version alone
+    assert_eq!(updated_at(&body, "c"), c_version);
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/todos/{}/move", ids["a"]),
+            serde_json::json!({"before": ids["b"], "after": ids["c"]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Drag-and-drop across quadrants through the batch endpoint
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            "/api/todos/batch",
+            serde_json::json!([{"id": ids["b"], "quadrant": "important-not-urgent"}]),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            "/api/todos/batch",
+            serde_json::json!([{"id": ids["a"], "quadrant": "important-not-urgent", "before": ids["b"]}]),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(build_app(state.clone()), list()).await;
+    assert_eq!(order(&body, "important-not-urgent"), ["a", "b"]);
+    assert_eq!(order(&body, "important-urgent"), ["c"]);
+
+    // A sibling from another list is rejected
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            &format!("/api/todos/{}/move", ids["c"]),
+            serde_json::json!({"after": ids["a"]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_delta_sync() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "sasha", "Sasha123");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let bare = |method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let find = |body: &serde_json::Value, id: &str| -> serde_json::Value {
+        body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == id)
+            .cloned()
+            .unwrap_or(serde_json::Value::Null)
+    };
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("POST", "/api/todos", serde_json::json!({"text": "Draft"})),
+    )
+    .await;
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/expenses",
+            serde_json::json!({"amount": 3.5, "date": "2026-10-02"}),
+        ),
+    )
+    .await;
+    let expense_id = body["entry"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/routines",
+            serde_json::json!({"text": "Stretch"}),
+        ),
+    )
+    .await;
+    let routine_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/reviews",
+            serde_json::json!({"text": "Inbox zero", "frequency": "weekly"}),
+        ),
+    )
+    .await;
+    let review_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    // Full snapshot
+    let (status, body) = send(build_app(state.clone()), bare("GET", "/api/sync")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(find(&body, &todo_id)["record"]["text"], "Draft");
+    assert_eq!(find(&body, &expense_id)["type"], "expense");
+    assert_eq!(find(&body, &routine_id)["type"], "routine");
+    assert_eq!(find(&body, &review_id)["record"]["frequency"], "weekly");
+    let cursor = body["cursor"].as_str().unwrap().to_string();
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/sync?since={}", cursor)),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+    assert_eq!(body["cursor"], cursor.as_str());
+
+    // Online edits show up as changes and tombstones
+    send(
+        build_app(state.clone()),
+        json_req(
+            "PUT",
+            &format!("/api/todos/{}", todo_id),
+            serde_json::json!({"text": "Final"}),
+        ),
+    )
+    .await;
+    send(
+        build_app(state.clone()),
+        bare("DELETE", &format!("/api/expenses/{}", expense_id)),
+    )
+    .await;
+    send(
+        build_app(state.clone()),
+        bare("DELETE", &format!("/api/routines/{}", routine_id)),
+    )
+    .await;
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("POST", "/api/todos", serde_json::json!({"text": "New"})),
+    )
+    .await;
+    let new_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/sync?since={}", cursor)),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 4);
+    let todo_change = find(&body, &todo_id);
+    assert_eq!(todo_change["op"], "updated");
+    assert_eq!(todo_change["record"]["text"], "Final");
+    assert_eq!(find(&body, &expense_id)["op"], "deleted");
+    assert!(find(&body, &expense_id)["record"].is_null());
+    assert_eq!(find(&body, &routine_id)["op"], "deleted");
+    assert_eq!(find(&body, &new_id)["op"], "created");
+    let todo_seq = todo_change["seq"].as_i64().unwrap();
+
+    // Paging
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/sync?since={}&limit=1", cursor)),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert_eq!(body["has_more"], true);
+    let (status, _) = send(build_app(state.clone()), bare("GET", "/api/sync?since=abc")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Push a queued offline batch
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/sync/push",
+            serde_json::json!({"mutations": [
+                {"type": "todo", "op": "create", "id": "offline-1",
+                 "fields": {"text": "Made offline", "quadrant": "important-urgent"}},
+                {"type": "todo", "op": "update", "id": todo_id, "base_seq": todo_seq - 1,
+                 "fields": {"text": "Stale"}},
+                {"type": "todo", "op": "update", "id": todo_id, "base_seq": todo_seq,
+                 "fields": {"progress": 100}},
+                {"type": "review", "op": "delete", "id": review_id},
+                {"type": "todo", "op": "update", "id": new_id, "fields": {"owner": "x"}},
+                {"type": "expense", "op": "update", "id": "missing", "fields": {"amount": 1}},
+                {"type": "todo", "op": "create", "id": "offline-1", "fields": {"text": "Again"}}
+            ]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["status"], "applied");
+    assert_eq!(results[0]["record"]["quadrant"], "important-urgent");
+    assert_eq!(results[1]["status"], "conflict");
+    assert_eq!(results[1]["record"]["text"], "Final");
+    assert_eq!(results[2]["status"], "applied");
+    assert_eq!(results[2]["record"]["completed"], true);
+    assert!(results[2]["record"]["completed_at"].is_string());
+    assert!(results[2]["seq"].as_i64().unwrap() > todo_seq);
+    assert_eq!(results[3]["status"], "applied");
+    assert_eq!(results[4]["status"], "invalid");
+    assert_eq!(results[5]["status"], "not_found");
+    assert_eq!(results[6]["status"], "conflict");
+    assert_eq!(results[6]["record"]["text"], "Made offline");
+
+    // Pushed edits are regular records with changelog entries
+    let (_, body) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/todos/{}", todo_id)),
+    )
+    .await;
+    assert_eq!(body["item"]["progress"], 100);
+    assert!(body["item"]["changelog"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["field"] == "progress"));
+    let (_, body) = send(build_app(state.clone()), bare("GET", "/api/reviews")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+
+    // Compaction keeps the answer the same
+    let (_, before) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/sync?since={}", cursor)),
+    )
+    .await;
+    next_server::services::sync::compact(&state.db.lock());
+    let (_, after) = send(
+        build_app(state.clone()),
+        bare("GET", &format!("/api/sync?since={}", cursor)),
+    )
+    .await;
+    assert_eq!(before["items"], after["items"]);
+}
+
+#[tokio::test]
+async fn test_if_match_conflicts() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "ingrid", "Ingrid123");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value, if_match: Option<&str>| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token));
+        if let Some(tag) = if_match {
+            builder = builder.header("if-match", tag);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+    let etag_of = |resp: &axum::response::Response| -> String {
+        resp.headers()
+            .get("etag")
+            .expect("etag header")
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/todos",
+            serde_json::json!({"text": "Draft"}),
+            None,
+        ),
+    )
+    .await;
+    let todo_id = body["item"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/expenses",
+            serde_json::json!({"amount": 8.0, "date": "2026-10-01", "notes": "Coffee"}),
+            None,
+        ),
+    )
+    .await;
+    let expense_id = body["entry"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/trips",
+            serde_json::json!({"title": "Lisbon", "date_from": "2026-11-01", "date_to": "2026-11-05"}),
+            None,
+        ),
+    )
+    .await;
+    let trip_id = body["trip"]["id"].as_str().unwrap().to_string();
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/reviews",
+            serde_json::json!({"text": "Budget check", "frequency": "daily"}),
+            None,
+        ),
+    )
+    .await;
+    let review_id = body["item"]["id"].as_str().unwrap().to_string();
+
+    // (uri, edited field, key holding the server copy in a 412 body)
+    let cases = [
+        (format!("/api/todos/{}", todo_id), "text", "item"),
+        (format!("/api/expenses/{}", expense_id), "notes", "entry"),
+        (format!("/api/trips/{}", trip_id), "title", "trip"),
+        (format!("/api/reviews/{}", review_id), "text", "item"),
+    ];
+    for (uri, field, copy) in cases {
+        let resp = build_app(state.clone())
+            .oneshot(json_req("GET", &uri, serde_json::json!({}), None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        let seen = etag_of(&resp);
+
+        // Another device edits first; the write carries the new version
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let resp = build_app(state.clone())
+            .oneshot(json_req(
+                "PUT",
+                &uri,
+                serde_json::json!({ field: "theirs" }),
+                Some(&seen),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        let latest = etag_of(&resp);
+        assert_ne!(latest, seen, "{}", uri);
+
+        // A stale version is refused with the current copy
+        let resp = build_app(state.clone())
+            .oneshot(json_req(
+                "PUT",
+                &uri,
+                serde_json::json!({ field: "mine" }),
+                Some(&seen),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED, "{}", uri);
+        assert_eq!(etag_of(&resp), latest, "{}", uri);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body[copy][field], "theirs", "{}", uri);
+
+        // Wildcards and plain writes keep working
+        let (status, _) = send(
+            build_app(state.clone()),
+            json_req("PUT", &uri, serde_json::json!({ field: "mine" }), Some("*")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        let (status, _) = send(
+            build_app(state.clone()),
+            json_req(
+                "PUT",
+                &uri,
+                serde_json::json!({ field: "mine again" }),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_import_desktop_files() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "greta", "Greta123");
+    let (other_id, _) = create_test_user(&state, "hugo", "Hugo1234");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Someone else already owns a todo with one of the desktop ids
+    {
+        let db = state.db.lock();
+        db.execute(
+            "INSERT INTO todos (id, user_id, text, created_at, updated_at) VALUES ('t003', ?1, 'Not yours', '2026-01-01', '2026-01-01')",
+            [&other_id],
+        )
+        .unwrap();
+    }
+
+    let files = serde_json::json!({
+        "todos": {"items": [
+            {
+                "id": "t002", "text": "所长上岗大纲梳理", "tab": "month", "quadrant": "important-not-urgent",
+                "completed": false, "created_at": "2025-01-01T09:00:00", "updated_at": "2026-01-07T18:09:57.617784",
+                "assignee": "Boris", "tags": ["work", "work"],
+                "changelog": [{"time": "2026-01-07T18:09:57.617784", "field": "quadrant",
+                               "from": "important-urgent", "to": "important-not-urgent", "label": "象限: A → B"}]
+            },
+            {"id": "t003", "text": "教授谈话问题总结", "tab": "today", "quadrant": "not-important-urgent",
+             "created_at": "2025-01-01T09:00:00", "updated_at": "2025-01-01T09:00:00"},
+            {"id": "t004", "text": "Bad tab", "tab": "someday", "created_at": "2025-01-01T09:00:00", "updated_at": "2025-01-01T09:00:00"},
+            {"id": "t002", "text": "所长上岗大纲梳理", "created_at": "2025-01-01T09:00:00", "updated_at": "2025-01-01T09:00:00"}
+        ]},
+        "routines": {"items": [{"id": "486c8a8b", "text": "上班打卡", "completed_today": false, "created_at": "2026-01-05T09:28:58.028648"}],
+                     "last_reset_date": "2026-01-12"},
+        "reviews": {"items": [
+            {"id": "r1", "text": "月度复盘", "frequency": "monthly", "frequency_config": {"day_of_month": 28},
+             "notes": "", "category": "工作", "paused": false, "created_at": "2026-01-01T00:00:00Z", "updated_at": "2026-01-01T00:00:00Z"},
+            {"id": "r2", "text": "Broken", "frequency": "hourly"}
+        ]},
+        "dry_run": true
+    });
+
+    // Dry run plans everything and writes nothing
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req("POST", "/api/import/desktop", files.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let report = &body["report"];
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["todos"]["created"], 2);
+    assert_eq!(report["todos"]["duplicates"], 1);
+    assert_eq!(report["todos"]["invalid"], 1);
+    assert_eq!(report["routines"]["created"], 1);
+    assert_eq!(report["reviews"]["created"], 1);
+    assert_eq!(report["reviews"]["invalid"], 1);
+    let t003 = report["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["id"] == "t003")
+        .unwrap();
+    assert!(t003["new_id"].is_string());
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("GET", "/api/todos?all=1", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+
+    // The real import keeps ids, tabs, quadrants, changelog and review config
+    let mut real = files.clone();
+    real["dry_run"] = serde_json::json!(false);
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req("POST", "/api/import/desktop", real.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["report"]["todos"]["created"], 2);
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("GET", "/api/todos/t002", serde_json::json!({})),
+    )
+    .await;
+    let todo = &body["item"];
+    assert_eq!(todo["tab"], "month");
+    assert_eq!(todo["quadrant"], "important-not-urgent");
+    assert_eq!(todo["tags"], serde_json::json!(["work"]));
+    assert!(todo["created_at"]
+        .as_str()
+        .unwrap()
+        .starts_with("2025-01-01T09:00:00"));
+    let changelog = todo["changelog"].as_array().unwrap();
+    assert_eq!(changelog.len(), 1);
+    assert_eq!(changelog[0]["field"], "quadrant");
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("GET", "/api/reviews/r1", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(body["item"]["frequency"], "monthly");
+    assert_eq!(body["item"]["frequency_config"]["day_of_month"], 28);
+
+    // Importing again only finds duplicates, and reports what drifted
+    {
+        let db = state.db.lock();
+        db.execute("UPDATE todos SET tab = 'week' WHERE id = 't002'", [])
+            .unwrap();
+    }
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("POST", "/api/import/desktop", real),
+    )
+    .await;
+    let report = &body["report"];
+    assert_eq!(report["todos"]["created"], 0);
+    assert_eq!(report["todos"]["duplicates"], 3);
+    assert_eq!(report["routines"]["duplicates"], 1);
+    assert_eq!(report["reviews"]["duplicates"], 1);
+    let t002 = report["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["id"] == "t002")
+        .unwrap();
+    assert_eq!(t002["differences"], serde_json::json!(["tab"]));
+    let t003 = report["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["id"] == "t003")
+        .unwrap();
+    assert_eq!(t003["action"], "duplicate");
+}
+
+#[tokio::test]
+async fn test_todo_text_import_export() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "ines", "Ines1234");
+
+    let json_req = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let download = |uri: &str| {
+        let app = build_app(state.clone());
+        let req = Request::builder()
+            .uri(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap();
+        async move {
+            let resp = app.oneshot(req).await.unwrap();
+            let status = resp.status();
+            let disposition = resp
+                .headers()
+                .get("content-disposition")
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default();
+            let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                disposition,
+                String::from_utf8(bytes.to_vec()).unwrap(),
+            )
+        }
+    };
+
+    let (status, _) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/projects",
+            serde_json::json!({"name": "Home Move"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let todotxt = "(A) 2026-01-05 Pack books +Home_Move @boxes due:2026-01-20 tab:week\n\
+                   x 2026-01-06 Sell couch +Garage @money pri:C\n\
+                   \n\
+                   Water plants\n";
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/import/todotxt",
+            serde_json::json!({"content": todotxt, "dry_run": true}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items"].as_array().unwrap().len(), 3);
+    // Unknown projects fall back to tags
+    assert_eq!(
+        body["items"][1]["tags"],
+        serde_json::json!(["money", "Garage"])
+    );
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("GET", "/api/todos?all=1", serde_json::json!({})),
+    )
+    .await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/import/todotxt",
+            serde_json::json!({"content": todotxt}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["created"].as_array().unwrap().len(), 3);
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req("GET", "/api/todos?all=1", serde_json::json!({})),
+    )
+    .await;
+    let items = body["items"].as_array().unwrap();
+    let pack = items.iter().find(|t| t["text"] == "Pack books").unwrap();
+    assert_eq!(pack["quadrant"], "important-urgent");
+    assert_eq!(pack["tab"], "week");
+    assert_eq!(pack["due_date"], "2026-01-20");
+    assert_eq!(pack["tags"], serde_json::json!(["boxes"]));
+    assert!(pack["project_id"].is_string());
+    let couch = items.iter().find(|t| t["text"] == "Sell couch").unwrap();
+    assert_eq!(couch["completed"], true);
+    assert_eq!(couch["quadrant"], "not-important-urgent");
+
+    // Exports carry the same tokens back out
+    let (status, disposition, text) = download("/api/export/todos").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(disposition.contains("todo.txt"));
+    assert!(text.contains("(A) 2026-01-05 Pack books +Home_Move @boxes tab:week due:2026-01-20\n"));
+    assert!(text.contains("x 2026-01-06 2026-01-06 Sell couch @money @Garage tab:today pri:C"));
+
+    let (_, _, open_only) = download("/api/export/todos?include_completed=false").await;
+    assert!(!open_only.contains("Sell couch"));
+
+    let (status, disposition, markdown) = download("/api/export/todos?format=markdown").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(disposition.contains("todos.md"));
+    assert!(markdown.contains("## 优先处理 (important-urgent)\n\n- [ ] Pack books +Home_Move #boxes tab:week due:2026-01-20\n"));
+    assert!(markdown.contains("- [x] Sell couch #money #Garage tab:today done:2026-01-06"));
+
+    // The Markdown export imports back into the same quadrants
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "POST",
+            "/api/import/markdown",
+            serde_json::json!({"content": markdown, "dry_run": true}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0]["quadrant"], "important-urgent");
+    assert_eq!(items[0]["project"], "Home Move");
+
+    let (status, _, _) = download("/api/export/todos?format=csv").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_csv_import_with_mapping() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "jonas", "Jonas1234");
+
+    let json_req = |uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Todoist export: CONTENT is the title, section rows are skipped
+    let todoist = "TYPE,CONTENT,DESCRIPTION,PRIORITY,DUE_DATE,LABELS\n\
+                   section,Errands,,,,\n\
+                   task,Buy stamps,Post office,1,2026-03-01,errands;#town\n\
+                   task,,No title,4,,\n\
+                   task,Renew passport,,p9,someday,\n";
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "/api/import/csv/preview",
+            serde_json::json!({"content": todoist}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let preview = &body["preview"];
+    assert_eq!(preview["mapping"]["title"], "CONTENT");
+    assert_eq!(preview["mapping"]["notes"], "DESCRIPTION");
+    assert_eq!(preview["mapping"]["tags"], "LABELS");
+    assert_eq!(preview["valid"], 1);
+    assert_eq!(preview["invalid"], 2);
+    let rows = preview["rows"].as_array().unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0]["row"], 2);
+    assert_eq!(rows[0]["quadrant"], "important-urgent");
+    assert_eq!(rows[0]["due_date"], "2026-03-01");
+    assert_eq!(rows[0]["tags"], serde_json::json!(["errands", "town"]));
+    assert_eq!(rows[2]["errors"].as_array().unwrap().len(), 2);
+
+    // Rows with errors block the commit unless skipped
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req("/api/import/csv", serde_json::json!({"content": todoist})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["preview"]["invalid"], 2);
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "/api/import/csv",
+            serde_json::json!({"content": todoist, "skip_invalid": true, "source": "Todoist"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let created = body["created"].as_array().unwrap();
+    assert_eq!(created.len(), 1);
+    let id = created[0].as_str().unwrap();
+
+    let req = Request::builder()
+        .uri(format!("/api/todos/{}", id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(body["item"]["text"], "Buy stamps");
+    assert_eq!(body["item"]["content"], "Post office");
+    assert!(body["item"]["changelog"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["field"] == "imported" && c["new_value"] == "Todoist 第 2 行"));
+
+    // Explicit mapping for unfamiliar headers
+    let sheet = "Was,Wann,Erledigt\nSteuern,2026/4/30,ja\nArzt,2026.02.10,x\n";
+    let (status, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "/api/import/csv/preview",
+            serde_json::json!({"content": sheet, "mapping": {"title": "Missing"}}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("Missing"));
+
+    let mapping = serde_json::json!({"title": "was", "due": "WANN", "completed": "Erledigt"});
+    let (_, body) = send(
+        build_app(state.clone()),
+        json_req(
+            "/api/import/csv/preview",
+            serde_json::json!({"content": sheet, "mapping": mapping}),
+        ),
+    )
+    .await;
+    let rows = body["preview"]["rows"].as_array().unwrap();
+    assert_eq!(rows[0]["due_date"], "2026-04-30");
+    assert!(rows[0]["errors"][0].as_str().unwrap().contains("ja"));
+    assert_eq!(rows[1]["completed"], true);
+}
+
+#[tokio::test]
+async fn test_weekly_report() {
+    let state = test_state();
+    let (user_id, token) = create_test_user(&state, "karla", "Karla1234");
+    let get = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Week of Monday 2026-03-02 (Asia/Shanghai)
+    {
+        let db = state.db.lock();
+        for (id, quadrant, completed_at, due, created_at) in [
+            (
+                "rA",
+                "important-urgent",
+                Some("2026-03-03T16:00:00Z"),
+                Some("2026-03-03"),
+                "2026-03-01T16:00:00Z",
+            ),
+            (
+                "rB",
+                "not-important-urgent",
+                None,
+                Some("2026-03-05"),
+                "2026-03-02T02:00:00Z",
+            ),
+            (
+                "rC",
+                "important-not-urgent",
+                Some("2026-03-06T02:00:00Z"),
+                None,
+                "2026-02-20T00:00:00Z",
+            ),
+        ] {
+            db.execute(
+                "INSERT INTO todos (id, user_id, text, content, tab, quadrant, progress, completed, completed_at, due_date, \
+                 assignee, tags, sort_order, created_at, updated_at, deleted) \
+                 VALUES (?1, ?2, ?1, '', 'week', ?3, 0, ?4, ?5, ?6, '', '[]', 'a0', ?7, ?7, 0)",
+                rusqlite::params![id, user_id, quadrant, completed_at.is_some() as i32, completed_at, due, created_at],
+            )
+            .unwrap();
+        }
+        db.execute(
+            "INSERT INTO todo_changelog (todo_id, time, field, from_val, to_val, label, from_raw, to_raw) \
+             VALUES ('rB', '2026-03-04T02:00:00Z', 'quadrant', '就等你翻牌子了', '待分类', '象限', \
+             'important-not-urgent', 'not-important-urgent')",
+            [],
+        )
+        .unwrap();
+
+        db.execute(
+            "INSERT INTO routines (id, user_id, text, completed_today, created_at) VALUES ('rt1', ?1, 'Stretch', 0, '2026-01-01T00:00:00Z')",
+            [&user_id],
+        )
+        .unwrap();
+        // Every completion lands in routine_completions; unticking removes it
+        for (done, date) in [
+            (1, Some("2026-03-03")),
+            (1, Some("2026-03-05")),
+            (0, None),
+            (1, Some("2026-03-06")),
+        ] {
+            db.execute(
+                "UPDATE routines SET completed_today = ?1, last_completed_date = ?2 WHERE id = 'rt1'",
+                rusqlite::params![done, date],
+            )
+            .unwrap();
+        }
+
+        for (id, frequency, last_completed) in [
+            ("rv1", "weekly", Some("2026-03-04T01:00:00Z")),
+            ("rv2", "daily", None),
+        ] {
+            db.execute(
+                "INSERT INTO reviews (id, user_id, text, frequency, last_completed, paused, created_at, updated_at) \
+                 VALUES (?1, ?2, ?1, ?3, ?4, 0, '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z')",
+                rusqlite::params![id, user_id, frequency, last_completed],
+            )
+            .unwrap();
+        }
+    }
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        get("/api/reports?period=week&date=2026-03-05"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let report = &body["report"];
+    assert_eq!(report["from"], "2026-03-02");
+    assert_eq!(report["to"], "2026-03-08");
+    assert_eq!(report["todos"]["created"], 2);
+    assert_eq!(report["todos"]["completed"], 2);
+    // (48h + 338h) / 2
+    assert_eq!(report["todos"]["avg_lead_time_hours"], 193.0);
+    assert_eq!(report["todos"]["overdue"], 1);
+    assert_eq!(report["todos"]["completed_late"], 1);
+
+    let quadrants = &report["quadrants"];
+    assert_eq!(quadrants["completed"]["important-urgent"], 1);
+    assert_eq!(quadrants["moved_in"]["not-important-urgent"], 1);
+    let days = quadrants["days"].as_array().unwrap();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0]["counts"]["important-urgent"], 1);
+    assert_eq!(days[0]["counts"]["important-not-urgent"], 2);
+    assert_eq!(days[6]["counts"]["important-not-urgent"], 0);
+    assert_eq!(days[6]["counts"]["not-important-urgent"], 1);
+
+    assert_eq!(report["routines"]["done"], 2);
+    assert_eq!(report["routines"]["due"], 7);
+    assert_eq!(report["routines"]["rate"], 28.6);
+    assert_eq!(report["reviews"]["done"], 1);
+    assert_eq!(report["reviews"]["due"], 8);
+
+    // Ticking a routine through the API logs today's completion too
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/routines/rt1/toggle")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::OK);
+    {
+        let db = state.db.lock();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let logged: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM routine_completions WHERE routine_id = 'rt1' AND completed_date = ?1",
+                [&today],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(logged, 1);
+    }
+    let (status, body) = send(build_app(state.clone()), get("/api/reports")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["report"]["period"], "week");
+
+    let resp = build_app(state.clone())
+        .oneshot(get("/api/reports/download?period=week&date=2026-03-05"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("next-week-2026-03-02.md"));
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let markdown = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(markdown.starts_with("# 周报 2026-03-02 ~ 2026-03-08"));
+    assert!(markdown.contains("| 逾期未完成 | 1 |"));
+    assert!(markdown.contains("| Stretch | 2 | 7 |"));
+
+    let resp = build_app(state.clone())
+        .oneshot(get(
+            "/api/reports/download?period=month&date=2026-03-05&format=html",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(html.contains("<h1>月报 2026-03-01 ~ 2026-03-31</h1>"));
+
+    let (status, _) = send(build_app(state.clone()), get("/api/reports?period=year")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Periods running past the calendar limits are rejected
+    for uri in [
+        "/api/reports?date=%2B262142-12-31",
+        "/api/reports?period=month&date=%2B262142-12-31",
+        "/api/reports?date=-262143-01-01",
+        "/api/reports/download?date=%2B262142-12-31",
+    ] {
+        let (status, _) = send(build_app(state.clone()), get(uri)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_routine_schedules() {
+    use chrono::Datelike;
+
+    let state = test_state();
+    let (_uid, token) = create_test_user(&state, "scheduser", "pass123");
+    let today = chrono::Local::now().date_naive();
+    let day = |n: i64| today - chrono::Duration::days(n);
+    let weekday = |n: i64| day(n).weekday().number_from_monday();
+
+    let create = |body: serde_json::Value| {
+        Request::post("/api/routines")
+            .header("cookie", auth_cookie(&token))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let list = |query: &str| {
+        Request::get(format!("/api/routines{}", query))
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = send(
+        build_app(state.clone()),
+        create(serde_json::json!({"text": "Gym", "schedule": "weekdays:9"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["success"], false);
+
+    // Only on tomorrow's weekday: a rest day today
+    let (status, body) = send(
+        build_app(state.clone()),
+        create(serde_json::json!({"text": "Gym", "schedule": format!("weekdays:{}", weekday(-1))})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let routine_id = body["item"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["item"]["due_today"], false);
+    let (_, body) = send(
+        build_app(state.clone()),
+        create(serde_json::json!({"text": "Stretch"})),
+    )
+    .await;
+    assert_eq!(body["item"]["schedule"], "daily");
+    assert_eq!(body["item"]["schedule_label"], "每天");
+
+    let (_, body) = send(build_app(state.clone()), list("")).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["text"], "Stretch");
+    let (_, body) = send(build_app(state.clone()), list("?all=1")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 2);
+
+    // Every other scheduled day: today, two and four days ago
+    {
+        let db = state.db.lock();
+        let created = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        db.execute(
+            "UPDATE routines SET created_at = ?1 WHERE id = ?2",
+            [&created, &routine_id],
+        )
+        .unwrap();
+    }
+    let schedule = format!("weekdays:{},{},{}", weekday(4), weekday(2), weekday(0));
+    let req = Request::put(format!("/api/routines/{}", routine_id))
+        .header("cookie", auth_cookie(&token))
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({ "schedule": schedule }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["item"]["text"], "Gym");
+    assert_eq!(body["item"]["due_today"], true);
+    let (_, body) = send(build_app(state.clone()), list("")).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 2);
+
+    let req = Request::put("/api/routines/nope")
+        .header("cookie", auth_cookie(&token))
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"text":"x"}"#))
+        .unwrap();
+    let (status, _) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Rest days in between neither count nor break the streak
+    for n in [4, 2, 0] {
+        let req = Request::post(format!("/api/routines/{}/completions", routine_id))
+            .header("cookie", auth_cookie(&token))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "date": day(n).to_string() }).to_string(),
+            ))
+            .unwrap();
+        let (status, _) = send(build_app(state.clone()), req).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let req = Request::get(format!("/api/routines/{}/stats", routine_id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    let stats = &body["stats"];
+    assert_eq!(stats["current_streak"], 3);
+    assert_eq!(stats["rate_7"], 100.0);
+    assert_eq!(stats["schedule"], schedule);
+
+    let req = Request::get(format!(
+        "/api/routines/{}/heatmap?from={}",
+        routine_id,
+        day(1)
+    ))
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(body["days"][0]["scheduled"], false);
+    assert_eq!(body["days"][1]["scheduled"], true);
+}
+
+#[tokio::test]
+async fn test_search_todos() {
+    let state = test_state();
+    let (_, token) = create_test_user(&state, "olga", "Olga1234");
+    let (_, other) = create_test_user(&state, "pete", "Pete1234");
+
+    let mut ids = Vec::new();
+    for text in ["Quarterly report draft", "买牛奶和面包", "Old report"] {
+        let app = build_app(state.clone());
+        let req = Request::post("/api/todos")
+            .header("content-type", "application/json")
+            .header("cookie", auth_cookie(&token))
+            .body(Body::from(
+                serde_json::to_string(&serde_json::json!({ "text": text })).unwrap(),
+            ))
+            .unwrap();
+        let (_, body) = send(app, req).await;
+        ids.push(body["item"]["id"].as_str().unwrap().to_string());
+    }
+
+    // Deleted todos drop out of the index
+    let app = build_app(state.clone());
+    let req = Request::delete(format!("/api/todos/{}", ids[2]))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let _ = send(app, req).await;
+
+    let app = build_app(state.clone());
+    let req = Request::get("/api/search?q=report")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let todos = body["results"]["todo"].as_array().unwrap();
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0]["id"], ids[0].as_str());
+    assert_eq!(todos[0]["title"], "Quarterly <mark>report</mark> draft");
+
+    // Edits replace the indexed text; a restored todo comes back
+    let app = build_app(state.clone());
+    let req = Request::put(format!("/api/todos/{}", ids[0]))
+        .header("content-type", "application/json")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::from(r#"{"text":"Quarterly summary"}"#))
+        .unwrap();
+    send(app, req).await;
+    let app = build_app(state.clone());
+    let req = Request::post(format!("/api/trash/todo/{}/restore", ids[2]))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let app = build_app(state.clone());
+    let req = Request::get("/api/search?q=report&module=todo")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["results"]["todo"][0]["id"], ids[2].as_str());
+    {
+        let db = state.db.lock();
+        let rows: i64 = db
+            .query_row("SELECT COUNT(*) FROM search_index", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 3);
+    }
+
+    // Short CJK terms use the substring fallback
+    let app = build_app(state.clone());
+    let req = Request::get("/api/search?q=%E7%89%9B%E5%A5%B6&module=todo")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(
+        body["results"]["todo"][0]["title"],
+        "买<mark>牛奶</mark>和面包"
+    );
+
+    // Other users see nothing
+    let app = build_app(state);
+    let req = Request::get("/api/search?q=report")
+        .header("cookie", auth_cookie(&other))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(app, req).await;
+    assert_eq!(body["total"], 0);
+}
+
 // ──────────────────── Edge cases ────────────────────
 
 #[tokio::test]
@@ -993,3 +4730,122 @@ async fn test_routine_toggle_and_list() {
     let items = body["items"].as_array().unwrap();
     assert!(!items[0]["completed_today"].as_bool().unwrap());
 }
+
+#[tokio::test]
+async fn test_routine_streaks_and_backfill() {
+    let state = test_state();
+    let (_uid, token) = create_test_user(&state, "streakuser", "pass123");
+    let today = chrono::Local::now().date_naive();
+    let day = |n: i64| (today - chrono::Duration::days(n)).to_string();
+
+    let req = Request::post("/api/routines")
+        .header("cookie", auth_cookie(&token))
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"text":"Read"}"#))
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    let routine_id = body["item"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["item"]["current_streak"], 0);
+    {
+        let db = state.db.lock();
+        let created = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        db.execute(
+            "UPDATE routines SET created_at = ?1 WHERE id = ?2",
+            [&created, &routine_id],
+        )
+        .unwrap();
+    }
+
+    let backfill = |date: String| {
+        Request::post(format!("/api/routines/{}/completions", routine_id))
+            .header("cookie", auth_cookie(&token))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "date": date }).to_string()))
+            .unwrap()
+    };
+    for n in [10, 3, 2, 1] {
+        let (status, _) = send(build_app(state.clone()), backfill(day(n))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    // Today still open: the streak counts up to yesterday
+    let (_, body) = send(build_app(state.clone()), backfill(day(1))).await;
+    assert_eq!(body["stats"]["current_streak"], 3);
+    assert_eq!(body["stats"]["total"], 4);
+
+    let (status, _) = send(build_app(state.clone()), backfill(day(-1))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(build_app(state.clone()), backfill(day(60))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Backfilling today is the same as ticking it off
+    let (_, body) = send(build_app(state.clone()), backfill(day(0))).await;
+    assert_eq!(body["stats"]["current_streak"], 4);
+    assert_eq!(body["stats"]["longest_streak"], 4);
+    let req = Request::get("/api/routines")
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    assert_eq!(body["items"][0]["completed_today"], true);
+    assert_eq!(body["items"][0]["current_streak"], 4);
+
+    // Removing yesterday splits the streak
+    let req = Request::delete(format!(
+        "/api/routines/{}/completions/{}",
+        routine_id,
+        day(1)
+    ))
+    .header("cookie", auth_cookie(&token))
+    .body(Body::empty())
+    .unwrap();
+    let (status, _) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::OK);
+    let req = Request::get(format!("/api/routines/{}/stats", routine_id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    let stats = &body["stats"];
+    assert_eq!(stats["current_streak"], 1);
+    assert_eq!(stats["longest_streak"], 2);
+    assert_eq!(stats["rate_7"], 42.9);
+    assert_eq!(stats["last_completed_date"], day(0));
+
+    let req = Request::get(format!("/api/routines/{}/heatmap", routine_id))
+        .header("cookie", auth_cookie(&token))
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = send(build_app(state.clone()), req).await;
+    let days = body["days"].as_array().unwrap();
+    assert_eq!(days.len(), 365);
+    assert_eq!(days[364]["date"], day(0));
+    assert_eq!(days[364]["done"], true);
+    assert_eq!(days[363]["done"], false);
+    assert_eq!(days[354]["done"], true);
+
+    // Dates at the calendar limits don't overflow
+    let heatmap = |query: &str| {
+        Request::get(format!("/api/routines/{}/heatmap?{}", routine_id, query))
+            .header("cookie", auth_cookie(&token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (status, _) = send(build_app(state.clone()), heatmap("to=-262143-01-01")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(
+        build_app(state.clone()),
+        heatmap("from=%2B262142-12-31&to=%2B262142-12-31"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["days"].as_array().unwrap().len(), 1);
+
+    // Other users can't see it
+    let (_, other) = create_test_user(&state, "nosy", "pass123");
+    let req = Request::get(format!("/api/routines/{}/stats", routine_id))
+        .header("cookie", auth_cookie(&other))
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(build_app(state.clone()), req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
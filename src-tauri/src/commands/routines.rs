@@ -36,17 +36,35 @@ pub struct RoutineSimpleResponse {
 #[derive(Debug, Deserialize)]
 pub struct CreateRoutineRequest {
     pub text: String,
+    /// 日程，默认 "daily"
+    #[serde(default)]
+    pub schedule: Option<String>,
 }
 
-/// 获取所有 Routines
+/// 更新 Routine 请求
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoutineRequest {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+/// 获取今天需要做的 Routines；`all` 为 true 时包括休息日的
 #[tauri::command]
 pub fn get_routines(
     state: tauri::State<'_, Mutex<AppState>>,
+    all: Option<bool>,
 ) -> Result<RoutinesResponse, String> {
     let state = state.lock().map_err(|e| e.to_string())?;
     let db = &state.routine_db;
 
-    let items: Vec<Routine> = db.all().into_iter().cloned().collect();
+    let items = if all.unwrap_or(false) {
+        db.all()
+    } else {
+        db.due_today()
+    };
+    let items: Vec<Routine> = items.into_iter().cloned().collect();
 
     Ok(RoutinesResponse {
         success: true,
@@ -61,9 +79,17 @@ pub fn create_routine(
     state: tauri::State<'_, Mutex<AppState>>,
     request: CreateRoutineRequest,
 ) -> Result<RoutineResponse, String> {
+    let text = request.text.trim();
+    if text.is_empty() {
+        return Err("日常任务内容不能为空".to_string());
+    }
+
     let mut state = state.lock().map_err(|e| e.to_string())?;
 
-    let routine = Routine::new(request.text);
+    let mut routine = Routine::new(text.to_string());
+    if let Some(schedule) = request.schedule {
+        routine.set_schedule(&schedule)?;
+    }
     state.routine_db.insert(routine.clone());
     state.routine_db.save().map_err(|e| e.to_string())?;
 
@@ -74,6 +100,41 @@ pub fn create_routine(
     })
 }
 
+/// 更新 Routine 文本或日程
+#[tauri::command]
+pub fn update_routine(
+    state: tauri::State<'_, Mutex<AppState>>,
+    id: String,
+    request: UpdateRoutineRequest,
+) -> Result<RoutineResponse, String> {
+    let text = request.text.as_deref().map(str::trim);
+    if text.is_some_and(str::is_empty) {
+        return Err("日常任务内容不能为空".to_string());
+    }
+
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+
+    let routine = state.routine_db
+        .get_mut(&id)
+        .ok_or_else(|| format!("日常任务不存在: {}", id))?;
+
+    if let Some(schedule) = request.schedule {
+        routine.set_schedule(&schedule)?;
+    }
+    if let Some(text) = text {
+        routine.text = text.to_string();
+    }
+
+    let updated = routine.clone();
+    state.routine_db.save().map_err(|e| e.to_string())?;
+
+    Ok(RoutineResponse {
+        success: true,
+        item: Some(updated),
+        message: Some("已更新".to_string()),
+    })
+}
+
 /// 切换 Routine 完成状态
 #[tauri::command]
 pub fn toggle_routine(
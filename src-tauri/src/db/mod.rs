@@ -235,6 +235,11 @@ impl RoutineDb {
         self.items.values().collect()
     }
 
+    /// 今天需要做的（按日程过滤掉休息日）
+    pub fn due_today(&self) -> Vec<&Routine> {
+        self.items.values().filter(|r| r.due_today).collect()
+    }
+
     /// 获取单个
     pub fn get(&self, id: &str) -> Option<&Routine> {
         self.items.get(id)
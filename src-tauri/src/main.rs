@@ -44,6 +44,7 @@ fn main() {
             // Routine commands
             commands::get_routines,
             commands::create_routine,
+            commands::update_routine,
             commands::toggle_routine,
             commands::delete_routine,
             // Review commands
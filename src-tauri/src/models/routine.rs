@@ -1,9 +1,88 @@
 //! Routine 日常任务数据结构
 
-use chrono::Utc;
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 完成记录保留的天数
+const HISTORY_DAYS: i64 = 400;
+
+/// 日程：哪些天需要做
+///
+/// 存储格式与服务端一致："daily"、"weekdays:1,3,5"（1 = 周一）、
+/// "weekly:N"（每周任意 N 天）、"every_other_day"（从创建日起隔天）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutineSchedule {
+    Daily,
+    Weekdays(Vec<u32>),
+    TimesPerWeek(u32),
+    EveryOtherDay,
+}
+
+impl RoutineSchedule {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "" | "daily" => Some(RoutineSchedule::Daily),
+            "every_other_day" => Some(RoutineSchedule::EveryOtherDay),
+            s => {
+                if let Some(days) = s.strip_prefix("weekdays:") {
+                    let days: BTreeSet<u32> = days
+                        .split(',')
+                        .map(|d| d.trim().parse().ok().filter(|d| (1..=7).contains(d)))
+                        .collect::<Option<_>>()?;
+                    return match days.len() {
+                        0 => None,
+                        7 => Some(RoutineSchedule::Daily),
+                        _ => Some(RoutineSchedule::Weekdays(days.into_iter().collect())),
+                    };
+                }
+                let n: u32 = s.strip_prefix("weekly:")?.trim().parse().ok()?;
+                match n {
+                    1..=6 => Some(RoutineSchedule::TimesPerWeek(n)),
+                    7 => Some(RoutineSchedule::Daily),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            RoutineSchedule::Daily => "daily".into(),
+            RoutineSchedule::Weekdays(days) => format!(
+                "weekdays:{}",
+                days.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",")
+            ),
+            RoutineSchedule::TimesPerWeek(n) => format!("weekly:{}", n),
+            RoutineSchedule::EveryOtherDay => "every_other_day".into(),
+        }
+    }
+
+    /// 是否为安排的日子；每周 N 次的任意一天都可以算
+    pub fn is_scheduled(&self, date: NaiveDate, anchor: NaiveDate) -> bool {
+        match self {
+            RoutineSchedule::Daily | RoutineSchedule::TimesPerWeek(_) => true,
+            RoutineSchedule::Weekdays(days) => days.contains(&date.weekday().number_from_monday()),
+            RoutineSchedule::EveryOtherDay => (date - anchor).num_days().rem_euclid(2) == 0,
+        }
+    }
+}
+
+/// 所在 ISO 周的周一
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn default_schedule() -> String {
+    "daily".to_string()
+}
+
+fn default_due_today() -> bool {
+    true
+}
+
 /// 日常任务项
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Routine {
@@ -15,6 +94,18 @@ pub struct Routine {
     pub last_completed_date: Option<String>,
     #[serde(default)]
     pub created_at: String,
+    /// 日程，见 `RoutineSchedule`
+    #[serde(default = "default_schedule")]
+    pub schedule: String,
+    /// 完成过的日期 (YYYY-MM-DD)，只保留最近一段时间
+    #[serde(default)]
+    pub history: Vec<String>,
+    /// 今天是否需要做（计算字段）
+    #[serde(default = "default_due_today")]
+    pub due_today: bool,
+    /// 连续完成次数，休息日不计入也不中断（计算字段）
+    #[serde(default)]
+    pub current_streak: i64,
 }
 
 impl Routine {
@@ -26,6 +117,10 @@ impl Routine {
             completed_today: false,
             last_completed_date: None,
             created_at: Utc::now().to_rfc3339(),
+            schedule: default_schedule(),
+            history: Vec::new(),
+            due_today: true,
+            current_streak: 0,
         }
     }
 
@@ -34,12 +129,63 @@ impl Routine {
         Uuid::new_v4().to_string()[..8].to_string()
     }
 
+    fn today() -> NaiveDate {
+        Utc::now().date_naive()
+    }
+
+    /// 解析后的日程，无法识别时按每天处理
+    pub fn parsed_schedule(&self) -> RoutineSchedule {
+        RoutineSchedule::parse(&self.schedule).unwrap_or(RoutineSchedule::Daily)
+    }
+
+    /// 隔天日程的起点：创建日
+    fn anchor(&self) -> NaiveDate {
+        chrono::DateTime::parse_from_rfc3339(&self.created_at)
+            .map(|t| t.with_timezone(&Utc).date_naive())
+            .unwrap_or_else(|_| Self::today())
+    }
+
+    fn done_dates(&self) -> BTreeSet<NaiveDate> {
+        let mut dates: BTreeSet<NaiveDate> = self
+            .history
+            .iter()
+            .filter_map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .collect();
+        // 旧数据没有 history，至少保留最后一次完成
+        if let Some(last) = self
+            .last_completed_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        {
+            dates.insert(last);
+        }
+        dates
+    }
+
+    /// 设置日程；无法识别时返回错误
+    pub fn set_schedule(&mut self, schedule: &str) -> Result<(), String> {
+        let parsed = RoutineSchedule::parse(schedule)
+            .ok_or_else(|| format!("无效的日程: {}", schedule))?;
+        self.schedule = parsed.as_string();
+        self.compute_progress();
+        Ok(())
+    }
+
     /// 切换今日完成状态
     pub fn toggle(&mut self) {
+        let today = Self::today().format("%Y-%m-%d").to_string();
         self.completed_today = !self.completed_today;
+        self.history.retain(|d| d != &today);
         if self.completed_today {
-            self.last_completed_date = Some(Utc::now().format("%Y-%m-%d").to_string());
+            self.last_completed_date = Some(today.clone());
+            self.history.push(today);
+        } else if self.last_completed_date.as_deref() == Some(today.as_str()) {
+            self.last_completed_date = self.history.iter().max().cloned();
         }
+        let cutoff = (Self::today() - Duration::days(HISTORY_DAYS)).format("%Y-%m-%d").to_string();
+        self.history.retain(|d| d >= &cutoff);
+        self.history.sort();
+        self.compute_progress();
     }
 
     /// 检查并重置每日状态
@@ -51,6 +197,69 @@ impl Routine {
                 self.completed_today = false;
             }
         }
+        self.compute_progress();
+    }
+
+    /// 计算 due_today 和 current_streak
+    pub fn compute_progress(&mut self) {
+        let today = Self::today();
+        self.due_today = self.is_due(today);
+        self.current_streak = self.streak(today);
+    }
+
+    /// 今天是否出现在列表中：安排的日子、本周次数未满，或今天已经做了
+    fn is_due(&self, date: NaiveDate) -> bool {
+        let done = self.done_dates();
+        if done.contains(&date) {
+            return true;
+        }
+        match self.parsed_schedule() {
+            RoutineSchedule::TimesPerWeek(n) => {
+                (done.range(week_start(date)..date).count() as u32) < n
+            }
+            schedule => schedule.is_scheduled(date, self.anchor()),
+        }
+    }
+
+    /// 当前连续次数；今天还没做不算中断，每周 N 次按周计算
+    fn streak(&self, today: NaiveDate) -> i64 {
+        let done = self.done_dates();
+        let Some(&first) = done.first() else {
+            return 0;
+        };
+        let schedule = self.parsed_schedule();
+        let mut run = 0;
+        if let RoutineSchedule::TimesPerWeek(n) = schedule {
+            let this_week = week_start(today);
+            let mut week = week_start(first);
+            while week <= this_week {
+                let count = done
+                    .range(week..week + Duration::days(7))
+                    .filter(|d| **d <= today)
+                    .count() as i64;
+                if count >= n as i64 || week == this_week {
+                    run += count;
+                } else {
+                    run = 0;
+                }
+                week += Duration::days(7);
+            }
+            return run;
+        }
+
+        let anchor = self.anchor();
+        let mut day = first;
+        while day <= today {
+            if schedule.is_scheduled(day, anchor) {
+                if done.contains(&day) {
+                    run += 1;
+                } else if day < today {
+                    run = 0;
+                }
+            }
+            day += Duration::days(1);
+        }
+        run
     }
 }
 
@@ -66,6 +275,7 @@ mod tests {
         assert!(!routine.completed_today);
         assert!(routine.last_completed_date.is_none());
         assert_eq!(routine.id.len(), 8);
+        assert_eq!(routine.schedule, "daily");
     }
 
     #[test]
@@ -75,8 +285,37 @@ mod tests {
         routine.toggle();
         assert!(routine.completed_today);
         assert!(routine.last_completed_date.is_some());
+        assert_eq!(routine.history.len(), 1);
+        assert_eq!(routine.current_streak, 1);
 
         routine.toggle();
         assert!(!routine.completed_today);
+        assert!(routine.history.is_empty());
+    }
+
+    #[test]
+    fn test_schedule_parse() {
+        assert_eq!(
+            RoutineSchedule::parse("weekdays:5,1,3"),
+            Some(RoutineSchedule::Weekdays(vec![1, 3, 5]))
+        );
+        assert_eq!(RoutineSchedule::parse("weekly:7"), Some(RoutineSchedule::Daily));
+        assert_eq!(RoutineSchedule::parse("weekdays:8"), None);
+        assert_eq!(RoutineSchedule::parse("weekly:0"), None);
+    }
+
+    #[test]
+    fn test_streak_skips_rest_days() {
+        let mut routine = Routine::new("健身".to_string());
+        routine.created_at = "2026-03-01T00:00:00+00:00".to_string();
+        // 2026-03-02 是周一
+        routine.schedule = "weekdays:1,3,5".to_string();
+        routine.history = vec!["2026-03-02".into(), "2026-03-04".into(), "2026-03-06".into()];
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        assert_eq!(routine.streak(date("2026-03-08")), 3);
+        assert_eq!(routine.streak(date("2026-03-10")), 0);
+        assert!(!routine.is_due(date("2026-03-07")));
+        assert!(routine.is_due(date("2026-03-09")));
     }
 }